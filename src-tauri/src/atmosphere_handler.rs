@@ -1,7 +1,9 @@
 use tauri::{AppHandle, Manager};
 use crate::models::{Atmosphere, AtmosphereWithSounds, AtmosphereCategory, AtmosphereSavePayload};
 use crate::models::{AtmosphereIntegrity, AtmosphereIntegrityBatchEntry};
-use crate::AppState;
+use crate::{AppState, AudioHandler};
+use crate::cover_art;
+use std::path::Path;
 
 /// Handler for atmosphere-related operations
 pub struct AtmosphereHandler;
@@ -90,13 +92,49 @@ impl AtmosphereHandler {
         })
     }
 
+    /// Use a cached cover art thumbnail as the atmosphere background image
+    pub fn set_atmosphere_cover_art(app_handle: AppHandle, atmosphere_id: i64, cover_art_hash: String) -> Result<Atmosphere, String> {
+        let state = app_handle.state::<AppState>();
+        let db = state.db.lock().unwrap();
+
+        let path = cover_art::thumbnail_path(Path::new(cover_art::COVER_ART_CACHE_DIR), &cover_art_hash)
+            .filter(|path| path.exists())
+            .ok_or_else(|| format!("Cover art not found in cache: {}", cover_art_hash))?;
+        let path = std::fs::canonicalize(&path).unwrap_or(path);
+
+        let mut atmosphere = db.get_atmosphere_by_id(atmosphere_id).map_err(|e| {
+            log::error!("Failed to get atmosphere by ID {}: {}", atmosphere_id, e);
+            e.to_string()
+        })?;
+        atmosphere.background_image = Some(path.to_string_lossy().to_string());
+
+        log::info!("Setting cover art {} as background of atmosphere {}", cover_art_hash, atmosphere_id);
+        db.save_atmosphere(&atmosphere).map_err(|e| {
+            log::error!("Failed to save atmosphere {}: {}", atmosphere_id, e);
+            e.to_string()
+        })?;
+        Ok(atmosphere)
+    }
+
     /// Add sound to atmosphere
-    pub fn add_sound_to_atmosphere(app_handle: AppHandle, atmosphere_id: i64, audio_file_id: i64, volume: f32, is_looping: bool) -> Result<i64, String> {
+    /// Without a volume, one normalized to the sound's loudness is suggested (analyzing the file first if needed)
+    pub fn add_sound_to_atmosphere(app_handle: AppHandle, atmosphere_id: i64, audio_file_id: i64, volume: Option<f32>, is_looping: bool) -> Result<i64, String> {
         let state = app_handle.state::<AppState>();
         let db = state.db.lock().unwrap();
         
-        log::info!("Adding sound to atmosphere: atmosphere_id={}, audio_file_id={}, volume={}, is_looping={}", 
+        log::info!("Adding sound to atmosphere: atmosphere_id={}, audio_file_id={}, volume={:?}, is_looping={}", 
                   atmosphere_id, audio_file_id, volume, is_looping);
+
+        if volume.is_none() {
+            if let Ok(audio_file) = db.get_audio_file_by_id(audio_file_id) {
+                if audio_file.loudness_integrated.is_none() {
+                    match AudioHandler::calculate_loudness(&audio_file.file_path) {
+                        Ok(loudness) => db.update_audio_file_loudness(audio_file_id, &loudness).map_err(|e| e.to_string())?,
+                        Err(e) => log::warn!("Failed to analyze loudness of {}: {}", audio_file.file_path, e),
+                    }
+                }
+            }
+        }
         
         db.add_sound_to_atmosphere(atmosphere_id, audio_file_id, volume, is_looping).map_err(|e| {
             log::error!("Failed to add sound to atmosphere {}: {}", atmosphere_id, e);
@@ -1,6 +1,15 @@
-use tauri::{AppHandle, Manager};
-use crate::models::AudioFile;
+use tauri::{AppHandle, Emitter, Manager};
+use crate::models::{AudioFile, AudioFilePatch, CoverArt, LibraryChangedEvent, LibrarySyncSummary, StreamSearchRequest, UnplayableFile, WaveformPeaks};
 use crate::{AppState, AudioHandler};
+use crate::ucs;
+use crate::cover_art;
+use crate::waveform;
+use crate::library_sync::{self, DiskFile, SyncPlan};
+use crate::library_watcher::LibraryChange;
+use crate::file_scanner::FileScanner;
+use crate::tag_backup::{self, TagWriteRun};
+use crate::database::Database;
+use std::path::Path;
 
 /// Handler for audio file CRUD operations
 pub struct AudioFileHandler;
@@ -13,7 +22,13 @@ impl AudioFileHandler {
         let db = state.db.lock().unwrap();
         
         log::debug!("Saving audio file: path={}, title={:?}", audio_file.file_path, audio_file.title);
-        
+
+        // A duplicate merged into another row stays merged when the folder is scanned again
+        if let Some(canonical_id) = db.get_merged_canonical(&audio_file.file_path).map_err(|e| e.to_string())? {
+            log::debug!("{} was merged into audio file {}", audio_file.file_path, canonical_id);
+            return Ok(canonical_id);
+        }
+
         db.save_audio_file(&audio_file).map_err(|e| {
             log::error!("Failed to save audio file {}: {}", audio_file.file_path, e);
             e.to_string()
@@ -33,6 +48,39 @@ impl AudioFileHandler {
         })
     }
 
+    /// Get the audio files holding the same decoded audio as `content_hash`, wherever they are now
+    pub fn get_audio_files_by_content_hash(app_handle: AppHandle, content_hash: String) -> Result<Vec<AudioFile>, String> {
+        let state = app_handle.state::<AppState>();
+        let db = state.db.lock().unwrap();
+
+        db.get_audio_files_by_content_hash(&content_hash).map_err(|e| {
+            log::error!("Failed to look up audio files by content hash {}: {}", content_hash, e);
+            e.to_string()
+        })
+    }
+
+    /// Find audio files by codec, container, channel count, sample rate, bit depth, bitrate or size
+    pub fn search_audio_files_by_stream(app_handle: AppHandle, request: StreamSearchRequest) -> Result<Vec<AudioFile>, String> {
+        let state = app_handle.state::<AppState>();
+        let db = state.db.lock().unwrap();
+
+        db.search_audio_files_by_stream(&request).map_err(|e| {
+            log::error!("Failed to search audio files by stream properties {:?}: {}", request, e);
+            e.to_string()
+        })
+    }
+
+    /// Library files that failed the decoder probe, so they can be fixed before a session
+    pub fn get_unplayable_files(app_handle: AppHandle) -> Result<Vec<UnplayableFile>, String> {
+        let state = app_handle.state::<AppState>();
+        let db = state.db.lock().unwrap();
+
+        db.get_unplayable_files().map_err(|e| {
+            log::error!("Failed to get unplayable files: {}", e);
+            e.to_string()
+        })
+    }
+
     /// Delete audio file from database
     pub fn delete_audio_file(app_handle: AppHandle, id: i64) -> Result<(), String> {
         let state = app_handle.state::<AppState>();
@@ -56,7 +104,7 @@ impl AudioFileHandler {
         })
     }
     
-    /// Load audio file metadata and import RPG tags from embedded TXXX fields
+    /// Load audio file metadata and import RPG tags from embedded TXXX fields and UCS categories
     pub fn load_audio_file_with_rpg_tags(_app_handle: AppHandle, file_path: String) -> Result<(AudioFile, Vec<(String, String)>), String> {
         log::debug!("Loading audio file with RPG tags: {}", file_path);
         
@@ -67,11 +115,20 @@ impl AudioFileHandler {
         })?;
         
         // Read RPG tags from file
-        let rpg_tags = AudioHandler::read_rpg_tags_from_file(&file_path).map_err(|e| {
+        let mut rpg_tags = AudioHandler::read_rpg_tags_from_file(&file_path).map_err(|e| {
             log::error!("Failed to read RPG tags from file {}: {}", file_path, e);
             e.to_string()
         })?;
         
+        // Add keyword vocabulary tags mapped from the UCS category
+        if let Some(category) = audio_file.category.as_deref() {
+            for tag in ucs::keyword_tags(category, audio_file.subcategory.as_deref()) {
+                if !rpg_tags.contains(&tag) {
+                    rpg_tags.push(tag);
+                }
+            }
+        }
+        
         if !rpg_tags.is_empty() {
             log::info!("Found {} RPG tags in file: {}", rpg_tags.len(), file_path);
         }
@@ -85,35 +142,30 @@ impl AudioFileHandler {
         
         // Save the audio file first
         let audio_file_id = Self::save_audio_file(app_handle.clone(), audio_file)?;
+        Self::import_rpg_tags(&app_handle, audio_file_id, &rpg_tags);
         
-        // Save RPG tags if any were found
-        if !rpg_tags.is_empty() {
-            let state = app_handle.state::<AppState>();
-            let rpg_tags_count = rpg_tags.len();
-            
-            for (tag_type, tag_value) in &rpg_tags {
-                match state.tag_manager.add_rpg_tag(audio_file_id, tag_type, tag_value) {
-                    Ok(_) => {
-                        log::debug!("Added RPG tag: {}:{} to audio file {}", tag_type, tag_value, audio_file_id);
-                    }
-                    Err(e) => {
-                        log::warn!("Failed to add RPG tag {}:{} to audio file {}: {}", tag_type, tag_value, audio_file_id, e);
-                        // Continue with other tags even if one fails
-                    }
+        Ok(audio_file_id)
+    }
+
+    /// Replace the tags last read from a file with the ones read from it now, keeping those
+    /// added in the app
+    fn import_rpg_tags(app_handle: &AppHandle, audio_file_id: i64, rpg_tags: &[(String, String)]) {
+        let state = app_handle.state::<AppState>();
+        match state.tag_manager.replace_file_rpg_tags(audio_file_id, rpg_tags) {
+            Ok(()) => {
+                if !rpg_tags.is_empty() {
+                    log::info!("Imported {} RPG tags for audio file: {}", rpg_tags.len(), audio_file_id);
                 }
             }
-            
-            log::info!("Imported {} RPG tags for audio file: {}", rpg_tags_count, audio_file_id);
+            Err(e) => log::warn!("Failed to import RPG tags for audio file {}: {}", audio_file_id, e),
         }
-        
-        Ok(audio_file_id)
     }
 
     /// Update audio file tags in both the file system and database
-    pub fn update_audio_file_tags(app_handle: AppHandle, file_path: String, updates: AudioFile) -> Result<(), String> {
+    pub fn update_audio_file_tags(app_handle: AppHandle, file_path: String, updates: AudioFilePatch) -> Result<(), String> {
         log::info!("Updating audio file tags: {}", file_path);
         
-        // Update ID3 tags in the file
+        // Update tags in the file
         AudioHandler::update_audio_file_tags(&file_path, &updates).map_err(|e| {
             log::error!("Failed to update audio file tags {}: {}", file_path, e);
             e.to_string()
@@ -124,46 +176,41 @@ impl AudioFileHandler {
         let db = state.db.lock().unwrap();
         
         // Find the audio file in database by file_path
-        if let Ok(existing_file) = db.get_audio_file_by_path(&file_path) {
-            // Create updated audio file with merged data
-            let mut updated_file = existing_file;
-            
-            // Merge the updates into the existing file data
-            if updates.title.is_some() { updated_file.title = updates.title; }
-            if updates.artist.is_some() { updated_file.artist = updates.artist; }
-            if updates.album.is_some() { updated_file.album = updates.album; }
-            if updates.album_artist.is_some() { updated_file.album_artist = updates.album_artist; }
-            if updates.genre.is_some() { updated_file.genre = updates.genre; }
-            if updates.year.is_some() { updated_file.year = updates.year; }
-            if updates.track_number.is_some() { updated_file.track_number = updates.track_number; }
-            if updates.total_tracks.is_some() { updated_file.total_tracks = updates.total_tracks; }
-            if updates.composer.is_some() { updated_file.composer = updates.composer; }
-            if updates.conductor.is_some() { updated_file.conductor = updates.conductor; }
-            if updates.producer.is_some() { updated_file.producer = updates.producer; }
-            if updates.remixer.is_some() { updated_file.remixer = updates.remixer; }
-            if updates.bpm.is_some() { updated_file.bpm = updates.bpm; }
-            if updates.initial_key.is_some() { updated_file.initial_key = updates.initial_key; }
-            if updates.mood.is_some() { updated_file.mood = updates.mood; }
-            if updates.language.is_some() { updated_file.language = updates.language; }
-            if updates.copyright.is_some() { updated_file.copyright = updates.copyright; }
-            if updates.publisher.is_some() { updated_file.publisher = updates.publisher; }
-            
-            // Update the database record
-            db.update_audio_file(&updated_file).map_err(|e| {
-                log::error!("Failed to update audio file in database {}: {}", file_path, e);
-                format!("Database update failed: {}", e)
-            })?;
-            
-            log::info!("Successfully updated both file tags and database for: {}", file_path);
-        } else {
-            log::warn!("Audio file not found in database, only file tags were updated: {}", file_path);
+        match db.get_audio_file_by_path(&file_path).ok().and_then(|file| file.id) {
+            Some(id) => {
+                db.patch_audio_file(id, &updates).map_err(|e| {
+                    log::error!("Failed to update audio file in database {}: {}", file_path, e);
+                    format!("Database update failed: {}", e)
+                })?;
+                Self::record_disk_state(&db, id, &file_path);
+                log::info!("Successfully updated both file tags and database for: {}", file_path);
+            }
+            None => {
+                log::warn!("Audio file not found in database, only file tags were updated: {}", file_path);
+            }
         }
 
         Ok(())
     }
 
-    /// Write RPG tags to file system
-    pub fn write_rpg_tags_to_file(app_handle: AppHandle, file_path: String) -> Result<(), String> {
+    /// Extract the embedded pictures of a file into the thumbnail cache
+    pub fn get_cover_art(file_path: String) -> Result<Vec<CoverArt>, String> {
+        cover_art::extract_cover_art(&file_path, Path::new(cover_art::COVER_ART_CACHE_DIR)).map_err(|e| {
+            log::error!("Failed to extract cover art from {}: {}", file_path, e);
+            e
+        })
+    }
+
+    /// Waveform peaks of a file for drawing `width` pixels, decoded once into the peak cache
+    pub fn get_waveform_peaks(file_path: String, start: Option<f64>, end: Option<f64>, width: usize) -> Result<WaveformPeaks, String> {
+        waveform::get_peaks(&file_path, Path::new(waveform::WAVEFORM_CACHE_DIR), start, end, width).map_err(|e| {
+            log::error!("Failed to get waveform peaks of {}: {}", file_path, e);
+            e
+        })
+    }
+
+    /// Write RPG tags to file system, backing up the original tag block first when `backup` is set
+    pub fn write_rpg_tags_to_file(app_handle: AppHandle, file_path: String, backup: bool) -> Result<(), String> {
         let state = app_handle.state::<AppState>();
         
         log::info!("Writing RPG tags to file: {}", file_path);
@@ -198,11 +245,27 @@ impl AudioFileHandler {
             // Convert to the format expected by write_rpg_tags_to_file
             let rpg_tag_tuples: Vec<(String, Vec<String>)> = tag_groups.into_iter().collect();
             
+            let backup_run = if backup {
+                let mut run = TagWriteRun::start(Path::new(tag_backup::TAG_BACKUP_DIR), "write_rpg_tags")?;
+                run.backup(&file_path)?;
+                Some(run)
+            } else {
+                None
+            };
+
             // Write RPG tags to the actual audio file
             AudioHandler::write_rpg_tags_to_file(&file_path, &rpg_tag_tuples).map_err(|e| {
                 log::error!("Failed to write RPG tags to file {}: {}", file_path, e);
                 e.to_string()
             })?;
+
+            if let Some(run) = backup_run {
+                run.finish()?;
+            }
+            Self::record_disk_state(&db, audio_file_id, &file_path);
+            if let Err(e) = state.tag_manager.mark_rpg_tags_in_file(audio_file_id) {
+                log::warn!("Failed to mark the RPG tags of {} as written: {}", file_path, e);
+            }
             
             log::info!("Successfully wrote RPG tags to file: {}, tag_count: {}", file_path, rpg_tag_tuples.len());
         } else {
@@ -213,13 +276,230 @@ impl AudioFileHandler {
         Ok(())
     }
 
-    /// Scan directory recursively for audio files
-    pub fn scan_directory_recursive(dir_path: String) -> Result<Vec<String>, String> {
+    /// Record the size and time of a file the app just re-tagged, so a sync doesn't read it again
+    fn record_disk_state(db: &Database, id: i64, file_path: &str) {
+        let recorded = library_sync::disk_state(file_path)
+            .and_then(|(file_size, file_modified)| {
+                db.update_audio_file_disk_state(id, file_size, file_modified).map_err(|e| e.to_string())
+            });
+        if let Err(e) = recorded {
+            log::warn!("Failed to record the disk state of {}: {}", file_path, e);
+        }
+    }
+
+    /// Scan directory recursively for audio files with the given extensions, or the supported ones
+    pub fn scan_directory_recursive(dir_path: String, extensions: Option<Vec<String>>) -> Result<Vec<String>, String> {
         log::info!("Scanning directory recursively: {}", dir_path);
         
-        crate::FileScanner::scan_directory_recursive(&dir_path).map_err(|e| {
+        FileScanner::scan_directory_recursive(&dir_path, extensions.as_deref()).map_err(|e| {
             log::error!("Failed to scan directory {}: {}", dir_path, e);
             e.to_string()
         })
     }
+
+    /// Bring the rows under `dir_path` in line with the disk: new files are loaded and saved,
+    /// files whose size or modification time changed are read again, and vanished ones are
+    /// flagged missing. Nothing is decoded; the new and changed rows are left for `hash_missing_files`
+    pub fn sync_library(app_handle: AppHandle, dir_path: String, extensions: Option<Vec<String>>) -> Result<LibrarySyncSummary, String> {
+        log::info!("Syncing library directory: {}", dir_path);
+
+        // A scan error (e.g. an unmounted drive) stops here rather than flagging everything missing
+        let on_disk = Self::scan_directory_recursive(dir_path.clone(), extensions)?
+            .into_iter()
+            .filter_map(|file_path| match library_sync::disk_state(&file_path) {
+                Ok((file_size, file_modified)) => Some(DiskFile { file_path, file_size, file_modified }),
+                Err(e) => {
+                    log::warn!("{}", e);
+                    None
+                }
+            })
+            .collect();
+
+        let state = app_handle.state::<AppState>();
+        let known = state.db.lock().unwrap().get_library_states().map_err(|e| {
+            log::error!("Failed to get library states: {}", e);
+            e.to_string()
+        })?;
+        let summary = Self::apply_sync_plan(&app_handle, library_sync::plan(on_disk, library_sync::within(&dir_path, known)))?;
+
+        log::info!("Library sync of {}: {:?}", dir_path, summary);
+        Ok(summary)
+    }
+
+    /// Save the added files, read the changed ones again and record the state of the rest
+    fn apply_sync_plan(app_handle: &AppHandle, plan: SyncPlan) -> Result<LibrarySyncSummary, String> {
+        let state = app_handle.state::<AppState>();
+        let mut summary = LibrarySyncSummary {
+            missing: plan.missing.len(),
+            restored: plan.restored,
+            unchanged: plan.unchanged + plan.touched.len(),
+            ..Default::default()
+        };
+
+        {
+            let db = state.db.lock().unwrap();
+            for id in &plan.missing {
+                db.mark_audio_file_missing(*id).map_err(|e| e.to_string())?;
+            }
+            for (id, disk) in &plan.touched {
+                db.update_audio_file_disk_state(*id, disk.file_size, disk.file_modified).map_err(|e| e.to_string())?;
+            }
+        }
+
+        for disk in plan.added {
+            // A duplicate merged into another row stays merged
+            let merged = state.db.lock().unwrap().get_merged_canonical(&disk.file_path).map_err(|e| e.to_string())?;
+            if merged.is_some() {
+                summary.unchanged += 1;
+                continue;
+            }
+
+            let saved = Self::load_audio_file_with_rpg_tags(app_handle.clone(), disk.file_path.clone())
+                .and_then(|(audio_file, rpg_tags)| Self::save_audio_file_with_rpg_tags(app_handle.clone(), audio_file, rpg_tags));
+            match saved {
+                Ok(_) => summary.added += 1,
+                Err(e) => {
+                    log::warn!("Failed to add {} to the library: {}", disk.file_path, e);
+                    summary.failed += 1;
+                }
+            }
+        }
+
+        for (id, disk) in plan.changed {
+            let (mut audio_file, rpg_tags) = match Self::load_audio_file_with_rpg_tags(app_handle.clone(), disk.file_path.clone()) {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    log::warn!("Failed to read changed file {}: {}", disk.file_path, e);
+                    summary.failed += 1;
+                    continue;
+                }
+            };
+            audio_file.id = Some(id);
+
+            let refreshed = state.db.lock().unwrap().refresh_audio_file(&audio_file);
+            match refreshed {
+                Ok(cleared) => {
+                    if cleared {
+                        log::info!("{} was never hashed, so its analyses were cleared", disk.file_path);
+                    }
+                    Self::import_rpg_tags(app_handle, id, &rpg_tags);
+                    summary.updated += 1;
+                }
+                Err(e) => {
+                    log::warn!("Failed to update changed file {}: {}", disk.file_path, e);
+                    summary.failed += 1;
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Watch `dir_paths` in place of any directories watched before. Settled changes to files with
+    /// one of `extensions` (or the defaults) go through the same pipeline as a sync, renames keep
+    /// their rows, and each batch that changed the library is emitted as `library-changed`
+    pub fn watch_library(app_handle: AppHandle, dir_paths: Vec<String>, extensions: Option<Vec<String>>) -> Result<(), String> {
+        log::info!("Watching library directories: {:?}", dir_paths);
+
+        let extensions = FileScanner::normalize_extensions(extensions.as_deref());
+        let watch_handle = app_handle.clone();
+        let watch_extensions = extensions.clone();
+        let state = app_handle.state::<AppState>();
+        state.library_watcher.start(dir_paths, extensions, move |changes| {
+            match Self::apply_library_changes(&watch_handle, changes, &watch_extensions) {
+                Ok(event) => {
+                    let summary = &event.summary;
+                    if event.renamed + summary.added + summary.updated + summary.missing + summary.restored > 0 {
+                        let _ = watch_handle.emit("library-changed", &event);
+                    }
+                }
+                Err(e) => log::error!("Failed to apply library changes: {}", e),
+            }
+        }).map_err(|e| {
+            log::error!("{}", e);
+            e
+        })
+    }
+
+    /// Stop the library watcher; returns whether it was running
+    pub fn stop_watching_library(app_handle: AppHandle) -> bool {
+        let state = app_handle.state::<AppState>();
+        state.library_watcher.stop()
+    }
+
+    pub fn get_watched_library_directories(app_handle: AppHandle) -> Vec<String> {
+        let state = app_handle.state::<AppState>();
+        state.library_watcher.roots()
+    }
+
+    /// Apply a batch from the library watcher. Removals and renames only touch paths in the
+    /// database; written files, and the files of new directories, are synced once the batch is read
+    fn apply_library_changes(app_handle: &AppHandle, changes: Vec<LibraryChange>, extensions: &[String]) -> Result<LibraryChangedEvent, String> {
+        let state = app_handle.state::<AppState>();
+        let mut event = LibraryChangedEvent::default();
+        let mut written: Vec<String> = Vec::new();
+
+        for change in changes {
+            match change {
+                LibraryChange::Written(file_path) => written.push(file_path),
+                LibraryChange::DirectoryCreated(dir_path) => {
+                    written.extend(Self::scan_directory_recursive(dir_path.clone(), Some(extensions.to_vec())).unwrap_or_default());
+                    event.paths.push(dir_path);
+                }
+                LibraryChange::Removed(path) => {
+                    event.summary.missing += state.db.lock().unwrap().mark_audio_files_missing_under(&path).map_err(|e| e.to_string())?;
+                    event.paths.push(path);
+                }
+                LibraryChange::Renamed { from, to } => {
+                    let is_dir = Path::new(&to).is_dir();
+                    if !is_dir && !FileScanner::has_extension(&to, extensions) {
+                        // Renamed to something that isn't audio, so it left the library
+                        event.summary.missing += state.db.lock().unwrap().mark_audio_files_missing_under(&from).map_err(|e| e.to_string())?;
+                    } else {
+                        let renamed = state.db.lock().unwrap().rename_audio_file_path(&from, &to);
+                        match renamed {
+                            Ok(count) if count > 0 => event.renamed += count,
+                            // Nothing was there before, e.g. a download renamed from its temporary name
+                            Ok(_) if is_dir => written.extend(Self::scan_directory_recursive(to.clone(), Some(extensions.to_vec())).unwrap_or_default()),
+                            Ok(_) => written.push(to.clone()),
+                            // The new path already had a row: the moved file replaced that one
+                            Err(e) => {
+                                log::warn!("Failed to move the rows of {} to {}: {}", from, to, e);
+                                event.summary.missing += state.db.lock().unwrap().mark_audio_files_missing_under(&from).map_err(|e| e.to_string())?;
+                                written.push(to.clone());
+                            }
+                        }
+                    }
+                    event.paths.push(from);
+                    event.paths.push(to);
+                }
+            }
+        }
+
+        written.sort();
+        written.dedup();
+        event.paths.extend(written.iter().cloned());
+
+        // Files gone again by now are in `known` but not on disk, so they end up missing
+        let on_disk = written.iter()
+            .filter_map(|file_path| library_sync::disk_state(file_path).ok().map(|(file_size, file_modified)| {
+                DiskFile { file_path: file_path.clone(), file_size, file_modified }
+            }))
+            .collect();
+        let known = state.db.lock().unwrap().get_library_states().map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|file| written.binary_search(&file.file_path).is_ok())
+            .collect();
+
+        let summary = Self::apply_sync_plan(app_handle, library_sync::plan(on_disk, known))?;
+        event.summary.added += summary.added;
+        event.summary.updated += summary.updated;
+        event.summary.missing += summary.missing;
+        event.summary.restored += summary.restored;
+        event.summary.unchanged += summary.unchanged;
+        event.summary.failed += summary.failed;
+
+        log::info!("Library watcher applied {} renames and {:?}", event.renamed, event.summary);
+        Ok(event)
+    }
 }
\ No newline at end of file
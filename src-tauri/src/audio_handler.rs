@@ -1,12 +1,23 @@
 use id3::{Tag, TagLike, Frame, Content, frame::ExtendedText};
-use crate::models::AudioFile;
-use symphonia::core::formats::FormatOptions;
+use crate::models::{AudioFile, AudioFilePatch, FieldPatch, KeyEstimate, LoopAnalysis, LoudnessAnalysis, QualityAnalysis, SilenceAnalysis, StreamProperties, TempoEstimate};
+use crate::loudness::LoudnessMeter;
+use crate::loop_analysis::LoopAnalyzer;
+use crate::key_detection::KeyDetector;
+use crate::tempo::TempoDetector;
+use crate::silence::SilenceDetector;
+use crate::fingerprint::{Fingerprint, Fingerprinter};
+use crate::sound_classifier::{SoundAnalyzer, SoundFeatures};
+use crate::content_hash::ContentHasher;
+use crate::quality::QualityAnalyzer;
+use crate::tag_formats::{self, TagFormat, involved_people, vorbis, mp4, wav, flac::FlacMetadata};
+use crate::ucs;
+use crate::library_sync;
+use symphonia::core::formats::{FormatOptions, FormatReader, Track};
 use symphonia::core::io::MediaSourceStream;
 use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
 use symphonia::core::audio::SampleBuffer;
 use std::fs::File;
-use aubio_rs::{Tempo, OnsetMode};
 
 pub struct AudioHandler;
 
@@ -65,12 +76,95 @@ impl AudioHandler {
             tempo: None,
             content_type: None,
             category: None,
+            subcategory: None,
             auto_tagged: None,
             auto_tag_date: None,
             auto_tag_version: None,
+            loudness_integrated: None,
+            loudness_range: None,
+            true_peak: None,
+            loop_start: None,
+            loop_end: None,
+            loop_seamless: None,
+            key_confidence: None,
+            leading_silence: None,
+            trailing_silence: None,
+            mostly_silent: None,
+            content_hash: None,
+            bpm_confidence: None,
+            quality_grade: None,
+            clipping_ratio: None,
+            noise_floor: None,
+            bandwidth: None,
+            sample_rate: None,
+            bit_depth: None,
+            codec: None,
+            container: None,
+            channels: None,
+            bitrate: None,
+            file_size: None,
+            decodable: None,
+            decode_error: None,
+            file_modified: None,
+            missing: None,
         };
 
-        if let Ok(tag) = Tag::read_from_path(file_path) {
+        let format = TagFormat::from_path(file_path);
+        Self::load_tags(file_path, format, &mut audio_file);
+
+        // WAV: fill what the id3 chunk didn't provide from RIFF INFO and BWF bext
+        let mut ixml = None;
+        if format == TagFormat::Wav {
+            match wav::read_metadata(file_path) {
+                Ok(metadata) => {
+                    wav::apply_to_audio_file(&metadata, &mut audio_file);
+                    ixml = metadata.ixml;
+                }
+                Err(e) => eprintln!("Failed to read WAV metadata from {}: {}", file_path, e),
+            }
+        }
+
+        // UCS category from iXML or a CatID_FXName_CreatorID_SourceID filename
+        if let Some(info) = ucs::detect(file_path, ixml.as_deref()) {
+            info.apply_to_audio_file(&mut audio_file);
+        }
+
+        // The size goes into the bitrate; size and time are what a library sync compares against
+        match library_sync::disk_state(file_path) {
+            Ok((file_size, file_modified)) => {
+                audio_file.file_size = Some(file_size);
+                audio_file.file_modified = Some(file_modified);
+                match Self::stream_properties(file_path, file_size) {
+                    Ok(stream) => {
+                        audio_file.codec = stream.codec;
+                        audio_file.container = stream.container;
+                        audio_file.sample_rate = stream.sample_rate;
+                        audio_file.channels = stream.channels;
+                        audio_file.bit_depth = stream.bit_depth;
+                        audio_file.bitrate = stream.bitrate;
+                    }
+                    Err(e) => eprintln!("Failed to read stream properties of {}: {}", file_path, e),
+                }
+            }
+            Err(e) => eprintln!("{}", e),
+        }
+
+        Ok(audio_file)
+    }
+
+    /// Fill metadata from the file's own tag format
+    fn load_tags(file_path: &str, format: TagFormat, audio_file: &mut AudioFile) {
+        match format {
+            TagFormat::Flac | TagFormat::Ogg => Self::load_vorbis_metadata(file_path, audio_file),
+            TagFormat::Mp4 => Self::load_mp4_metadata(file_path, audio_file),
+            TagFormat::Id3 | TagFormat::Wav => Self::load_id3_metadata(file_path, audio_file),
+            TagFormat::Unsupported => {}
+        }
+    }
+
+    /// Fill metadata from the ID3 tag of a file (the `id3 ` chunk for WAV files)
+    fn load_id3_metadata(file_path: &str, audio_file: &mut AudioFile) {
+        if let Some(tag) = tag_formats::read_id3_tag(file_path) {
             // Basic tags
             audio_file.title = tag.title().map(|s| s.to_string());
             audio_file.artist = tag.artist().map(|s| s.to_string());
@@ -106,22 +200,6 @@ impl AudioHandler {
                     "TEXT" => audio_file.lyricist = frame.content().text().map(|s| s.to_string()),
                     "TOPE" => audio_file.original_artist = frame.content().text().map(|s| s.to_string()),
                     "TPE4" => audio_file.remixer = frame.content().text().map(|s| s.to_string()),
-                    "TIPL" => {
-                        // Involved people list - could contain arranger, engineer, producer, etc.
-                        if let Some(text) = frame.content().text() {
-                            if text.to_lowercase().contains("arranger") {
-                                audio_file.arranger = Some(text.to_string());
-                            } else if text.to_lowercase().contains("engineer") {
-                                audio_file.engineer = Some(text.to_string());
-                            } else if text.to_lowercase().contains("producer") {
-                                audio_file.producer = Some(text.to_string());
-                            } else if text.to_lowercase().contains("dj") {
-                                audio_file.dj_mixer = Some(text.to_string());
-                            } else if text.to_lowercase().contains("mix") {
-                                audio_file.mixer = Some(text.to_string());
-                            }
-                        }
-                    },
                     "TIT1" => audio_file.content_group = frame.content().text().map(|s| s.to_string()),
                     "TIT3" => audio_file.subtitle = frame.content().text().map(|s| s.to_string()),
                     "TKEY" => audio_file.initial_key = frame.content().text().map(|s| s.to_string()),
@@ -145,19 +223,78 @@ impl AudioHandler {
                     "TSRC" => audio_file.isrc = frame.content().text().map(|s| s.to_string()),
                     "TPUB" => audio_file.publisher = frame.content().text().map(|s| s.to_string()),
                     "TMOO" => audio_file.mood = frame.content().text().map(|s| s.to_string()),
+                    "TXXX" => {
+                        if let Some(extended_text) = frame.content().extended_text() {
+                            let value = Some(extended_text.value.clone());
+                            match extended_text.description.as_str() {
+                                "Occasion" => audio_file.occasion = value,
+                                "TEMPO" => audio_file.tempo = value,
+                                "CONTENTTYPE" => audio_file.content_type = value,
+                                "CATEGORY" => audio_file.category = value,
+                                "SUBCATEGORY" => audio_file.subcategory = value,
+                                _ => {}
+                            }
+                        }
+                    }
                     _ => {} // Ignore unknown frames
                 }
             }
+
+            // Credits from the TIPL/TMCL (ID3v2.4) and IPLS (ID3v2.3) role/name lists
+            involved_people::apply_to_audio_file(&tag, audio_file);
+        }
+    }
+
+    /// Fill metadata from the Vorbis comments of a FLAC or OGG file
+    fn load_vorbis_metadata(file_path: &str, audio_file: &mut AudioFile) {
+        match tag_formats::read_vorbis_comments(file_path) {
+            Ok(Some(comments)) => vorbis::apply_to_audio_file(&comments, audio_file),
+            Ok(None) => {}
+            Err(e) => eprintln!("Failed to read Vorbis comments from {}: {}", file_path, e),
         }
 
-        Ok(audio_file)
+        // FLAC carries an exact duration in STREAMINFO
+        if TagFormat::from_path(file_path) == TagFormat::Flac {
+            audio_file.duration = FlacMetadata::read_from_path(file_path)
+                .ok()
+                .and_then(|metadata| metadata.duration());
+        }
+    }
+
+    /// Fill metadata from the iTunes atoms of an MP4/M4A file
+    fn load_mp4_metadata(file_path: &str, audio_file: &mut AudioFile) {
+        match mp4::read_tag(file_path) {
+            Ok(Some(tag)) => mp4::apply_to_audio_file(&tag, audio_file),
+            Ok(None) => {}
+            Err(e) => eprintln!("Failed to read MP4 metadata from {}: {}", file_path, e),
+        }
+        audio_file.duration = mp4::read_duration(file_path);
     }
     
     /// Read RPG tags from TXXX fields in audio file
     pub fn read_rpg_tags_from_file(file_path: &str) -> Result<Vec<(String, String)>, String> {
         let mut rpg_tags = Vec::new();
+
+        // FLAC and OGG keep RPG tags in native OCCASION/KEYWORDS/QUALITY comments,
+        // MP4 in freeform ----:com.apple.iTunes:Occasion/Keywords/Quality atoms
+        match TagFormat::from_path(file_path) {
+            TagFormat::Flac | TagFormat::Ogg => {
+                if let Ok(Some(comments)) = tag_formats::read_vorbis_comments(file_path) {
+                    rpg_tags = vorbis::read_rpg_tags(&comments);
+                }
+                return Ok(rpg_tags);
+            }
+            TagFormat::Mp4 => {
+                if let Ok(Some(tag)) = mp4::read_tag(file_path) {
+                    rpg_tags = mp4::read_rpg_tags(&tag);
+                }
+                return Ok(rpg_tags);
+            }
+            TagFormat::Unsupported => return Ok(rpg_tags),
+            TagFormat::Id3 | TagFormat::Wav => {}
+        }
         
-        if let Ok(tag) = Tag::read_from_path(file_path) {
+        if let Some(tag) = tag_formats::read_id3_tag(file_path) {
             // Look for RPG-specific TXXX frames following the format from STORE_TAGS.md
             for frame in tag.frames() {
                 if let Some(extended_text) = frame.content().extended_text() {
@@ -264,107 +401,162 @@ impl AudioHandler {
         }
     }
 
-    pub fn update_audio_file_tags(file_path: &str, updates: &AudioFile) -> Result<(), String> {
+    pub fn update_audio_file_tags(file_path: &str, updates: &AudioFilePatch) -> Result<(), String> {
         // Check if file exists before attempting to update tags
         if !std::path::Path::new(file_path).exists() {
             return Err(format!("File not found: {}", file_path));
         }
-        
-        let mut tag = Tag::read_from_path(file_path).unwrap_or_else(|_| Tag::new());
-        
-        // Update basic tags
-        if let Some(title) = &updates.title {
-            tag.set_title(title);
-        }
-        if let Some(artist) = &updates.artist {
-            tag.set_artist(artist);
-        }
-        if let Some(album) = &updates.album {
-            tag.set_album(album);
-        }
-        if let Some(album_artist) = &updates.album_artist {
-            tag.set_album_artist(album_artist);
-        }
-        if let Some(genre) = &updates.genre {
-            tag.set_genre(genre);
-        }
-        if let Some(year) = updates.year {
-            tag.set_year(year);
-        }
-        if let Some(track) = updates.track_number {
-            tag.set_track(track);
-        }
-        if let Some(total_tracks) = updates.total_tracks {
-            tag.set_total_tracks(total_tracks);
-        }
-        if let Some(disc) = updates.disc_number {
-            tag.set_disc(disc);
-        }
-        if let Some(total_discs) = updates.total_discs {
-            tag.set_total_discs(total_discs);
+
+        match TagFormat::from_path(file_path) {
+            TagFormat::Flac | TagFormat::Ogg => {
+                let mut comments = tag_formats::read_or_new_vorbis_comments(file_path)?;
+                vorbis::apply_patch(&mut comments, updates);
+                return tag_formats::write_vorbis_comments(file_path, &comments)
+                    .map_err(|e| format!("Failed to write tags: {}", e));
+            }
+            TagFormat::Mp4 => {
+                let mut tag = mp4::read_tag(file_path)?.unwrap_or_default();
+                mp4::apply_patch(&mut tag, updates);
+                return tag_formats::write_mp4_tag(file_path, &tag)
+                    .map_err(|e| format!("Failed to write tags: {}", e));
+            }
+            TagFormat::Unsupported => return Err(tag_formats::unsupported_format(file_path)),
+            TagFormat::Id3 | TagFormat::Wav => {}
         }
         
-        // Update extended tags using frame manipulation
-        if let Some(composer) = &updates.composer {
-            tag.set_text("TCOM", composer);
-        }
-        if let Some(conductor) = &updates.conductor {
-            tag.set_text("TPE3", conductor);
-        }
-        if let Some(lyricist) = &updates.lyricist {
-            tag.set_text("TEXT", lyricist);
-        }
-        if let Some(original_artist) = &updates.original_artist {
-            tag.set_text("TOPE", original_artist);
-        }
-        if let Some(remixer) = &updates.remixer {
-            tag.set_text("TPE4", remixer);
-        }
-        if let Some(content_group) = &updates.content_group {
-            tag.set_text("TIT1", content_group);
-        }
-        if let Some(subtitle) = &updates.subtitle {
-            tag.set_text("TIT3", subtitle);
-        }
-        if let Some(initial_key) = &updates.initial_key {
-            tag.set_text("TKEY", initial_key);
-        }
-        if let Some(bpm) = updates.bpm {
-            tag.set_text("TBPM", &bpm.to_string());
-        }
-        if let Some(language) = &updates.language {
-            tag.set_text("TLAN", language);
-        }
-        if let Some(media_type) = &updates.media_type {
-            tag.set_text("TMED", media_type);
-        }
-        if let Some(copyright) = &updates.copyright {
-            tag.set_text("TCOP", copyright);
-        }
-        if let Some(encoded_by) = &updates.encoded_by {
-            tag.set_text("TENC", encoded_by);
-        }
-        if let Some(publisher) = &updates.publisher {
-            tag.set_text("TPUB", publisher);
-        }
-        if let Some(mood) = &updates.mood {
-            tag.set_text("TMOO", mood);
-        }
+        let mut tag = tag_formats::read_id3_tag(file_path).unwrap_or_else(Tag::new);
+        Self::apply_patch_to_id3(&mut tag, updates);
         
         // Write the updated tag back to the file
-        tag.write_to_path(file_path, id3::Version::Id3v24)
+        tag_formats::write_id3_tag(file_path, &tag)
             .map_err(|e| format!("Failed to write tags: {}", e))?;
         
         Ok(())
     }
 
+    /// Apply a patch to an ID3 tag: set fields are replaced, cleared fields removed
+    ///
+    /// `duration` and the auto-tagging fields only live in the database.
+    fn apply_patch_to_id3(tag: &mut Tag, updates: &AudioFilePatch) {
+        // Basic tags
+        patch_text_frame(tag, "TIT2", &updates.title);
+        patch_text_frame(tag, "TPE1", &updates.artist);
+        patch_text_frame(tag, "TALB", &updates.album);
+        patch_text_frame(tag, "TPE2", &updates.album_artist);
+        patch_text_frame(tag, "TCON", &updates.genre);
+        match updates.year {
+            FieldPatch::Set(year) => tag.set_year(year),
+            FieldPatch::Clear => tag.remove_year(),
+            FieldPatch::Leave => {}
+        }
+
+        // TRCK/TPOS hold "number/total"; a total can't be stored without a number
+        for (id, number, total) in [
+            ("TRCK", &updates.track_number, &updates.total_tracks),
+            ("TPOS", &updates.disc_number, &updates.total_discs),
+        ] {
+            if number.is_leave() && total.is_leave() {
+                continue;
+            }
+            let (old_number, old_total) = if id == "TRCK" {
+                (tag.track(), tag.total_tracks())
+            } else {
+                (tag.disc(), tag.total_discs())
+            };
+            match (number.apply(old_number), total.apply(old_total)) {
+                (Some(number), Some(total)) => tag.set_text(id, format!("{}/{}", number, total)),
+                (Some(number), None) => tag.set_text(id, number.to_string()),
+                (None, _) => {
+                    tag.remove(id);
+                }
+            }
+        }
+
+        // `date` and `recording_time` are both read from TDRC
+        patch_text_frame(tag, "TDRC", &updates.date.clone().or(updates.recording_time.clone()));
+
+        // Extended tags
+        patch_text_frame(tag, "TCOM", &updates.composer);
+        patch_text_frame(tag, "TPE3", &updates.conductor);
+        patch_text_frame(tag, "TEXT", &updates.lyricist);
+        patch_text_frame(tag, "TOPE", &updates.original_artist);
+        patch_text_frame(tag, "TPE4", &updates.remixer);
+        involved_people::update_tag(tag, updates);
+
+        // Content tags
+        patch_text_frame(tag, "TIT1", &updates.content_group);
+        patch_text_frame(tag, "TIT3", &updates.subtitle);
+        patch_text_frame(tag, "TKEY", &updates.initial_key);
+        patch_text_frame(tag, "TBPM", &updates.bpm.map(|b| b.to_string()));
+        patch_text_frame(tag, "TLAN", &updates.language);
+        patch_text_frame(tag, "TMED", &updates.media_type);
+        patch_text_frame(tag, "TOFN", &updates.original_filename);
+        patch_text_frame(tag, "TOLY", &updates.original_lyricist);
+        patch_text_frame(tag, "TORY", &updates.original_release_time);
+        patch_text_frame(tag, "TDLY", &updates.playlist_delay.map(|d| d.to_string()));
+
+        // Recording info
+        patch_text_frame(tag, "TDRL", &updates.release_time);
+        patch_text_frame(tag, "TDTG", &updates.tagging_time);
+        patch_text_frame(tag, "TDEN", &updates.encoding_time);
+        patch_text_frame(tag, "TSSE", &updates.encoding_settings);
+        patch_text_frame(tag, "TENC", &updates.encoded_by);
+
+        // Copyright and legal
+        patch_text_frame(tag, "TCOP", &updates.copyright);
+        patch_text_frame(tag, "TOWN", &updates.file_owner);
+        patch_text_frame(tag, "TRSN", &updates.internet_radio_station_name);
+        patch_text_frame(tag, "TRSO", &updates.internet_radio_station_owner);
+        patch_text_frame(tag, "TSRC", &updates.isrc);
+        patch_text_frame(tag, "TPUB", &updates.publisher);
+
+        // Additional metadata; fields without a standard frame go to TXXX
+        patch_text_frame(tag, "TMOO", &updates.mood);
+        for (description, value) in [
+            ("Occasion", &updates.occasion),
+            ("TEMPO", &updates.tempo),
+            ("CONTENTTYPE", &updates.content_type),
+            ("CATEGORY", &updates.category),
+            ("SUBCATEGORY", &updates.subcategory),
+        ] {
+            match value {
+                FieldPatch::Set(value) => {
+                    let extended_text = ExtendedText {
+                        description: description.to_string(),
+                        value: value.clone(),
+                    };
+                    tag.add_frame(Frame::with_content("TXXX", Content::ExtendedText(extended_text)));
+                }
+                FieldPatch::Clear => tag.remove_extended_text(Some(description), None),
+                FieldPatch::Leave => {}
+            }
+        }
+    }
+
     pub fn write_rpg_tags_to_file(file_path: &str, rpg_tags: &[(String, Vec<String>)]) -> Result<(), String> {
         // Check if file exists before attempting to write RPG tags
         if !std::path::Path::new(file_path).exists() {
             return Err(format!("File not found: {}", file_path));
         }
+
+        match TagFormat::from_path(file_path) {
+            TagFormat::Flac | TagFormat::Ogg => {
+                let mut comments = tag_formats::read_or_new_vorbis_comments(file_path)?;
+                vorbis::write_rpg_tags(&mut comments, rpg_tags);
+                return tag_formats::write_vorbis_comments(file_path, &comments)
+                    .map_err(|e| format!("Failed to write RPG tags: {}", e));
+            }
+            TagFormat::Mp4 => {
+                let mut tag = mp4::read_tag(file_path)?.unwrap_or_default();
+                mp4::write_rpg_tags(&mut tag, rpg_tags);
+                return tag_formats::write_mp4_tag(file_path, &tag)
+                    .map_err(|e| format!("Failed to write RPG tags: {}", e));
+            }
+            TagFormat::Unsupported => return Err(tag_formats::unsupported_format(file_path)),
+            TagFormat::Id3 | TagFormat::Wav => {}
+        }
         
-        let mut tag = Tag::read_from_path(file_path).unwrap_or_else(|_| Tag::new());
+        let mut tag = tag_formats::read_id3_tag(file_path).unwrap_or_else(Tag::new);
         
         // Remove existing RPG TXXX frames first
         tag.remove("TXXX:Occasion");
@@ -415,7 +607,7 @@ impl AudioHandler {
         }
         
         // Write the updated tag back to the file
-        tag.write_to_path(file_path, id3::Version::Id3v24)
+        tag_formats::write_id3_tag(file_path, &tag)
             .map_err(|e| format!("Failed to write RPG tags: {}", e))?;
         
         Ok(())
@@ -540,128 +732,227 @@ impl AudioHandler {
         Ok(duration_seconds)
     }
 
-    pub fn calculate_audio_bpm(file_path: &str) -> Result<f32, String> {
-        // Open the media source
-        let src = File::open(file_path)
-            .map_err(|e| format!("Failed to open file: {}", e))?;
-        
-        // Create the media source stream
-        let mss = MediaSourceStream::new(Box::new(src), Default::default());
-        
-        // Create a probe hint using the file extension
-        let mut hint = Hint::new();
-        if let Some(extension) = std::path::Path::new(file_path).extension() {
-            if let Some(ext_str) = extension.to_str() {
-                hint.with_extension(ext_str);
-            }
-        }
-        
-        // Use the default options for metadata and format readers
-        let meta_opts: MetadataOptions = Default::default();
-        let fmt_opts: FormatOptions = Default::default();
-        
-        // Probe the media source
-        let probed = symphonia::default::get_probe()
-            .format(&hint, mss, &fmt_opts, &meta_opts)
-            .map_err(|e| format!("Failed to probe format: {}", e))?;
-        
-        // Get the instantiated format reader
-        let mut format = probed.format;
-        
-        // Find the first audio track
-        let track = format
-            .tracks()
-            .iter()
-            .find(|t| t.codec_params.codec != symphonia::core::codecs::CODEC_TYPE_NULL)
-            .ok_or("No suitable audio track found")?;
-        
+    /// Estimate the tempo from the first minute of a file; the BPM is `None` for audio without a steady beat
+    pub fn calculate_audio_bpm(file_path: &str) -> Result<TempoEstimate, String> {
+        let mut detector: Option<TempoDetector> = None;
+        Self::decode_samples(file_path, |sample_rate, channels, samples| {
+            detector.get_or_insert_with(|| TempoDetector::new(sample_rate, channels)).add_samples(samples)
+        })?;
+        detector.map(TempoDetector::finish).ok_or_else(|| "No audio could be decoded".to_string())
+    }
+
+    /// Estimate the musical key from the first minutes of a file; the key is `None` for non-tonal audio
+    pub fn detect_key(file_path: &str) -> Result<KeyEstimate, String> {
+        let mut detector: Option<KeyDetector> = None;
+        Self::decode_samples(file_path, |sample_rate, channels, samples| {
+            detector.get_or_insert_with(|| KeyDetector::new(sample_rate, channels)).add_samples(samples)
+        })?;
+        detector.map(KeyDetector::finish).ok_or_else(|| "No audio could be decoded".to_string())
+    }
+
+    /// Decode the first audio track, handing its samples (interleaved f32) to `on_samples` along with
+    /// the sample rate and channel count; decoding stops early once `on_samples` returns `false`
+    pub fn decode_samples<F>(file_path: &str, mut on_samples: F) -> Result<(), String>
+    where
+        F: FnMut(u32, usize, &[f32]) -> bool,
+    {
+        let (mut format, track) = Self::open_audio_track(file_path)?;
         let track_id = track.id;
-        let codec_params = &track.codec_params;
-        let sample_rate = codec_params.sample_rate.ok_or("No sample rate found")?;
-        
-        // Create decoder
         let mut decoder = symphonia::default::get_codecs()
-            .make(&codec_params, &Default::default())
+            .make(&track.codec_params, &Default::default())
             .map_err(|e| format!("Failed to create decoder: {}", e))?;
-        
-        // Initialize aubio tempo detection
-        let mut tempo = Tempo::new(OnsetMode::Energy, 1024, 512, sample_rate)
-            .map_err(|e| format!("Failed to create tempo detector: {:?}", e))?;
-        
-        // Get channels
-        let channels = codec_params.channels.unwrap_or(symphonia::core::audio::Channels::FRONT_LEFT | symphonia::core::audio::Channels::FRONT_RIGHT);
-        
-        // Create signal spec for sample buffer using simple constructor
-        let spec = symphonia::core::audio::SignalSpec::new(sample_rate, channels);
-        
-        // Sample buffer for f32 samples
-        let mut sample_buf = SampleBuffer::<f32>::new(
-            codec_params.n_frames.unwrap_or(1024) as u64,
-            spec
-        );
-        
-        let mut total_samples_processed = 0;
-        let max_samples = sample_rate * 60; // Process up to 60 seconds for BPM detection
-        
-        // Process packets for BPM detection
+
+        let mut sample_buf: Option<SampleBuffer<f32>> = None;
         loop {
-            if total_samples_processed >= max_samples {
-                break; // Process enough audio for reliable BPM detection
-            }
-            
-            // Get the next packet from the media format
             let packet = match format.next_packet() {
                 Ok(packet) => packet,
                 Err(symphonia::core::errors::Error::ResetRequired) => break,
-                Err(symphonia::core::errors::Error::IoError(e)) 
+                Err(symphonia::core::errors::Error::IoError(e))
                     if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
-                Err(_) => continue,
+                Err(e) => return Err(format!("Decode error: {}", e)),
             };
-            
-            // If the packet does not belong to the selected track, skip over it
             if packet.track_id() != track_id {
                 continue;
             }
-            
-            // Decode the packet into audio samples
+
             match decoder.decode(&packet) {
                 Ok(decoded) => {
-                    // Store frame count before moving decoded
-                    let frames = decoded.frames();
-                    
-                    // Convert to f32 samples
-                    sample_buf.copy_interleaved_ref(decoded);
-                    let samples = sample_buf.samples();
-                    
-                    // Process samples in chunks suitable for aubio (hop size = 512)
-                    for chunk in samples.chunks(512) {
-                        if chunk.len() == 512 {
-                            tempo.do_result(chunk)
-                                .map_err(|e| format!("Failed to process tempo: {:?}", e))?;
-                        }
+                    let spec = *decoded.spec();
+                    // Packets can grow (e.g. variable block sizes), so the buffer grows with them
+                    let buf = match &mut sample_buf {
+                        Some(buf) if buf.capacity() >= decoded.capacity() * spec.channels.count() => buf,
+                        _ => sample_buf.insert(SampleBuffer::new(decoded.capacity() as u64, spec)),
+                    };
+                    buf.copy_interleaved_ref(decoded);
+                    if !on_samples(spec.rate, spec.channels.count(), buf.samples()) {
+                        break;
                     }
-                    
-                    total_samples_processed += frames as u32;
                 }
-                Err(symphonia::core::errors::Error::IoError(e)) 
+                Err(symphonia::core::errors::Error::IoError(e))
                     if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
                 Err(symphonia::core::errors::Error::DecodeError(_)) => continue,
-                Err(_) => continue,
+                Err(e) => return Err(format!("Decode error: {}", e)),
             }
         }
-        
-        // Get the detected BPM
-        let bpm = tempo.get_bpm();
-        
-        if bpm > 0.0 && bpm < 300.0 { // Reasonable BPM range
-            Ok(bpm)
-        } else {
-            Err("Could not detect valid BPM".to_string())
+        Ok(())
+    }
+
+    /// Probe a file's container and find its first audio track
+    fn open_audio_track(file_path: &str) -> Result<(Box<dyn FormatReader>, Track), String> {
+        let src = File::open(file_path)
+            .map_err(|e| format!("Failed to open file: {}", e))?;
+        let mss = MediaSourceStream::new(Box::new(src), Default::default());
+
+        let mut hint = Hint::new();
+        if let Some(ext_str) = std::path::Path::new(file_path).extension().and_then(|e| e.to_str()) {
+            hint.with_extension(ext_str);
         }
+
+        let probed = symphonia::default::get_probe()
+            .format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default())
+            .map_err(|e| format!("Failed to probe format: {}", e))?;
+
+        let track = probed.format
+            .tracks()
+            .iter()
+            .find(|t| t.codec_params.codec != symphonia::core::codecs::CODEC_TYPE_NULL)
+            .cloned()
+            .ok_or("No suitable audio track found")?;
+        Ok((probed.format, track))
+    }
+
+    /// Measure EBU R128 integrated loudness, loudness range and true peak of a whole file
+    pub fn calculate_loudness(file_path: &str) -> Result<LoudnessAnalysis, String> {
+        let mut meter: Option<LoudnessMeter> = None;
+        Self::decode_samples(file_path, |sample_rate, channels, samples| {
+            meter.get_or_insert_with(|| LoudnessMeter::new(sample_rate, channels)).add_samples(samples);
+            true
+        })?;
+        meter.map(LoudnessMeter::finish).ok_or_else(|| "No audio could be decoded".to_string())
+    }
+
+    /// Check how cleanly a file loops and suggest loop points at zero crossings
+    pub fn analyze_loop(file_path: &str) -> Result<LoopAnalysis, String> {
+        let mut analyzer: Option<LoopAnalyzer> = None;
+        Self::decode_samples(file_path, |sample_rate, channels, samples| {
+            analyzer.get_or_insert_with(|| LoopAnalyzer::new(sample_rate, channels)).add_samples(samples);
+            true
+        })?;
+        analyzer.map(LoopAnalyzer::finish).ok_or_else(|| "No audio could be decoded".to_string())
+    }
+
+    /// Find the dead air at either end of a file
+    pub fn detect_silence(file_path: &str) -> Result<SilenceAnalysis, String> {
+        let mut detector: Option<SilenceDetector> = None;
+        Self::decode_samples(file_path, |sample_rate, channels, samples| {
+            detector.get_or_insert_with(|| SilenceDetector::new(sample_rate, channels)).add_samples(samples);
+            true
+        })?;
+        detector.map(SilenceDetector::finish).ok_or_else(|| "No audio could be decoded".to_string())
+    }
+
+    /// Compute the acoustic fingerprint used to find the same audio in other files
+    pub fn calculate_fingerprint(file_path: &str) -> Result<Fingerprint, String> {
+        let mut fingerprinter: Option<Fingerprinter> = None;
+        Self::decode_samples(file_path, |sample_rate, channels, samples| {
+            fingerprinter.get_or_insert_with(|| Fingerprinter::new(sample_rate, channels)).add_samples(samples);
+            true
+        })?;
+        fingerprinter.map(Fingerprinter::finish).ok_or_else(|| "No audio could be decoded".to_string())
+    }
+
+    /// Hash the decoded audio, which identifies the sound whatever its path or tags
+    pub fn calculate_content_hash(file_path: &str) -> Result<String, String> {
+        let mut hasher: Option<ContentHasher> = None;
+        Self::decode_samples(file_path, |sample_rate, channels, samples| {
+            hasher.get_or_insert_with(|| ContentHasher::new(sample_rate, channels)).add_samples(samples);
+            true
+        })?;
+        hasher.map(ContentHasher::finish).ok_or_else(|| "No audio could be decoded".to_string())
+    }
+
+    /// Compute the features the offline sound classifier decides on
+    pub fn analyze_sound_features(file_path: &str) -> Result<SoundFeatures, String> {
+        let mut analyzer: Option<SoundAnalyzer> = None;
+        Self::decode_samples(file_path, |sample_rate, channels, samples| {
+            analyzer.get_or_insert_with(|| SoundAnalyzer::new(sample_rate, channels)).add_samples(samples)
+        })?;
+        analyzer.map(SoundAnalyzer::finish).ok_or_else(|| "No audio could be decoded".to_string())
+    }
+
+    /// Check that a file opens and its first audio decodes; the error says why it won't play
+    pub fn check_decodable(file_path: &str) -> Result<(), String> {
+        let mut decoded = false;
+        Self::decode_samples(file_path, |_, _, _| {
+            decoded = true;
+            false
+        })
+        .map_err(|e| {
+            let extension = std::path::Path::new(file_path).extension().and_then(|e| e.to_str()).unwrap_or_default();
+            if extension.eq_ignore_ascii_case("m4p") {
+                format!("DRM-protected iTunes audio ({})", e)
+            } else {
+                e
+            }
+        })?;
+        if decoded { Ok(()) } else { Err("No audio could be decoded".to_string()) }
+    }
+
+    /// Read codec and format of a file's audio stream from its headers, without decoding it
+    pub fn read_stream_properties(file_path: &str) -> Result<StreamProperties, String> {
+        let (file_size, _) = library_sync::disk_state(file_path)?;
+        Self::stream_properties(file_path, file_size)
+    }
+
+    /// Stream properties of a file whose size on disk is already known
+    fn stream_properties(file_path: &str, file_size: u64) -> Result<StreamProperties, String> {
+        let (_, track) = Self::open_audio_track(file_path)?;
+        let params = &track.codec_params;
+
+        let codec = symphonia::default::get_codecs().get_codec(params.codec).map(|codec| codec.short_name.to_string());
+        let channels = params.channels.map(|channels| channels.count() as u32);
+        let bitrate = match (codec.as_deref(), params.sample_rate, channels, params.bits_per_sample) {
+            // Uncompressed audio has an exact bitrate; elsewhere it's averaged over the whole file, tags included
+            (Some(codec), Some(rate), Some(channels), Some(bits)) if codec.starts_with("pcm") => {
+                Some(rate * channels * bits / 1000)
+            }
+            _ => params.n_frames.zip(params.sample_rate)
+                .map(|(frames, rate)| frames as f64 / rate as f64)
+                .filter(|&seconds| seconds > 0.0)
+                .map(|seconds| (file_size as f64 * 8.0 / seconds / 1000.0).round() as u32),
+        };
+
+        Ok(StreamProperties {
+            codec,
+            container: container_name(file_path),
+            sample_rate: params.sample_rate,
+            channels,
+            bit_depth: params.bits_per_sample,
+            bitrate,
+            file_size: Some(file_size),
+        })
+    }
+
+    /// Measure clipping, noise floor and bandwidth of a whole file and grade its technical quality
+    pub fn analyze_quality(file_path: &str) -> Result<QualityAnalysis, String> {
+        let bit_depth = Self::open_audio_track(file_path)?.1.codec_params.bits_per_sample;
+        let mut analyzer: Option<QualityAnalyzer> = None;
+        Self::decode_samples(file_path, |sample_rate, channels, samples| {
+            analyzer.get_or_insert_with(|| QualityAnalyzer::new(sample_rate, channels, bit_depth)).add_samples(samples);
+            true
+        })?;
+        analyzer.map(QualityAnalyzer::finish).ok_or_else(|| "No audio could be decoded".to_string())
     }
 
     /// Check if duration and BPM already exist in ID3 tags before calculating
     pub fn get_existing_duration_and_bpm(file_path: &str) -> Result<(Option<f64>, Option<f32>), String> {
+        let format = TagFormat::from_path(file_path);
+        if format != TagFormat::Id3 {
+            let mut audio_file = AudioFile { file_path: file_path.to_string(), ..Default::default() };
+            Self::load_tags(file_path, format, &mut audio_file);
+            return Ok((audio_file.duration, audio_file.bpm.map(|b| b as f32)));
+        }
+
         if let Ok(tag) = Tag::read_from_path(file_path) {
             let mut duration = tag.duration().map(|d| d as f64);
             
@@ -690,7 +981,7 @@ impl AudioHandler {
         }
     }
 
-    pub fn calculate_duration_and_bpm(file_path: &str) -> Result<(Option<f64>, Option<f32>), String> {
+    pub fn calculate_duration_and_bpm(file_path: &str) -> Result<(Option<f64>, Option<TempoEstimate>), String> {
         let duration = match Self::calculate_audio_duration(file_path) {
             Ok(d) => Some(d),
             Err(e) => {
@@ -713,4 +1004,29 @@ impl AudioHandler {
             Ok((duration, bpm))
         }
     }
-}
\ No newline at end of file
+}
+
+/// Container format of a file, named after its extension family
+fn container_name(file_path: &str) -> Option<String> {
+    let extension = std::path::Path::new(file_path).extension()?.to_str()?.to_lowercase();
+    let container = match extension.as_str() {
+        "wav" | "wave" => "wav",
+        "aif" | "aiff" | "aifc" => "aiff",
+        "m4a" | "m4b" | "m4p" | "mp4" => "mp4",
+        "ogg" | "oga" | "opus" => "ogg",
+        "mka" | "mkv" | "webm" => "matroska",
+        other => other,
+    };
+    Some(container.to_string())
+}
+
+/// Set or remove a text frame according to a patch
+fn patch_text_frame(tag: &mut Tag, id: &str, value: &FieldPatch<String>) {
+    match value {
+        FieldPatch::Set(value) => tag.set_text(id, value.clone()),
+        FieldPatch::Clear => {
+            tag.remove(id);
+        }
+        FieldPatch::Leave => {}
+    }
+}
@@ -1,210 +1,636 @@
-use tauri::{AppHandle, Manager};
-use crate::models::AudioFile;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+use rusqlite::Connection;
+use crate::database::{AudioFileOps, DuplicateOps};
+use crate::models::{AnalysisProgress, AudioFile, DuplicateFile, DuplicateGroup, DurationProgress, LoopableProposal, QualityProposal, TempoEstimate};
+use crate::fingerprint;
+use crate::loop_analysis::LOOPABLE_KEYWORD;
+use crate::quality::GRADE_LOW;
 use crate::{AppState, AudioHandler};
 
+/// Files decoded at the same time by an analysis job
+const MAX_ANALYSIS_WORKERS: usize = 4;
+/// Results written per transaction, and how often progress is reported
+const ANALYSIS_WRITE_BATCH: usize = 25;
+
+/// Run state of a long analysis job, shared between the job and the command cancelling it
+#[derive(Default)]
+pub struct AnalysisJob {
+    running: AtomicBool,
+    cancelled: AtomicBool,
+}
+
+impl AnalysisJob {
+    /// Mark the job as running until the returned guard is dropped; fails when it already runs
+    fn start(&self) -> Result<AnalysisJobRun<'_>, String> {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return Err("This analysis is already running".to_string());
+        }
+        self.cancelled.store(false, Ordering::SeqCst);
+        Ok(AnalysisJobRun(self))
+    }
+
+    /// Ask the running job to stop; returns whether one was running
+    pub fn cancel(&self) -> bool {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.running.load(Ordering::SeqCst)
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+struct AnalysisJobRun<'a>(&'a AnalysisJob);
+
+impl Drop for AnalysisJobRun<'_> {
+    fn drop(&mut self) {
+        self.0.running.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Duration and BPM found for a file that was missing them
+struct MeasuredFile {
+    id: i64,
+    file_path: String,
+    duration: Option<f64>,
+    /// BPM from the file's tags
+    bpm: Option<u32>,
+    /// Tempo detected in the audio where the tags have none
+    tempo: Option<TempoEstimate>,
+}
+
+/// Outcome of a batch analysis run by `run_analysis`
+struct AnalysisRun {
+    total_files: usize,
+    processed_files: usize,
+    cancelled: bool,
+}
+
+impl AnalysisRun {
+    /// The summary of the run, noting how far it got when it was cancelled
+    fn summary(&self, message: String) -> String {
+        if self.cancelled {
+            format!("Cancelled after {} of {} files. {}", self.processed_files, self.total_files, message)
+        } else {
+            message
+        }
+    }
+}
+
 /// Handler for audio processing operations (BPM, duration calculations)
 pub struct AudioProcessingHandler;
 
 impl AudioProcessingHandler {
 
-    /// Calculate missing durations and BPMs for all audio files
+    /// Calculate missing durations and BPMs for all audio files on a pool of worker threads, writing
+    /// results in batches through the connection pool; stops early when the job is cancelled
     pub fn calculate_missing_durations(app_handle: AppHandle) -> Result<String, String> {
         let state = app_handle.state::<AppState>();
-        let db = state.db.lock().unwrap();
-        
+        let job = &state.duration_job;
+        let _run = job.start()?;
+
         log::info!("Starting calculation of missing durations and BPMs");
-        
-        // Get all audio files and filter those missing duration or BPM
-        let audio_files = db.get_all_audio_files().map_err(|e| e.to_string())?;
-        let files_to_process: Vec<_> = audio_files
-            .into_iter()
-            .filter(|file| file.duration.is_none() || file.bpm.is_none())
-            .collect();
-        
-        let mut duration_updated = 0u32;
-        let mut bpm_updated = 0u32;
+
+        let files_to_process: Vec<AudioFile> = {
+            let conn = state.db_pool.get_connection().map_err(|e| e.to_string())?;
+            AudioFileOps::get_all(&conn).map_err(|e| e.to_string())?
+                .into_iter()
+                .filter(|file| file.id.is_some() && (file.duration.is_none() || Self::needs_bpm(file)))
+                .collect()
+        };
         let total_files = files_to_process.len();
-        
         log::info!("Found {} files needing processing", total_files);
-        
-        for (index, audio_file) in files_to_process.iter().enumerate() {
-            log::info!("Processing file {} of {}: {}", index + 1, total_files, audio_file.file_path);
-            
-            // Check what needs to be calculated
-            let needs_duration = audio_file.duration.is_none();
-            let needs_bpm = audio_file.bpm.is_none();
-            
-            if needs_duration && needs_bpm {
-                let (duration_count, bpm_count) = Self::calculate_both_duration_and_bpm(&db, &audio_file)?;
-                duration_updated += duration_count;
-                bpm_updated += bpm_count;
-            } else if needs_duration {
-                duration_updated += Self::calculate_duration_only(&db, &audio_file)?;
-            } else if needs_bpm {
-                bpm_updated += Self::calculate_bpm_only(&db, &audio_file)?;
-            }
+
+        let mut progress = DurationProgress { total_files, ..Default::default() };
+        Self::run_on_workers(job, files_to_process, Self::measure_duration_and_bpm, |batch| {
+            Self::write_duration_batch(&app_handle, batch, &mut progress);
+        });
+
+        let cancelled = job.is_cancelled() && progress.processed_files < total_files;
+        progress.status = if cancelled { "Cancelled" } else { "Complete" }.to_string();
+        let _ = app_handle.emit("duration-progress", &progress);
+
+        log::info!("Processing {}, processed: {}, duration_updated: {}, bpm_updated: {}, non_rhythmic: {}, failed: {}",
+                  if cancelled { "cancelled" } else { "completed" },
+                  progress.processed_files, progress.duration_updated, progress.bpm_updated,
+                  progress.non_rhythmic, progress.failed_files);
+
+        let summary = Self::create_summary_message(progress.duration_updated, progress.bpm_updated, progress.non_rhythmic)?;
+        if cancelled {
+            Ok(format!("Cancelled after {} of {} files. {}", progress.processed_files, total_files, summary))
+        } else {
+            Ok(summary)
         }
-        
-        log::info!("Processing completed, duration_updated: {}, bpm_updated: {}", duration_updated, bpm_updated);
-        
-        // Return a summary message
-        Self::create_summary_message(duration_updated, bpm_updated)
     }
 
-    // Helper methods
+    /// Ask a running duration/BPM job to stop; files being decoded are finished and saved.
+    /// Returns whether a job was running
+    pub fn cancel_duration_calculation(app_handle: AppHandle) -> bool {
+        let state = app_handle.state::<AppState>();
+        state.duration_job.cancel()
+    }
 
-    /// Calculate both duration and BPM for a file
-    fn calculate_both_duration_and_bpm(db: &crate::database::Database, audio_file: &AudioFile) -> Result<(u32, u32), String> {
-        // First, check if the values already exist in ID3 tags
-        log::info!("Checking existing ID3 tags for duration and BPM for {}", audio_file.file_path);
-        let (existing_duration, existing_bpm) = AudioHandler::get_existing_duration_and_bpm(&audio_file.file_path)?;
-        
-        // Decide what needs to be calculated
-        let needs_duration = existing_duration.is_none();
-        let needs_bpm = existing_bpm.is_none();
-        
-        if !needs_duration && !needs_bpm {
-            // Both values exist in ID3 tags, just update the database
-            log::info!("Found existing duration ({:.2}s) and BPM ({}) in ID3 tags for {}", 
-                      existing_duration.unwrap(), existing_bpm.unwrap(), audio_file.file_path);
-                      
-            if let Some(id) = audio_file.id {
-                let bpm_u32 = Some(existing_bpm.unwrap().round() as u32);
-                if let Err(e) = db.update_audio_file_duration_and_bpm(id, existing_duration, bpm_u32) {
-                    log::error!("Failed to update existing values for {}: {}", audio_file.file_path, e);
-                    return Ok((0, 0));
+    /// Ask the running batch analysis to stop; files being decoded are finished and saved.
+    /// Returns whether one was running
+    pub fn cancel_analysis(app_handle: AppHandle) -> bool {
+        let state = app_handle.state::<AppState>();
+        state.analysis_job.cancel()
+    }
+
+    /// Measure EBU R128 loudness of every audio file that hasn't been analyzed yet
+    pub fn analyze_missing_loudness(app_handle: AppHandle) -> Result<String, String> {
+        let files = Self::files_where(&app_handle, |file| file.loudness_integrated.is_none())?;
+
+        let mut analyzed = 0u32;
+        let run = Self::run_analysis(&app_handle, "loudness analysis", files,
+            AudioHandler::calculate_loudness,
+            AudioFileOps::update_loudness,
+            |_, _| analyzed += 1)?;
+
+        Ok(run.summary(format!("Analyzed loudness of {} files ({} failed)", analyzed, run.processed_files - analyzed as usize)))
+    }
+
+    /// Check the loop seam of every audio file that hasn't been analyzed yet
+    pub fn analyze_missing_loops(app_handle: AppHandle) -> Result<String, String> {
+        let files = Self::files_where(&app_handle, |file| file.loop_seamless.is_none())?;
+
+        let mut seamless = 0u32;
+        let mut analyzed = 0u32;
+        let run = Self::run_analysis(&app_handle, "loop analysis", files,
+            AudioHandler::analyze_loop,
+            AudioFileOps::update_loop_analysis,
+            |file_path, analysis| {
+                analyzed += 1;
+                if analysis.issues.is_empty() {
+                    seamless += 1;
+                } else {
+                    log::info!("{} doesn't loop cleanly: {}", file_path, analysis.issues.join("; "));
+                }
+            })?;
+
+        Ok(run.summary(format!("Analyzed loops of {} files, {} loop cleanly ({} failed)", analyzed, seamless, run.processed_files - analyzed as usize)))
+    }
+
+    /// Estimate the key of every file without one that hasn't been through key detection yet
+    pub fn detect_missing_keys(app_handle: AppHandle) -> Result<String, String> {
+        let files = Self::files_where(&app_handle, |file| file.initial_key.is_none() && file.key_confidence.is_none())?;
+
+        let mut detected = 0u32;
+        let mut non_tonal = 0u32;
+        let run = Self::run_analysis(&app_handle, "key detection", files,
+            AudioHandler::detect_key,
+            AudioFileOps::update_key,
+            |file_path, estimate| match &estimate.key {
+                Some(key) => {
+                    detected += 1;
+                    log::info!("Detected key {} for {} (confidence {:.2})", key, file_path, estimate.confidence);
+                }
+                None => non_tonal += 1,
+            })?;
+
+        let failed = run.processed_files - (detected + non_tonal) as usize;
+        Ok(run.summary(format!("Detected the key of {} files, {} are non-tonal ({} failed)", detected, non_tonal, failed)))
+    }
+
+    /// Find leading/trailing silence of every audio file that hasn't been checked yet
+    pub fn analyze_missing_silence(app_handle: AppHandle) -> Result<String, String> {
+        let files = Self::files_where(&app_handle, |file| file.mostly_silent.is_none())?;
+
+        let mut trimmed = 0u32;
+        let mut mostly_silent = 0u32;
+        let mut analyzed = 0u32;
+        let run = Self::run_analysis(&app_handle, "silence detection", files,
+            AudioHandler::detect_silence,
+            AudioFileOps::update_silence,
+            |_, silence| {
+                analyzed += 1;
+                if silence.mostly_silent {
+                    mostly_silent += 1;
+                } else if silence.leading_silence > 0.0 {
+                    trimmed += 1;
+                }
+            })?;
+
+        let failed = run.processed_files - analyzed as usize;
+        Ok(run.summary(format!("Checked {} files for silence, {} start late and {} are mostly silent ({} failed)", analyzed, trimmed, mostly_silent, failed)))
+    }
+
+    /// Read the stream properties of every audio file saved before they were recorded
+    pub fn read_missing_stream_properties(app_handle: AppHandle) -> Result<String, String> {
+        // The size is known for every file whose headers were read, even with an unknown codec
+        let files = Self::files_where(&app_handle, |file| file.file_size.is_none())?;
+
+        let mut updated = 0u32;
+        let run = Self::run_analysis(&app_handle, "stream properties", files,
+            AudioHandler::read_stream_properties,
+            AudioFileOps::update_stream_properties,
+            |_, _| updated += 1)?;
+
+        Ok(run.summary(format!("Read the stream properties of {} files ({} failed)", updated, run.processed_files - updated as usize)))
+    }
+
+    /// Check whether every audio file that hasn't been probed yet decodes
+    pub fn probe_missing_decodability(app_handle: AppHandle) -> Result<String, String> {
+        let files = Self::files_where(&app_handle, |file| file.decodable.is_none())?;
+
+        let mut probed = 0u32;
+        let mut unplayable = 0u32;
+        // A file that won't decode is a result of the probe, not a failure
+        let run = Self::run_analysis(&app_handle, "decoder probe", files,
+            |file_path| Ok(AudioHandler::check_decodable(file_path).err()),
+            |conn, id, decode_error: &Option<String>| AudioFileOps::update_decodability(conn, id, decode_error.as_deref()),
+            |file_path, decode_error| {
+                probed += 1;
+                if let Some(e) = decode_error {
+                    unplayable += 1;
+                    log::warn!("{} can't be decoded: {}", file_path, e);
+                }
+            })?;
+
+        let failed = run.processed_files - probed as usize;
+        Ok(run.summary(format!("Probed {} files, {} can't be decoded ({} failed)", probed, unplayable, failed)))
+    }
+
+    /// Measure and grade the technical quality of every audio file that hasn't been assessed yet
+    pub fn assess_missing_quality(app_handle: AppHandle) -> Result<String, String> {
+        let files = Self::files_where(&app_handle, |file| file.quality_grade.is_none())?;
+
+        let mut assessed = 0u32;
+        let mut low = 0u32;
+        let run = Self::run_analysis(&app_handle, "quality assessment", files,
+            AudioHandler::analyze_quality,
+            AudioFileOps::update_quality,
+            |file_path, analysis| {
+                assessed += 1;
+                if analysis.grade == GRADE_LOW {
+                    low += 1;
+                    log::info!("{} is low quality: {:?}", file_path, analysis);
+                }
+            })?;
+
+        let failed = run.processed_files - assessed as usize;
+        Ok(run.summary(format!("Assessed the quality of {} files, {} are low quality ({} failed)", assessed, low, failed)))
+    }
+
+    /// Hash the decoded audio of every file that has no content hash yet
+    pub fn hash_missing_files(app_handle: AppHandle) -> Result<String, String> {
+        let files = Self::files_where(&app_handle, |file| file.content_hash.is_none())?;
+
+        let mut hashed = 0u32;
+        let run = Self::run_analysis(&app_handle, "content hashing", files,
+            AudioHandler::calculate_content_hash,
+            |conn, id, content_hash: &String| AudioFileOps::update_content_hash(conn, id, content_hash),
+            |_, _| hashed += 1)?;
+
+        Ok(run.summary(format!("Hashed {} files ({} failed)", hashed, run.processed_files - hashed as usize)))
+    }
+
+    /// Fingerprint every audio file that doesn't have one yet
+    pub fn fingerprint_missing_files(app_handle: AppHandle) -> Result<String, String> {
+        let files = {
+            let state = app_handle.state::<AppState>();
+            let conn = state.db_pool.get_connection().map_err(|e| e.to_string())?;
+            DuplicateOps::get_unfingerprinted(&conn).map_err(|e| e.to_string())?
+        };
+
+        let mut fingerprinted = 0u32;
+        let run = Self::run_analysis(&app_handle, "fingerprinting", files,
+            AudioHandler::calculate_fingerprint,
+            DuplicateOps::save_fingerprint,
+            |_, _| fingerprinted += 1)?;
+
+        Ok(run.summary(format!("Fingerprinted {} files ({} failed)", fingerprinted, run.processed_files - fingerprinted as usize)))
+    }
+
+    /// Groups of fingerprinted files holding the same audio, each with a suggested copy to keep
+    pub fn find_duplicates(app_handle: AppHandle) -> Result<Vec<DuplicateGroup>, String> {
+        let state = app_handle.state::<AppState>();
+        let db = state.db.lock().unwrap();
+
+        let stored = db.get_audio_fingerprints().map_err(|e| e.to_string())?;
+        let fingerprints: Vec<_> = stored.iter().map(|s| &s.fingerprint).collect();
+        let mut groups: Vec<DuplicateGroup> = fingerprint::group_duplicates(&fingerprints)
+            .into_iter()
+            .filter_map(|(members, similarity)| {
+                let mut files: Vec<DuplicateFile> = members.iter()
+                    .map(|&i| DuplicateFile {
+                        audio_file_id: stored[i].audio_file_id,
+                        file_path: stored[i].file_path.clone(),
+                        duration: stored[i].fingerprint.duration,
+                        file_size: stored[i].file_size,
+                        tag_count: stored[i].tag_count,
+                    })
+                    .collect();
+                files.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+                let canonical_id = fingerprint::suggest_canonical(&files)?;
+                Some(DuplicateGroup { canonical_id, similarity, files })
+            })
+            .collect();
+        groups.sort_by(|a, b| a.files[0].file_path.cmp(&b.files[0].file_path));
+
+        log::info!("Found {} groups of duplicates among {} fingerprinted files", groups.len(), stored.len());
+        Ok(groups)
+    }
+
+    /// Merge duplicates onto the row chosen to keep; the files on disk are left alone
+    pub fn merge_duplicates(app_handle: AppHandle, canonical_id: i64, duplicate_ids: Vec<i64>) -> Result<usize, String> {
+        let state = app_handle.state::<AppState>();
+        let db = state.db.lock().unwrap();
+
+        db.merge_duplicates(canonical_id, &duplicate_ids).map_err(|e| {
+            log::error!("Failed to merge duplicates {:?} into {}: {}", duplicate_ids, canonical_id, e);
+            e.to_string()
+        })?;
+        let merged = duplicate_ids.iter().filter(|&&id| id != canonical_id).count();
+        log::info!("Merged {} duplicates into audio file {}", merged, canonical_id);
+        Ok(merged)
+    }
+
+    /// Cleanly looping files that could be tagged `util:loopable`
+    pub fn get_loopable_proposals(app_handle: AppHandle) -> Result<Vec<LoopableProposal>, String> {
+        let state = app_handle.state::<AppState>();
+        let db = state.db.lock().unwrap();
+        db.get_loopable_proposals(LOOPABLE_KEYWORD).map_err(|e| e.to_string())
+    }
+
+    /// Tag the accepted proposals with `util:loopable`
+    pub fn apply_loopable_proposals(app_handle: AppHandle, audio_file_ids: Vec<i64>) -> Result<usize, String> {
+        let state = app_handle.state::<AppState>();
+        let db = state.db.lock().unwrap();
+
+        for id in &audio_file_ids {
+            db.add_rpg_tag(*id, "keyword", LOOPABLE_KEYWORD).map_err(|e| e.to_string())?;
+        }
+        log::info!("Tagged {} files as {}", audio_file_ids.len(), LOOPABLE_KEYWORD);
+        Ok(audio_file_ids.len())
+    }
+
+    /// Graded files whose `quality` tag is missing or disagrees with the measurements
+    pub fn get_quality_proposals(app_handle: AppHandle) -> Result<Vec<QualityProposal>, String> {
+        let state = app_handle.state::<AppState>();
+        let db = state.db.lock().unwrap();
+        db.get_quality_proposals().map_err(|e| e.to_string())
+    }
+
+    /// Replace the `quality` tag of the accepted proposals with their measured grade
+    pub fn apply_quality_proposals(app_handle: AppHandle, audio_file_ids: Vec<i64>) -> Result<usize, String> {
+        let state = app_handle.state::<AppState>();
+        let db = state.db.lock().unwrap();
+
+        let mut tagged = 0;
+        for id in &audio_file_ids {
+            let audio_file = db.get_audio_file_by_id(*id).map_err(|e| e.to_string())?;
+            let Some(grade) = audio_file.quality_grade else { continue };
+            for tag in db.get_rpg_tags_for_file(*id).map_err(|e| e.to_string())? {
+                if tag.tag_type == "quality" {
+                    db.remove_rpg_tag(*id, "quality", &tag.tag_value).map_err(|e| e.to_string())?;
                 }
-                log::info!("Updated database with existing duration and BPM for {}", audio_file.file_path);
-                return Ok((1, 1)); // Both values updated
-            } else {
-                log::error!("Audio file has no ID: {}", audio_file.file_path);
-                return Ok((0, 0));
             }
+            db.add_rpg_tag(*id, "quality", &grade).map_err(|e| e.to_string())?;
+            tagged += 1;
         }
-        
-        // Some values need calculation
-        log::info!("Some values missing from ID3 tags (duration: {}, BPM: {}), performing audio analysis for {}", 
-                  if needs_duration { "missing" } else { "found" },
-                  if needs_bpm { "missing" } else { "found" },
-                  audio_file.file_path);
-                  
-        match AudioHandler::calculate_duration_and_bpm(&audio_file.file_path) {
-            Ok((calculated_duration, calculated_bpm)) => {
-                // Use existing values where available, calculated where not
-                let final_duration = if needs_duration { calculated_duration } else { existing_duration };
-                let final_bpm = if needs_bpm { calculated_bpm } else { existing_bpm };
-                if let Some(id) = audio_file.id {
-                    let bpm_u32 = final_bpm.map(|b| b.round() as u32);
-                    if let Err(e) = db.update_audio_file_duration_and_bpm(id, final_duration, bpm_u32) {
-                        log::error!("Failed to update duration and BPM together for {}: {}", audio_file.file_path, e);
-                        log::info!("Attempting to update duration and BPM separately for {}", audio_file.file_path);
-                        
-                        // Try to update them separately as fallback
-                        let mut duration_count = 0;
-                        let mut bpm_count = 0;
-                        
-                        if let Some(dur) = final_duration {
-                            if let Err(e2) = db.update_audio_file_duration(id, dur) {
-                                log::error!("Failed to update duration separately for {}: {}", audio_file.file_path, e2);
-                            } else {
-                                duration_count = 1;
-                                log::info!("Updated duration separately for {}: {:.2}s", audio_file.file_path, dur);
-                            }
-                        }
-                        
-                        if let Some(b) = bpm_u32 {
-                            if let Err(e3) = db.update_audio_file_bpm(id, b) {
-                                log::error!("Failed to update BPM separately for {}: {}", audio_file.file_path, e3);
-                            } else {
-                                bpm_count = 1;
-                                log::info!("Updated BPM separately for {}: {}", audio_file.file_path, b);
-                            }
-                        }
-                        
-                        return Ok((duration_count, bpm_count));
+        log::info!("Tagged the quality of {} files", tagged);
+        Ok(tagged)
+    }
+
+    // Helper methods
+
+    /// Ids and paths of the files matching `filter`
+    fn files_where(app_handle: &AppHandle, filter: impl Fn(&AudioFile) -> bool) -> Result<Vec<(i64, String)>, String> {
+        let state = app_handle.state::<AppState>();
+        let conn = state.db_pool.get_connection().map_err(|e| e.to_string())?;
+        Ok(AudioFileOps::get_all(&conn).map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|file| filter(file))
+            .filter_map(|file| Some((file.id?, file.file_path)))
+            .collect())
+    }
+
+    /// Run one analysis over `files` as the shared analysis job: `measure` decodes on the worker
+    /// threads, each batch of results is written by `store` in one transaction through the pool,
+    /// and `on_stored` sees every result that was written. Progress goes out as `analysis-progress`
+    fn run_analysis<R, M, S, T>(app_handle: &AppHandle, analysis: &str, files: Vec<(i64, String)>, measure: M, store: S, mut on_stored: T) -> Result<AnalysisRun, String>
+    where
+        R: Send,
+        M: Fn(&str) -> Result<R, String> + Sync,
+        S: Fn(&Connection, i64, &R) -> rusqlite::Result<()>,
+        T: FnMut(&str, &R),
+    {
+        let state = app_handle.state::<AppState>();
+        let job = &state.analysis_job;
+        let _run = job.start()?;
+
+        let total_files = files.len();
+        log::info!("Starting {} of {} files", analysis, total_files);
+        let mut progress = AnalysisProgress { analysis: analysis.to_string(), total_files, ..Default::default() };
+
+        let measure_file = |(id, file_path): &(i64, String)| (*id, file_path.clone(), measure(file_path));
+        Self::run_on_workers(job, files, measure_file, |batch| {
+            let measured: Vec<_> = batch.iter()
+                .filter_map(|(id, file_path, result)| match result {
+                    Ok(result) => Some((*id, file_path, result)),
+                    Err(e) => {
+                        log::error!("{} failed for {}: {}", analysis, file_path, e);
+                        None
                     }
-                    
-                    let mut duration_count = 0;
-                    let mut bpm_count = 0;
-                    
-                    if final_duration.is_some() {
-                        duration_count = 1;
-                        log::info!("Updated duration for {}: {:.2}s", audio_file.file_path, final_duration.unwrap());
+                })
+                .collect();
+            progress.failed_files += batch.len() - measured.len();
+
+            let written = state.db_pool.get_connection()
+                .map_err(|e| e.to_string())
+                .and_then(|mut conn| {
+                    let tx = conn.transaction().map_err(|e| e.to_string())?;
+                    for (id, _, result) in &measured {
+                        store(&tx, *id, result).map_err(|e| e.to_string())?;
                     }
-                    if final_bpm.is_some() {
-                        bpm_count = 1;
-                        log::info!("Updated BPM for {}: {:.1}", audio_file.file_path, final_bpm.unwrap());
+                    tx.commit().map_err(|e| e.to_string())
+                });
+            match written {
+                Ok(()) => {
+                    for (_, file_path, result) in &measured {
+                        on_stored(file_path, result);
                     }
-                    
-                    Ok((duration_count, bpm_count))
-                } else {
-                    log::error!("Audio file has no ID: {}", audio_file.file_path);
-                    Ok((0, 0))
+                }
+                Err(e) => {
+                    log::error!("Failed to store {} results of {} files: {}", analysis, measured.len(), e);
+                    progress.failed_files += measured.len();
                 }
             }
-            Err(e) => {
-                // If the combined calculation failed entirely, try to get at least duration
-                log::warn!("Combined duration and BPM calculation failed for {}: {}", audio_file.file_path, e);
-                log::info!("Attempting to calculate duration only for {}", audio_file.file_path);
-                
-                // Fall back to duration-only calculation
-                let duration_count = Self::calculate_duration_only(db, audio_file)?;
-                Ok((duration_count, 0)) // 0 BPM updates since it failed
+
+            progress.processed_files += batch.len();
+            progress.status = format!("Processed {} of {} files", progress.processed_files, progress.total_files);
+            let _ = app_handle.emit("analysis-progress", &progress);
+        });
+
+        let cancelled = job.is_cancelled() && progress.processed_files < total_files;
+        progress.status = if cancelled { "Cancelled" } else { "Complete" }.to_string();
+        let _ = app_handle.emit("analysis-progress", &progress);
+
+        log::info!("{} {}, processed: {}, failed: {}", analysis, if cancelled { "cancelled" } else { "completed" },
+                  progress.processed_files, progress.failed_files);
+        Ok(AnalysisRun { total_files, processed_files: progress.processed_files, cancelled })
+    }
+
+    /// Measure `items` on a pool of worker threads, handing the results to `write_batch` on this
+    /// thread in batches; workers stop taking new items once `job` is cancelled
+    fn run_on_workers<I, R>(job: &AnalysisJob, items: Vec<I>, measure: impl Fn(&I) -> R + Sync, mut write_batch: impl FnMut(&[R]))
+    where
+        I: Send,
+        R: Send,
+    {
+        let workers = std::thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(MAX_ANALYSIS_WORKERS)
+            .min(items.len().max(1));
+        log::info!("Processing {} files on {} workers", items.len(), workers);
+
+        let queue = Mutex::new(items.into_iter());
+        let (sender, receiver) = mpsc::channel::<R>();
+        let measure = &measure;
+
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                let sender = sender.clone();
+                let queue = &queue;
+                scope.spawn(move || {
+                    while !job.is_cancelled() {
+                        let Some(item) = queue.lock().unwrap().next() else { break };
+                        if sender.send(measure(&item)).is_err() {
+                            break;
+                        }
+                    }
+                });
             }
-        }
+            drop(sender);
+
+            // Workers hang up once the queue is empty or the job is cancelled
+            let mut batch = Vec::with_capacity(ANALYSIS_WRITE_BATCH);
+            for result in receiver {
+                batch.push(result);
+                if batch.len() == ANALYSIS_WRITE_BATCH {
+                    write_batch(&batch);
+                    batch.clear();
+                }
+            }
+            if !batch.is_empty() {
+                write_batch(&batch);
+            }
+        });
+    }
+
+    /// Files found to have no steady beat keep their missing BPM
+    fn needs_bpm(audio_file: &AudioFile) -> bool {
+        audio_file.bpm.is_none() && audio_file.bpm_confidence.is_none()
     }
 
-    /// Calculate only duration for a file
-    fn calculate_duration_only(db: &crate::database::Database, audio_file: &AudioFile) -> Result<u32, String> {
-        match AudioHandler::calculate_audio_duration(&audio_file.file_path) {
-            Ok(duration) => {
-                if let Some(id) = audio_file.id {
-                    if let Err(e) = db.update_audio_file_duration(id, duration) {
-                        log::error!("Failed to update duration for {}: {}", audio_file.file_path, e);
-                        return Ok(0);
+    /// Find the missing duration and/or BPM of a file, from its tags where they have them
+    fn measure_duration_and_bpm(audio_file: &AudioFile) -> MeasuredFile {
+        let needs_duration = audio_file.duration.is_none();
+        let needs_bpm = Self::needs_bpm(audio_file);
+        let mut measured = MeasuredFile {
+            id: audio_file.id.unwrap_or_default(),
+            file_path: audio_file.file_path.clone(),
+            duration: None,
+            bpm: None,
+            tempo: None,
+        };
+
+        if needs_duration && needs_bpm {
+            let (existing_duration, existing_bpm) = match AudioHandler::get_existing_duration_and_bpm(&audio_file.file_path) {
+                Ok(existing) => existing,
+                Err(e) => {
+                    log::warn!("Failed to read existing duration and BPM of {}: {}", audio_file.file_path, e);
+                    (None, None)
+                }
+            };
+            if existing_duration.is_some() && existing_bpm.is_some() {
+                log::info!("Found existing duration and BPM in tags for {}", audio_file.file_path);
+                measured.duration = existing_duration;
+                measured.bpm = existing_bpm.map(|b| b.round() as u32);
+                return measured;
+            }
+
+            match AudioHandler::calculate_duration_and_bpm(&audio_file.file_path) {
+                Ok((calculated_duration, calculated_tempo)) => {
+                    // Use existing values where available, calculated where not
+                    measured.duration = existing_duration.or(calculated_duration);
+                    measured.bpm = existing_bpm.map(|b| b.round() as u32);
+                    if measured.bpm.is_none() {
+                        measured.tempo = calculated_tempo;
                     }
-                    
-                    log::info!("Updated duration for {}: {:.2}s", audio_file.file_path, duration);
-                    Ok(1)
-                } else {
-                    log::error!("Audio file has no ID: {}", audio_file.file_path);
-                    Ok(0)
+                }
+                Err(e) => {
+                    // If the combined calculation failed entirely, try to get at least duration
+                    log::warn!("Combined duration and BPM calculation failed for {}: {}", audio_file.file_path, e);
+                    measured.duration = existing_duration.or_else(|| Self::calculate_duration(&audio_file.file_path));
                 }
             }
-            Err(e) => {
-                log::error!("Failed to calculate duration for {}: {}", audio_file.file_path, e);
-                Ok(0)
+        } else if needs_duration {
+            measured.duration = Self::calculate_duration(&audio_file.file_path);
+        } else if needs_bpm {
+            match AudioHandler::calculate_audio_bpm(&audio_file.file_path) {
+                Ok(tempo) => measured.tempo = Some(tempo),
+                Err(e) => log::error!("Failed to calculate BPM for {}: {}", audio_file.file_path, e),
             }
         }
+        measured
     }
 
-    /// Calculate only BPM for a file
-    fn calculate_bpm_only(db: &crate::database::Database, audio_file: &AudioFile) -> Result<u32, String> {
-        match AudioHandler::calculate_audio_bpm(&audio_file.file_path) {
-            Ok(bpm) => {
-                if let Some(id) = audio_file.id {
-                    let bpm_u32 = bpm.round() as u32;
-                    if let Err(e) = db.update_audio_file_bpm(id, bpm_u32) {
-                        log::error!("Failed to update BPM for {}: {}", audio_file.file_path, e);
-                        return Ok(0);
+    fn calculate_duration(file_path: &str) -> Option<f64> {
+        AudioHandler::calculate_audio_duration(file_path)
+            .map_err(|e| log::error!("Failed to calculate duration for {}: {}", file_path, e))
+            .ok()
+    }
+
+    /// Store a batch of measured files in one transaction, then report progress
+    fn write_duration_batch(app_handle: &AppHandle, batch: &[MeasuredFile], progress: &mut DurationProgress) {
+        let state = app_handle.state::<AppState>();
+        let written = state.db_pool.get_connection()
+            .map_err(|e| e.to_string())
+            .and_then(|mut conn| {
+                let tx = conn.transaction().map_err(|e| e.to_string())?;
+                for measured in batch.iter() {
+                    if let Some(duration) = measured.duration {
+                        AudioFileOps::update_duration(&tx, measured.id, duration).map_err(|e| e.to_string())?;
+                    }
+                    if let Some(bpm) = measured.bpm {
+                        AudioFileOps::update_bpm(&tx, measured.id, bpm).map_err(|e| e.to_string())?;
+                    }
+                    if let Some(tempo) = &measured.tempo {
+                        AudioFileOps::update_tempo(&tx, measured.id, tempo).map_err(|e| e.to_string())?;
+                    }
+                }
+                tx.commit().map_err(|e| e.to_string())
+            });
+
+        match written {
+            Ok(()) => {
+                for measured in batch.iter() {
+                    let detected_bpm = measured.tempo.as_ref().is_some_and(|tempo| tempo.bpm.is_some());
+                    progress.duration_updated += measured.duration.is_some() as usize;
+                    progress.bpm_updated += (measured.bpm.is_some() || detected_bpm) as usize;
+                    progress.non_rhythmic += (measured.tempo.is_some() && !detected_bpm) as usize;
+                    if measured.duration.is_none() && measured.bpm.is_none() && measured.tempo.is_none() {
+                        progress.failed_files += 1;
+                        log::warn!("Found neither duration nor BPM for {}", measured.file_path);
                     }
-                    
-                    log::info!("Updated BPM for {}: {:.1}", audio_file.file_path, bpm);
-                    Ok(1)
-                } else {
-                    log::error!("Audio file has no ID: {}", audio_file.file_path);
-                    Ok(0)
                 }
             }
             Err(e) => {
-                log::error!("Failed to calculate BPM for {}: {}", audio_file.file_path, e);
-                Ok(0)
+                log::error!("Failed to store durations and BPMs of {} files: {}", batch.len(), e);
+                progress.failed_files += batch.len();
             }
         }
+        progress.processed_files += batch.len();
+        progress.status = format!("Processed {} of {} files", progress.processed_files, progress.total_files);
+        let _ = app_handle.emit("duration-progress", &*progress);
     }
 
     /// Create summary message based on updated counts
-    fn create_summary_message(duration_updated: u32, bpm_updated: u32) -> Result<String, String> {
+    fn create_summary_message(duration_updated: usize, bpm_updated: usize, non_rhythmic: usize) -> Result<String, String> {
         let mut summary_parts = Vec::new();
         if duration_updated > 0 {
             summary_parts.push(format!("{} durations", duration_updated));
@@ -213,10 +639,16 @@ impl AudioProcessingHandler {
             summary_parts.push(format!("{} BPMs", bpm_updated));
         }
         
+        let non_rhythmic_note = if non_rhythmic > 0 {
+            format!(" {} files have no steady beat.", non_rhythmic)
+        } else {
+            String::new()
+        };
+
         if summary_parts.is_empty() {
-            Ok("All files already have complete duration and BPM information.".to_string())
+            Ok(format!("All files already have complete duration and BPM information.{}", non_rhythmic_note))
         } else {
-            Ok(format!("Successfully calculated and updated {}.", summary_parts.join(" and ")))
+            Ok(format!("Successfully calculated and updated {}.{}", summary_parts.join(" and "), non_rhythmic_note))
         }
     }
 }
\ No newline at end of file
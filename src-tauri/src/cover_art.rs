@@ -0,0 +1,114 @@
+use crate::models::CoverArt;
+use crate::tag_formats::picture::{self, EmbeddedPicture};
+use image::codecs::jpeg::JpegEncoder;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Thumbnail cache, kept next to the database
+pub const COVER_ART_CACHE_DIR: &str = "../db/cover_art";
+
+/// Longest edge of cached thumbnails in pixels
+pub const THUMBNAIL_SIZE: u32 = 512;
+
+const JPEG_QUALITY: u8 = 85;
+
+/// Hex SHA-256 of the original picture bytes, used as the cache key
+pub fn content_hash(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Cached thumbnail of a hash; `None` for anything that isn't a SHA-256 hex digest
+pub fn thumbnail_path(cache_dir: &Path, hash: &str) -> Option<PathBuf> {
+    if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some(cache_dir.join(format!("{}.jpg", hash.to_ascii_lowercase())))
+}
+
+/// Write a picture to the cache as a JPEG thumbnail, reusing an existing entry
+fn cache_thumbnail(picture: &EmbeddedPicture, cache_dir: &Path) -> Result<CoverArt, String> {
+    let hash = content_hash(&picture.data);
+    let path = cache_dir.join(format!("{}.jpg", hash));
+
+    let (width, height) = if path.exists() {
+        image::image_dimensions(&path).map_err(|e| format!("Failed to read cached thumbnail: {}", e))?
+    } else {
+        let image = image::load_from_memory(&picture.data)
+            .map_err(|e| format!("Failed to decode picture: {}", e))?;
+        let thumbnail = image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgb8();
+
+        fs::create_dir_all(cache_dir).map_err(|e| format!("Failed to create cover art cache: {}", e))?;
+        // Write under a temporary name so an interrupted write never leaves a truncated thumbnail
+        let temp_path = path.with_extension("jpg.tmp");
+        let mut file = fs::File::create(&temp_path).map_err(|e| format!("Failed to create thumbnail: {}", e))?;
+        JpegEncoder::new_with_quality(&mut file, JPEG_QUALITY)
+            .encode_image(&thumbnail)
+            .map_err(|e| format!("Failed to encode thumbnail: {}", e))?;
+        fs::rename(&temp_path, &path).map_err(|e| format!("Failed to store thumbnail: {}", e))?;
+        thumbnail.dimensions()
+    };
+
+    let path = fs::canonicalize(&path).unwrap_or(path);
+    Ok(CoverArt {
+        hash,
+        path: path.to_string_lossy().to_string(),
+        mime_type: picture.mime_type.clone(),
+        picture_type: picture.picture_type,
+        description: picture.description.clone(),
+        width,
+        height,
+    })
+}
+
+/// Extract the embedded pictures of a file into the thumbnail cache, front covers first
+///
+/// Pictures that fail to decode are skipped; identical pictures are returned once.
+pub fn extract_cover_art(file_path: &str, cache_dir: &Path) -> Result<Vec<CoverArt>, String> {
+    let mut cover_art: Vec<CoverArt> = Vec::new();
+    for picture in picture::read_pictures(file_path)? {
+        match cache_thumbnail(&picture, cache_dir) {
+            Ok(art) => {
+                if !cover_art.iter().any(|a| a.hash == art.hash) {
+                    cover_art.push(art);
+                }
+            }
+            Err(e) => log::warn!("Skipping embedded picture in {}: {}", file_path, e),
+        }
+    }
+    Ok(cover_art)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, ImageFormat, RgbImage};
+    use std::io::Cursor;
+
+    #[test]
+    fn caches_resized_thumbnails_by_content() {
+        let cache_dir = std::env::temp_dir().join("ligeia_cover_art_test");
+        fs::remove_dir_all(&cache_dir).ok();
+
+        let mut png = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(1024, 256))
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        let picture = EmbeddedPicture {
+            mime_type: "image/png".to_string(),
+            picture_type: picture::PICTURE_TYPE_FRONT_COVER,
+            description: String::new(),
+            data: png,
+        };
+
+        let art = cache_thumbnail(&picture, &cache_dir).unwrap();
+        assert_eq!((art.width, art.height), (THUMBNAIL_SIZE, THUMBNAIL_SIZE / 4));
+        assert_eq!(thumbnail_path(&cache_dir, &art.hash), Some(cache_dir.join(format!("{}.jpg", art.hash))));
+        assert!(Path::new(&art.path).exists());
+
+        // Second extraction reuses the cached file
+        assert_eq!(cache_thumbnail(&picture, &cache_dir).unwrap(), art);
+        assert!(thumbnail_path(&cache_dir, "../audio_player.db").is_none());
+        fs::remove_dir_all(&cache_dir).ok();
+    }
+}
@@ -4,6 +4,7 @@ pub mod genre_mappings;
 pub mod mood_mappings; 
 pub mod occasion_mappings;
 pub mod keyword_mappings;
+pub mod ucs_mappings;
 
 #[derive(Debug, Clone)]
 pub struct TagFolderMapping {
@@ -67,6 +68,7 @@ pub enum AssignmentType {
     Occasion,
     Keyword,
     Mood,
+    Ucs,
 }
 
 #[derive(Debug, Clone)]
@@ -142,4 +144,23 @@ pub fn get_detailed_folders_for_tags(
     DetailedTagFolderMapping {
         folder_assignments: assignments
     }
+}
+
+/// Folder assignments for a UCS category/subcategory
+pub fn get_ucs_folder_assignments(category: &str, subcategory: Option<&str>) -> Vec<FolderAssignment> {
+    let reason = match subcategory {
+        Some(sub) => format!("UCS: {}/{}", category, sub),
+        None => format!("UCS: {}", category),
+    };
+
+    ucs_mappings::lookup_ucs_folders(category, subcategory)
+        .unwrap_or(&[])
+        .iter()
+        .map(|(folder_path, confidence)| FolderAssignment {
+            folder_path: folder_path.to_string(),
+            assignment_reason: reason.clone(),
+            confidence: (*confidence as f32) / 10.0,
+            assignment_type: AssignmentType::Ucs,
+        })
+        .collect()
 }
\ No newline at end of file
@@ -0,0 +1,166 @@
+/// (category, subcategory, [keyword tags], [(folder_path, confidence_score)])
+pub type UcsMapping = (&'static str, &'static str, &'static [&'static str], &'static [(&'static str, u8)]);
+
+/// UCS category to keyword vocabulary and folder mappings
+/// Format: (category, subcategory, [keyword tags], [(folder_path, confidence_score)])
+/// An empty subcategory applies to every subcategory of the category that has no entry of its own
+/// Confidence: 5-10 (5=low, 10=perfect match)
+pub const UCS_CATEGORY_MAPPINGS: &[UcsMapping] = &[
+    // Ambiences
+    ("AMBIENCE", "CELEBRATION", &[], &[("Social/Crowds/Celebration Crowds", 9), ("Social/Entertainment/Festivals", 8)]),
+    ("AMBIENCE", "DESERT", &["biome:desert"], &[("Environments/Natural Landscapes/Deserts", 10)]),
+    ("AMBIENCE", "FOREST", &["biome:forest"], &[("Environments/Natural Landscapes/Forests", 10)]),
+    ("AMBIENCE", "INDUSTRIAL", &["sfx:machinery"], &[("SFX/Objects/Machinery", 8)]),
+    ("AMBIENCE", "LAKESIDE", &["biome:lake"], &[("Environments/Natural Landscapes/Rivers", 8)]),
+    ("AMBIENCE", "MARKET", &["loc:market", "sfx:market-crowd"], &[("Environments/Settlements/Markets", 10), ("Social/Crowds/Market Crowds", 8)]),
+    ("AMBIENCE", "MOUNTAIN", &["biome:mountain"], &[("Environments/Natural Landscapes/Mountains", 10)]),
+    ("AMBIENCE", "NAUTICAL", &["loc:harbor"], &[("Environments/Settlements/Harbors", 9)]),
+    ("AMBIENCE", "PLAINS", &["biome:plains"], &[("Environments/Natural Landscapes/Grasslands", 10)]),
+    ("AMBIENCE", "PRISON", &["loc:prison"], &[("Environments/Settlements/Prisons", 10)]),
+    ("AMBIENCE", "RELIGIOUS", &["loc:temple"], &[("Environments/Settlements/Temples", 10)]),
+    ("AMBIENCE", "RESTAURANT & BAR", &["loc:tavern", "sfx:tavern-murmur"], &[("Environments/Settlements/Taverns", 10), ("Social/Conversations/Tavern Chatter", 8)]),
+    ("AMBIENCE", "RURAL", &[], &[("Environments/Settlements/Villages", 9)]),
+    ("AMBIENCE", "SCIFI", &[], &[("Environments/Futuristic/Space Stations", 9)]),
+    ("AMBIENCE", "SEASIDE", &["biome:coast", "sfx:ocean-surf"], &[("Environments/Natural Landscapes/Coasts", 10), ("Environments/Natural Landscapes/Oceans", 8)]),
+    ("AMBIENCE", "SWAMP", &["biome:swamp"], &[("Environments/Natural Landscapes/Wetlands", 10)]),
+    ("AMBIENCE", "TROPICAL", &["biome:jungle"], &[("Environments/Natural Landscapes/Jungles", 10)]),
+    ("AMBIENCE", "UNDERGROUND", &["biome:cave"], &[("Environments/Natural Landscapes/Caves", 9), ("Environments/Dungeons & Ruins/Mines", 7)]),
+    ("AMBIENCE", "URBAN", &[], &[("Environments/Settlements/Cities", 10)]),
+    ("AMBIENCE", "WARFARE", &[], &[("Combat/Battle Ambience/Battlefield", 10)]),
+
+    // Weather
+    ("RAIN", "", &["weather:rain", "sfx:rain"], &[("Environments/Weather/Rain", 10)]),
+    ("WEATHER", "RAIN", &["weather:rain", "sfx:rain"], &[("Environments/Weather/Rain", 10)]),
+    ("WEATHER", "HAIL", &["weather:hail"], &[("Environments/Weather/Storms", 8)]),
+    ("WEATHER", "STORM", &["weather:thunderstorm"], &[("Environments/Weather/Storms", 10)]),
+    ("THUNDER", "", &["weather:thunderstorm", "sfx:thunder"], &[("Environments/Weather/Storms", 10)]),
+    ("WIND", "", &["weather:wind", "sfx:wind"], &[("Environments/Weather/Wind", 10)]),
+    ("SNOW", "", &["weather:snow"], &[("Environments/Weather/Snow", 10)]),
+    ("ICE", "", &["element:ice"], &[("Environments/Weather/Snow", 7), ("Magic/Elemental Magic/Ice Magic", 7)]),
+    ("NATURAL DISASTER", "TORNADO", &["weather:wind"], &[("Environments/Weather/Storms", 9)]),
+    ("GEOTHERMAL", "", &["biome:volcanic"], &[("Environments/Natural Landscapes/Volcanic", 10)]),
+
+    // Elements
+    ("FIRE", "", &["element:fire"], &[("Magic/Elemental Magic/Fire Magic", 7)]),
+    ("FIRE", "CRACKLE", &["element:fire", "sfx:campfire", "sfx:wood-crackle"], &[]),
+    ("WATER", "", &["element:water"], &[("Magic/Elemental Magic/Water Magic", 6)]),
+    ("WATER", "DRIP", &["sfx:water-drip"], &[("Environments/Natural Landscapes/Caves", 7)]),
+    ("WATER", "FLOW", &["sfx:river"], &[("Environments/Natural Landscapes/Rivers", 10)]),
+    ("WATER", "STREAM", &["sfx:river", "biome:river"], &[("Environments/Natural Landscapes/Rivers", 10)]),
+    ("WATER", "SURF", &["sfx:ocean-surf"], &[("Environments/Natural Landscapes/Oceans", 10)]),
+    ("WATER", "WAVES", &["sfx:ocean-surf", "biome:open-sea"], &[("Environments/Natural Landscapes/Oceans", 10)]),
+    ("ELECTRICITY", "", &["element:lightning"], &[("Magic/Elemental Magic/Lightning Magic", 7)]),
+
+    // Weapons & combat
+    ("WEAPONS", "", &[], &[("SFX/Weapons/Melee Weapons", 9)]),
+    ("WEAPONS", "SWORD", &["sfx:sword-clash"], &[("SFX/Weapons/Melee Weapons", 10), ("SFX/Weapons/Clashing", 8)]),
+    ("WEAPONS", "BOW", &["sfx:bow-release"], &[("SFX/Weapons/Ranged Weapons", 10)]),
+    ("WEAPONS", "ARROW", &["sfx:bow-release"], &[("SFX/Weapons/Ranged Weapons", 10)]),
+    ("WEAPONS", "ARMOR", &["sfx:armor-clank"], &[("SFX/Movement/Armor Movement", 10)]),
+    ("GUNS", "", &["sfx:gunshot"], &[("SFX/Weapons/Ranged Weapons", 10)]),
+    ("GUNS", "HANDLING", &["sfx:reload"], &[("SFX/Weapons/Weapon Handling", 10)]),
+    ("BULLETS", "RICOCHET", &["sfx:ricochet"], &[("SFX/Weapons/Ranged Weapons", 8)]),
+    ("FIGHT", "", &["sfx:melee-impact", "sfx:body-impact"], &[("SFX/Impacts & Crashes/Body Impacts", 10)]),
+    ("EXPLOSIONS", "", &["sfx:explosion"], &[("SFX/Impacts & Crashes/Explosion Impacts", 10)]),
+    ("LASERS", "", &["sfx:energy-blast"], &[("SFX/Magical Effects/Energy Blasts", 7)]),
+
+    // Materials & impacts
+    ("METAL", "", &["sfx:metal-impact"], &[("SFX/Impacts & Crashes/Metal Impacts", 10)]),
+    ("WOOD", "", &["sfx:wood-impact"], &[("SFX/Impacts & Crashes/Wood Impacts", 10)]),
+    ("ROCKS", "", &["sfx:stone-impact"], &[("SFX/Impacts & Crashes/Stone Impacts", 10)]),
+    ("GLASS", "", &["sfx:glass-impact"], &[("SFX/Impacts & Crashes/Glass Impacts", 10)]),
+    ("CHAINS", "", &["sfx:chains"], &[]),
+
+    // Objects & movement
+    ("DOORS", "", &["sound-design:objects"], &[("SFX/Objects/Doors", 10)]),
+    ("DOORS", "CREAK", &["sfx:door-creak"], &[("SFX/Objects/Doors", 10)]),
+    ("DOORS", "GATE", &["sfx:gate-bang"], &[("SFX/Objects/Doors", 10)]),
+    ("OBJECTS", "BOOK", &["sfx:pages-turning"], &[("SFX/Objects/Books", 10)]),
+    ("OBJECTS", "COIN", &["sfx:coins"], &[("SFX/Objects/Coins", 10)]),
+    ("OBJECTS", "CONTAINER", &["sfx:container-open"], &[("SFX/Objects/Containers", 10)]),
+    ("OBJECTS", "WRITING", &["sfx:quill-scratch"], &[("SFX/Objects/Books", 8)]),
+    ("PAPER", "PAGE TURN", &["sfx:pages-turning"], &[("SFX/Objects/Books", 10)]),
+    ("TOOLS", "", &["sfx:tool-use"], &[("SFX/Objects/Tools", 10)]),
+    ("MACHINES", "", &["sfx:machinery"], &[("SFX/Objects/Machinery", 10)]),
+    ("MECHANICAL", "", &["sfx:machinery"], &[("SFX/Objects/Machinery", 8)]),
+    ("CLOCKS", "", &["timbre:clockwork"], &[("SFX/Objects/Machinery", 7)]),
+    ("FOOTSTEPS", "", &["sfx:footsteps"], &[("SFX/Movement/Footsteps", 10)]),
+    ("FOOTSTEPS", "CREATURE", &["sfx:creature-movement"], &[("SFX/Movement/Creature Movement", 10)]),
+    ("FOOTSTEPS", "HORSE", &["vehicle:horse"], &[("SFX/Movement/Footsteps", 8)]),
+    ("CLOTH", "", &["sfx:cloth-rustle"], &[("SFX/Movement/Cloth Movement", 10)]),
+    ("LEATHER", "", &["sfx:cloth-rustle"], &[("SFX/Movement/Cloth Movement", 8), ("Combat/Armor & Defense/Leather Armor", 7)]),
+    ("BELLS", "CHURCH", &["sfx:church-bells"], &[("Environments/Settlements/Temples", 8)]),
+    ("ALARMS", "", &["sfx:alarm"], &[]),
+
+    // Creatures & voices
+    ("CREATURES", "", &["sfx:monster-roar"], &[("SFX/Movement/Creature Movement", 7)]),
+    ("CREATURES", "DRAGON", &["creature:dragon", "sfx:dragon-breath"], &[("Magic/Magical Creatures/Dragons", 10), ("Combat/Monster Combat/Dragon Fights", 8)]),
+    ("CREATURES", "ELEMENTAL", &["creature:elemental"], &[("Magic/Magical Creatures/Elementals", 10)]),
+    ("CREATURES", "ETHEREAL", &["creature:ghost", "sfx:ghost-wail"], &[("Magic/Magical Creatures/Spirits", 10)]),
+    ("ANIMALS", "", &["sfx:beast-sounds"], &[("Combat/Monster Combat/Beast Battles", 6)]),
+    ("ANIMALS", "HORSE", &["vehicle:horse"], &[]),
+    ("ANIMALS", "WILD", &["creature:beast", "sfx:beast-sounds"], &[("Combat/Monster Combat/Beast Battles", 7)]),
+    ("CROWDS", "", &["sfx:crowd-voices"], &[("SFX/Voice & Vocal/Crowd Voices", 10)]),
+    ("CROWDS", "ANGRY", &["sfx:crowd-voices"], &[("Social/Crowds/Angry Mobs", 10)]),
+    ("CROWDS", "BATTLE", &["sfx:crowd-voices"], &[("Combat/Battle Ambience/Battlefield", 10)]),
+    ("CROWDS", "CELEBRATION", &["sfx:crowd-voices"], &[("Social/Crowds/Celebration Crowds", 10)]),
+    ("CROWDS", "PANIC", &["sfx:crowd-voices"], &[("Social/Crowds/Panic Crowds", 10)]),
+    ("VOICES", "WHISPER", &["sfx:whispers"], &[("SFX/Voice & Vocal/Whispers", 10)]),
+    ("VOICES", "EFFORTS", &["sfx:emotional-vocals"], &[("SFX/Voice & Vocal/Emotional Vocals", 9)]),
+    ("VOICES", "SCREAM", &["sfx:emotional-vocals"], &[("SFX/Voice & Vocal/Emotional Vocals", 10)]),
+    ("HUMAN", "BREATH", &["sfx:breathing"], &[("SFX/Voice & Vocal/Breathing", 10)]),
+    ("ROBOTS", "", &["creature:construct"], &[]),
+
+    // Magic & sci-fi
+    ("MAGIC", "", &["sfx:magic-whoosh"], &[("SFX/Magical Effects/Spell Casting", 9)]),
+    ("MAGIC", "ANGELIC", &["sfx:healing-magic"], &[("SFX/Magical Effects/Healing Magic", 9)]),
+    ("MAGIC", "EVIL", &["sfx:curse"], &[("SFX/Magical Effects/Curses", 9)]),
+    ("MAGIC", "POOF", &["sfx:teleport"], &[("SFX/Magical Effects/Teleportation", 9)]),
+    ("MAGIC", "SPELL", &["sfx:spell-impact"], &[("SFX/Magical Effects/Spell Casting", 10)]),
+    ("SCIFI", "COMPUTER", &["sfx:scanner-beeps"], &[("Environments/Futuristic/Laboratories", 7)]),
+    ("SCIFI", "SHIP", &["sfx:space-engine-hum"], &[("Environments/Futuristic/Spaceports", 8)]),
+    ("SCIFI", "WEAPON", &["sfx:energy-blast"], &[("SFX/Magical Effects/Energy Blasts", 7)]),
+    ("COMPUTERS", "KEYBOARD & MOUSE", &["sfx:keyboard"], &[]),
+
+    // Vehicles
+    ("VEHICLES", "", &["sfx:vehicle-movement"], &[("SFX/Movement/Vehicle Movement", 10)]),
+    ("VEHICLES", "HORSE DRAWN", &["vehicle:carriage"], &[("SFX/Movement/Vehicle Movement", 10)]),
+    ("VEHICLES", "MILITARY", &["vehicle:armored-vehicle"], &[("SFX/Movement/Vehicle Movement", 10)]),
+    ("VEHICLES", "MOTORCYCLE", &["vehicle:motorbike"], &[("SFX/Movement/Vehicle Movement", 10)]),
+    ("TRAINS", "", &["vehicle:train"], &[("SFX/Movement/Vehicle Movement", 9)]),
+    ("TRAINS", "SUBWAY", &["vehicle:subway"], &[("SFX/Movement/Vehicle Movement", 9)]),
+    ("AIRCRAFT", "", &["vehicle:airplane"], &[("SFX/Movement/Vehicle Movement", 8)]),
+    ("AIRCRAFT", "HELICOPTER", &["vehicle:helicopter"], &[("SFX/Movement/Vehicle Movement", 8)]),
+
+    // Designed & interface
+    ("DESIGNED", "DRONE", &["util:drone"], &[("Music/Electronic/Drone", 8)]),
+    ("DESIGNED", "STINGER", &["util:stinger"], &[]),
+    ("DESIGNED", "RISER", &["util:transition"], &[]),
+    ("USER INTERFACE", "", &["ui:notify"], &[]),
+    ("USER INTERFACE", "BEEP", &["ui:ping"], &[]),
+    ("USER INTERFACE", "CLICK", &["ui:confirm"], &[]),
+    ("USER INTERFACE", "GLITCH", &["sfx:hacking-glitches"], &[]),
+];
+
+/// Lookup function for a UCS category/subcategory, preferring the subcategory entry
+pub fn lookup_ucs_mapping(category: &str, subcategory: Option<&str>) -> Option<&'static UcsMapping> {
+    let exact = subcategory.and_then(|sub| {
+        UCS_CATEGORY_MAPPINGS.iter()
+            .find(|(cat, s, _, _)| cat.eq_ignore_ascii_case(category) && s.eq_ignore_ascii_case(sub))
+    });
+
+    exact.or_else(|| {
+        UCS_CATEGORY_MAPPINGS.iter()
+            .find(|(cat, s, _, _)| cat.eq_ignore_ascii_case(category) && s.is_empty())
+    })
+}
+
+/// Lookup function for UCS folders with confidence scores
+pub fn lookup_ucs_folders(category: &str, subcategory: Option<&str>) -> Option<&'static [(&'static str, u8)]> {
+    lookup_ucs_mapping(category, subcategory).map(|(_, _, _, folders)| *folders)
+}
+
+/// Keyword vocabulary tags for a UCS category/subcategory
+pub fn lookup_ucs_keywords(category: &str, subcategory: Option<&str>) -> &'static [&'static str] {
+    lookup_ucs_mapping(category, subcategory).map(|(_, _, keywords, _)| *keywords).unwrap_or(&[])
+}
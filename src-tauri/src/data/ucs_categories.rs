@@ -0,0 +1,657 @@
+// Universal Category System (UCS) CatID list
+// Format: (cat_id, category, subcategory)
+// CatIDs are matched case-insensitively; category and subcategory are kept in
+// the upper-case spelling used by the UCS spreadsheet
+[
+    // AIR
+    ("AIRBrst", "AIR", "BURST"),
+    ("AIRHiss", "AIR", "HISS"),
+    ("AIRMisc", "AIR", "MISC"),
+    ("AIRSuck", "AIR", "SUCTION"),
+
+    // AIRCRAFT
+    ("AEROHeli", "AIRCRAFT", "HELICOPTER"),
+    ("AEROInt", "AIRCRAFT", "INTERIOR"),
+    ("AEROJet", "AIRCRAFT", "JET"),
+    ("AEROMil", "AIRCRAFT", "MILITARY"),
+    ("AEROMisc", "AIRCRAFT", "MISC"),
+    ("AEROProp", "AIRCRAFT", "PROP"),
+    ("AERORadio", "AIRCRAFT", "RADIO CONTROLLED"),
+    ("AERORckt", "AIRCRAFT", "ROCKET"),
+
+    // ALARMS
+    ("ALRMBell", "ALARMS", "BELL"),
+    ("ALRMBuzr", "ALARMS", "BUZZER"),
+    ("ALRMClock", "ALARMS", "CLOCK"),
+    ("ALRMMisc", "ALARMS", "MISC"),
+    ("ALRMSirn", "ALARMS", "SIREN"),
+
+    // AMBIENCE
+    ("AMBAir", "AMBIENCE", "AIR"),
+    ("AMBBird", "AMBIENCE", "BIRDSONG"),
+    ("AMBCele", "AMBIENCE", "CELEBRATION"),
+    ("AMBCnst", "AMBIENCE", "CONSTRUCTION"),
+    ("AMBDsgn", "AMBIENCE", "DESIGNED"),
+    ("AMBDsrt", "AMBIENCE", "DESERT"),
+    ("AMBEmrg", "AMBIENCE", "EMERGENCY"),
+    ("AMBFarm", "AMBIENCE", "FARM"),
+    ("AMBForst", "AMBIENCE", "FOREST"),
+    ("AMBHist", "AMBIENCE", "HISTORICAL"),
+    ("AMBHosp", "AMBIENCE", "HOSPITAL"),
+    ("AMBInd", "AMBIENCE", "INDUSTRIAL"),
+    ("AMBLake", "AMBIENCE", "LAKESIDE"),
+    ("AMBMrkt", "AMBIENCE", "MARKET"),
+    ("AMBMisc", "AMBIENCE", "MISC"),
+    ("AMBMntn", "AMBIENCE", "MOUNTAIN"),
+    ("AMBNaut", "AMBIENCE", "NAUTICAL"),
+    ("AMBOffc", "AMBIENCE", "OFFICE"),
+    ("AMBPark", "AMBIENCE", "PARK"),
+    ("AMBPlns", "AMBIENCE", "PLAINS"),
+    ("AMBPrsn", "AMBIENCE", "PRISON"),
+    ("AMBPubl", "AMBIENCE", "PUBLIC PLACE"),
+    ("AMBRest", "AMBIENCE", "RESTAURANT & BAR"),
+    ("AMBRlgn", "AMBIENCE", "RELIGIOUS"),
+    ("AMBRoom", "AMBIENCE", "ROOM TONE"),
+    ("AMBRurl", "AMBIENCE", "RURAL"),
+    ("AMBSchl", "AMBIENCE", "SCHOOL"),
+    ("AMBScif", "AMBIENCE", "SCIFI"),
+    ("AMBSeas", "AMBIENCE", "SEASIDE"),
+    ("AMBSprt", "AMBIENCE", "SPORT"),
+    ("AMBSubn", "AMBIENCE", "SUBURBAN"),
+    ("AMBSwmp", "AMBIENCE", "SWAMP"),
+    ("AMBTraf", "AMBIENCE", "TRAFFIC"),
+    ("AMBTran", "AMBIENCE", "TRANSPORTATION"),
+    ("AMBTrop", "AMBIENCE", "TROPICAL"),
+    ("AMBUndr", "AMBIENCE", "UNDERGROUND"),
+    ("AMBUndwtr", "AMBIENCE", "UNDERWATER"),
+    ("AMBUrbn", "AMBIENCE", "URBAN"),
+    ("AMBWar", "AMBIENCE", "WARFARE"),
+
+    // ANIMALS
+    ("ANMLAmph", "ANIMALS", "AMPHIBIAN"),
+    ("ANMLAqua", "ANIMALS", "AQUATIC"),
+    ("ANMLBat", "ANIMALS", "BAT"),
+    ("ANMLCat", "ANIMALS", "CAT DOMESTIC"),
+    ("ANMLCatWild", "ANIMALS", "CAT WILD"),
+    ("ANMLDog", "ANIMALS", "DOG"),
+    ("ANMLFarm", "ANIMALS", "FARM"),
+    ("ANMLHors", "ANIMALS", "HORSE"),
+    ("ANMLInsct", "ANIMALS", "INSECT"),
+    ("ANMLMisc", "ANIMALS", "MISC"),
+    ("ANMLPrim", "ANIMALS", "PRIMATE"),
+    ("ANMLRept", "ANIMALS", "REPTILE"),
+    ("ANMLRdnt", "ANIMALS", "RODENT"),
+    ("ANMLWild", "ANIMALS", "WILD"),
+
+    // BEEPS
+    ("BEEPAppl", "BEEPS", "APPLIANCE"),
+    ("BEEPElec", "BEEPS", "ELECTRONIC"),
+    ("BEEPMisc", "BEEPS", "MISC"),
+    ("BEEPTimer", "BEEPS", "TIMER"),
+
+    // BELLS
+    ("BELLAnml", "BELLS", "ANIMAL"),
+    ("BELLChur", "BELLS", "CHURCH"),
+    ("BELLDoor", "BELLS", "DOORBELL"),
+    ("BELLGong", "BELLS", "GONG"),
+    ("BELLHand", "BELLS", "HANDBELL"),
+    ("BELLMisc", "BELLS", "MISC"),
+
+    // BIRDS
+    ("BIRDCrow", "BIRDS", "CROW"),
+    ("BIRDFowl", "BIRDS", "FOWL"),
+    ("BIRDMisc", "BIRDS", "MISC"),
+    ("BIRDPrey", "BIRDS", "PREY"),
+    ("BIRDSong", "BIRDS", "SONGBIRD"),
+    ("BIRDTrop", "BIRDS", "TROPICAL"),
+    ("BIRDWatr", "BIRDS", "WATERFOWL"),
+
+    // BOATS
+    ("BOATAir", "BOATS", "AIRBOAT"),
+    ("BOATMisc", "BOATS", "MISC"),
+    ("BOATMotr", "BOATS", "MOTORBOAT"),
+    ("BOATRow", "BOATS", "ROWBOAT"),
+    ("BOATSail", "BOATS", "SAILBOAT"),
+    ("BOATShip", "BOATS", "SHIP"),
+    ("BOATSub", "BOATS", "SUBMARINE"),
+
+    // BULLETS
+    ("BULLImpt", "BULLETS", "IMPACT"),
+    ("BULLMisc", "BULLETS", "MISC"),
+    ("BULLRico", "BULLETS", "RICOCHET"),
+    ("BULLShell", "BULLETS", "SHELL"),
+    ("BULLWhiz", "BULLETS", "WHIZ BY"),
+
+    // CARTOON
+    ("TOONAnml", "CARTOON", "ANIMAL"),
+    ("TOONBoing", "CARTOON", "BOING"),
+    ("TOONImpt", "CARTOON", "IMPACT"),
+    ("TOONMisc", "CARTOON", "MISC"),
+    ("TOONWhsh", "CARTOON", "WHOOSH"),
+
+    // CERAMICS
+    ("CERMBrk", "CERAMICS", "BREAK"),
+    ("CERMImpt", "CERAMICS", "IMPACT"),
+    ("CERMMisc", "CERAMICS", "MISC"),
+    ("CERMMvmt", "CERAMICS", "MOVEMENT"),
+
+    // CHAINS
+    ("CHAINBrk", "CHAINS", "BREAK"),
+    ("CHAINImpt", "CHAINS", "IMPACT"),
+    ("CHAINMisc", "CHAINS", "MISC"),
+    ("CHAINMvmt", "CHAINS", "MOVEMENT"),
+
+    // CHEMICALS
+    ("CHEMAcid", "CHEMICALS", "ACID"),
+    ("CHEMBubl", "CHEMICALS", "BUBBLING"),
+    ("CHEMMisc", "CHEMICALS", "MISC"),
+
+    // CLOCKS
+    ("CLOCKChim", "CLOCKS", "CHIME"),
+    ("CLOCKMech", "CLOCKS", "MECHANICS"),
+    ("CLOCKMisc", "CLOCKS", "MISC"),
+    ("CLOCKTick", "CLOCKS", "TICK"),
+
+    // CLOTH
+    ("CLOTHFlp", "CLOTH", "FLAP"),
+    ("CLOTHMisc", "CLOTH", "MISC"),
+    ("CLOTHMvmt", "CLOTH", "MOVEMENT"),
+    ("CLOTHRip", "CLOTH", "RIP"),
+
+    // COMMUNICATIONS
+    ("COMCell", "COMMUNICATIONS", "CELLPHONE"),
+    ("COMMisc", "COMMUNICATIONS", "MISC"),
+    ("COMRadio", "COMMUNICATIONS", "RADIO"),
+    ("COMTelm", "COMMUNICATIONS", "TELEMETRY"),
+    ("COMTelph", "COMMUNICATIONS", "TELEPHONE"),
+
+    // COMPUTERS
+    ("COMPHard", "COMPUTERS", "HARD DRIVE"),
+    ("COMPKey", "COMPUTERS", "KEYBOARD & MOUSE"),
+    ("COMPMisc", "COMPUTERS", "MISC"),
+
+    // CREATURES
+    ("CREAAqua", "CREATURES", "AQUATIC"),
+    ("CREABlob", "CREATURES", "BLOB"),
+    ("CREADino", "CREATURES", "DINOSAUR"),
+    ("CREADrgn", "CREATURES", "DRAGON"),
+    ("CREAElem", "CREATURES", "ELEMENTAL"),
+    ("CREAEthr", "CREATURES", "ETHEREAL"),
+    ("CREAHmn", "CREATURES", "HUMANOID"),
+    ("CREAInsct", "CREATURES", "INSECTOID"),
+    ("CREAMisc", "CREATURES", "MISC"),
+    ("CREAMnstr", "CREATURES", "MONSTER"),
+    ("CREARept", "CREATURES", "REPTILIAN"),
+    ("CREASmall", "CREATURES", "SMALL"),
+
+    // CROWDS
+    ("CROWDAngr", "CROWDS", "ANGRY"),
+    ("CROWDAppl", "CROWDS", "APPLAUSE"),
+    ("CROWDBatl", "CROWDS", "BATTLE"),
+    ("CROWDCele", "CROWDS", "CELEBRATION"),
+    ("CROWDChld", "CROWDS", "CHILDREN"),
+    ("CROWDLaff", "CROWDS", "LAUGHTER"),
+    ("CROWDMisc", "CROWDS", "MISC"),
+    ("CROWDPanic", "CROWDS", "PANIC"),
+    ("CROWDReac", "CROWDS", "REACTION"),
+    ("CROWDSing", "CROWDS", "SINGING"),
+    ("CROWDSprt", "CROWDS", "SPORT"),
+    ("CROWDWalla", "CROWDS", "WALLA"),
+
+    // DESIGNED
+    ("DSGNBass", "DESIGNED", "BASS DIVE"),
+    ("DSGNDist", "DESIGNED", "DISTORTION"),
+    ("DSGNDron", "DESIGNED", "DRONE"),
+    ("DSGNImpt", "DESIGNED", "IMPACT"),
+    ("DSGNMisc", "DESIGNED", "MISC"),
+    ("DSGNRise", "DESIGNED", "RISER"),
+    ("DSGNRmbl", "DESIGNED", "RUMBLE"),
+    ("DSGNSrce", "DESIGNED", "SOURCE"),
+    ("DSGNStngr", "DESIGNED", "STINGER"),
+    ("DSGNSynth", "DESIGNED", "SYNTHETIC"),
+    ("DSGNTonl", "DESIGNED", "TONAL"),
+    ("DSGNWhsh", "DESIGNED", "WHOOSH"),
+
+    // DESTRUCTION
+    ("DSTRCrsh", "DESTRUCTION", "CRASH & DEBRIS"),
+    ("DSTRCrmbl", "DESTRUCTION", "CRUMBLE"),
+    ("DSTRMisc", "DESTRUCTION", "MISC"),
+
+    // DIRT & SAND
+    ("DIRTImpt", "DIRT & SAND", "IMPACT"),
+    ("DIRTMisc", "DIRT & SAND", "MISC"),
+    ("DIRTMvmt", "DIRT & SAND", "MOVEMENT"),
+
+    // DOORS
+    ("DOORAntq", "DOORS", "ANTIQUE"),
+    ("DOORAppl", "DOORS", "APPLIANCE"),
+    ("DOORCab", "DOORS", "CABINET"),
+    ("DOORCreak", "DOORS", "CREAK"),
+    ("DOORGate", "DOORS", "GATE"),
+    ("DOORGlss", "DOORS", "GLASS"),
+    ("DOORHdwr", "DOORS", "HARDWARE"),
+    ("DOORHydr", "DOORS", "HYDRAULIC & PNEUMATIC"),
+    ("DOORKnck", "DOORS", "KNOCK"),
+    ("DOORMetl", "DOORS", "METAL"),
+    ("DOORMisc", "DOORS", "MISC"),
+    ("DOORPlst", "DOORS", "PLASTIC"),
+    ("DOORScif", "DOORS", "SCIFI"),
+    ("DOORSlid", "DOORS", "SLIDING"),
+    ("DOORStne", "DOORS", "STONE"),
+    ("DOORVhcl", "DOORS", "VEHICLE"),
+    ("DOORWood", "DOORS", "WOOD"),
+
+    // DRAWERS
+    ("DRWRMetl", "DRAWERS", "METAL"),
+    ("DRWRMisc", "DRAWERS", "MISC"),
+    ("DRWRWood", "DRAWERS", "WOOD"),
+
+    // ELECTRICITY
+    ("ELECArc", "ELECTRICITY", "ARC"),
+    ("ELECBuzz", "ELECTRICITY", "BUZZ & HUM"),
+    ("ELECMisc", "ELECTRICITY", "MISC"),
+    ("ELECSprk", "ELECTRICITY", "SPARKS"),
+    ("ELECZap", "ELECTRICITY", "ZAP"),
+
+    // EXPLOSIONS
+    ("EXPLDsgn", "EXPLOSIONS", "DESIGNED"),
+    ("EXPLMisc", "EXPLOSIONS", "MISC"),
+    ("EXPLReal", "EXPLOSIONS", "REAL"),
+
+    // FIGHT
+    ("FGHTBf", "FIGHT", "BODYFALL"),
+    ("FGHTClth", "FIGHT", "CLOTHING"),
+    ("FGHTGrab", "FIGHT", "GRAB"),
+    ("FGHTImpt", "FIGHT", "IMPACT"),
+    ("FGHTMisc", "FIGHT", "MISC"),
+
+    // FIRE
+    ("FIREBrst", "FIRE", "BURST"),
+    ("FIREBurn", "FIRE", "BURNING"),
+    ("FIRECrkl", "FIRE", "CRACKLE"),
+    ("FIREGas", "FIRE", "GAS"),
+    ("FIREMisc", "FIRE", "MISC"),
+    ("FIRESizz", "FIRE", "SIZZLE"),
+    ("FIRETrch", "FIRE", "TORCH"),
+    ("FIREWhsh", "FIRE", "WHOOSH"),
+
+    // FIREWORKS
+    ("FIREWRKMisc", "FIREWORKS", "MISC"),
+    ("FIREWRKRckt", "FIREWORKS", "ROCKET"),
+
+    // FOLEY
+    ("FOLYFeet", "FOLEY", "FEET"),
+    ("FOLYHand", "FOLEY", "HANDS"),
+    ("FOLYMisc", "FOLEY", "MISC"),
+    ("FOLYProp", "FOLEY", "PROP"),
+
+    // FOOD & DRINK
+    ("FOODCook", "FOOD & DRINK", "COOKING"),
+    ("FOODDrink", "FOOD & DRINK", "DRINKING"),
+    ("FOODEat", "FOOD & DRINK", "EATING"),
+    ("FOODGlas", "FOOD & DRINK", "GLASSWARE"),
+    ("FOODIngr", "FOOD & DRINK", "INGREDIENTS"),
+    ("FOODMisc", "FOOD & DRINK", "MISC"),
+
+    // FOOTSTEPS
+    ("FEETCrea", "FOOTSTEPS", "CREATURE"),
+    ("FEETHmn", "FOOTSTEPS", "HUMAN"),
+    ("FEETHors", "FOOTSTEPS", "HORSE"),
+    ("FEETMisc", "FOOTSTEPS", "MISC"),
+
+    // GAMES
+    ("GAMEArcd", "GAMES", "ARCADE"),
+    ("GAMEBoard", "GAMES", "BOARD"),
+    ("GAMECas", "GAMES", "CASINO"),
+    ("GAMEMisc", "GAMES", "MISC"),
+    ("GAMEVideo", "GAMES", "VIDEO"),
+
+    // GEOTHERMAL
+    ("GEOBubl", "GEOTHERMAL", "BUBBLING"),
+    ("GEOFuml", "GEOTHERMAL", "FUMAROLE"),
+    ("GEOLava", "GEOTHERMAL", "LAVA"),
+    ("GEOMisc", "GEOTHERMAL", "MISC"),
+
+    // GLASS
+    ("GLASBrk", "GLASS", "BREAK"),
+    ("GLASCrsh", "GLASS", "CRASH & DEBRIS"),
+    ("GLASImpt", "GLASS", "IMPACT"),
+    ("GLASMisc", "GLASS", "MISC"),
+    ("GLASMvmt", "GLASS", "MOVEMENT"),
+
+    // GORE
+    ("GOREBone", "GORE", "BONE"),
+    ("GOREMisc", "GORE", "MISC"),
+    ("GORESplt", "GORE", "SPLATTER"),
+    ("GORESqsh", "GORE", "SQUISH"),
+    ("GOREStab", "GORE", "STAB"),
+
+    // GUNS
+    ("GUNAntq", "GUNS", "ANTIQUE"),
+    ("GUNArtl", "GUNS", "ARTILLERY"),
+    ("GUNAuto", "GUNS", "AUTOMATIC"),
+    ("GUNCano", "GUNS", "CANNON"),
+    ("GUNHand", "GUNS", "HANDLING"),
+    ("GUNMech", "GUNS", "MECHANISM"),
+    ("GUNMisc", "GUNS", "MISC"),
+    ("GUNPis", "GUNS", "PISTOL"),
+    ("GUNRif", "GUNS", "RIFLE"),
+    ("GUNShotg", "GUNS", "SHOTGUN"),
+    ("GUNSupr", "GUNS", "SUPPRESSED"),
+
+    // HORNS
+    ("HORNAir", "HORNS", "AIR POWERED"),
+    ("HORNMisc", "HORNS", "MISC"),
+    ("HORNTrad", "HORNS", "TRADITIONAL"),
+
+    // HUMAN
+    ("HMNBrth", "HUMAN", "BREATH"),
+    ("HMNBurp", "HUMAN", "BURP"),
+    ("HMNCough", "HUMAN", "COUGH"),
+    ("HMNHart", "HUMAN", "HEARTBEAT"),
+    ("HMNKiss", "HUMAN", "KISS"),
+    ("HMNMisc", "HUMAN", "MISC"),
+    ("HMNSneez", "HUMAN", "SNEEZE"),
+    ("HMNSnor", "HUMAN", "SNORE"),
+
+    // ICE
+    ("ICEBrk", "ICE", "BREAK"),
+    ("ICECrsh", "ICE", "CRASH & DEBRIS"),
+    ("ICEImpt", "ICE", "IMPACT"),
+    ("ICEMisc", "ICE", "MISC"),
+    ("ICEMvmt", "ICE", "MOVEMENT"),
+
+    // LASERS
+    ("LASRBeam", "LASERS", "BEAM"),
+    ("LASRGun", "LASERS", "GUN"),
+    ("LASRImpt", "LASERS", "IMPACT"),
+    ("LASRMisc", "LASERS", "MISC"),
+
+    // LEATHER
+    ("LETHCreak", "LEATHER", "CREAK"),
+    ("LETHMisc", "LEATHER", "MISC"),
+    ("LETHMvmt", "LEATHER", "MOVEMENT"),
+
+    // LIQUID & MUD
+    ("LIQDBubl", "LIQUID & MUD", "BUBBLES"),
+    ("LIQDMisc", "LIQUID & MUD", "MISC"),
+    ("LIQDMvmt", "LIQUID & MUD", "MOVEMENT"),
+    ("LIQDSuct", "LIQUID & MUD", "SUCTION"),
+
+    // MACHINES
+    ("MACHAppl", "MACHINES", "APPLIANCE"),
+    ("MACHElev", "MACHINES", "ELEVATOR"),
+    ("MACHFan", "MACHINES", "FAN"),
+    ("MACHHvy", "MACHINES", "HEAVY"),
+    ("MACHMisc", "MACHINES", "MISC"),
+    ("MACHOffc", "MACHINES", "OFFICE"),
+
+    // MAGIC
+    ("MAGAngl", "MAGIC", "ANGELIC"),
+    ("MAGElem", "MAGIC", "ELEMENTAL"),
+    ("MAGEvil", "MAGIC", "EVIL"),
+    ("MAGMisc", "MAGIC", "MISC"),
+    ("MAGPoof", "MAGIC", "POOF"),
+    ("MAGShim", "MAGIC", "SHIMMER"),
+    ("MAGSpel", "MAGIC", "SPELL"),
+
+    // MECHANICAL
+    ("MECHGear", "MECHANICAL", "GEARS"),
+    ("MECHHydr", "MECHANICAL", "HYDRAULIC & PNEUMATIC"),
+    ("MECHLtch", "MECHANICAL", "LATCH"),
+    ("MECHLvr", "MECHANICAL", "LEVER"),
+    ("MECHMisc", "MECHANICAL", "MISC"),
+    ("MECHPuly", "MECHANICAL", "PULLEY"),
+    ("MECHRtch", "MECHANICAL", "RATCHET"),
+
+    // METAL
+    ("METLBrk", "METAL", "BREAK"),
+    ("METLCrsh", "METAL", "CRASH & DEBRIS"),
+    ("METLFric", "METAL", "FRICTION"),
+    ("METLImpt", "METAL", "IMPACT"),
+    ("METLMisc", "METAL", "MISC"),
+    ("METLMvmt", "METAL", "MOVEMENT"),
+    ("METLTonl", "METAL", "TONAL"),
+
+    // MOTORS
+    ("MOTRAntq", "MOTORS", "ANTIQUE"),
+    ("MOTRElec", "MOTORS", "ELECTRIC"),
+    ("MOTRGas", "MOTORS", "GAS"),
+    ("MOTRMisc", "MOTORS", "MISC"),
+    ("MOTRTurb", "MOTORS", "TURBINE"),
+
+    // MOVEMENT
+    ("MOVEActv", "MOVEMENT", "ACTIVITY"),
+    ("MOVEAnml", "MOVEMENT", "ANIMAL"),
+    ("MOVEMisc", "MOVEMENT", "MISC"),
+
+    // MUSICAL
+    ("MUSCBras", "MUSICAL", "BRASS"),
+    ("MUSCChim", "MUSICAL", "CHIMES"),
+    ("MUSCKey", "MUSICAL", "KEYED"),
+    ("MUSCMisc", "MUSICAL", "MISC"),
+    ("MUSCPerc", "MUSICAL", "PERCUSSION"),
+    ("MUSCPlck", "MUSICAL", "PLUCKED"),
+    ("MUSCStr", "MUSICAL", "STRINGED"),
+    ("MUSCWind", "MUSICAL", "WOODWIND"),
+
+    // NATURAL DISASTER
+    ("NATDAval", "NATURAL DISASTER", "AVALANCHE"),
+    ("NATDEarth", "NATURAL DISASTER", "EARTHQUAKE"),
+    ("NATDMisc", "NATURAL DISASTER", "MISC"),
+    ("NATDTorn", "NATURAL DISASTER", "TORNADO"),
+    ("NATDTsun", "NATURAL DISASTER", "TSUNAMI"),
+
+    // OBJECTS
+    ("OBJBag", "OBJECTS", "BAG"),
+    ("OBJBook", "OBJECTS", "BOOK"),
+    ("OBJCoin", "OBJECTS", "COIN"),
+    ("OBJCont", "OBJECTS", "CONTAINER"),
+    ("OBJKey", "OBJECTS", "KEYS"),
+    ("OBJLug", "OBJECTS", "LUGGAGE"),
+    ("OBJMisc", "OBJECTS", "MISC"),
+    ("OBJWrit", "OBJECTS", "WRITING"),
+
+    // PAPER
+    ("PAPRFltr", "PAPER", "FLUTTER"),
+    ("PAPRHndl", "PAPER", "HANDLE"),
+    ("PAPRMisc", "PAPER", "MISC"),
+    ("PAPRRip", "PAPER", "RIP"),
+    ("PAPRTurn", "PAPER", "PAGE TURN"),
+
+    // PLASTIC
+    ("PLASCrsh", "PLASTIC", "CRASH & DEBRIS"),
+    ("PLASImpt", "PLASTIC", "IMPACT"),
+    ("PLASMisc", "PLASTIC", "MISC"),
+    ("PLASMvmt", "PLASTIC", "MOVEMENT"),
+
+    // RAIN
+    ("RAINClos", "RAIN", "CLOSE"),
+    ("RAINGen", "RAIN", "GENERAL"),
+    ("RAINInt", "RAIN", "INTERIOR"),
+    ("RAINMisc", "RAIN", "MISC"),
+    ("RAINSurf", "RAIN", "SURFACE"),
+    ("RAINVege", "RAIN", "VEGETATION"),
+    ("RAINWatr", "RAIN", "WATER"),
+
+    // ROBOTS
+    ("ROBTMisc", "ROBOTS", "MISC"),
+    ("ROBTMvmt", "ROBOTS", "MOVEMENT"),
+    ("ROBTVox", "ROBOTS", "VOCAL"),
+
+    // ROCKS
+    ("ROCKBrk", "ROCKS", "BREAK"),
+    ("ROCKCrsh", "ROCKS", "CRASH & DEBRIS"),
+    ("ROCKImpt", "ROCKS", "IMPACT"),
+    ("ROCKMisc", "ROCKS", "MISC"),
+    ("ROCKMvmt", "ROCKS", "MOVEMENT"),
+
+    // ROPE
+    ("ROPECreak", "ROPE", "CREAK"),
+    ("ROPEMisc", "ROPE", "MISC"),
+    ("ROPEMvmt", "ROPE", "MOVEMENT"),
+
+    // RUBBER
+    ("RUBRCrsh", "RUBBER", "CRASH & DEBRIS"),
+    ("RUBRImpt", "RUBBER", "IMPACT"),
+    ("RUBRMisc", "RUBBER", "MISC"),
+    ("RUBRMvmt", "RUBBER", "MOVEMENT"),
+
+    // SCIFI
+    ("SCIAlrm", "SCIFI", "ALARM"),
+    ("SCIComp", "SCIFI", "COMPUTER"),
+    ("SCIEnrg", "SCIFI", "ENERGY"),
+    ("SCIMech", "SCIFI", "MECHANISM"),
+    ("SCIMisc", "SCIFI", "MISC"),
+    ("SCIRetro", "SCIFI", "RETRO"),
+    ("SCIShip", "SCIFI", "SHIP"),
+    ("SCIWeap", "SCIFI", "WEAPON"),
+
+    // SNOW
+    ("SNOWCrsh", "SNOW", "CRASH & DEBRIS"),
+    ("SNOWImpt", "SNOW", "IMPACT"),
+    ("SNOWMisc", "SNOW", "MISC"),
+    ("SNOWMvmt", "SNOW", "MOVEMENT"),
+
+    // SPORTS
+    ("SPRTBall", "SPORTS", "BALL"),
+    ("SPRTMisc", "SPORTS", "MISC"),
+    ("SPRTTrck", "SPORTS", "TRACK & FIELD"),
+
+    // SWOOSHES
+    ("SWSHMisc", "SWOOSHES", "MISC"),
+    ("SWSHSwsh", "SWOOSHES", "SWISH"),
+    ("SWSHWhsh", "SWOOSHES", "WHOOSH"),
+
+    // THUNDER
+    ("THUNCrck", "THUNDER", "CRACK"),
+    ("THUNMisc", "THUNDER", "MISC"),
+    ("THUNRmbl", "THUNDER", "RUMBLE"),
+
+    // TOOLS
+    ("TOOLGard", "TOOLS", "GARDEN"),
+    ("TOOLHand", "TOOLS", "HAND"),
+    ("TOOLMisc", "TOOLS", "MISC"),
+    ("TOOLPneu", "TOOLS", "PNEUMATIC"),
+    ("TOOLPowr", "TOOLS", "POWER"),
+
+    // TOYS
+    ("TOYElec", "TOYS", "ELECTRONIC"),
+    ("TOYMech", "TOYS", "MECHANICAL"),
+    ("TOYMisc", "TOYS", "MISC"),
+
+    // TRAINS
+    ("TRNDiesl", "TRAINS", "DIESEL"),
+    ("TRNElec", "TRAINS", "ELECTRIC"),
+    ("TRNHspd", "TRAINS", "HIGH SPEED"),
+    ("TRNMisc", "TRAINS", "MISC"),
+    ("TRNSteam", "TRAINS", "STEAM"),
+    ("TRNSubwy", "TRAINS", "SUBWAY"),
+    ("TRNTram", "TRAINS", "TRAM"),
+
+    // USER INTERFACE
+    ("UIAlert", "USER INTERFACE", "ALERT"),
+    ("UIBeep", "USER INTERFACE", "BEEP"),
+    ("UIClick", "USER INTERFACE", "CLICK"),
+    ("UIData", "USER INTERFACE", "DATA"),
+    ("UIGlitch", "USER INTERFACE", "GLITCH"),
+    ("UIMisc", "USER INTERFACE", "MISC"),
+    ("UIMvmt", "USER INTERFACE", "MOTION"),
+
+    // VEHICLES
+    ("VEHAntq", "VEHICLES", "ANTIQUE"),
+    ("VEHBike", "VEHICLES", "BICYCLE"),
+    ("VEHBus", "VEHICLES", "BUS"),
+    ("VEHCar", "VEHICLES", "CAR"),
+    ("VEHEmrg", "VEHICLES", "EMERGENCY"),
+    ("VEHHorse", "VEHICLES", "HORSE DRAWN"),
+    ("VEHMil", "VEHICLES", "MILITARY"),
+    ("VEHMisc", "VEHICLES", "MISC"),
+    ("VEHMoto", "VEHICLES", "MOTORCYCLE"),
+    ("VEHTire", "VEHICLES", "TIRE"),
+    ("VEHTruck", "VEHICLES", "TRUCK & VAN"),
+
+    // VOICES
+    ("VOXAlien", "VOICES", "ALIEN"),
+    ("VOXBaby", "VOICES", "BABY"),
+    ("VOXChld", "VOICES", "CHILD"),
+    ("VOXEfrt", "VOICES", "EFFORTS"),
+    ("VOXFem", "VOICES", "FEMALE"),
+    ("VOXFutz", "VOICES", "FUTZED"),
+    ("VOXMale", "VOICES", "MALE"),
+    ("VOXMisc", "VOICES", "MISC"),
+    ("VOXReac", "VOICES", "REACTION"),
+    ("VOXScrm", "VOICES", "SCREAM"),
+    ("VOXWhsp", "VOICES", "WHISPER"),
+
+    // WATER
+    ("WATRBubl", "WATER", "BUBBLES"),
+    ("WATRDrip", "WATER", "DRIP"),
+    ("WATRFall", "WATER", "WATERFALL"),
+    ("WATRFlow", "WATER", "FLOW"),
+    ("WATRFoun", "WATER", "FOUNTAIN"),
+    ("WATRImpt", "WATER", "IMPACT"),
+    ("WATRLap", "WATER", "LAP"),
+    ("WATRMisc", "WATER", "MISC"),
+    ("WATRMvmt", "WATER", "MOVEMENT"),
+    ("WATRPour", "WATER", "POUR"),
+    ("WATRSplsh", "WATER", "SPLASH"),
+    ("WATRStrm", "WATER", "STREAM"),
+    ("WATRSurf", "WATER", "SURF"),
+    ("WATRUndwtr", "WATER", "UNDERWATER"),
+    ("WATRWave", "WATER", "WAVES"),
+
+    // WEAPONS
+    ("WEAPArmr", "WEAPONS", "ARMOR"),
+    ("WEAPArro", "WEAPONS", "ARROW"),
+    ("WEAPAxe", "WEAPONS", "AXE"),
+    ("WEAPBlnt", "WEAPONS", "BLUNT"),
+    ("WEAPBow", "WEAPONS", "BOW"),
+    ("WEAPKnif", "WEAPONS", "KNIFE"),
+    ("WEAPMisc", "WEAPONS", "MISC"),
+    ("WEAPPole", "WEAPONS", "POLEARM"),
+    ("WEAPSwrd", "WEAPONS", "SWORD"),
+    ("WEAPWhip", "WEAPONS", "WHIP"),
+
+    // WEATHER
+    ("WEATHHail", "WEATHER", "HAIL"),
+    ("WEATHMisc", "WEATHER", "MISC"),
+    ("WEATHRain", "WEATHER", "RAIN"),
+    ("WEATHStorm", "WEATHER", "STORM"),
+
+    // WHISTLES
+    ("WHSTHmn", "WHISTLES", "HUMAN"),
+    ("WHSTMech", "WHISTLES", "MECHANICAL"),
+    ("WHSTMisc", "WHISTLES", "MISC"),
+
+    // WIND
+    ("WINDDsgn", "WIND", "DESIGNED"),
+    ("WINDGust", "WIND", "GUST"),
+    ("WINDInt", "WIND", "INTERIOR"),
+    ("WINDMisc", "WIND", "MISC"),
+    ("WINDTonl", "WIND", "TONAL"),
+    ("WINDTurb", "WIND", "TURBULENT"),
+    ("WINDVege", "WIND", "VEGETATION"),
+
+    // WINDOWS
+    ("WINDWHdwr", "WINDOWS", "HARDWARE"),
+    ("WINDWKnck", "WINDOWS", "KNOCK"),
+    ("WINDWMetl", "WINDOWS", "METAL"),
+    ("WINDWMisc", "WINDOWS", "MISC"),
+    ("WINDWWood", "WINDOWS", "WOOD"),
+
+    // WINGS
+    ("WINGBird", "WINGS", "BIRD"),
+    ("WINGCrea", "WINGS", "CREATURE"),
+    ("WINGInsct", "WINGS", "INSECT"),
+    ("WINGMisc", "WINGS", "MISC"),
+
+    // WOOD
+    ("WOODBrk", "WOOD", "BREAK"),
+    ("WOODCrsh", "WOOD", "CRASH & DEBRIS"),
+    ("WOODFric", "WOOD", "FRICTION"),
+    ("WOODImpt", "WOOD", "IMPACT"),
+    ("WOODMisc", "WOOD", "MISC"),
+    ("WOODMvmt", "WOOD", "MOVEMENT"),
+    ("WOODTonl", "WOOD", "TONAL"),
+]
@@ -38,7 +38,8 @@ pub fn get_audio_file_by_id(conn: &Connection, id: i64) -> Result<AudioFile> {
                 release_time, tagging_time, encoding_time, encoding_settings,
                 encoded_by, copyright, file_owner, internet_radio_station_name,
                 internet_radio_station_owner, isrc, publisher, mood,
-                occasion, tempo, content_type, category
+                occasion, tempo, content_type, category,
+                leading_silence, trailing_silence, mostly_silent
          FROM audio_files WHERE id = ?1"
     )?;
 
@@ -95,6 +96,9 @@ pub fn get_audio_file_by_id(conn: &Connection, id: i64) -> Result<AudioFile> {
             tempo: row.get(48)?,
             content_type: row.get(49)?,
             category: row.get(50)?,
+            leading_silence: row.get(51)?,
+            trailing_silence: row.get(52)?,
+            mostly_silent: row.get(53)?,
             ..Default::default()
         })
     })
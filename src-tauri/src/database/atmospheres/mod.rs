@@ -107,7 +107,9 @@ mod tests {
                 title TEXT,
                 artist TEXT,
                 album TEXT,
-                duration REAL
+                duration REAL,
+                loudness_integrated REAL,
+                true_peak REAL
             )",
             [],
         ).unwrap();
@@ -171,4 +173,24 @@ mod tests {
         AtmosphereOps::delete(&conn, id).unwrap();
         assert!(AtmosphereOps::get_by_id(&conn, id).is_err());
     }
+
+    #[test]
+    fn test_add_sound_suggests_normalized_volume() {
+        let conn = create_test_db();
+        conn.execute(
+            "INSERT INTO audio_files (id, file_path, loudness_integrated, true_peak) VALUES
+                (1, 'loud_rain.wav', -12.0, -0.5), (2, 'quiet_rain.wav', -30.0, -14.0), (3, 'new.wav', NULL, NULL)",
+            [],
+        ).unwrap();
+        conn.execute("INSERT INTO atmospheres (id, name, title) VALUES (1, 'Storm', 'Storm')", []).unwrap();
+
+        for file_id in 1..=3 {
+            AtmosphereOps::add_sound(&conn, 1, file_id, None, true).unwrap();
+        }
+        AtmosphereOps::add_sound(&conn, 1, 3, Some(0.8), true).unwrap();
+
+        let mut stmt = conn.prepare("SELECT volume FROM atmosphere_sounds ORDER BY audio_file_id").unwrap();
+        let volumes: Vec<f32> = stmt.query_map([], |row| row.get(0)).unwrap().map(|v| v.unwrap()).collect();
+        assert_eq!(volumes, vec![0.14, 1.0, 0.8]);
+    }
 }
\ No newline at end of file
@@ -1,11 +1,16 @@
-use rusqlite::{Connection, params, Result};
+use rusqlite::{Connection, OptionalExtension, params, Result};
+use crate::loudness;
 use crate::models::{AtmosphereSoundMapping, AtmosphereWithSounds};
 use super::helpers;
 use super::AtmosphereOps;
 
 impl AtmosphereOps {
-    /// Add sound to atmosphere
-    pub fn add_sound(conn: &Connection, atmosphere_id: i64, audio_file_id: i64, volume: f32, is_looping: bool) -> Result<i64> {
+    /// Add sound to atmosphere; without a volume one is suggested from the file's loudness
+    pub fn add_sound(conn: &Connection, atmosphere_id: i64, audio_file_id: i64, volume: Option<f32>, is_looping: bool) -> Result<i64> {
+        let volume = match volume {
+            Some(volume) => volume,
+            None => Self::suggest_volume(conn, audio_file_id)?,
+        };
         conn.execute(
             "INSERT OR REPLACE INTO atmosphere_sounds 
              (atmosphere_id, audio_file_id, volume, is_looping, is_muted, min_seconds, max_seconds)
@@ -15,6 +20,20 @@ impl AtmosphereOps {
         Ok(conn.last_insert_rowid())
     }
 
+    /// Volume that normalizes a sound to the target loudness; the default volume while it hasn't been analyzed
+    pub fn suggest_volume(conn: &Connection, audio_file_id: i64) -> Result<f32> {
+        let measured: Option<(Option<f64>, Option<f64>)> = conn.query_row(
+            "SELECT loudness_integrated, true_peak FROM audio_files WHERE id = ?1",
+            [audio_file_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).optional()?;
+
+        Ok(match measured {
+            Some((Some(integrated), true_peak)) => loudness::suggested_volume(integrated, true_peak),
+            _ => loudness::REFERENCE_VOLUME,
+        })
+    }
+
     /// Remove sound from atmosphere
     pub fn remove_sound(conn: &Connection, atmosphere_id: i64, audio_file_id: i64) -> Result<()> {
         conn.execute(
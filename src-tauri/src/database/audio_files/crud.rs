@@ -1,5 +1,6 @@
-use rusqlite::{Connection, params, Result};
-use crate::models::AudioFile;
+use rusqlite::{Connection, params, params_from_iter, Result, ToSql};
+use rusqlite::types::Null;
+use crate::models::{AudioFile, AudioFilePatch, FieldPatch, StreamSearchRequest};
 use super::helpers;
 use super::AudioFileOps;
 
@@ -18,13 +19,17 @@ impl AudioFileOps {
                 release_time, tagging_time, encoding_time, encoding_settings,
                 encoded_by, copyright, file_owner, internet_radio_station_name,
                 internet_radio_station_owner, isrc, publisher, mood,
-                occasion, tempo, content_type, category
+                occasion, tempo, content_type, category, subcategory,
+                codec, container, sample_rate, channels, bit_depth, bitrate, file_size,
+                decodable, decode_error, file_modified, content_hash
             ) VALUES (
                 ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10,
                 ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20,
                 ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30,
                 ?31, ?32, ?33, ?34, ?35, ?36, ?37, ?38, ?39, ?40,
-                ?41, ?42, ?43, ?44, ?45, ?46, ?47, ?48, ?49, ?50
+                ?41, ?42, ?43, ?44, ?45, ?46, ?47, ?48, ?49, ?50,
+                ?51, ?52, ?53, ?54, ?55, ?56, ?57, ?58, ?59, ?60,
+                ?61, ?62
             )",
             params![
                 audio_file.file_path, audio_file.title, audio_file.artist,
@@ -45,7 +50,11 @@ impl AudioFileOps {
                 audio_file.internet_radio_station_name, audio_file.internet_radio_station_owner,
                 audio_file.isrc, audio_file.publisher, audio_file.mood,
                 audio_file.occasion, audio_file.tempo, audio_file.content_type,
-                audio_file.category
+                audio_file.category, audio_file.subcategory,
+                audio_file.codec, audio_file.container, audio_file.sample_rate,
+                audio_file.channels, audio_file.bit_depth, audio_file.bitrate, audio_file.file_size,
+                audio_file.decodable, audio_file.decode_error, audio_file.file_modified,
+                audio_file.content_hash
             ],
         )?;
         Ok(conn.last_insert_rowid())
@@ -95,6 +104,69 @@ impl AudioFileOps {
         })
     }
 
+    /// Get the audio files holding the same decoded audio, in path order
+    pub fn get_by_content_hash(conn: &Connection, content_hash: &str) -> Result<Vec<AudioFile>> {
+        let existing_columns = helpers::get_existing_columns(conn)?;
+        let (mut query, column_order) = helpers::build_select_query(&existing_columns);
+
+        query.push_str(" WHERE content_hash = ?1 ORDER BY file_path");
+
+        let mut stmt = conn.prepare(&query)?;
+        let rows = stmt.query_map([content_hash], |row| {
+            helpers::map_row_to_audio_file(row, &existing_columns, &column_order)
+        })?;
+
+        rows.collect()
+    }
+
+    /// Get the audio files matching every given stream criterion, in path order; no criteria match nothing
+    pub fn search_by_stream(conn: &Connection, request: &StreamSearchRequest) -> Result<Vec<AudioFile>> {
+        let mut conditions = Vec::new();
+        let mut values: Vec<Box<dyn ToSql>> = Vec::new();
+
+        for (column, names) in [("codec", &request.codecs), ("container", &request.containers)] {
+            let Some(names) = names.as_ref().filter(|names| !names.is_empty()) else { continue };
+            let placeholders: Vec<String> = names.iter()
+                .map(|name| {
+                    values.push(Box::new(name.to_lowercase()));
+                    format!("?{}", values.len())
+                })
+                .collect();
+            conditions.push(format!("LOWER({}) IN ({})", column, placeholders.join(", ")));
+        }
+
+        let bounds = [
+            ("channels =", request.channels.map(i64::from)),
+            ("sample_rate >=", request.min_sample_rate.map(i64::from)),
+            ("sample_rate <=", request.max_sample_rate.map(i64::from)),
+            ("bit_depth >=", request.min_bit_depth.map(i64::from)),
+            ("bit_depth <=", request.max_bit_depth.map(i64::from)),
+            ("bitrate >=", request.min_bitrate.map(i64::from)),
+            ("bitrate <=", request.max_bitrate.map(i64::from)),
+            ("file_size >=", request.min_file_size.map(|size| size as i64)),
+        ];
+        for (condition, value) in bounds {
+            let Some(value) = value else { continue };
+            values.push(Box::new(value));
+            conditions.push(format!("{} ?{}", condition, values.len()));
+        }
+
+        if conditions.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let existing_columns = helpers::get_existing_columns(conn)?;
+        let (mut query, column_order) = helpers::build_select_query(&existing_columns);
+        query.push_str(&format!(" WHERE {} ORDER BY file_path", conditions.join(" AND ")));
+
+        let mut stmt = conn.prepare(&query)?;
+        let rows = stmt.query_map(params_from_iter(values.iter()), |row| {
+            helpers::map_row_to_audio_file(row, &existing_columns, &column_order)
+        })?;
+
+        rows.collect()
+    }
+
     /// Update an existing audio file
     pub fn update(conn: &Connection, audio_file: &AudioFile) -> Result<()> {
         if let Some(id) = audio_file.id {
@@ -112,8 +184,9 @@ impl AudioFileOps {
                     encoding_time = ?37, encoding_settings = ?38, encoded_by = ?39,
                     copyright = ?40, file_owner = ?41, internet_radio_station_name = ?42,
                     internet_radio_station_owner = ?43, isrc = ?44, publisher = ?45,
-                    mood = ?46, occasion = ?47, tempo = ?48, content_type = ?49, category = ?50
-                WHERE id = ?51",
+                    mood = ?46, occasion = ?47, tempo = ?48, content_type = ?49, category = ?50,
+                    subcategory = ?51
+                WHERE id = ?52",
                 params![
                     audio_file.file_path, audio_file.title, audio_file.artist,
                     audio_file.album, audio_file.duration, audio_file.genre,
@@ -133,13 +206,151 @@ impl AudioFileOps {
                     audio_file.internet_radio_station_name, audio_file.internet_radio_station_owner,
                     audio_file.isrc, audio_file.publisher, audio_file.mood,
                     audio_file.occasion, audio_file.tempo, audio_file.content_type,
-                    audio_file.category, id
+                    audio_file.category, audio_file.subcategory, id
                 ],
             )?;
         }
         Ok(())
     }
 
+    /// Overwrite a row with what was read again from its changed file. Its `content_hash` is
+    /// cleared for the hashing job, which keeps the analyses on a re-tag and clears them once the
+    /// audio turns out to differ. A row that was never hashed can't be compared, so its analyses
+    /// are cleared right away; returns whether they were
+    pub fn refresh(conn: &Connection, audio_file: &AudioFile) -> Result<bool> {
+        let Some(id) = audio_file.id else {
+            return Ok(false);
+        };
+
+        let (stored_hash, previous_hash): (Option<String>, Option<String>) = conn.query_row(
+            "SELECT content_hash, previous_content_hash FROM audio_files WHERE id = ?1",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        // A second change before the hashing job ran still compares against the last known hash
+        let previous_hash = previous_hash.or(stored_hash);
+
+        Self::update(conn, audio_file)?;
+        conn.execute(
+            "UPDATE audio_files SET
+                codec = ?1, container = ?2, sample_rate = ?3, channels = ?4, bit_depth = ?5,
+                bitrate = ?6, file_size = ?7, decodable = ?8, decode_error = ?9,
+                file_modified = ?10, content_hash = NULL, previous_content_hash = ?11, missing = 0
+            WHERE id = ?12",
+            params![
+                audio_file.codec, audio_file.container, audio_file.sample_rate,
+                audio_file.channels, audio_file.bit_depth, audio_file.bitrate,
+                audio_file.file_size, audio_file.decodable, audio_file.decode_error,
+                audio_file.file_modified, previous_hash, id
+            ],
+        )?;
+        if previous_hash.is_none() {
+            Self::clear_analyses(conn, id)?;
+        }
+        Ok(previous_hash.is_none())
+    }
+
+    /// Drop the analyses and fingerprint of a row whose audio changed, for the batch commands to redo
+    pub(super) fn clear_analyses(conn: &Connection, id: i64) -> Result<()> {
+        conn.execute(
+            "UPDATE audio_files SET
+                loudness_integrated = NULL, loudness_range = NULL, true_peak = NULL,
+                loop_start = NULL, loop_end = NULL, loop_seamless = NULL, key_confidence = NULL,
+                leading_silence = NULL, trailing_silence = NULL, mostly_silent = NULL,
+                bpm_confidence = NULL, quality_grade = NULL,
+                clipping_ratio = NULL, noise_floor = NULL, bandwidth = NULL
+            WHERE id = ?1",
+            [id],
+        )?;
+        conn.execute("DELETE FROM audio_fingerprints WHERE audio_file_id = ?1", [id])?;
+        Ok(())
+    }
+
+    /// Apply a patch to an existing audio file, touching only the columns it sets or clears
+    pub fn patch(conn: &Connection, id: i64, patch: &AudioFilePatch) -> Result<()> {
+        fn push<'a, T: ToSql>(changes: &mut Vec<(&'static str, &'a dyn ToSql)>, column: &'static str, field: &'a FieldPatch<T>) {
+            match field {
+                FieldPatch::Leave => {}
+                FieldPatch::Clear => changes.push((column, &Null)),
+                FieldPatch::Set(value) => changes.push((column, value)),
+            }
+        }
+
+        let mut changes = Vec::new();
+        push(&mut changes, "title", &patch.title);
+        push(&mut changes, "artist", &patch.artist);
+        push(&mut changes, "album", &patch.album);
+        push(&mut changes, "album_artist", &patch.album_artist);
+        push(&mut changes, "genre", &patch.genre);
+        push(&mut changes, "year", &patch.year);
+        push(&mut changes, "date", &patch.date);
+        push(&mut changes, "track_number", &patch.track_number);
+        push(&mut changes, "total_tracks", &patch.total_tracks);
+        push(&mut changes, "disc_number", &patch.disc_number);
+        push(&mut changes, "total_discs", &patch.total_discs);
+        push(&mut changes, "duration", &patch.duration);
+        push(&mut changes, "composer", &patch.composer);
+        push(&mut changes, "conductor", &patch.conductor);
+        push(&mut changes, "lyricist", &patch.lyricist);
+        push(&mut changes, "original_artist", &patch.original_artist);
+        push(&mut changes, "remixer", &patch.remixer);
+        push(&mut changes, "arranger", &patch.arranger);
+        push(&mut changes, "engineer", &patch.engineer);
+        push(&mut changes, "producer", &patch.producer);
+        push(&mut changes, "dj_mixer", &patch.dj_mixer);
+        push(&mut changes, "mixer", &patch.mixer);
+        push(&mut changes, "content_group", &patch.content_group);
+        push(&mut changes, "subtitle", &patch.subtitle);
+        push(&mut changes, "initial_key", &patch.initial_key);
+        push(&mut changes, "bpm", &patch.bpm);
+        push(&mut changes, "language", &patch.language);
+        push(&mut changes, "media_type", &patch.media_type);
+        push(&mut changes, "original_filename", &patch.original_filename);
+        push(&mut changes, "original_lyricist", &patch.original_lyricist);
+        push(&mut changes, "original_release_time", &patch.original_release_time);
+        push(&mut changes, "playlist_delay", &patch.playlist_delay);
+        push(&mut changes, "recording_time", &patch.recording_time);
+        push(&mut changes, "release_time", &patch.release_time);
+        push(&mut changes, "tagging_time", &patch.tagging_time);
+        push(&mut changes, "encoding_time", &patch.encoding_time);
+        push(&mut changes, "encoding_settings", &patch.encoding_settings);
+        push(&mut changes, "encoded_by", &patch.encoded_by);
+        push(&mut changes, "copyright", &patch.copyright);
+        push(&mut changes, "file_owner", &patch.file_owner);
+        push(&mut changes, "internet_radio_station_name", &patch.internet_radio_station_name);
+        push(&mut changes, "internet_radio_station_owner", &patch.internet_radio_station_owner);
+        push(&mut changes, "isrc", &patch.isrc);
+        push(&mut changes, "publisher", &patch.publisher);
+        push(&mut changes, "mood", &patch.mood);
+        push(&mut changes, "occasion", &patch.occasion);
+        push(&mut changes, "tempo", &patch.tempo);
+        push(&mut changes, "content_type", &patch.content_type);
+        push(&mut changes, "category", &patch.category);
+        push(&mut changes, "subcategory", &patch.subcategory);
+        push(&mut changes, "auto_tagged", &patch.auto_tagged);
+        push(&mut changes, "auto_tag_date", &patch.auto_tag_date);
+        push(&mut changes, "auto_tag_version", &patch.auto_tag_version);
+
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        let assignments: Vec<String> = changes.iter()
+            .enumerate()
+            .map(|(i, (column, _))| format!("{} = ?{}", column, i + 1))
+            .collect();
+        let query = format!(
+            "UPDATE audio_files SET {} WHERE id = ?{}",
+            assignments.join(", "),
+            changes.len() + 1
+        );
+
+        let mut values: Vec<&dyn ToSql> = changes.into_iter().map(|(_, value)| value).collect();
+        values.push(&id);
+        conn.execute(&query, params_from_iter(values))?;
+        Ok(())
+    }
+
     /// Delete an audio file from the database
     pub fn delete(conn: &Connection, id: i64) -> Result<()> {
         conn.execute(
@@ -29,7 +29,12 @@ pub fn build_select_query(existing_columns: &HashSet<String>) -> (String, Vec<St
         "encoding_time", "encoding_settings", "encoded_by", "copyright", 
         "file_owner", "internet_radio_station_name", "internet_radio_station_owner", 
         "isrc", "publisher", "mood", "occasion", "tempo", "content_type", "category",
-        "auto_tagged", "auto_tag_date", "auto_tag_version"
+        "subcategory", "auto_tagged", "auto_tag_date", "auto_tag_version",
+        "loudness_integrated", "loudness_range", "true_peak", "loop_start", "loop_end",
+        "loop_seamless", "key_confidence", "leading_silence", "trailing_silence", "mostly_silent",
+        "content_hash", "bpm_confidence", "quality_grade", "clipping_ratio", "noise_floor",
+        "bandwidth", "sample_rate", "bit_depth", "codec", "container", "channels", "bitrate", "file_size",
+        "decodable", "decode_error", "file_modified", "missing"
     ];
     
     let mut selected_columns = Vec::new();
@@ -79,6 +84,15 @@ pub fn map_row_to_audio_file(
         }
     };
     
+    // Helper to get optional i64 value
+    let get_optional_i64 = |col_name: &str| -> Result<Option<i64>> {
+        if let Some(idx) = get_value_by_name(col_name) {
+            row.get(idx)
+        } else {
+            Ok(None)
+        }
+    };
+    
     // Helper to get optional f64 value
     let get_optional_f64 = |col_name: &str| -> Result<Option<f64>> {
         if let Some(idx) = get_value_by_name(col_name) {
@@ -164,8 +178,36 @@ pub fn map_row_to_audio_file(
         tempo: get_optional("tempo")?,
         content_type: get_optional("content_type")?,
         category: get_optional("category")?,
+        subcategory: get_optional("subcategory")?,
         auto_tagged: get_optional_bool("auto_tagged")?,
         auto_tag_date: get_optional("auto_tag_date")?,
         auto_tag_version: get_optional("auto_tag_version")?,
+        loudness_integrated: get_optional_f64("loudness_integrated")?,
+        loudness_range: get_optional_f64("loudness_range")?,
+        true_peak: get_optional_f64("true_peak")?,
+        loop_start: get_optional_i64("loop_start")?,
+        loop_end: get_optional_i64("loop_end")?,
+        loop_seamless: get_optional_bool("loop_seamless")?,
+        key_confidence: get_optional_f64("key_confidence")?,
+        leading_silence: get_optional_f64("leading_silence")?,
+        trailing_silence: get_optional_f64("trailing_silence")?,
+        mostly_silent: get_optional_bool("mostly_silent")?,
+        content_hash: get_optional("content_hash")?,
+        bpm_confidence: get_optional_f64("bpm_confidence")?,
+        quality_grade: get_optional("quality_grade")?,
+        clipping_ratio: get_optional_f64("clipping_ratio")?,
+        noise_floor: get_optional_f64("noise_floor")?,
+        bandwidth: get_optional_f64("bandwidth")?,
+        sample_rate: get_optional_i32("sample_rate")?.map(|v| v as u32),
+        bit_depth: get_optional_i32("bit_depth")?.map(|v| v as u32),
+        codec: get_optional("codec")?,
+        container: get_optional("container")?,
+        channels: get_optional_i32("channels")?.map(|v| v as u32),
+        bitrate: get_optional_i32("bitrate")?.map(|v| v as u32),
+        file_size: get_optional_i64("file_size")?.map(|v| v as u64),
+        decodable: get_optional_bool("decodable")?,
+        decode_error: get_optional("decode_error")?,
+        file_modified: get_optional_i64("file_modified")?,
+        missing: get_optional_bool("missing")?,
     })
 }
\ No newline at end of file
@@ -1,5 +1,8 @@
 use rusqlite::{Connection, params, Result};
+use crate::library_sync::KnownFile;
+use crate::models::{KeyEstimate, LoopAnalysis, LoopableProposal, LoudnessAnalysis, QualityAnalysis, QualityProposal, SilenceAnalysis, StreamProperties, TempoEstimate, UnplayableFile};
 use super::AudioFileOps;
+use std::path::MAIN_SEPARATOR;
 
 impl AudioFileOps {
     /// Update the duration of an audio file
@@ -20,6 +23,234 @@ impl AudioFileOps {
         Ok(())
     }
 
+    /// Store the loudness analysis of an audio file
+    pub fn update_loudness(conn: &Connection, id: i64, loudness: &LoudnessAnalysis) -> Result<()> {
+        conn.execute(
+            "UPDATE audio_files SET loudness_integrated = ?1, loudness_range = ?2, true_peak = ?3 WHERE id = ?4",
+            params![loudness.integrated, loudness.range, loudness.true_peak, id],
+        )?;
+        Ok(())
+    }
+
+    /// Store the suggested loop points of an audio file and whether it loops cleanly
+    pub fn update_loop_analysis(conn: &Connection, id: i64, analysis: &LoopAnalysis) -> Result<()> {
+        conn.execute(
+            "UPDATE audio_files SET loop_start = ?1, loop_end = ?2, loop_seamless = ?3 WHERE id = ?4",
+            params![analysis.loop_start as i64, analysis.loop_end as i64, analysis.issues.is_empty(), id],
+        )?;
+        Ok(())
+    }
+
+    /// Store a detected key; non-tonal audio only records the confidence and keeps any existing key
+    pub fn update_key(conn: &Connection, id: i64, estimate: &KeyEstimate) -> Result<()> {
+        conn.execute(
+            "UPDATE audio_files SET initial_key = COALESCE(?1, initial_key), key_confidence = ?2 WHERE id = ?3",
+            params![estimate.key, estimate.confidence, id],
+        )?;
+        Ok(())
+    }
+
+    /// Store a detected tempo; non-rhythmic audio only records the confidence and keeps any existing BPM
+    pub fn update_tempo(conn: &Connection, id: i64, estimate: &TempoEstimate) -> Result<()> {
+        conn.execute(
+            "UPDATE audio_files SET bpm = COALESCE(?1, bpm), bpm_confidence = ?2 WHERE id = ?3",
+            params![estimate.bpm.map(|bpm| bpm.round() as u32), estimate.confidence, id],
+        )?;
+        Ok(())
+    }
+
+    /// Store the dead air at either end of an audio file
+    pub fn update_silence(conn: &Connection, id: i64, silence: &SilenceAnalysis) -> Result<()> {
+        conn.execute(
+            "UPDATE audio_files SET leading_silence = ?1, trailing_silence = ?2, mostly_silent = ?3 WHERE id = ?4",
+            params![silence.leading_silence, silence.trailing_silence, silence.mostly_silent, id],
+        )?;
+        Ok(())
+    }
+
+    /// Store the hash of an audio file's decoded audio. When the file changed on disk since it was
+    /// last hashed and the audio differs, rather than only its tags, the analyses are cleared
+    pub fn update_content_hash(conn: &Connection, id: i64, content_hash: &str) -> Result<()> {
+        let previous_hash: Option<String> = conn.query_row(
+            "SELECT previous_content_hash FROM audio_files WHERE id = ?1",
+            [id],
+            |row| row.get(0),
+        )?;
+        if previous_hash.is_some_and(|previous| previous != content_hash) {
+            Self::clear_analyses(conn, id)?;
+        }
+        conn.execute(
+            "UPDATE audio_files SET content_hash = ?1, previous_content_hash = NULL WHERE id = ?2",
+            params![content_hash, id],
+        )?;
+        Ok(())
+    }
+
+    /// Store the quality measurements of an audio file and the grade they add up to
+    pub fn update_quality(conn: &Connection, id: i64, analysis: &QualityAnalysis) -> Result<()> {
+        conn.execute(
+            "UPDATE audio_files SET quality_grade = ?1, clipping_ratio = ?2, noise_floor = ?3, bandwidth = ?4,
+                 sample_rate = ?5, bit_depth = ?6 WHERE id = ?7",
+            params![analysis.grade, analysis.clipping_ratio, analysis.noise_floor, analysis.bandwidth,
+                    analysis.sample_rate, analysis.bit_depth, id],
+        )?;
+        Ok(())
+    }
+
+    /// Store the codec, container and format of an audio file's stream, with its size and bitrate
+    pub fn update_stream_properties(conn: &Connection, id: i64, stream: &StreamProperties) -> Result<()> {
+        conn.execute(
+            "UPDATE audio_files SET codec = ?1, container = ?2, sample_rate = ?3, channels = ?4, bit_depth = ?5,
+                 bitrate = ?6, file_size = ?7 WHERE id = ?8",
+            params![stream.codec, stream.container, stream.sample_rate, stream.channels, stream.bit_depth,
+                    stream.bitrate, stream.file_size, id],
+        )?;
+        Ok(())
+    }
+
+    /// Record whether an audio file decodes; `decode_error` says why it doesn't
+    pub fn update_decodability(conn: &Connection, id: i64, decode_error: Option<&str>) -> Result<()> {
+        conn.execute(
+            "UPDATE audio_files SET decodable = ?1, decode_error = ?2 WHERE id = ?3",
+            params![decode_error.is_none(), decode_error, id],
+        )?;
+        Ok(())
+    }
+
+    /// Files found not to decode, with the names of the atmospheres using them
+    pub fn get_unplayable(conn: &Connection) -> Result<Vec<UnplayableFile>> {
+        let mut stmt = conn.prepare(
+            "SELECT af.id, af.file_path, af.decode_error, GROUP_CONCAT(a.name, char(31))
+             FROM audio_files af
+             LEFT JOIN atmosphere_sounds s ON s.audio_file_id = af.id
+             LEFT JOIN atmospheres a ON a.id = s.atmosphere_id
+             WHERE af.decodable = 0
+             GROUP BY af.id
+             ORDER BY af.file_path"
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            let atmospheres: Option<String> = row.get(3)?;
+            Ok(UnplayableFile {
+                audio_file_id: row.get(0)?,
+                file_path: row.get(1)?,
+                decode_error: row.get(2)?,
+                atmospheres: atmospheres
+                    .map(|names| names.split('\u{1f}').map(str::to_string).collect())
+                    .unwrap_or_default(),
+            })
+        })?;
+
+        rows.collect()
+    }
+
+    /// The size, modification time and missing flag recorded for every file
+    pub fn get_library_states(conn: &Connection) -> Result<Vec<KnownFile>> {
+        let mut stmt = conn.prepare(
+            "SELECT id, file_path, file_size, file_modified, missing FROM audio_files"
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(KnownFile {
+                id: row.get(0)?,
+                file_path: row.get(1)?,
+                file_size: row.get::<_, Option<i64>>(2)?.map(|v| v as u64),
+                file_modified: row.get(3)?,
+                missing: row.get::<_, Option<bool>>(4)?.unwrap_or(false),
+            })
+        })?;
+
+        rows.collect()
+    }
+
+    /// Record the size and modification time a file was found with, clearing its missing flag
+    pub fn update_disk_state(conn: &Connection, id: i64, file_size: u64, file_modified: i64) -> Result<()> {
+        conn.execute(
+            "UPDATE audio_files SET file_size = ?1, file_modified = ?2, missing = 0 WHERE id = ?3",
+            params![file_size, file_modified, id],
+        )?;
+        Ok(())
+    }
+
+    /// Flag a file a library sync no longer found on disk
+    pub fn mark_missing(conn: &Connection, id: i64) -> Result<()> {
+        conn.execute("UPDATE audio_files SET missing = 1 WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    /// Flag the file at `path`, or every file under it when it was a directory, as missing;
+    /// returns how many weren't flagged before
+    pub fn mark_missing_under(conn: &Connection, path: &str) -> Result<usize> {
+        conn.execute(
+            "UPDATE audio_files SET missing = 1
+             WHERE (file_path = ?1 OR substr(file_path, 1, length(?1) + 1) = ?1 || ?2)
+               AND NOT COALESCE(missing, 0)",
+            params![path, MAIN_SEPARATOR.to_string()],
+        )
+    }
+
+    /// Point the row of a moved file, or the rows under a moved directory, at the new path so
+    /// tags and atmospheres follow it; returns how many rows moved
+    pub fn rename_path(conn: &Connection, from: &str, to: &str) -> Result<usize> {
+        conn.execute(
+            "UPDATE audio_files SET file_path = ?2 || substr(file_path, length(?1) + 1), missing = 0
+             WHERE file_path = ?1 OR substr(file_path, 1, length(?1) + 1) = ?1 || ?3",
+            params![from, to, MAIN_SEPARATOR.to_string()],
+        )
+    }
+
+    /// Graded files whose `quality` tag is missing or differs from the grade
+    pub fn get_quality_proposals(conn: &Connection) -> Result<Vec<QualityProposal>> {
+        let mut stmt = conn.prepare(
+            "SELECT id, file_path, quality_grade,
+                    (SELECT tag_value FROM rpg_tags WHERE audio_file_id = af.id AND tag_type = 'quality' LIMIT 1),
+                    clipping_ratio, noise_floor, bandwidth
+             FROM audio_files af
+             WHERE quality_grade IS NOT NULL AND NOT EXISTS (
+                 SELECT 1 FROM rpg_tags
+                 WHERE audio_file_id = af.id AND tag_type = 'quality' AND tag_value = af.quality_grade COLLATE NOCASE
+             )
+             ORDER BY file_path"
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(QualityProposal {
+                audio_file_id: row.get(0)?,
+                file_path: row.get(1)?,
+                quality_grade: row.get(2)?,
+                current_quality: row.get(3)?,
+                clipping_ratio: row.get(4)?,
+                noise_floor: row.get(5)?,
+                bandwidth: row.get(6)?,
+            })
+        })?;
+
+        rows.collect()
+    }
+
+    /// Files that loop cleanly but don't carry the given keyword yet
+    pub fn get_loopable_proposals(conn: &Connection, keyword: &str) -> Result<Vec<LoopableProposal>> {
+        let mut stmt = conn.prepare(
+            "SELECT id, file_path, loop_start, loop_end FROM audio_files af
+             WHERE loop_seamless = 1 AND NOT EXISTS (
+                 SELECT 1 FROM rpg_tags
+                 WHERE audio_file_id = af.id AND tag_type IN ('keyword', 'keywords') AND tag_value = ?1
+             )
+             ORDER BY file_path"
+        )?;
+
+        let rows = stmt.query_map([keyword], |row| {
+            Ok(LoopableProposal {
+                audio_file_id: row.get(0)?,
+                file_path: row.get(1)?,
+                loop_start: row.get(2)?,
+                loop_end: row.get(3)?,
+            })
+        })?;
+
+        rows.collect()
+    }
+
     /// Update multiple metadata fields at once
     pub fn update_metadata(conn: &Connection, id: i64, field: &str, value: Option<&str>) -> Result<()> {
         // Validate field name to prevent SQL injection
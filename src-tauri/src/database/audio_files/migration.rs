@@ -53,6 +53,38 @@ pub fn apply_migrations(conn: &Connection) -> Result<()> {
         ("tempo", "TEXT"),
         ("content_type", "TEXT"),
         ("category", "TEXT"),
+        ("subcategory", "TEXT"),
+        ("auto_tagged", "BOOLEAN DEFAULT FALSE"),
+        ("auto_tag_date", "TEXT"),
+        ("auto_tag_version", "TEXT"),
+        ("loudness_integrated", "REAL"),
+        ("loudness_range", "REAL"),
+        ("true_peak", "REAL"),
+        ("loop_start", "INTEGER"),
+        ("loop_end", "INTEGER"),
+        ("loop_seamless", "BOOLEAN"),
+        ("key_confidence", "REAL"),
+        ("leading_silence", "REAL"),
+        ("trailing_silence", "REAL"),
+        ("mostly_silent", "BOOLEAN"),
+        ("content_hash", "TEXT"),
+        ("previous_content_hash", "TEXT"),
+        ("bpm_confidence", "REAL"),
+        ("quality_grade", "TEXT"),
+        ("clipping_ratio", "REAL"),
+        ("noise_floor", "REAL"),
+        ("bandwidth", "REAL"),
+        ("sample_rate", "INTEGER"),
+        ("bit_depth", "INTEGER"),
+        ("codec", "TEXT"),
+        ("container", "TEXT"),
+        ("channels", "INTEGER"),
+        ("bitrate", "INTEGER"),
+        ("file_size", "INTEGER"),
+        ("decodable", "BOOLEAN"),
+        ("decode_error", "TEXT"),
+        ("file_modified", "INTEGER"),
+        ("missing", "BOOLEAN"),
     ];
     
     for (column_name, column_type) in columns_to_add {
@@ -64,7 +64,39 @@ impl AudioFileOps {
                 occasion TEXT,
                 tempo TEXT,
                 content_type TEXT,
-                category TEXT
+                category TEXT,
+                subcategory TEXT,
+                auto_tagged BOOLEAN DEFAULT FALSE,
+                auto_tag_date TEXT,
+                auto_tag_version TEXT,
+                loudness_integrated REAL,
+                loudness_range REAL,
+                true_peak REAL,
+                loop_start INTEGER,
+                loop_end INTEGER,
+                loop_seamless BOOLEAN,
+                key_confidence REAL,
+                leading_silence REAL,
+                trailing_silence REAL,
+                mostly_silent BOOLEAN,
+                content_hash TEXT,
+                previous_content_hash TEXT,
+                bpm_confidence REAL,
+                quality_grade TEXT,
+                clipping_ratio REAL,
+                noise_floor REAL,
+                bandwidth REAL,
+                sample_rate INTEGER,
+                bit_depth INTEGER,
+                codec TEXT,
+                container TEXT,
+                channels INTEGER,
+                bitrate INTEGER,
+                file_size INTEGER,
+                decodable BOOLEAN,
+                decode_error TEXT,
+                file_modified INTEGER,
+                missing BOOLEAN
             )",
             [],
         )?;
@@ -78,6 +110,11 @@ impl AudioFileOps {
         // Apply migrations for existing databases
         migration::apply_migrations(conn)?;
 
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_audio_files_content_hash ON audio_files(content_hash)",
+            [],
+        )?;
+
         Ok(())
     }
 }
@@ -85,7 +122,9 @@ impl AudioFileOps {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::AudioFile;
+    use crate::database::schema::SchemaManager;
+    use crate::database::RpgTagRepository;
+    use crate::models::{AudioFile, AudioFilePatch, FieldPatch, LoopAnalysis, QualityAnalysis, StreamSearchRequest};
 
     fn create_test_db() -> Connection {
         let conn = Connection::open_in_memory().unwrap();
@@ -93,6 +132,16 @@ mod tests {
         conn
     }
 
+    /// The whole schema, for queries that join the tag and atmosphere tables, with one file saved
+    fn create_schema_test_db() -> (Connection, i64) {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute("PRAGMA foreign_keys = ON", []).unwrap();
+        SchemaManager::new(&conn).create_tables(&conn).unwrap();
+        AudioFileOps::create_table(&conn).unwrap();
+        let file_id = AudioFileOps::save(&conn, &create_test_audio_file()).unwrap();
+        (conn, file_id)
+    }
+
     fn create_test_audio_file() -> AudioFile {
         AudioFile {
             id: None,
@@ -146,6 +195,37 @@ mod tests {
             tempo: None,
             content_type: None,
             category: None,
+            subcategory: None,
+            auto_tagged: None,
+            auto_tag_date: None,
+            auto_tag_version: None,
+            loudness_integrated: None,
+            loudness_range: None,
+            true_peak: None,
+            loop_start: None,
+            loop_end: None,
+            loop_seamless: None,
+            key_confidence: None,
+            leading_silence: None,
+            trailing_silence: None,
+            mostly_silent: None,
+            content_hash: None,
+            bpm_confidence: None,
+            quality_grade: None,
+            clipping_ratio: None,
+            noise_floor: None,
+            bandwidth: None,
+            sample_rate: None,
+            bit_depth: None,
+            codec: None,
+            container: None,
+            channels: None,
+            bitrate: None,
+            file_size: None,
+            decodable: None,
+            decode_error: None,
+            file_modified: None,
+            missing: None,
         }
     }
 
@@ -195,6 +275,193 @@ mod tests {
         assert_eq!(files[0].bpm, Some(140));
     }
 
+    #[test]
+    fn test_patch_sets_and_clears_columns() {
+        let conn = create_test_db();
+        let id = AudioFileOps::save(&conn, &create_test_audio_file()).unwrap();
+
+        let patch = AudioFilePatch {
+            title: FieldPatch::Clear,
+            mood: FieldPatch::Set("tense".to_string()),
+            auto_tagged: FieldPatch::Set(true),
+            ..Default::default()
+        };
+        AudioFileOps::patch(&conn, id, &patch).unwrap();
+
+        let file = AudioFileOps::get_by_path(&conn, "/test/path/song.mp3").unwrap();
+        assert_eq!(file.title, None);
+        assert_eq!(file.mood.as_deref(), Some("tense"));
+        assert_eq!(file.auto_tagged, Some(true));
+        assert_eq!(file.artist.as_deref(), Some("Test Artist"));
+        assert_eq!(file.bpm, Some(120));
+    }
+
+    #[test]
+    fn test_find_by_content_hash_after_move() {
+        let conn = create_test_db();
+        let id = AudioFileOps::save(&conn, &create_test_audio_file()).unwrap();
+        AudioFileOps::update_content_hash(&conn, id, "ab12").unwrap();
+
+        let moved = AudioFile { file_path: "/moved/song.mp3".to_string(), ..Default::default() };
+        let moved_id = AudioFileOps::save(&conn, &moved).unwrap();
+        AudioFileOps::update_content_hash(&conn, moved_id, "ab12").unwrap();
+
+        let same: Vec<_> = AudioFileOps::get_by_content_hash(&conn, "ab12").unwrap()
+            .into_iter()
+            .map(|file| file.id)
+            .collect();
+        assert_eq!(same, vec![Some(moved_id), Some(id)]);
+        assert!(AudioFileOps::get_by_content_hash(&conn, "cd34").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_proposes_loopable_keyword_until_tagged() {
+        let (conn, file_id) = create_schema_test_db();
+        let repo = RpgTagRepository::new();
+        let analysis = LoopAnalysis { loop_start: 12, loop_end: 96000, ..Default::default() };
+        AudioFileOps::update_loop_analysis(&conn, file_id, &analysis).unwrap();
+
+        let proposals = AudioFileOps::get_loopable_proposals(&conn, "util:loopable").unwrap();
+        assert_eq!(proposals.len(), 1);
+        assert_eq!((proposals[0].audio_file_id, proposals[0].loop_start, proposals[0].loop_end), (file_id, Some(12), Some(96000)));
+
+        repo.add(&conn, file_id, "keyword", "util:loopable").unwrap();
+        assert!(AudioFileOps::get_loopable_proposals(&conn, "util:loopable").unwrap().is_empty());
+
+        // A file with seam issues is never proposed
+        repo.remove(&conn, file_id, "keyword", "util:loopable").unwrap();
+        let clicking = LoopAnalysis { issues: vec!["Click at the loop seam".to_string()], ..analysis };
+        AudioFileOps::update_loop_analysis(&conn, file_id, &clicking).unwrap();
+        assert!(AudioFileOps::get_loopable_proposals(&conn, "util:loopable").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_proposes_quality_grade_until_tagged() {
+        let (conn, file_id) = create_schema_test_db();
+        let repo = RpgTagRepository::new();
+        assert!(AudioFileOps::get_quality_proposals(&conn).unwrap().is_empty());
+
+        let analysis = QualityAnalysis { sample_rate: 44100, bandwidth: Some(11000.0), grade: "Low".to_string(), ..Default::default() };
+        AudioFileOps::update_quality(&conn, file_id, &analysis).unwrap();
+        repo.add(&conn, file_id, "quality", "High").unwrap();
+
+        let proposals = AudioFileOps::get_quality_proposals(&conn).unwrap();
+        assert_eq!(proposals.len(), 1);
+        assert_eq!((proposals[0].quality_grade.as_str(), proposals[0].current_quality.as_deref()), ("Low", Some("High")));
+        assert_eq!(proposals[0].bandwidth, Some(11000.0));
+
+        repo.remove(&conn, file_id, "quality", "High").unwrap();
+        repo.add(&conn, file_id, "quality", "low").unwrap();
+        assert!(AudioFileOps::get_quality_proposals(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reports_unplayable_files_with_their_atmospheres() {
+        let (conn, file_id) = create_schema_test_db();
+        let playable = AudioFile { file_path: "/tmp/rain.flac".into(), decodable: Some(true), ..Default::default() };
+        AudioFileOps::save(&conn, &playable).unwrap();
+        conn.execute("INSERT INTO atmospheres (id, name, title) VALUES (1, 'Tavern, night', ''), (2, 'Storm', '')", []).unwrap();
+        conn.execute("INSERT INTO atmosphere_sounds (atmosphere_id, audio_file_id) VALUES (1, ?1), (2, ?1)", [file_id]).unwrap();
+        assert!(AudioFileOps::get_unplayable(&conn).unwrap().is_empty());
+
+        AudioFileOps::update_decodability(&conn, file_id, Some("Failed to probe format: unsupported format")).unwrap();
+        let unplayable = AudioFileOps::get_unplayable(&conn).unwrap();
+        assert_eq!(unplayable.len(), 1);
+        assert_eq!(unplayable[0].decode_error.as_deref(), Some("Failed to probe format: unsupported format"));
+        let mut atmospheres = unplayable[0].atmospheres.clone();
+        atmospheres.sort();
+        assert_eq!(atmospheres, vec!["Storm", "Tavern, night"]);
+
+        AudioFileOps::update_decodability(&conn, file_id, None).unwrap();
+        assert!(AudioFileOps::get_unplayable(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_changed_audio_drops_its_analyses_once_hashed() {
+        let (conn, file_id) = create_schema_test_db();
+        AudioFileOps::update_content_hash(&conn, file_id, "abc123").unwrap();
+        let analysis = QualityAnalysis { sample_rate: 44100, grade: "Good".to_string(), ..Default::default() };
+        AudioFileOps::update_quality(&conn, file_id, &analysis).unwrap();
+        AudioFileOps::update_disk_state(&conn, file_id, 1000, 50).unwrap();
+        AudioFileOps::mark_missing(&conn, file_id).unwrap();
+
+        let state = AudioFileOps::get_library_states(&conn).unwrap().into_iter().find(|f| f.id == file_id).unwrap();
+        assert_eq!((state.file_size, state.file_modified, state.missing), (Some(1000), Some(50), true));
+
+        let reread = AudioFile {
+            id: Some(file_id), file_path: "/test/path/song.mp3".into(), title: Some("Rain v2".into()),
+            file_size: Some(1200), file_modified: Some(90), ..Default::default()
+        };
+        // The analyses wait for the hashing job to tell a re-tag from new audio
+        assert!(!AudioFileOps::refresh(&conn, &reread).unwrap());
+        let stored = AudioFileOps::get_by_id(&conn, file_id).unwrap();
+        assert_eq!(stored.title.as_deref(), Some("Rain v2"));
+        assert_eq!((stored.file_size, stored.file_modified, stored.missing), (Some(1200), Some(90), Some(false)));
+        assert_eq!((stored.content_hash, stored.quality_grade.as_deref()), (None, Some("Good")));
+
+        // Same audio under new tags keeps its analyses
+        AudioFileOps::update_content_hash(&conn, file_id, "abc123").unwrap();
+        assert_eq!(AudioFileOps::get_by_id(&conn, file_id).unwrap().quality_grade.as_deref(), Some("Good"));
+
+        AudioFileOps::refresh(&conn, &reread).unwrap();
+        AudioFileOps::update_content_hash(&conn, file_id, "def456").unwrap();
+        let stored = AudioFileOps::get_by_id(&conn, file_id).unwrap();
+        assert_eq!((stored.content_hash.as_deref(), stored.quality_grade), (Some("def456"), None));
+
+        // Without a hash to compare against, the analyses go right away
+        AudioFileOps::update_quality(&conn, file_id, &analysis).unwrap();
+        conn.execute("UPDATE audio_files SET content_hash = NULL WHERE id = ?1", [file_id]).unwrap();
+        assert!(AudioFileOps::refresh(&conn, &reread).unwrap());
+        assert_eq!(AudioFileOps::get_by_id(&conn, file_id).unwrap().quality_grade, None);
+    }
+
+    #[test]
+    fn test_moving_a_directory_moves_only_the_rows_under_it() {
+        let (conn, _file_id) = create_schema_test_db();
+        for path in ["/library/pack/rain.wav", "/library/pack/wind.wav", "/library/pack2/fire.wav"] {
+            AudioFileOps::save(&conn, &AudioFile { file_path: path.into(), ..Default::default() }).unwrap();
+        }
+
+        assert_eq!(AudioFileOps::rename_path(&conn, "/library/pack", "/library/weather").unwrap(), 2);
+        assert!(AudioFileOps::get_by_path(&conn, "/library/weather/rain.wav").is_ok());
+        assert!(AudioFileOps::get_by_path(&conn, "/library/pack2/fire.wav").is_ok());
+
+        assert_eq!(AudioFileOps::mark_missing_under(&conn, "/library/weather").unwrap(), 2);
+        assert_eq!(AudioFileOps::mark_missing_under(&conn, "/library/weather/rain.wav").unwrap(), 0);
+        assert_eq!(AudioFileOps::get_by_path(&conn, "/library/pack2/fire.wav").unwrap().missing, None);
+    }
+
+    #[test]
+    fn test_search_by_stream_properties() {
+        let conn = create_test_db();
+        let stream = |path: &str, codec: &str, channels: u32, bitrate: u32| AudioFile {
+            file_path: path.to_string(),
+            codec: Some(codec.to_string()),
+            container: Some(if codec == "mp3" { "mp3" } else { "wav" }.to_string()),
+            sample_rate: Some(44100),
+            channels: Some(channels),
+            bitrate: Some(bitrate),
+            file_size: Some(1_000_000),
+            ..Default::default()
+        };
+        AudioFileOps::save(&conn, &stream("/sfx/door.wav", "pcm_s16le", 1, 706)).unwrap();
+        AudioFileOps::save(&conn, &stream("/music/theme.mp3", "mp3", 2, 64)).unwrap();
+        AudioFileOps::save(&conn, &stream("/sfx/step.mp3", "mp3", 1, 128)).unwrap();
+
+        let paths = |request: StreamSearchRequest| -> Vec<String> {
+            AudioFileOps::search_by_stream(&conn, &request).unwrap().into_iter().map(|file| file.file_path).collect()
+        };
+        assert_eq!(paths(StreamSearchRequest { channels: Some(1), ..Default::default() }), vec!["/sfx/door.wav", "/sfx/step.mp3"]);
+        assert_eq!(
+            paths(StreamSearchRequest { codecs: Some(vec!["MP3".to_string()]), max_bitrate: Some(96), ..Default::default() }),
+            vec!["/music/theme.mp3"]
+        );
+        assert!(paths(StreamSearchRequest::default()).is_empty());
+
+        let saved = AudioFileOps::get_by_path(&conn, "/sfx/door.wav").unwrap();
+        assert_eq!((saved.codec.as_deref(), saved.channels, saved.file_size), (Some("pcm_s16le"), Some(1), Some(1_000_000)));
+    }
+
     #[test]
     fn test_delete() {
         let conn = create_test_db();
@@ -0,0 +1,180 @@
+use rusqlite::{Connection, OptionalExtension, params, Result};
+use crate::fingerprint::Fingerprint;
+
+/// Descriptive columns a canonical row takes over from its duplicates where it has none
+const INHERITED_COLUMNS: [&str; 13] = [
+    "title", "artist", "album", "album_artist", "genre", "composer", "copyright",
+    "publisher", "mood", "occasion", "content_type", "category", "subcategory",
+];
+
+/// A stored fingerprint with what the duplicate finder shows about its file
+pub struct StoredFingerprint {
+    pub audio_file_id: i64,
+    pub file_path: String,
+    pub file_size: Option<u64>,
+    pub tag_count: i64,
+    pub fingerprint: Fingerprint,
+}
+
+/// Fingerprint storage and merging of duplicate audio files
+pub struct DuplicateOps;
+
+impl DuplicateOps {
+    pub fn save_fingerprint(conn: &Connection, audio_file_id: i64, fingerprint: &Fingerprint) -> Result<()> {
+        conn.execute(
+            "INSERT OR REPLACE INTO audio_fingerprints (audio_file_id, duration, fingerprint) VALUES (?1, ?2, ?3)",
+            params![audio_file_id, fingerprint.duration, fingerprint.to_bytes()],
+        )?;
+        Ok(())
+    }
+
+    /// Ids and paths of files without a fingerprint
+    pub fn get_unfingerprinted(conn: &Connection) -> Result<Vec<(i64, String)>> {
+        let mut stmt = conn.prepare(
+            "SELECT id, file_path FROM audio_files
+             WHERE id NOT IN (SELECT audio_file_id FROM audio_fingerprints)
+             ORDER BY file_path"
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    pub fn get_fingerprints(conn: &Connection) -> Result<Vec<StoredFingerprint>> {
+        let mut stmt = conn.prepare(
+            "SELECT af.id, af.file_path, fp.duration, fp.fingerprint,
+                    (SELECT COUNT(*) FROM rpg_tags WHERE audio_file_id = af.id), af.file_size
+             FROM audio_fingerprints fp JOIN audio_files af ON af.id = fp.audio_file_id"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let bytes: Vec<u8> = row.get(3)?;
+            Ok(StoredFingerprint {
+                audio_file_id: row.get(0)?,
+                file_path: row.get(1)?,
+                fingerprint: Fingerprint::from_bytes(row.get(2)?, &bytes),
+                tag_count: row.get(4)?,
+                file_size: row.get::<_, Option<i64>>(5)?.map(|v| v as u64),
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Row a merged duplicate's path now belongs to, if it was merged and that row still exists
+    pub fn get_merged_canonical(conn: &Connection, file_path: &str) -> Result<Option<i64>> {
+        conn.query_row(
+            "SELECT m.canonical_id FROM merged_duplicates m JOIN audio_files af ON af.id = m.canonical_id
+             WHERE m.file_path = ?1",
+            [file_path],
+            |row| row.get(0),
+        ).optional()
+    }
+
+    /// Move tags, virtual folder memberships and atmosphere references of the duplicates onto the
+    /// canonical row, fill its empty descriptive fields from them, then drop the duplicate rows
+    pub fn merge(conn: &Connection, canonical_id: i64, duplicate_ids: &[i64]) -> Result<()> {
+        let tx = conn.unchecked_transaction()?;
+        let inherit = INHERITED_COLUMNS.iter()
+            .map(|column| format!("{0} = COALESCE({0}, (SELECT {0} FROM audio_files WHERE id = ?2))", column))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        for &duplicate_id in duplicate_ids.iter().filter(|&&id| id != canonical_id) {
+            tx.execute(
+                "INSERT OR IGNORE INTO rpg_tags (audio_file_id, tag_type, tag_value)
+                 SELECT ?1, tag_type, tag_value FROM rpg_tags WHERE audio_file_id = ?2",
+                params![canonical_id, duplicate_id],
+            )?;
+            tx.execute(
+                "INSERT OR IGNORE INTO virtual_folder_contents (folder_id, audio_file_id, added_by, file_order, notes)
+                 SELECT folder_id, ?1, added_by, file_order, notes FROM virtual_folder_contents WHERE audio_file_id = ?2",
+                params![canonical_id, duplicate_id],
+            )?;
+            // Where both were in an atmosphere, the canonical row keeps its own settings
+            tx.execute(
+                "INSERT OR IGNORE INTO atmosphere_sounds
+                    (atmosphere_id, audio_file_id, volume, is_looping, is_muted, min_seconds, max_seconds)
+                 SELECT atmosphere_id, ?1, volume, is_looping, is_muted, min_seconds, max_seconds
+                 FROM atmosphere_sounds WHERE audio_file_id = ?2",
+                params![canonical_id, duplicate_id],
+            )?;
+            tx.execute(
+                &format!("UPDATE audio_files SET {} WHERE id = ?1", inherit),
+                params![canonical_id, duplicate_id],
+            )?;
+
+            tx.execute(
+                "INSERT OR REPLACE INTO merged_duplicates (file_path, canonical_id)
+                 SELECT file_path, ?1 FROM audio_files WHERE id = ?2",
+                params![canonical_id, duplicate_id],
+            )?;
+            tx.execute(
+                "UPDATE merged_duplicates SET canonical_id = ?1 WHERE canonical_id = ?2",
+                params![canonical_id, duplicate_id],
+            )?;
+
+            // Foreign keys aren't enforced on every connection, so dependents go explicitly
+            for table in ["rpg_tags", "virtual_folder_contents", "atmosphere_sounds", "audio_fingerprints"] {
+                tx.execute(&format!("DELETE FROM {} WHERE audio_file_id = ?1", table), [duplicate_id])?;
+            }
+            tx.execute("DELETE FROM auto_tag_history WHERE file_id = ?1", [duplicate_id])?;
+            tx.execute("DELETE FROM audio_files WHERE id = ?1", [duplicate_id])?;
+        }
+
+        tx.commit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{AtmosphereOps, AudioFileOps, SchemaManager};
+    use crate::models::AudioFile;
+
+    fn setup() -> (Connection, i64, i64) {
+        let conn = Connection::open_in_memory().expect("mem db");
+        SchemaManager::new(&conn).create_tables(&conn).expect("schema");
+        AudioFileOps::create_table(&conn).expect("audio_files");
+        AtmosphereOps::create_tables(&conn).expect("atmospheres");
+
+        let save = |path: &str, title: Option<&str>, mood: Option<&str>| {
+            let file = AudioFile {
+                file_path: path.to_string(),
+                title: title.map(String::from),
+                mood: mood.map(String::from),
+                ..Default::default()
+            };
+            AudioFileOps::save(&conn, &file).unwrap()
+        };
+        let canonical = save("/packs/a/thunder.wav", Some("Thunder Clap"), None);
+        let duplicate = save("/packs/b/thunder.mp3", Some("thunder_01"), Some("tense"));
+        (conn, canonical, duplicate)
+    }
+
+    #[test]
+    fn merges_references_onto_the_canonical_row() {
+        let (conn, canonical, duplicate) = setup();
+        conn.execute_batch(&format!(
+            "INSERT INTO rpg_tags (audio_file_id, tag_type, tag_value) VALUES
+                ({c}, 'genre', 'nature'), ({d}, 'genre', 'nature'), ({d}, 'mood', 'tense');
+             INSERT INTO virtual_folders (id, name) VALUES (1, 'Storms');
+             INSERT INTO virtual_folder_contents (folder_id, audio_file_id) VALUES (1, {d});
+             INSERT INTO atmospheres (id, name, title) VALUES (1, 'Storm', 'Storm');
+             INSERT INTO atmosphere_sounds (atmosphere_id, audio_file_id, volume) VALUES (1, {d}, 0.7);",
+            c = canonical, d = duplicate,
+        )).unwrap();
+        DuplicateOps::save_fingerprint(&conn, duplicate, &Fingerprint { duration: 2.0, words: vec![1, 2] }).unwrap();
+
+        DuplicateOps::merge(&conn, canonical, &[canonical, duplicate]).unwrap();
+
+        let count = |sql: &str| conn.query_row(sql, [], |row| row.get::<_, i64>(0)).unwrap();
+        assert_eq!(count(&format!("SELECT COUNT(*) FROM rpg_tags WHERE audio_file_id = {}", canonical)), 2);
+        assert_eq!(count(&format!("SELECT COUNT(*) FROM virtual_folder_contents WHERE audio_file_id = {}", canonical)), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM atmosphere_sounds WHERE volume = 0.7"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM audio_files"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM audio_fingerprints"), 0);
+
+        let merged = AudioFileOps::get_by_id(&conn, canonical).unwrap();
+        assert_eq!((merged.title.as_deref(), merged.mood.as_deref()), (Some("Thunder Clap"), Some("tense")));
+        assert_eq!(DuplicateOps::get_merged_canonical(&conn, "/packs/b/thunder.mp3").unwrap(), Some(canonical));
+        assert_eq!(DuplicateOps::get_merged_canonical(&conn, "/packs/c/thunder.ogg").unwrap(), None);
+    }
+}
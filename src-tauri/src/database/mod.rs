@@ -1,5 +1,7 @@
 use rusqlite::{Connection, Result};
-use crate::models::{AudioFile, RpgTag, TagVocabulary, AudioFileWithTags, Atmosphere, AtmosphereWithSounds, AtmosphereSoundMapping, AtmosphereCategory, VirtualFolder, VirtualFolderTree, VirtualFolderWithContents, FolderTemplate};
+use crate::fingerprint::Fingerprint;
+use crate::library_sync::KnownFile;
+use crate::models::{AudioFile, AudioFilePatch, KeyEstimate, LoopAnalysis, LoopableProposal, LoudnessAnalysis, QualityAnalysis, QualityProposal, SilenceAnalysis, StreamProperties, StreamSearchRequest, UnplayableFile, RpgTag, TagVocabulary, AudioFileWithTags, Atmosphere, AtmosphereWithSounds, AtmosphereSoundMapping, AtmosphereCategory, VirtualFolder, VirtualFolderTree, VirtualFolderWithContents, FolderTemplate};
 
 pub mod schema;
 pub mod audio_files;
@@ -9,6 +11,7 @@ pub mod search;
 pub mod atmospheres;
 pub mod virtual_folders;
 pub mod tag_mapping;
+pub mod duplicates;
 pub mod pool;
 
 pub use schema::SchemaManager;
@@ -19,6 +22,7 @@ pub use search::SearchRepository;
 pub use atmospheres::AtmosphereOps;
 pub use virtual_folders::VirtualFolderOps;
 pub use tag_mapping::TagMappingCache;
+pub use duplicates::{DuplicateOps, StoredFingerprint};
 pub use pool::DatabasePool;
 
 /// Main database struct that coordinates all database operations
@@ -97,6 +101,10 @@ impl Database {
         AudioFileOps::get_all(&self.conn)
     }
 
+    pub fn get_audio_file_by_id(&self, id: i64) -> Result<AudioFile> {
+        AudioFileOps::get_by_id(&self.conn, id)
+    }
+
     pub fn get_audio_file_by_path(&self, file_path: &str) -> Result<AudioFile> {
         AudioFileOps::get_by_path(&self.conn, file_path)
     }
@@ -105,6 +113,10 @@ impl Database {
         AudioFileOps::update(&self.conn, audio_file)
     }
 
+    pub fn patch_audio_file(&self, id: i64, patch: &AudioFilePatch) -> Result<()> {
+        AudioFileOps::patch(&self.conn, id, patch)
+    }
+
     pub fn delete_audio_file(&self, id: i64) -> Result<()> {
         AudioFileOps::delete(&self.conn, id)
     }
@@ -117,6 +129,102 @@ impl Database {
         AudioFileOps::update_bpm(&self.conn, id, bpm)
     }
 
+    pub fn update_audio_file_loudness(&self, id: i64, loudness: &LoudnessAnalysis) -> Result<()> {
+        AudioFileOps::update_loudness(&self.conn, id, loudness)
+    }
+
+    pub fn update_audio_file_loop_analysis(&self, id: i64, analysis: &LoopAnalysis) -> Result<()> {
+        AudioFileOps::update_loop_analysis(&self.conn, id, analysis)
+    }
+
+    pub fn update_audio_file_key(&self, id: i64, estimate: &KeyEstimate) -> Result<()> {
+        AudioFileOps::update_key(&self.conn, id, estimate)
+    }
+
+    pub fn update_audio_file_silence(&self, id: i64, silence: &SilenceAnalysis) -> Result<()> {
+        AudioFileOps::update_silence(&self.conn, id, silence)
+    }
+
+    pub fn update_audio_file_quality(&self, id: i64, analysis: &QualityAnalysis) -> Result<()> {
+        AudioFileOps::update_quality(&self.conn, id, analysis)
+    }
+
+    pub fn update_audio_file_stream_properties(&self, id: i64, stream: &StreamProperties) -> Result<()> {
+        AudioFileOps::update_stream_properties(&self.conn, id, stream)
+    }
+
+    pub fn search_audio_files_by_stream(&self, request: &StreamSearchRequest) -> Result<Vec<AudioFile>> {
+        AudioFileOps::search_by_stream(&self.conn, request)
+    }
+
+    pub fn update_audio_file_decodability(&self, id: i64, decode_error: Option<&str>) -> Result<()> {
+        AudioFileOps::update_decodability(&self.conn, id, decode_error)
+    }
+
+    pub fn get_unplayable_files(&self) -> Result<Vec<UnplayableFile>> {
+        AudioFileOps::get_unplayable(&self.conn)
+    }
+
+    pub fn refresh_audio_file(&self, audio_file: &AudioFile) -> Result<bool> {
+        AudioFileOps::refresh(&self.conn, audio_file)
+    }
+
+    pub fn get_library_states(&self) -> Result<Vec<KnownFile>> {
+        AudioFileOps::get_library_states(&self.conn)
+    }
+
+    pub fn update_audio_file_disk_state(&self, id: i64, file_size: u64, file_modified: i64) -> Result<()> {
+        AudioFileOps::update_disk_state(&self.conn, id, file_size, file_modified)
+    }
+
+    pub fn mark_audio_file_missing(&self, id: i64) -> Result<()> {
+        AudioFileOps::mark_missing(&self.conn, id)
+    }
+
+    pub fn mark_audio_files_missing_under(&self, path: &str) -> Result<usize> {
+        AudioFileOps::mark_missing_under(&self.conn, path)
+    }
+
+    pub fn rename_audio_file_path(&self, from: &str, to: &str) -> Result<usize> {
+        AudioFileOps::rename_path(&self.conn, from, to)
+    }
+
+    pub fn get_quality_proposals(&self) -> Result<Vec<QualityProposal>> {
+        AudioFileOps::get_quality_proposals(&self.conn)
+    }
+
+    pub fn update_audio_file_content_hash(&self, id: i64, content_hash: &str) -> Result<()> {
+        AudioFileOps::update_content_hash(&self.conn, id, content_hash)
+    }
+
+    pub fn get_audio_files_by_content_hash(&self, content_hash: &str) -> Result<Vec<AudioFile>> {
+        AudioFileOps::get_by_content_hash(&self.conn, content_hash)
+    }
+
+    pub fn save_audio_fingerprint(&self, id: i64, fingerprint: &Fingerprint) -> Result<()> {
+        DuplicateOps::save_fingerprint(&self.conn, id, fingerprint)
+    }
+
+    pub fn get_unfingerprinted_files(&self) -> Result<Vec<(i64, String)>> {
+        DuplicateOps::get_unfingerprinted(&self.conn)
+    }
+
+    pub fn get_audio_fingerprints(&self) -> Result<Vec<StoredFingerprint>> {
+        DuplicateOps::get_fingerprints(&self.conn)
+    }
+
+    pub fn get_merged_canonical(&self, file_path: &str) -> Result<Option<i64>> {
+        DuplicateOps::get_merged_canonical(&self.conn, file_path)
+    }
+
+    pub fn merge_duplicates(&self, canonical_id: i64, duplicate_ids: &[i64]) -> Result<()> {
+        DuplicateOps::merge(&self.conn, canonical_id, duplicate_ids)
+    }
+
+    pub fn get_loopable_proposals(&self, keyword: &str) -> Result<Vec<LoopableProposal>> {
+        AudioFileOps::get_loopable_proposals(&self.conn, keyword)
+    }
+
     pub fn update_audio_file_duration_and_bpm(&self, id: i64, duration: Option<f64>, bpm: Option<u32>) -> Result<()> {
         if let Some(dur) = duration {
             AudioFileOps::update_duration(&self.conn, id, dur)?;
@@ -135,6 +243,14 @@ impl Database {
         self.rpg_tags.remove(&self.conn, audio_file_id, tag_type, tag_value)
     }
 
+    pub fn replace_file_rpg_tags(&self, audio_file_id: i64, rpg_tags: &[(String, String)]) -> Result<()> {
+        self.rpg_tags.replace_from_file(&self.conn, audio_file_id, rpg_tags)
+    }
+
+    pub fn mark_rpg_tags_in_file(&self, audio_file_id: i64) -> Result<()> {
+        self.rpg_tags.mark_in_file(&self.conn, audio_file_id)
+    }
+
     pub fn get_rpg_tags_for_file(&self, audio_file_id: i64) -> Result<Vec<RpgTag>> {
         self.rpg_tags.get_for_file(&self.conn, audio_file_id)
     }
@@ -181,7 +297,7 @@ impl Database {
         AtmosphereOps::delete(&self.conn, id)
     }
 
-    pub fn add_sound_to_atmosphere(&self, atmosphere_id: i64, audio_file_id: i64, volume: f32, is_looping: bool) -> Result<i64> {
+    pub fn add_sound_to_atmosphere(&self, atmosphere_id: i64, audio_file_id: i64, volume: Option<f32>, is_looping: bool) -> Result<i64> {
         AtmosphereOps::add_sound(&self.conn, atmosphere_id, audio_file_id, volume, is_looping)
     }
 
@@ -28,6 +28,31 @@ impl RpgTagRepository {
         Ok(())
     }
 
+    /// Replace the tags last read from an audio file with the ones read from it now. Tags added
+    /// in the app and never written to the file are kept
+    pub fn replace_from_file(&self, conn: &Connection, audio_file_id: i64, rpg_tags: &[(String, String)]) -> Result<()> {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM rpg_tags WHERE audio_file_id = ?1 AND from_file = 1", [audio_file_id])?;
+        for (tag_type, tag_value) in rpg_tags {
+            tx.execute(
+                "INSERT OR IGNORE INTO rpg_tags (audio_file_id, tag_type, tag_value, from_file)
+                 VALUES (?1, ?2, ?3, 1)",
+                params![audio_file_id, tag_type, tag_value],
+            )?;
+        }
+        tx.commit()
+    }
+
+    /// Mark the tags of the types stored in audio files as in the file, once they were written to it
+    pub fn mark_in_file(&self, conn: &Connection, audio_file_id: i64) -> Result<()> {
+        conn.execute(
+            "UPDATE rpg_tags SET from_file = 1
+             WHERE audio_file_id = ?1 AND tag_type IN ('occasion', 'keyword', 'quality')",
+            [audio_file_id],
+        )?;
+        Ok(())
+    }
+
     /// Get all RPG tags for a specific audio file
     pub fn get_for_file(&self, conn: &Connection, audio_file_id: i64) -> Result<Vec<RpgTag>> {
         log::debug!("RpgTagRepository::get_for_file called with audio_file_id: {}", audio_file_id);
@@ -192,7 +217,16 @@ mod tests {
             encoded_by: None, copyright: None, file_owner: None,
             internet_radio_station_name: None, internet_radio_station_owner: None,
             isrc: None, publisher: None, mood: None, occasion: None, tempo: None,
-            content_type: None, category: None,
+            content_type: None, category: None, subcategory: None,
+            auto_tagged: None, auto_tag_date: None, auto_tag_version: None,
+            loudness_integrated: None, loudness_range: None, true_peak: None,
+            loop_start: None, loop_end: None, loop_seamless: None,
+            key_confidence: None, leading_silence: None, trailing_silence: None, mostly_silent: None,
+            content_hash: None, bpm_confidence: None,
+            quality_grade: None, clipping_ratio: None, noise_floor: None, bandwidth: None, sample_rate: None, bit_depth: None,
+            codec: None, container: None, channels: None, bitrate: None, file_size: None,
+            decodable: None, decode_error: None,
+            file_modified: None, missing: None,
         };
         let file_id = AudioFileOps::save(&conn, &file).unwrap();
 
@@ -214,4 +248,24 @@ mod tests {
         assert_eq!(tags.len(), 1);
         assert!(tags.iter().all(|t| t.tag_type != "genre" || t.tag_value != "ambient"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn rereading_a_file_replaces_only_its_own_tags() {
+        let (conn, repo, file_id) = setup();
+        let tag = |tag_type: &str, tag_value: &str| (tag_type.to_string(), tag_value.to_string());
+        repo.replace_from_file(&conn, file_id, &[tag("occasion", "tavern"), tag("keyword", "rain")]).unwrap();
+        repo.add(&conn, file_id, "mood", "calm").unwrap();
+
+        // The file lost its keyword and gained another occasion
+        repo.replace_from_file(&conn, file_id, &[tag("occasion", "tavern"), tag("occasion", "market")]).unwrap();
+        let values: Vec<String> = repo.get_for_file(&conn, file_id).unwrap().into_iter().map(|t| t.tag_value).collect();
+        assert_eq!(values, vec!["calm", "market", "tavern"]);
+
+        // Once written to the file, an app tag of a stored type goes with the file's tags
+        repo.add(&conn, file_id, "keyword", "wind").unwrap();
+        repo.mark_in_file(&conn, file_id).unwrap();
+        repo.replace_from_file(&conn, file_id, &[tag("occasion", "market")]).unwrap();
+        let values: Vec<String> = repo.get_for_file(&conn, file_id).unwrap().into_iter().map(|t| t.tag_value).collect();
+        assert_eq!(values, vec!["calm", "market"]);
+    }
+}
@@ -16,6 +16,7 @@ impl SchemaManager {
         self.create_atmospheres_tables(conn)?;
         self.create_virtual_folders_tables(conn)?;
         self.create_auto_tagging_tables(conn)?;
+        self.create_duplicate_tables(conn)?;
         self.create_indexes(conn)?;
         Ok(())
     }
@@ -84,11 +85,40 @@ impl SchemaManager {
             ("tempo", "TEXT"),
             ("content_type", "TEXT"),
             ("category", "TEXT"),
+            ("subcategory", "TEXT"),
             ("created_at", "DATETIME DEFAULT CURRENT_TIMESTAMP"),
             ("updated_at", "DATETIME DEFAULT CURRENT_TIMESTAMP"),
             ("auto_tagged", "BOOLEAN DEFAULT FALSE"),
             ("auto_tag_date", "TEXT"),
             ("auto_tag_version", "TEXT"),
+            ("loudness_integrated", "REAL"),
+            ("loudness_range", "REAL"),
+            ("true_peak", "REAL"),
+            ("loop_start", "INTEGER"),
+            ("loop_end", "INTEGER"),
+            ("loop_seamless", "BOOLEAN"),
+            ("key_confidence", "REAL"),
+            ("leading_silence", "REAL"),
+            ("trailing_silence", "REAL"),
+            ("mostly_silent", "BOOLEAN"),
+            ("content_hash", "TEXT"),
+            ("previous_content_hash", "TEXT"),
+            ("bpm_confidence", "REAL"),
+            ("quality_grade", "TEXT"),
+            ("clipping_ratio", "REAL"),
+            ("noise_floor", "REAL"),
+            ("bandwidth", "REAL"),
+            ("sample_rate", "INTEGER"),
+            ("bit_depth", "INTEGER"),
+            ("codec", "TEXT"),
+            ("container", "TEXT"),
+            ("channels", "INTEGER"),
+            ("bitrate", "INTEGER"),
+            ("file_size", "INTEGER"),
+            ("decodable", "BOOLEAN"),
+            ("decode_error", "TEXT"),
+            ("file_modified", "INTEGER"),
+            ("missing", "BOOLEAN"),
         ];
 
         // Add each column if it doesn't exist
@@ -110,11 +140,21 @@ impl SchemaManager {
                 tag_type TEXT NOT NULL,
                 tag_value TEXT NOT NULL,
                 created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                from_file INTEGER NOT NULL DEFAULT 0,
                 FOREIGN KEY (audio_file_id) REFERENCES audio_files (id) ON DELETE CASCADE,
                 UNIQUE(audio_file_id, tag_type, tag_value)
             )",
             [],
         )?;
+
+        // Whether the tag is stored in the audio file itself, for tables made before it was tracked
+        let has_from_file = conn.prepare("PRAGMA table_info(rpg_tags)")?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(|name| name.ok())
+            .any(|name| name == "from_file");
+        if !has_from_file {
+            conn.execute("ALTER TABLE rpg_tags ADD COLUMN from_file INTEGER NOT NULL DEFAULT 0", [])?;
+        }
         Ok(())
     }
 
@@ -265,8 +305,38 @@ impl SchemaManager {
         Ok(())
     }
 
+    fn create_duplicate_tables(&self, conn: &Connection) -> Result<()> {
+        // Acoustic fingerprints, kept apart from audio_files so listings don't carry them
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS audio_fingerprints (
+                audio_file_id INTEGER PRIMARY KEY,
+                duration REAL NOT NULL,
+                fingerprint BLOB NOT NULL,
+                FOREIGN KEY (audio_file_id) REFERENCES audio_files (id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        // Paths of duplicates merged into another row, so rescans don't bring them back
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS merged_duplicates (
+                file_path TEXT PRIMARY KEY,
+                canonical_id INTEGER NOT NULL,
+                merged_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )?;
+
+        Ok(())
+    }
+
     fn create_indexes(&self, conn: &Connection) -> Result<()> {
         // Indexes for performance
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_audio_files_content_hash ON audio_files(content_hash)",
+            [],
+        )?;
+
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_rpg_tags_audio_file ON rpg_tags(audio_file_id)",
             [],
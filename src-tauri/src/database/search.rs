@@ -23,7 +23,14 @@ impl SearchRepository {
                     af.encoded_by, af.copyright, af.file_owner, af.internet_radio_station_name,
                     af.internet_radio_station_owner, af.isrc, af.publisher, af.mood,
                     af.occasion, af.tempo, af.content_type, af.category, af.auto_tagged,
-                    af.auto_tag_date, af.auto_tag_version
+                    af.auto_tag_date, af.auto_tag_version, af.subcategory,
+                    af.loudness_integrated, af.loudness_range, af.true_peak,
+                    af.loop_start, af.loop_end, af.loop_seamless, af.key_confidence,
+                    af.leading_silence, af.trailing_silence, af.mostly_silent, af.content_hash,
+                    af.bpm_confidence, af.quality_grade, af.clipping_ratio, af.noise_floor,
+                    af.bandwidth, af.sample_rate, af.bit_depth, af.codec, af.container,
+                    af.channels, af.bitrate, af.file_size, af.decodable, af.decode_error,
+                    af.file_modified, af.missing
              FROM audio_files af
              ORDER BY af.artist, af.album, af.track_number"
         )?;
@@ -84,6 +91,34 @@ impl SearchRepository {
                 auto_tagged: row.get(51)?,
                 auto_tag_date: row.get(52)?,
                 auto_tag_version: row.get(53)?,
+                subcategory: row.get(54)?,
+                loudness_integrated: row.get(55)?,
+                loudness_range: row.get(56)?,
+                true_peak: row.get(57)?,
+                loop_start: row.get(58)?,
+                loop_end: row.get(59)?,
+                loop_seamless: row.get(60)?,
+                key_confidence: row.get(61)?,
+                leading_silence: row.get(62)?,
+                trailing_silence: row.get(63)?,
+                mostly_silent: row.get(64)?,
+                content_hash: row.get(65)?,
+                bpm_confidence: row.get(66)?,
+                quality_grade: row.get(67)?,
+                clipping_ratio: row.get(68)?,
+                noise_floor: row.get(69)?,
+                bandwidth: row.get(70)?,
+                sample_rate: row.get(71)?,
+                bit_depth: row.get(72)?,
+                codec: row.get(73)?,
+                container: row.get(74)?,
+                channels: row.get(75)?,
+                bitrate: row.get(76)?,
+                file_size: row.get(77)?,
+                decodable: row.get(78)?,
+                decode_error: row.get(79)?,
+                file_modified: row.get(80)?,
+                missing: row.get(81)?,
             })
         })?;
 
@@ -205,7 +240,14 @@ impl SearchRepository {
                     encoded_by, copyright, file_owner, internet_radio_station_name,
                     internet_radio_station_owner, isrc, publisher, mood,
                     occasion, tempo, content_type, category, auto_tagged,
-                    auto_tag_date, auto_tag_version
+                    auto_tag_date, auto_tag_version, subcategory,
+                    loudness_integrated, loudness_range, true_peak,
+                    loop_start, loop_end, loop_seamless, key_confidence,
+                    leading_silence, trailing_silence, mostly_silent, content_hash,
+                    bpm_confidence, quality_grade, clipping_ratio, noise_floor,
+                    bandwidth, sample_rate, bit_depth, codec, container,
+                    channels, bitrate, file_size, decodable, decode_error,
+                    file_modified, missing
              FROM audio_files WHERE id = ?1"
         )?;
 
@@ -265,6 +307,34 @@ impl SearchRepository {
                 auto_tagged: row.get(51)?,
                 auto_tag_date: row.get(52)?,
                 auto_tag_version: row.get(53)?,
+                subcategory: row.get(54)?,
+                loudness_integrated: row.get(55)?,
+                loudness_range: row.get(56)?,
+                true_peak: row.get(57)?,
+                loop_start: row.get(58)?,
+                loop_end: row.get(59)?,
+                loop_seamless: row.get(60)?,
+                key_confidence: row.get(61)?,
+                leading_silence: row.get(62)?,
+                trailing_silence: row.get(63)?,
+                mostly_silent: row.get(64)?,
+                content_hash: row.get(65)?,
+                bpm_confidence: row.get(66)?,
+                quality_grade: row.get(67)?,
+                clipping_ratio: row.get(68)?,
+                noise_floor: row.get(69)?,
+                bandwidth: row.get(70)?,
+                sample_rate: row.get(71)?,
+                bit_depth: row.get(72)?,
+                codec: row.get(73)?,
+                container: row.get(74)?,
+                channels: row.get(75)?,
+                bitrate: row.get(76)?,
+                file_size: row.get(77)?,
+                decodable: row.get(78)?,
+                decode_error: row.get(79)?,
+                file_modified: row.get(80)?,
+                missing: row.get(81)?,
             })
         })
     }
@@ -113,9 +113,37 @@ impl VirtualFolderContent {
                 tempo: row.get("tempo")?,
                 content_type: row.get("content_type")?,
                 category: row.get("category")?,
+                subcategory: row.get("subcategory")?,
                 auto_tagged: row.get("auto_tagged")?,
                 auto_tag_date: row.get("auto_tag_date")?,
                 auto_tag_version: row.get("auto_tag_version")?,
+                loudness_integrated: row.get("loudness_integrated")?,
+                loudness_range: row.get("loudness_range")?,
+                true_peak: row.get("true_peak")?,
+                loop_start: row.get("loop_start")?,
+                loop_end: row.get("loop_end")?,
+                loop_seamless: row.get("loop_seamless")?,
+                key_confidence: row.get("key_confidence")?,
+                leading_silence: row.get("leading_silence")?,
+                trailing_silence: row.get("trailing_silence")?,
+                mostly_silent: row.get("mostly_silent")?,
+                content_hash: row.get("content_hash")?,
+                bpm_confidence: row.get("bpm_confidence")?,
+                quality_grade: row.get("quality_grade")?,
+                clipping_ratio: row.get("clipping_ratio")?,
+                noise_floor: row.get("noise_floor")?,
+                bandwidth: row.get("bandwidth")?,
+                sample_rate: row.get("sample_rate")?,
+                bit_depth: row.get("bit_depth")?,
+                codec: row.get("codec")?,
+                container: row.get("container")?,
+                channels: row.get("channels")?,
+                bitrate: row.get("bitrate")?,
+                file_size: row.get("file_size")?,
+                decodable: row.get("decodable")?,
+                decode_error: row.get("decode_error")?,
+                file_modified: row.get("file_modified")?,
+                missing: row.get("missing")?,
             })
         })?;
         
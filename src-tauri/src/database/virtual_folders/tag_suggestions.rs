@@ -24,8 +24,9 @@ impl VirtualFolderTagSuggestions {
         
         // Get all tags for the file
         let file_tags = Self::get_file_tags(conn, audio_file_id)?;
+        let ucs_category = Self::get_ucs_category(conn, audio_file_id)?;
         
-        if file_tags.is_empty() {
+        if file_tags.is_empty() && ucs_category.is_none() {
             return Ok(Vec::new());
         }
         
@@ -67,13 +68,20 @@ impl VirtualFolderTagSuggestions {
         let occasion_refs: Vec<&str> = occasion_tags.iter().map(|s| s.as_str()).collect();
         let keyword_refs: Vec<&str> = keyword_tags.iter().map(|s| s.as_str()).collect();
         
-        let detailed_mappings = tag_mappings::get_detailed_folders_for_tags(
+        let mut detailed_mappings = tag_mappings::get_detailed_folders_for_tags(
             genre_ref,
             &mood_refs,
             &occasion_refs,
             &keyword_refs
         );
         
+        // UCS category/subcategory from the filename or iXML maps straight onto folders
+        if let Some((category, subcategory)) = &ucs_category {
+            detailed_mappings.folder_assignments.extend(
+                tag_mappings::get_ucs_folder_assignments(category, subcategory.as_deref())
+            );
+        }
+        
         let mut folder_suggestions: std::collections::HashMap<String, f64> = std::collections::HashMap::new();
         
         // Process folder assignments with NEW CONFIDENCE SCORING SYSTEM:
@@ -320,6 +328,21 @@ impl VirtualFolderTagSuggestions {
     }
     
     /// Get all RPG tags for a file in "type:value" format
+    /// Get the UCS category and subcategory stored for a file
+    fn get_ucs_category(conn: &Connection, audio_file_id: i64) -> Result<Option<(String, Option<String>)>> {
+        let row = conn.query_row(
+            "SELECT category, subcategory FROM audio_files WHERE id = ?",
+            [audio_file_id],
+            |row| Ok((row.get::<_, Option<String>>(0)?, row.get::<_, Option<String>>(1)?)),
+        );
+        
+        match row {
+            Ok((Some(category), subcategory)) if !category.trim().is_empty() => Ok(Some((category, subcategory))),
+            Ok(_) | Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+    
     fn get_file_tags(conn: &Connection, audio_file_id: i64) -> Result<Vec<String>> {
         let mut all_tags = Vec::new();
         
@@ -1,40 +1,60 @@
 use scan_dir::ScanDir;
+use crate::tag_formats::safe_write;
+
+/// Extensions scanned when no list is given: every container the decoder can open.
+/// WMA (ASF) can't be opened at all and M4P is DRM-protected, so both are left out
+pub const DEFAULT_EXTENSIONS: [&str; 16] = [
+    "mp3", "wav", "wave", "ogg", "oga", "opus", "flac", "aac",
+    "m4a", "m4b", "aif", "aiff", "aifc", "caf", "webm", "mka",
+];
 
 pub struct FileScanner;
 
 impl FileScanner {
-    pub fn scan_directory_recursive(dir_path: &str) -> Result<Vec<String>, String> {
+    /// Find files with one of `extensions` (case-insensitive, without the dot), or the defaults
+    pub fn scan_directory_recursive(dir_path: &str, extensions: Option<&[String]>) -> Result<Vec<String>, String> {
         println!("Scanning directory recursively: {}", dir_path);
-        
-        let audio_extensions = vec!["mp3", "wav", "ogg", "flac", "aac", "m4a", "wma", "m4p"];
-        
+
+        let audio_extensions = Self::normalize_extensions(extensions);
+
         let audio_files = ScanDir::files().walk(dir_path, |iter| {
-            iter.filter(|&(_, ref name)| {
-                audio_extensions.iter().any(|ext| {
-                    name.to_lowercase().ends_with(&format!(".{}", ext))
-                })
-            })
+            iter.filter(|&(_, ref name)| Self::has_extension(name, &audio_extensions))
             .map(|(entry, _)| entry.path().to_string_lossy().to_string())
             .collect::<Vec<String>>()
         }).map_err(|e| format!("Failed to scan directory: {:?}", e))?;
-        
+
         println!("Found {} audio files", audio_files.len());
         for file in &audio_files {
             println!("Audio file: {}", file);
         }
-        
+
         Ok(audio_files)
     }
 
-    #[allow(dead_code)]
-    pub fn get_supported_extensions() -> Vec<&'static str> {
-        vec!["mp3", "wav", "ogg", "flac", "aac", "m4a", "wma", "m4p"]
+    /// `extensions` lowercased and without dots, or the defaults
+    pub fn normalize_extensions(extensions: Option<&[String]>) -> Vec<String> {
+        match extensions {
+            Some(extensions) => extensions.iter().map(|ext| ext.trim_start_matches('.').to_lowercase()).collect(),
+            None => Self::get_supported_extensions(),
+        }
+    }
+
+    pub fn get_supported_extensions() -> Vec<String> {
+        DEFAULT_EXTENSIONS.iter().map(|ext| ext.to_string()).collect()
     }
 
     #[allow(dead_code)]
     pub fn is_audio_file(file_path: &str) -> bool {
-        let audio_extensions = Self::get_supported_extensions();
+        Self::has_extension(file_path, &Self::get_supported_extensions())
+    }
+
+    /// Whether the file name ends in one of `extensions` (lowercase, without the dot). The copies
+    /// made while re-tagging a file keep its extension but never count
+    pub fn has_extension(file_path: &str, extensions: &[String]) -> bool {
+        if safe_write::is_temp_file(file_path) {
+            return false;
+        }
         let file_lower = file_path.to_lowercase();
-        audio_extensions.iter().any(|ext| file_lower.ends_with(&format!(".{}", ext)))
+        extensions.iter().any(|ext| file_lower.ends_with(&format!(".{}", ext)))
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,286 @@
+use crate::key_detection::Decimator;
+use crate::models::DuplicateFile;
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+const FRAME_SECONDS: f64 = 0.2;
+const HOP_SECONDS: f64 = 0.05;
+/// 33 bands between these edges give one 32-bit word per frame
+const MIN_FREQUENCY: f64 = 300.0;
+const MAX_FREQUENCY: f64 = 2000.0;
+const BANDS: usize = 33;
+/// The fingerprint starts at the first frame above -50 dBFS RMS, so differently padded copies line up
+const MIN_ONSET_RMS: f64 = 0.003;
+/// Words kept, 30 s worth; the rest of the file only counts towards its duration
+const MAX_WORDS: usize = 600;
+/// Copies trimmed differently may be shifted against each other by up to half a second
+const MAX_OFFSET: usize = 10;
+/// Fewer overlapping words (a quarter second) are too little to compare
+const MIN_OVERLAP: usize = 5;
+/// Share of matching bits from which two files count as the same audio; unrelated audio matches about half
+const DUPLICATE_SIMILARITY: f64 = 0.85;
+/// Durations of duplicates differ by at most this share, plus a second for trimmed silence
+const DURATION_TOLERANCE: f64 = 0.05;
+
+const LOSSLESS_EXTENSIONS: [&str; 5] = ["wav", "flac", "aif", "aiff", "wv"];
+
+/// Compact acoustic fingerprint: one word per 50 ms of audio, one bit per band energy change
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Fingerprint {
+    /// Length of the whole file in seconds
+    pub duration: f64,
+    pub words: Vec<u32>,
+}
+
+impl Fingerprint {
+    /// Little-endian bytes of the words, as stored in the database
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.words.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    pub fn from_bytes(duration: f64, bytes: &[u8]) -> Self {
+        let words = bytes.chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        Fingerprint { duration, words }
+    }
+}
+
+/// Streaming fingerprint calculation, fed with interleaved samples
+pub struct Fingerprinter {
+    sample_rate: u32,
+    decimator: Decimator,
+    frames: u64,
+    frame_len: usize,
+    hop: usize,
+    /// Decimated mono samples not yet covered by a full frame
+    pending: Vec<f64>,
+    window: Vec<f64>,
+    fft: Arc<dyn Fft<f64>>,
+    /// Band of every spectrum bin between the band edges
+    bin_bands: Vec<(usize, usize)>,
+    previous: Option<[f64; BANDS]>,
+    words: Vec<u32>,
+}
+
+impl Fingerprinter {
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        let decimator = Decimator::new(sample_rate, channels);
+        let rate = decimator.rate(sample_rate);
+        let frame_len = (FRAME_SECONDS * rate) as usize;
+        let band_ratio = (MAX_FREQUENCY / MIN_FREQUENCY).ln() / BANDS as f64;
+
+        let bin_bands = (1..frame_len / 2)
+            .filter_map(|bin| {
+                let frequency = bin as f64 * rate / frame_len as f64;
+                (MIN_FREQUENCY..MAX_FREQUENCY).contains(&frequency)
+                    .then(|| (bin, ((frequency / MIN_FREQUENCY).ln() / band_ratio) as usize))
+            })
+            .collect();
+
+        Fingerprinter {
+            sample_rate,
+            decimator,
+            frames: 0,
+            frame_len,
+            hop: (HOP_SECONDS * rate) as usize,
+            pending: Vec::with_capacity(frame_len),
+            window: (0..frame_len).map(|n| 0.5 - 0.5 * (2.0 * PI * n as f64 / frame_len as f64).cos()).collect(),
+            fft: FftPlanner::new().plan_fft_forward(frame_len),
+            bin_bands,
+            previous: None,
+            words: Vec::new(),
+        }
+    }
+
+    /// Feed interleaved samples; a trailing partial frame is ignored
+    pub fn add_samples(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(self.decimator.channels()) {
+            self.frames += 1;
+            if self.words.len() >= MAX_WORDS {
+                continue;
+            }
+            let Some(mono) = self.decimator.push(frame) else { continue };
+            self.pending.push(mono);
+            if self.pending.len() == self.frame_len {
+                self.analyze_frame();
+                self.pending.drain(..self.hop);
+            }
+        }
+    }
+
+    fn analyze_frame(&mut self) {
+        if self.previous.is_none() {
+            let rms = (self.pending.iter().map(|s| s * s).sum::<f64>() / self.frame_len as f64).sqrt();
+            if rms < MIN_ONSET_RMS {
+                return;
+            }
+        }
+
+        let mut spectrum: Vec<Complex<f64>> = self.pending.iter()
+            .zip(&self.window)
+            .map(|(s, w)| Complex::new(s * w, 0.0))
+            .collect();
+        self.fft.process(&mut spectrum);
+
+        let mut energies = [0.0; BANDS];
+        for &(bin, band) in &self.bin_bands {
+            energies[band] += spectrum[bin].norm_sqr();
+        }
+        if let Some(previous) = &self.previous {
+            let word = (0..BANDS - 1).fold(0u32, |word, band| {
+                let change = (energies[band] - energies[band + 1]) - (previous[band] - previous[band + 1]);
+                word | (((change > 0.0) as u32) << band)
+            });
+            self.words.push(word);
+        }
+        self.previous = Some(energies);
+    }
+
+    pub fn finish(self) -> Fingerprint {
+        Fingerprint { duration: self.frames as f64 / self.sample_rate as f64, words: self.words }
+    }
+}
+
+/// Best share of matching bits over the offsets two copies may be shifted by; `None` without enough overlap
+pub fn similarity(a: &Fingerprint, b: &Fingerprint) -> Option<f64> {
+    let compare = |a: &[u32], b: &[u32]| {
+        let overlap = a.len().min(b.len());
+        (overlap >= MIN_OVERLAP).then(|| {
+            let differing: u32 = a.iter().zip(b).map(|(x, y)| (x ^ y).count_ones()).sum();
+            1.0 - differing as f64 / (overlap * 32) as f64
+        })
+    };
+    (0..=MAX_OFFSET)
+        .flat_map(|offset| [
+            compare(a.words.get(offset..).unwrap_or_default(), &b.words),
+            compare(&a.words, b.words.get(offset..).unwrap_or_default()),
+        ])
+        .flatten()
+        .reduce(f64::max)
+}
+
+/// Groups of fingerprints (as indices) that sound the same, with the lowest similarity that joined each group
+pub fn group_duplicates(fingerprints: &[&Fingerprint]) -> Vec<(Vec<usize>, f64)> {
+    let mut order: Vec<usize> = (0..fingerprints.len()).collect();
+    order.sort_by(|&a, &b| fingerprints[a].duration.total_cmp(&fingerprints[b].duration));
+
+    let mut parent: Vec<usize> = (0..fingerprints.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    let mut joined_at = vec![1.0f64; fingerprints.len()];
+    for (position, &i) in order.iter().enumerate() {
+        let longest = fingerprints[i].duration * (1.0 + DURATION_TOLERANCE) + 1.0;
+        for &j in order[position + 1..].iter().take_while(|&&j| fingerprints[j].duration <= longest) {
+            let Some(score) = similarity(fingerprints[i], fingerprints[j]) else { continue };
+            if score < DUPLICATE_SIMILARITY {
+                continue;
+            }
+            let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+            let lowest = joined_at[a].min(joined_at[b]).min(score);
+            parent[b] = a;
+            joined_at[a] = lowest;
+        }
+    }
+
+    let mut groups: Vec<(usize, Vec<usize>)> = Vec::new();
+    for i in 0..fingerprints.len() {
+        let group_root = root(&mut parent, i);
+        match groups.iter_mut().find(|(r, _)| *r == group_root) {
+            Some((_, members)) => members.push(i),
+            None => groups.push((group_root, vec![i])),
+        }
+    }
+    groups.into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(group_root, members)| (members, joined_at[group_root]))
+        .collect()
+}
+
+/// The copy worth keeping: lossless before lossy, then the larger file, then the better tagged one
+pub fn suggest_canonical(files: &[DuplicateFile]) -> Option<i64> {
+    let lossless = |file: &DuplicateFile| {
+        std::path::Path::new(&file.file_path).extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| LOSSLESS_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+    };
+    files.iter()
+        .max_by(|a, b| {
+            (lossless(a), a.file_size, a.tag_count, std::cmp::Reverse(a.audio_file_id))
+                .cmp(&(lossless(b), b.file_size, b.tag_count, std::cmp::Reverse(b.audio_file_id)))
+        })
+        .map(|file| file.audio_file_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Four seconds of partials swelling at their own pace after `delay` seconds of silence, at any rate
+    fn render(rate: u32, delay: f64, variant: f64, noise: f32) -> Vec<f32> {
+        let partials: Vec<(f64, f64)> = (0..66)
+            .map(|i| (250.0 * 9f64.powf(i as f64 / 66.0), 0.3 + ((i as f64 + variant) * 0.37) % 2.5))
+            .collect();
+        let mut seed = 7u32;
+        (0..((4.0 + delay) * rate as f64) as usize)
+            .map(|n| {
+                let t = n as f64 / rate as f64 - delay;
+                if t < 0.0 {
+                    return 0.0;
+                }
+                let sound: f64 = partials.iter()
+                    .map(|&(frequency, swell)| (2.0 * PI * frequency * t).sin() * (0.5 + 0.5 * (2.0 * PI * swell * t).sin()))
+                    .sum::<f64>() * 0.02;
+                seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                sound as f32 + noise * ((seed >> 8) as f32 / (1 << 24) as f32 - 0.5)
+            })
+            .collect()
+    }
+
+    fn fingerprint(rate: u32, samples: &[f32]) -> Fingerprint {
+        let mut fingerprinter = Fingerprinter::new(rate, 1);
+        for chunk in samples.chunks(1000) {
+            fingerprinter.add_samples(chunk);
+        }
+        fingerprinter.finish()
+    }
+
+    #[test]
+    fn groups_copies_across_rates_and_padding() {
+        let original = fingerprint(44100, &render(44100, 0.0, 0.0, 0.0));
+        let copy = fingerprint(48000, &render(48000, 0.33, 0.0, 0.01));
+        let other = fingerprint(44100, &render(44100, 0.0, 3.0, 0.0));
+        assert_eq!(Fingerprint::from_bytes(original.duration, &original.to_bytes()), original);
+
+        let copy_score = similarity(&original, &copy).unwrap();
+        assert!(copy_score >= DUPLICATE_SIMILARITY, "{}", copy_score);
+        assert!(similarity(&original, &other).unwrap() < DUPLICATE_SIMILARITY);
+
+        let groups = group_duplicates(&[&other, &original, &copy]);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].0, vec![1, 2]);
+        assert_eq!(groups[0].1, copy_score);
+    }
+
+    #[test]
+    fn prefers_lossless_then_larger_copies() {
+        let file = |id: i64, path: &str, size: u64| DuplicateFile {
+            audio_file_id: id,
+            file_path: path.to_string(),
+            duration: 4.0,
+            file_size: Some(size),
+            tag_count: 0,
+        };
+        let files = [file(1, "a/thunder.mp3", 900_000), file(2, "b/thunder.WAV", 700_000), file(3, "c/thunder.ogg", 800_000)];
+        assert_eq!(suggest_canonical(&files), Some(2));
+        assert_eq!(suggest_canonical(&[files[0].clone(), files[2].clone()]), Some(1));
+    }
+}
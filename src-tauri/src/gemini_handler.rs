@@ -1,5 +1,8 @@
 use crate::gemini_tagger::{AudioFile, GeminiTagger, TaggedFile, TaggingProgress};
-use crate::database::TagMappingCache;
+use crate::audio_handler::AudioHandler;
+use crate::database::{TagMappingCache, VocabularyRepository};
+use crate::models::{AudioFilePatch, FieldPatch};
+use crate::sound_classifier;
 use crate::AppState;
 use anyhow::Result;
 use dotenv::dotenv;
@@ -323,18 +326,16 @@ async fn save_tagged_batch(
     let mut saved_count = 0;
     
     for file in batch {
-        // Update the audio file with genre and mood, and mark as auto-tagged
-        let audio_file = crate::models::AudioFile {
-            id: Some(file.id as i64),
-            file_path: file.file_path.clone(),
-            genre: Some(file.genre.clone()),
-            mood: Some(file.mood.clone()),
-            auto_tagged: Some(true), // Mark as auto-tagged to prevent reprocessing
+        // Update genre and mood only, and mark as auto-tagged
+        let patch = AudioFilePatch {
+            genre: FieldPatch::Set(file.genre.clone()),
+            mood: FieldPatch::Set(file.mood.clone()),
+            auto_tagged: FieldPatch::Set(true), // Mark as auto-tagged to prevent reprocessing
             ..Default::default()
         };
         
         // Use the database abstraction methods with the transaction connection
-        crate::database::AudioFileOps::update(&tx, &audio_file)
+        crate::database::AudioFileOps::patch(&tx, file.id as i64, &patch)
             .map_err(|e| format!("Failed to update audio file {}: {}", file.id, e))?;
         
         // Use repository instance for tag operations
@@ -370,6 +371,72 @@ async fn save_tagged_batch(
     Ok(saved_count)
 }
 
+// Offline auto-tagging from audio features: no API key or network, same save path as Gemini
+#[tauri::command]
+pub async fn auto_tag_files_offline(
+    app_handle: AppHandle,
+    batch_size: Option<usize>,
+) -> Result<String, String> {
+    info!("Starting offline auto-tag process");
+
+    let untagged_files = get_untagged_files(app_handle.clone()).await?;
+    if untagged_files.is_empty() {
+        return Ok("No untagged files found".to_string());
+    }
+
+    let state = app_handle.state::<AppState>();
+    let vocabulary = {
+        let conn = state.db_pool.get_connection()
+            .map_err(|e| format!("Failed to get database connection: {}", e))?;
+        VocabularyRepository::new().get(&conn, None)
+            .map_err(|e| format!("Failed to load tag vocabulary: {}", e))?
+    };
+
+    let total_files = untagged_files.len();
+    let batch_size = batch_size.unwrap_or(50).max(1);
+    let total_batches = total_files.div_ceil(batch_size);
+    let mut processed_count = 0;
+    let mut unclassified_count = 0;
+    let mut failed_count = 0;
+
+    for (batch_idx, batch) in untagged_files.chunks(batch_size).enumerate() {
+        let mut tagged_files = Vec::with_capacity(batch.len());
+        for file in batch {
+            match AudioHandler::analyze_sound_features(&file.file_path) {
+                Ok(features) => match sound_classifier::propose_tags(file, &features, &vocabulary) {
+                    Some(tagged) => tagged_files.push(tagged),
+                    None => unclassified_count += 1,
+                },
+                Err(e) => {
+                    error!("Failed to analyze {}: {}", file.file_path, e);
+                    failed_count += 1;
+                }
+            }
+        }
+
+        match save_tagged_batch(&state, &tagged_files).await {
+            Ok(saved_count) => processed_count += saved_count,
+            Err(e) => {
+                error!("Failed to save offline batch {}: {}", batch_idx + 1, e);
+                failed_count += tagged_files.len();
+            }
+        }
+
+        let _ = app_handle.emit("tagging-progress", TaggingProgress {
+            total_files,
+            processed_files: processed_count,
+            failed_files: failed_count,
+            current_batch: batch_idx + 1,
+            total_batches,
+            status: format!("Batch {} classified offline", batch_idx + 1),
+        });
+    }
+
+    Ok(format!(
+        "Offline auto-tagging complete: {} files tagged, {} silent, {} failed",
+        processed_count, unclassified_count, failed_count
+    ))
+}
 
 // Get tagging history for a file
 #[tauri::command]
@@ -309,9 +309,37 @@ impl ImportExportHandler {
             tempo: None,
             content_type: None,
             category: None,
+            subcategory: None,
             auto_tagged: None,
             auto_tag_date: None,
             auto_tag_version: None,
+            loudness_integrated: None,
+            loudness_range: None,
+            true_peak: None,
+            loop_start: None,
+            loop_end: None,
+            loop_seamless: None,
+            key_confidence: None,
+            leading_silence: None,
+            trailing_silence: None,
+            mostly_silent: None,
+            content_hash: None,
+            bpm_confidence: None,
+            quality_grade: None,
+            clipping_ratio: None,
+            noise_floor: None,
+            bandwidth: None,
+            sample_rate: None,
+            bit_depth: None,
+            codec: None,
+            container: None,
+            channels: None,
+            bitrate: None,
+            file_size: None,
+            decodable: None,
+            decode_error: None,
+            file_modified: None,
+            missing: None,
         }
     }
 
@@ -0,0 +1,299 @@
+use crate::models::KeyEstimate;
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// Audio is mixed to mono and decimated to roughly this rate, plenty for the pitches that carry the key
+const ANALYSIS_RATE: u32 = 11025;
+/// Low-pass applied before decimating so higher partials don't fold into the analyzed range
+const ANTI_ALIAS_FREQUENCY: f64 = 3000.0;
+const FRAME_SIZE: usize = 4096;
+const HOP_SIZE: usize = 2048;
+/// Pitch range folded into the chroma, A1 to about C7
+const MIN_FREQUENCY: f64 = 55.0;
+const MAX_FREQUENCY: f64 = 2100.0;
+/// Like the BPM detection, only the start of long files is listened to
+const MAX_SECONDS: f64 = 180.0;
+/// Frames below -50 dBFS RMS carry no usable pitch
+const MIN_FRAME_RMS: f64 = 0.003;
+/// Fewer sounding frames (about 1.5 s) are too little to tell a key from
+const MIN_FRAMES: usize = 8;
+/// Spectra flatter than this on average are noise-like: wind, rain, crowds, impacts
+const MAX_FLATNESS: f64 = 0.25;
+/// Weaker matches with every key profile count as non-tonal
+const MIN_CONFIDENCE: f64 = 0.6;
+
+/// Krumhansl-Kessler key profiles, starting at the tonic
+const MAJOR_PROFILE: [f64; 12] = [6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88];
+const MINOR_PROFILE: [f64; 12] = [6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17];
+const MAJOR_KEYS: [&str; 12] = ["C", "Db", "D", "Eb", "E", "F", "F#", "G", "Ab", "A", "Bb", "B"];
+const MINOR_KEYS: [&str; 12] = ["Cm", "C#m", "Dm", "Ebm", "Em", "Fm", "F#m", "Gm", "G#m", "Am", "Bbm", "Bm"];
+
+/// Second-order low-pass (RBJ cookbook), run twice for a steeper slope
+#[derive(Clone, Copy)]
+struct LowPass {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    z1: f64,
+    z2: f64,
+}
+
+impl LowPass {
+    fn new(sample_rate: f64, frequency: f64) -> Self {
+        let w0 = 2.0 * PI * frequency / sample_rate;
+        let alpha = w0.sin() / (2.0 * std::f64::consts::FRAC_1_SQRT_2);
+        let a0 = 1.0 + alpha;
+        let b1 = (1.0 - w0.cos()) / a0;
+        LowPass {
+            b0: b1 / 2.0,
+            b1,
+            b2: b1 / 2.0,
+            a1: -2.0 * w0.cos() / a0,
+            a2: (1.0 - alpha) / a0,
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y
+    }
+}
+
+/// Mixes interleaved frames to mono and decimates them to about `ANALYSIS_RATE`
+pub struct Decimator {
+    channels: usize,
+    decimation: usize,
+    /// `None` when the audio is already close to the analysis rate
+    filters: Option<[LowPass; 2]>,
+    phase: usize,
+}
+
+impl Decimator {
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        let decimation = ((sample_rate as f64 / ANALYSIS_RATE as f64).round() as usize).max(1);
+        Decimator {
+            channels: channels.max(1),
+            decimation,
+            filters: (decimation > 1).then(|| [LowPass::new(sample_rate as f64, ANTI_ALIAS_FREQUENCY); 2]),
+            phase: 0,
+        }
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// Rate of the decimated signal
+    pub fn rate(&self, sample_rate: u32) -> f64 {
+        sample_rate as f64 / self.decimation as f64
+    }
+
+    /// Take one interleaved frame; yields a sample for every `decimation` frames
+    pub fn push(&mut self, frame: &[f32]) -> Option<f64> {
+        let mut mono = frame.iter().map(|&s| s as f64).sum::<f64>() / self.channels as f64;
+        if let Some(filters) = &mut self.filters {
+            mono = filters.iter_mut().fold(mono, |x, filter| filter.process(x));
+        }
+        self.phase += 1;
+        if self.phase < self.decimation {
+            return None;
+        }
+        self.phase = 0;
+        Some(mono)
+    }
+}
+
+/// Streaming chroma-based key estimation, fed with interleaved samples
+pub struct KeyDetector {
+    decimator: Decimator,
+    remaining: u64,
+    /// Decimated mono samples not yet covered by a full frame
+    pending: Vec<f64>,
+    window: Vec<f64>,
+    fft: Arc<dyn Fft<f64>>,
+    /// Pitch class of every spectrum bin inside the pitch range
+    bin_classes: Vec<(usize, usize)>,
+    chroma: [f64; 12],
+    flatness: f64,
+    frames: usize,
+}
+
+impl KeyDetector {
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        let decimator = Decimator::new(sample_rate, channels);
+        let rate = decimator.rate(sample_rate);
+
+        let bin_classes = (1..FRAME_SIZE / 2)
+            .filter_map(|bin| {
+                let frequency = bin as f64 * rate / FRAME_SIZE as f64;
+                (MIN_FREQUENCY..=MAX_FREQUENCY).contains(&frequency).then(|| {
+                    let midi = (69.0 + 12.0 * (frequency / 440.0).log2()).round() as usize;
+                    (bin, midi % 12)
+                })
+            })
+            .collect();
+
+        KeyDetector {
+            decimator,
+            remaining: (MAX_SECONDS * sample_rate as f64) as u64,
+            pending: Vec::with_capacity(FRAME_SIZE * 2),
+            window: (0..FRAME_SIZE).map(|n| 0.5 - 0.5 * (2.0 * PI * n as f64 / FRAME_SIZE as f64).cos()).collect(),
+            fft: FftPlanner::new().plan_fft_forward(FRAME_SIZE),
+            bin_classes,
+            chroma: [0.0; 12],
+            flatness: 0.0,
+            frames: 0,
+        }
+    }
+
+    /// Feed interleaved samples; returns `false` once enough audio has been heard
+    pub fn add_samples(&mut self, samples: &[f32]) -> bool {
+        for frame in samples.chunks_exact(self.decimator.channels()) {
+            if self.remaining == 0 {
+                return false;
+            }
+            self.remaining -= 1;
+
+            let Some(mono) = self.decimator.push(frame) else { continue };
+            self.pending.push(mono);
+            if self.pending.len() == FRAME_SIZE {
+                self.analyze_frame();
+                self.pending.drain(..HOP_SIZE);
+            }
+        }
+        self.remaining > 0
+    }
+
+    fn analyze_frame(&mut self) {
+        let rms = (self.pending.iter().map(|s| s * s).sum::<f64>() / FRAME_SIZE as f64).sqrt();
+        if rms < MIN_FRAME_RMS {
+            return;
+        }
+
+        let mut spectrum: Vec<Complex<f64>> = self.pending.iter()
+            .zip(&self.window)
+            .map(|(s, w)| Complex::new(s * w, 0.0))
+            .collect();
+        self.fft.process(&mut spectrum);
+
+        let mut chroma = [0.0; 12];
+        let mut log_sum = 0.0;
+        let mut power_sum = 0.0;
+        for &(bin, class) in &self.bin_classes {
+            let power = spectrum[bin].norm_sqr() + 1e-12;
+            chroma[class] += power.sqrt();
+            log_sum += power.ln();
+            power_sum += power;
+        }
+        let bins = self.bin_classes.len() as f64;
+        self.flatness += (log_sum / bins).exp() / (power_sum / bins);
+
+        // Each frame counts the same, however loud
+        let total: f64 = chroma.iter().sum();
+        for (sum, value) in self.chroma.iter_mut().zip(chroma) {
+            *sum += value / total;
+        }
+        self.frames += 1;
+    }
+
+    pub fn finish(self) -> KeyEstimate {
+        if self.frames < MIN_FRAMES {
+            return KeyEstimate::default();
+        }
+
+        let mut best = (f64::MIN, "");
+        for tonic in 0..12 {
+            for (profile, names) in [(&MAJOR_PROFILE, &MAJOR_KEYS), (&MINOR_PROFILE, &MINOR_KEYS)] {
+                let rotated: Vec<f64> = (0..12).map(|class| profile[(class + 12 - tonic) % 12]).collect();
+                let r = correlation(&self.chroma, &rotated);
+                if r > best.0 {
+                    best = (r, names[tonic]);
+                }
+            }
+        }
+
+        let confidence = best.0.clamp(0.0, 1.0);
+        let tonal = self.flatness / (self.frames as f64) <= MAX_FLATNESS && confidence >= MIN_CONFIDENCE;
+        KeyEstimate { key: tonal.then(|| best.1.to_string()), confidence }
+    }
+}
+
+/// Pearson correlation of two equally long vectors
+fn correlation(a: &[f64], b: &[f64]) -> f64 {
+    let n = a.len() as f64;
+    let (mean_a, mean_b) = (a.iter().sum::<f64>() / n, b.iter().sum::<f64>() / n);
+    let mut covariance = 0.0;
+    let mut variance_a = 0.0;
+    let mut variance_b = 0.0;
+    for (x, y) in a.iter().zip(b) {
+        covariance += (x - mean_a) * (y - mean_b);
+        variance_a += (x - mean_a).powi(2);
+        variance_b += (y - mean_b).powi(2);
+    }
+    if variance_a == 0.0 || variance_b == 0.0 {
+        return 0.0;
+    }
+    covariance / (variance_a * variance_b).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 44100;
+
+    /// Stereo chord of equal-tempered notes (MIDI numbers), each a second long
+    fn chords(chords: &[&[u8]]) -> Vec<f32> {
+        let mut samples = Vec::new();
+        for notes in chords {
+            for n in 0..RATE as usize {
+                let t = n as f64 / RATE as f64;
+                let value = notes.iter()
+                    .map(|&note| (2.0 * PI * 440.0 * 2f64.powf((note as f64 - 69.0) / 12.0) * t).sin())
+                    .sum::<f64>() * 0.2;
+                samples.extend([value as f32; 2]);
+            }
+        }
+        samples
+    }
+
+    fn detect(samples: &[f32]) -> KeyEstimate {
+        let mut detector = KeyDetector::new(RATE, 2);
+        detector.add_samples(samples);
+        detector.finish()
+    }
+
+    #[test]
+    fn detects_major_and_minor_keys() {
+        // I - IV - V - I in C major
+        let c_major = detect(&chords(&[&[48, 60, 64, 67], &[53, 60, 65, 69], &[55, 59, 62, 67], &[48, 60, 64, 67]]));
+        assert_eq!(c_major.key.as_deref(), Some("C"));
+        assert!(c_major.confidence >= MIN_CONFIDENCE, "{:?}", c_major);
+
+        // i - iv - V - i in A minor
+        let a_minor = detect(&chords(&[&[45, 57, 60, 64], &[50, 57, 62, 65], &[52, 56, 59, 64], &[45, 57, 60, 64]]));
+        assert_eq!(a_minor.key.as_deref(), Some("Am"));
+    }
+
+    #[test]
+    fn leaves_noise_and_silence_without_a_key() {
+        let mut seed = 12345u32;
+        let noise: Vec<f32> = (0..RATE as usize * 8)
+            .map(|_| {
+                seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                (seed >> 8) as f32 / (1 << 24) as f32 - 0.5
+            })
+            .collect();
+        assert_eq!(detect(&noise).key, None);
+
+        assert_eq!(detect(&vec![0.0; RATE as usize * 8]), KeyEstimate::default());
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// An audio file found on disk by a library sync
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskFile {
+    pub file_path: String,
+    pub file_size: u64,
+    pub file_modified: i64,
+}
+
+/// What the database last recorded about a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnownFile {
+    pub id: i64,
+    pub file_path: String,
+    pub file_size: Option<u64>,
+    pub file_modified: Option<i64>,
+    pub missing: bool,
+}
+
+/// What a sync has to do to bring the rows under a directory in line with the disk
+#[derive(Debug, Default, PartialEq)]
+pub struct SyncPlan {
+    /// Files without a row
+    pub added: Vec<DiskFile>,
+    /// Rows whose file changed size or modification time, to be read again
+    pub changed: Vec<(i64, DiskFile)>,
+    /// Rows whose file is unchanged but whose size or time was never recorded, or which were missing
+    pub touched: Vec<(i64, DiskFile)>,
+    /// Rows whose file is gone
+    pub missing: Vec<i64>,
+    /// Rows flagged missing whose file is back
+    pub restored: usize,
+    pub unchanged: usize,
+}
+
+/// Size in bytes and modification time in seconds since the Unix epoch of a file
+pub fn disk_state(file_path: &str) -> Result<(u64, i64), String> {
+    let metadata = fs::metadata(file_path).map_err(|e| format!("Failed to stat {}: {}", file_path, e))?;
+    let modified = metadata.modified()
+        .map_err(|e| format!("Failed to read the modification time of {}: {}", file_path, e))?;
+    // Times before the epoch only come from broken clocks; treat them as the epoch
+    let seconds = modified.duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0);
+    Ok((metadata.len(), seconds))
+}
+
+/// The rows at or under `root`
+pub fn within(root: &str, known: Vec<KnownFile>) -> Vec<KnownFile> {
+    let root = Path::new(root);
+    known.into_iter().filter(|file| Path::new(&file.file_path).starts_with(root)).collect()
+}
+
+/// Compare the files found on disk with the rows that should be there; rows without a file are
+/// missing. A size or time that was never recorded counts as unchanged, so rows saved before sync
+/// existed aren't all read again
+pub fn plan(on_disk: Vec<DiskFile>, known: Vec<KnownFile>) -> SyncPlan {
+    let mut known: HashMap<String, KnownFile> = known.into_iter()
+        .map(|file| (file.file_path.clone(), file))
+        .collect();
+
+    let mut plan = SyncPlan::default();
+    for disk in on_disk {
+        let Some(row) = known.remove(&disk.file_path) else {
+            plan.added.push(disk);
+            continue;
+        };
+
+        if row.missing {
+            plan.restored += 1;
+        }
+        let changed = row.file_size.is_some_and(|size| size != disk.file_size)
+            || row.file_modified.is_some_and(|modified| modified != disk.file_modified);
+        if changed {
+            plan.changed.push((row.id, disk));
+        } else if row.missing || row.file_size.is_none() || row.file_modified.is_none() {
+            plan.touched.push((row.id, disk));
+        } else {
+            plan.unchanged += 1;
+        }
+    }
+
+    // Whatever wasn't found on disk is gone
+    plan.missing = known.into_values().map(|row| row.id).collect();
+    plan.missing.sort_unstable();
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn disk(file_path: &str, file_size: u64, file_modified: i64) -> DiskFile {
+        DiskFile { file_path: file_path.to_string(), file_size, file_modified }
+    }
+
+    fn known(id: i64, file_path: &str, file_size: Option<u64>, file_modified: Option<i64>, missing: bool) -> KnownFile {
+        KnownFile { id, file_path: file_path.to_string(), file_size, file_modified, missing }
+    }
+
+    #[test]
+    fn sorts_files_into_added_changed_and_missing() {
+        let on_disk = vec![
+            disk("/library/rain.flac", 1000, 50),
+            disk("/library/wind.flac", 2000, 60),
+            disk("/library/thunder.flac", 3000, 70),
+            disk("/library/new/tavern.ogg", 4000, 80),
+        ];
+        let rows = vec![
+            known(1, "/library/rain.flac", Some(1000), Some(50), false),
+            known(2, "/library/wind.flac", Some(2000), Some(65), false),
+            known(3, "/library/thunder.flac", Some(2500), Some(70), false),
+            known(4, "/library/gone.flac", Some(100), Some(10), false),
+            known(5, "/elsewhere/gone.flac", Some(100), Some(10), false),
+        ];
+
+        let plan = plan(on_disk, within("/library", rows));
+        assert_eq!(plan.added, vec![disk("/library/new/tavern.ogg", 4000, 80)]);
+        assert_eq!(plan.changed, vec![
+            (2, disk("/library/wind.flac", 2000, 60)),
+            (3, disk("/library/thunder.flac", 3000, 70)),
+        ]);
+        assert!(plan.touched.is_empty());
+        // Rows outside the synced directory are left alone
+        assert_eq!(plan.missing, vec![4]);
+        assert_eq!(plan.unchanged, 1);
+    }
+
+    #[test]
+    fn records_unknown_state_and_restores_missing_rows_without_reading_them() {
+        let on_disk = vec![
+            disk("/library/rain.flac", 1000, 50),
+            disk("/library/wind.flac", 2000, 60),
+            disk("/library/fire.flac", 3000, 70),
+        ];
+        let rows = vec![
+            known(1, "/library/rain.flac", None, None, false),
+            known(2, "/library/wind.flac", Some(2000), None, false),
+            known(3, "/library/fire.flac", Some(3000), Some(70), true),
+        ];
+
+        let plan = plan(on_disk, within("/library", rows));
+        assert!(plan.added.is_empty() && plan.changed.is_empty() && plan.missing.is_empty());
+        assert_eq!(plan.touched.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(plan.restored, 1);
+        assert_eq!(plan.unchanged, 0);
+    }
+}
@@ -0,0 +1,201 @@
+use notify_debouncer_full::notify::event::{AccessKind, AccessMode, EventKind, ModifyKind, RenameMode};
+use notify_debouncer_full::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{new_debouncer, DebounceEventResult, DebouncedEvent, Debouncer, RecommendedCache};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use crate::file_scanner::FileScanner;
+use crate::tag_formats::safe_write;
+
+/// How long a path has to stay quiet before its events are handed on, so a file still being
+/// copied isn't read half-written
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A change to a watched library, boiled down from the debounced filesystem events
+#[derive(Debug, Clone, PartialEq)]
+pub enum LibraryChange {
+    /// An audio file appeared or was written to
+    Written(String),
+    /// A directory appeared, e.g. a copied sound pack; files already in it raise no events of their own
+    DirectoryCreated(String),
+    /// A file or directory was deleted or moved out of the watched directories
+    Removed(String),
+    /// A file or directory was moved within the watched directories
+    Renamed { from: String, to: String },
+}
+
+struct ActiveWatch {
+    /// Dropping the debouncer stops it
+    _debouncer: Debouncer<RecommendedWatcher, RecommendedCache>,
+    roots: Vec<String>,
+}
+
+/// The optional watcher over the library directories (inotify on Linux)
+#[derive(Default)]
+pub struct LibraryWatcher {
+    active: Mutex<Option<ActiveWatch>>,
+}
+
+impl LibraryWatcher {
+    /// Watch `roots` recursively in place of whatever was watched before, calling `on_changes` on
+    /// the watcher thread with every settled batch of changes to files with one of `extensions`
+    pub fn start<F>(&self, roots: Vec<String>, extensions: Vec<String>, mut on_changes: F) -> Result<(), String>
+    where
+        F: FnMut(Vec<LibraryChange>) + Send + 'static,
+    {
+        let mut debouncer = new_debouncer(DEBOUNCE_TIMEOUT, None, move |result: DebounceEventResult| {
+            match result {
+                Ok(events) => {
+                    let changes = classify(&events, &extensions);
+                    if !changes.is_empty() {
+                        on_changes(changes);
+                    }
+                }
+                Err(errors) => {
+                    for error in errors {
+                        log::warn!("Library watcher error: {}", error);
+                    }
+                }
+            }
+        }).map_err(|e| format!("Failed to start the library watcher: {}", e))?;
+
+        for root in &roots {
+            debouncer.watch(Path::new(root), RecursiveMode::Recursive)
+                .map_err(|e| format!("Failed to watch {}: {}", root, e))?;
+        }
+
+        *self.active.lock().unwrap() = Some(ActiveWatch { _debouncer: debouncer, roots });
+        Ok(())
+    }
+
+    /// Stop watching; returns whether a watch was running
+    pub fn stop(&self) -> bool {
+        self.active.lock().unwrap().take().is_some()
+    }
+
+    pub fn roots(&self) -> Vec<String> {
+        self.active.lock().unwrap().as_ref().map(|active| active.roots.clone()).unwrap_or_default()
+    }
+}
+
+/// Turn debounced events into library changes in the order they happened. Repeated writes to a
+/// file collapse into its last one; the app's own re-tagging, through a temporary copy renamed
+/// over the file, raises none
+pub fn classify(events: &[DebouncedEvent], extensions: &[String]) -> Vec<LibraryChange> {
+    let mut changes = Vec::new();
+    let path_string = |path: &Path| path.to_string_lossy().to_string();
+
+    for event in events {
+        match event.kind {
+            EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                for path in &event.paths {
+                    push_created(&mut changes, path, extensions);
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+                // A temporary copy put in place of the file it was made from
+                if safe_write::is_temp_file(&path_string(&event.paths[0])) {
+                    push_written(&mut changes, &event.paths[1], extensions);
+                    continue;
+                }
+                changes.push(LibraryChange::Renamed {
+                    from: path_string(&event.paths[0]),
+                    to: path_string(&event.paths[1]),
+                });
+            }
+            EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                changes.extend(event.paths.iter()
+                    .map(|path| path_string(path))
+                    .filter(|path| !safe_write::is_temp_file(path))
+                    .map(LibraryChange::Removed));
+            }
+            // Backends that can't pair up renames only say that the path changed
+            EventKind::Modify(ModifyKind::Name(_)) => {
+                for path in &event.paths {
+                    if path.exists() {
+                        push_created(&mut changes, path, extensions);
+                    } else if !safe_write::is_temp_file(&path_string(path)) {
+                        changes.push(LibraryChange::Removed(path_string(path)));
+                    }
+                }
+            }
+            EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Any)
+            | EventKind::Access(AccessKind::Close(AccessMode::Write)) => {
+                for path in &event.paths {
+                    push_written(&mut changes, path, extensions);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    changes
+}
+
+fn push_created(changes: &mut Vec<LibraryChange>, path: &Path, extensions: &[String]) {
+    if path.is_dir() {
+        changes.push(LibraryChange::DirectoryCreated(path.to_string_lossy().to_string()));
+    } else {
+        push_written(changes, path, extensions);
+    }
+}
+
+fn push_written(changes: &mut Vec<LibraryChange>, path: &Path, extensions: &[String]) {
+    let path = path.to_string_lossy().to_string();
+    if !FileScanner::has_extension(&path, extensions) || safe_write::is_own_write(&path) {
+        return;
+    }
+    let written = LibraryChange::Written(path);
+    changes.retain(|change| change != &written);
+    changes.push(written);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify_debouncer_full::notify::event::{CreateKind, DataChange, Event, RemoveKind};
+    use std::time::Instant;
+
+    fn event(kind: EventKind, paths: &[&str]) -> DebouncedEvent {
+        let event = paths.iter().fold(Event::new(kind), |event, path| event.add_path(path.into()));
+        DebouncedEvent::new(event, Instant::now())
+    }
+
+    fn extensions() -> Vec<String> {
+        vec!["wav".to_string(), "ogg".to_string()]
+    }
+
+    #[test]
+    fn collapses_writes_and_skips_other_file_types() {
+        let events = [
+            event(EventKind::Create(CreateKind::File), &["/library/missing/rain.wav"]),
+            event(EventKind::Modify(ModifyKind::Data(DataChange::Content)), &["/library/missing/rain.wav"]),
+            event(EventKind::Create(CreateKind::File), &["/library/missing/notes.txt"]),
+            event(EventKind::Access(AccessKind::Close(AccessMode::Write)), &["/library/missing/wind.OGG"]),
+            event(EventKind::Access(AccessKind::Close(AccessMode::Write)), &["/library/missing/rain.wav"]),
+            event(EventKind::Create(CreateKind::File), &["/library/missing/.wind.ligeia-tmp.ogg"]),
+            event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &["/library/missing/.wind.ligeia-tmp.ogg", "/library/missing/wind.ogg"]),
+        ];
+
+        assert_eq!(classify(&events, &extensions()), vec![
+            LibraryChange::Written("/library/missing/wind.OGG".to_string()),
+            LibraryChange::Written("/library/missing/rain.wav".to_string()),
+            LibraryChange::Written("/library/missing/wind.ogg".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn keeps_renames_and_removals_in_order() {
+        let events = [
+            event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &["/library/rain.wav", "/library/storm/rain.wav"]),
+            event(EventKind::Remove(RemoveKind::Folder), &["/library/old pack"]),
+            event(EventKind::Modify(ModifyKind::Name(RenameMode::From)), &["/library/wind.wav"]),
+        ];
+
+        assert_eq!(classify(&events, &extensions()), vec![
+            LibraryChange::Renamed { from: "/library/rain.wav".to_string(), to: "/library/storm/rain.wav".to_string() },
+            LibraryChange::Removed("/library/old pack".to_string()),
+            LibraryChange::Removed("/library/wind.wav".to_string()),
+        ]);
+    }
+}
@@ -0,0 +1,294 @@
+use crate::models::LoopAnalysis;
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+/// Keyword proposed for files that loop cleanly
+pub const LOOPABLE_KEYWORD: &str = "util:loopable";
+
+/// Samples below -60 dBFS count as silence
+const SILENCE_THRESHOLD: f32 = 0.001;
+/// Shorter gaps at either end aren't heard as a pause
+const MAX_SILENCE_SECONDS: f64 = 0.05;
+const MAX_DC_OFFSET: f64 = 0.01;
+/// Largest jump across the seam, relative to the signal's typical sample-to-sample step
+const MAX_SEAM_JUMP: f64 = 3.0;
+const MAX_LEVEL_CHANGE_DB: f64 = 3.0;
+const MAX_SPECTRAL_DISTANCE_DB: f64 = 6.0;
+
+/// How far a loop point may move from the edge of the sound to land on a zero crossing
+const CROSSING_SEARCH_SECONDS: f64 = 0.02;
+/// Audio compared on each side of the seam: 10 ms for steps, 100 ms for level and spectrum
+const STEP_WINDOW_SECONDS: f64 = 0.01;
+const LEVEL_WINDOW_SECONDS: f64 = 0.1;
+/// Audio kept from each end of the file; a seam hidden behind more silence than this isn't checked
+const EDGE_SECONDS: f64 = 5.0;
+
+const SPECTRUM_SIZE: usize = 512;
+/// Octave bands from the lowest bin up to Nyquist
+const SPECTRUM_BANDS: usize = 8;
+
+/// Streaming loop seam analysis, fed with interleaved samples
+pub struct LoopAnalyzer {
+    sample_rate: u32,
+    channels: usize,
+    edge_frames: usize,
+    /// Mono mix of the first and last `edge_frames` frames
+    head: Vec<f32>,
+    tail: VecDeque<f32>,
+    channel_sums: Vec<f64>,
+    frames: u64,
+    first_sound: Option<u64>,
+    last_sound: u64,
+}
+
+impl LoopAnalyzer {
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        let channels = channels.max(1);
+        let edge_frames = (EDGE_SECONDS * sample_rate as f64) as usize;
+        LoopAnalyzer {
+            sample_rate,
+            channels,
+            edge_frames,
+            head: Vec::with_capacity(edge_frames),
+            tail: VecDeque::with_capacity(edge_frames + 1),
+            channel_sums: vec![0.0; channels],
+            frames: 0,
+            first_sound: None,
+            last_sound: 0,
+        }
+    }
+
+    /// Feed interleaved samples; a trailing partial frame is ignored
+    pub fn add_samples(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(self.channels) {
+            for (sum, &sample) in self.channel_sums.iter_mut().zip(frame) {
+                *sum += sample as f64;
+            }
+            if frame.iter().any(|sample| sample.abs() > SILENCE_THRESHOLD) {
+                self.first_sound.get_or_insert(self.frames);
+                self.last_sound = self.frames;
+            }
+
+            let mono = frame.iter().sum::<f32>() / self.channels as f32;
+            if self.head.len() < self.edge_frames {
+                self.head.push(mono);
+            }
+            self.tail.push_back(mono);
+            if self.tail.len() > self.edge_frames {
+                self.tail.pop_front();
+            }
+            self.frames += 1;
+        }
+    }
+
+    pub fn finish(self) -> LoopAnalysis {
+        let rate = self.sample_rate as f64;
+        let dc_offset = self.channel_sums.iter()
+            .map(|sum| (sum / self.frames.max(1) as f64).abs())
+            .fold(0.0, f64::max);
+        let mut analysis = LoopAnalysis { loop_start: 0, loop_end: self.frames, dc_offset, ..Default::default() };
+
+        let Some(first_sound) = self.first_sound else {
+            analysis.issues.push("The file is silent".to_string());
+            return analysis;
+        };
+        let sound_end = self.last_sound + 1;
+        analysis.leading_silence = first_sound as f64 / rate;
+        analysis.trailing_silence = (self.frames - sound_end) as f64 / rate;
+
+        if dc_offset > MAX_DC_OFFSET {
+            analysis.issues.push(format!("DC offset of {:.3}", dc_offset));
+        }
+        if analysis.leading_silence > MAX_SILENCE_SECONDS {
+            analysis.issues.push(format!("{:.2}s of silence at the start", analysis.leading_silence));
+        }
+        if analysis.trailing_silence > MAX_SILENCE_SECONDS {
+            analysis.issues.push(format!("{:.2}s of silence at the end", analysis.trailing_silence));
+        }
+
+        let head = self.head;
+        let tail = Vec::from(self.tail);
+        let tail_offset = self.frames - tail.len() as u64;
+        let search = (CROSSING_SEARCH_SECONDS * rate) as usize;
+        let step_window = ((STEP_WINDOW_SECONDS * rate) as usize).max(2);
+        let level_window = ((LEVEL_WINDOW_SECONDS * rate) as usize).max(1);
+
+        // A file that starts and ends on sound also loops as-is, so its own boundary must be clean
+        if analysis.issues.is_empty() {
+            if let (Some(&first), Some(&last)) = (head.first(), tail.last()) {
+                let jump = seam_jump(&head, &tail, first, last, step_window);
+                if jump > MAX_SEAM_JUMP {
+                    analysis.issues.push("Click where the file wraps around (use the suggested loop points)".to_string());
+                }
+            }
+        }
+
+        let start = (first_sound < head.len() as u64)
+            .then(|| nearest_start_crossing(&head, first_sound as usize, search));
+        let end = (sound_end > tail_offset)
+            .then(|| tail_offset + nearest_end_crossing(&tail, (sound_end - tail_offset) as usize, search) as u64);
+        let (Some(start), Some(end)) = (start, end) else {
+            return analysis;
+        };
+        if end <= start as u64 {
+            return analysis;
+        }
+        analysis.loop_start = start as u64;
+        analysis.loop_end = end;
+
+        let after = &head[start..];
+        let before = &tail[..(end - tail_offset) as usize];
+        let jump = seam_jump(after, before, after[0], before[before.len() - 1], step_window);
+        analysis.seam_jump = Some(jump);
+        if jump > MAX_SEAM_JUMP {
+            analysis.issues.push("Click at the loop seam".to_string());
+        }
+
+        let level_after = level_db(&after[..level_window.min(after.len())]);
+        let level_before = level_db(&before[before.len().saturating_sub(level_window)..]);
+        let level_change = (level_after - level_before).abs();
+        analysis.level_change_db = Some(level_change);
+        if level_change > MAX_LEVEL_CHANGE_DB {
+            analysis.issues.push(format!("Level jumps {:.1} dB at the loop seam", level_change));
+        }
+
+        let spectrum_window = level_window.max(SPECTRUM_SIZE);
+        if after.len() >= spectrum_window && before.len() >= spectrum_window {
+            let distance = spectral_distance(
+                &band_levels(&after[..spectrum_window]),
+                &band_levels(&before[before.len() - spectrum_window..]),
+            );
+            analysis.spectral_distance_db = Some(distance);
+            if distance > MAX_SPECTRAL_DISTANCE_DB {
+                analysis.issues.push(format!("Spectrum changes {:.1} dB at the loop seam", distance));
+            }
+        }
+
+        analysis
+    }
+}
+
+/// Rising zero crossing closest to `target` where a loop can start: a non-negative sample after a negative one
+fn nearest_start_crossing(samples: &[f32], target: usize, search: usize) -> usize {
+    let is_crossing = |i: usize| samples[i] >= 0.0 && (i == 0 || samples[i - 1] < 0.0);
+    let low = target.saturating_sub(search);
+    let high = (target + search).min(samples.len() - 1);
+    (low..=high).filter(|&i| is_crossing(i)).min_by_key(|&i| i.abs_diff(target)).unwrap_or(target)
+}
+
+/// Rising zero crossing closest to `target` where a loop can end (exclusive): right after a negative sample
+fn nearest_end_crossing(samples: &[f32], target: usize, search: usize) -> usize {
+    let is_crossing = |i: usize| samples[i - 1] < 0.0 && (i == samples.len() || samples[i] >= 0.0);
+    let low = target.saturating_sub(search).max(1);
+    let high = (target + search).min(samples.len());
+    (low..=high).filter(|&i| is_crossing(i)).min_by_key(|&i| i.abs_diff(target)).unwrap_or(target)
+}
+
+/// Jump from `last` to `first`, relative to the RMS step of the audio on either side of the seam
+fn seam_jump(after: &[f32], before: &[f32], first: f32, last: f32, window: usize) -> f64 {
+    let steps = after[..window.min(after.len())].windows(2)
+        .chain(before[before.len().saturating_sub(window)..].windows(2))
+        .map(|pair| ((pair[1] - pair[0]) as f64).powi(2))
+        .collect::<Vec<_>>();
+    let typical = (steps.iter().sum::<f64>() / steps.len().max(1) as f64).sqrt();
+    (first - last).abs() as f64 / typical.max(1e-4)
+}
+
+fn level_db(samples: &[f32]) -> f64 {
+    let mean_square = samples.iter().map(|&x| (x as f64).powi(2)).sum::<f64>() / samples.len().max(1) as f64;
+    10.0 * mean_square.max(1e-12).log10()
+}
+
+/// Octave band levels in dB of a Welch power spectrum (Hann windows at 50% overlap)
+fn band_levels(samples: &[f32]) -> [f64; SPECTRUM_BANDS] {
+    let window: Vec<f64> = (0..SPECTRUM_SIZE)
+        .map(|n| 0.5 - 0.5 * (2.0 * PI * n as f64 / SPECTRUM_SIZE as f64).cos())
+        .collect();
+    let cos: Vec<f64> = (0..SPECTRUM_SIZE).map(|n| (2.0 * PI * n as f64 / SPECTRUM_SIZE as f64).cos()).collect();
+    let sin: Vec<f64> = (0..SPECTRUM_SIZE).map(|n| (2.0 * PI * n as f64 / SPECTRUM_SIZE as f64).sin()).collect();
+
+    let mut power = vec![0.0; SPECTRUM_SIZE / 2];
+    for frame in samples.windows(SPECTRUM_SIZE).step_by(SPECTRUM_SIZE / 2) {
+        for (bin, bin_power) in power.iter_mut().enumerate().skip(1) {
+            let (mut re, mut im) = (0.0, 0.0);
+            for (n, &x) in frame.iter().enumerate() {
+                let x = x as f64 * window[n];
+                let index = (bin * n) % SPECTRUM_SIZE;
+                re += x * cos[index];
+                im -= x * sin[index];
+            }
+            *bin_power += re * re + im * im;
+        }
+    }
+
+    let mut bands = [0.0; SPECTRUM_BANDS];
+    for (band, level) in bands.iter_mut().enumerate() {
+        let bins = &power[1 << band..(2 << band).min(power.len())];
+        let mean = bins.iter().sum::<f64>() / bins.len().max(1) as f64;
+        *level = 10.0 * mean.max(1e-12).log10();
+    }
+    bands
+}
+
+/// Spread of the per-band level differences, so an overall level change doesn't count as a spectral one
+fn spectral_distance(a: &[f64], b: &[f64]) -> f64 {
+    let differences: Vec<f64> = a.iter().zip(b).map(|(a, b)| a - b).collect();
+    let mean = differences.iter().sum::<f64>() / differences.len() as f64;
+    (differences.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / differences.len() as f64).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 48000;
+
+    /// Mono 100 Hz sine, so every 480 frames is a full cycle
+    fn sine(frames: usize, amplitude: f32) -> Vec<f32> {
+        (0..frames)
+            .map(|n| amplitude * (2.0 * PI * 100.0 * n as f64 / RATE as f64).sin() as f32)
+            .collect()
+    }
+
+    fn analyze(samples: &[f32]) -> LoopAnalysis {
+        let mut analyzer = LoopAnalyzer::new(RATE, 1);
+        analyzer.add_samples(samples);
+        analyzer.finish()
+    }
+
+    #[test]
+    fn whole_cycles_loop_cleanly() {
+        let analysis = analyze(&sine(96000, 0.5));
+        assert!(analysis.issues.is_empty(), "{:?}", analysis.issues);
+        assert_eq!((analysis.loop_start, analysis.loop_end), (0, 96000));
+        assert!(analysis.spectral_distance_db.unwrap() < 1.0);
+    }
+
+    #[test]
+    fn suggests_zero_crossings_for_a_cut_off_cycle() {
+        // Cut a quarter cycle past the last full cycle, on a peak
+        let analysis = analyze(&sine(96120, 0.5));
+        assert_eq!(analysis.issues.len(), 1, "{:?}", analysis.issues);
+        assert_eq!(analysis.loop_start, 0);
+        assert!(analysis.loop_end.abs_diff(96000) <= 1, "{}", analysis.loop_end);
+        assert!(analysis.seam_jump.unwrap() < MAX_SEAM_JUMP);
+    }
+
+    #[test]
+    fn reports_silence_offset_and_level_jumps() {
+        let mut samples = vec![0.0; 24000];
+        samples.extend(sine(96000, 0.5).iter().map(|x| x + 0.05));
+        let analysis = analyze(&samples);
+        assert!(analysis.issues.iter().any(|issue| issue.starts_with("DC offset")));
+        assert!(analysis.issues.iter().any(|issue| issue.contains("silence at the start")));
+        assert!(analysis.loop_start.abs_diff(24000) <= 480);
+
+        let mut fading = sine(48000, 0.5);
+        fading.extend(sine(48000, 0.1));
+        let analysis = analyze(&fading);
+        assert!(analysis.level_change_db.unwrap() > 13.0);
+        assert!(analysis.issues.iter().any(|issue| issue.starts_with("Level jumps")));
+
+        assert_eq!(analyze(&[0.0; 4800]).issues, vec!["The file is silent".to_string()]);
+    }
+}
@@ -0,0 +1,287 @@
+use crate::models::LoudnessAnalysis;
+use std::f64::consts::PI;
+
+/// EBU R128 programme loudness target
+pub const TARGET_LOUDNESS: f64 = -23.0;
+/// Volume a sound at the target loudness gets in an atmosphere (the `atmosphere_sounds` default)
+pub const REFERENCE_VOLUME: f32 = 0.5;
+/// Highest true peak a suggested volume may push a sound to, in dBTP
+const PEAK_CEILING: f64 = -1.0;
+const MIN_SUGGESTED_VOLUME: f64 = 0.05;
+
+const ABSOLUTE_GATE: f64 = -70.0;
+const RELATIVE_GATE: f64 = -10.0;
+const RANGE_RELATIVE_GATE: f64 = -20.0;
+
+/// Gating blocks advance in 100 ms steps; momentary blocks span 400 ms, short-term windows 3 s
+const STEPS_PER_SECOND: u32 = 10;
+const MOMENTARY_STEPS: usize = 4;
+const SHORT_TERM_STEPS: usize = 30;
+
+/// True peak is measured on a 4x oversampled signal
+const OVERSAMPLING: usize = 4;
+const TAPS_PER_PHASE: usize = 12;
+
+/// Biquad filter in transposed direct form II
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Biquad { b, a, z: [0.0; 2] }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// The two K-weighting stages of BS.1770 (head shelf, then RLB high-pass), derived for any sample rate
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let rate = sample_rate as f64;
+
+    let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (PI * f0 / rate).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad::new(
+        [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+        [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (PI * f0 / rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad::new([1.0, -2.0, 1.0], [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0]);
+
+    [shelf, high_pass]
+}
+
+/// BS.1770 channel weight, assuming the usual L R C (LFE) Ls Rs order for 5.0 and 5.1
+fn channel_weight(channels: usize, index: usize) -> f64 {
+    match (channels, index) {
+        (5, 3 | 4) | (6, 4 | 5) => 1.41,
+        (6, 3) => 0.0,
+        _ => 1.0,
+    }
+}
+
+/// Polyphase coefficients of a Hann-windowed sinc interpolator; phase 0 passes the samples through
+fn interpolation_phases() -> [[f64; TAPS_PER_PHASE]; OVERSAMPLING] {
+    let center = (TAPS_PER_PHASE * OVERSAMPLING / 2) as f64;
+    let mut phases = [[0.0; TAPS_PER_PHASE]; OVERSAMPLING];
+    for (phase, taps) in phases.iter_mut().enumerate() {
+        for (k, tap) in taps.iter_mut().enumerate() {
+            let t = (k * OVERSAMPLING + phase) as f64 - center;
+            let x = t / OVERSAMPLING as f64;
+            let sinc = if x == 0.0 { 1.0 } else { (PI * x).sin() / (PI * x) };
+            let window = 0.5 + 0.5 * (PI * t / (center + 1.0)).cos();
+            *tap = sinc * window;
+        }
+        let sum: f64 = taps.iter().sum();
+        taps.iter_mut().for_each(|tap| *tap /= sum);
+    }
+    phases
+}
+
+fn energy_to_loudness(energy: f64) -> f64 {
+    -0.691 + 10.0 * energy.log10()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Mean energy of every window of `len` steps, sliding one step at a time
+fn windows(steps: &[f64], len: usize) -> Vec<f64> {
+    steps.windows(len).map(mean).collect()
+}
+
+/// Gated integrated loudness of momentary blocks (absolute gate, then relative gate)
+fn integrated_loudness(blocks: &[f64]) -> Option<f64> {
+    let above_absolute: Vec<f64> = blocks.iter().copied()
+        .filter(|&energy| energy_to_loudness(energy) > ABSOLUTE_GATE)
+        .collect();
+    if above_absolute.is_empty() {
+        return None;
+    }
+
+    let relative_gate = energy_to_loudness(mean(&above_absolute)) + RELATIVE_GATE;
+    let gated: Vec<f64> = above_absolute.into_iter()
+        .filter(|&energy| energy_to_loudness(energy) > relative_gate)
+        .collect();
+    Some(energy_to_loudness(mean(&gated)))
+}
+
+/// Loudness range (EBU Tech 3342): spread between the 10th and 95th percentile of gated short-term loudness
+fn loudness_range(short_term: &[f64]) -> Option<f64> {
+    let above_absolute: Vec<f64> = short_term.iter().copied()
+        .filter(|&energy| energy_to_loudness(energy) > ABSOLUTE_GATE)
+        .collect();
+    if above_absolute.is_empty() {
+        return None;
+    }
+
+    let relative_gate = energy_to_loudness(mean(&above_absolute)) + RANGE_RELATIVE_GATE;
+    let mut loudness: Vec<f64> = above_absolute.into_iter()
+        .map(energy_to_loudness)
+        .filter(|&l| l > relative_gate)
+        .collect();
+    loudness.sort_by(|a, b| a.total_cmp(b));
+
+    let percentile = |p: f64| loudness[((loudness.len() - 1) as f64 * p).round() as usize];
+    Some(percentile(0.95) - percentile(0.10))
+}
+
+/// Streaming EBU R128 meter, fed with interleaved samples
+pub struct LoudnessMeter {
+    channels: usize,
+    weights: Vec<f64>,
+    filters: Vec<[Biquad; 2]>,
+    /// Most recent input samples per channel, newest first, for the true peak interpolator
+    history: Vec<[f64; TAPS_PER_PHASE]>,
+    phases: [[f64; TAPS_PER_PHASE]; OVERSAMPLING],
+    step_frames: usize,
+    frames_in_step: usize,
+    step_energy: f64,
+    /// Mean channel-weighted energy of every completed 100 ms step
+    steps: Vec<f64>,
+    peak: f64,
+}
+
+impl LoudnessMeter {
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        let channels = channels.max(1);
+        LoudnessMeter {
+            channels,
+            weights: (0..channels).map(|index| channel_weight(channels, index)).collect(),
+            filters: vec![k_weighting(sample_rate); channels],
+            history: vec![[0.0; TAPS_PER_PHASE]; channels],
+            phases: interpolation_phases(),
+            step_frames: (sample_rate / STEPS_PER_SECOND).max(1) as usize,
+            frames_in_step: 0,
+            step_energy: 0.0,
+            steps: Vec::new(),
+            peak: 0.0,
+        }
+    }
+
+    /// Feed interleaved samples; a trailing partial frame is ignored
+    pub fn add_samples(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(self.channels) {
+            for (channel, &sample) in frame.iter().enumerate() {
+                let x = sample as f64;
+                let [shelf, high_pass] = &mut self.filters[channel];
+                let y = high_pass.process(shelf.process(x));
+                self.step_energy += self.weights[channel] * y * y;
+
+                let history = &mut self.history[channel];
+                history.copy_within(0..TAPS_PER_PHASE - 1, 1);
+                history[0] = x;
+                for taps in &self.phases {
+                    let interpolated: f64 = taps.iter().zip(history.iter()).map(|(t, h)| t * h).sum();
+                    self.peak = self.peak.max(interpolated.abs());
+                }
+                // The last few samples never reach the interpolator's center
+                self.peak = self.peak.max(x.abs());
+            }
+
+            self.frames_in_step += 1;
+            if self.frames_in_step == self.step_frames {
+                self.steps.push(self.step_energy / self.step_frames as f64);
+                self.step_energy = 0.0;
+                self.frames_in_step = 0;
+            }
+        }
+    }
+
+    pub fn finish(self) -> LoudnessAnalysis {
+        LoudnessAnalysis {
+            integrated: integrated_loudness(&windows(&self.steps, MOMENTARY_STEPS)),
+            range: loudness_range(&windows(&self.steps, SHORT_TERM_STEPS)),
+            true_peak: (self.peak > 0.0).then(|| 20.0 * self.peak.log10()),
+        }
+    }
+}
+
+/// Atmosphere volume that brings a sound to the target loudness without its peaks passing the ceiling
+pub fn suggested_volume(integrated: f64, true_peak: Option<f64>) -> f32 {
+    let mut volume = REFERENCE_VOLUME as f64 * 10f64.powf((TARGET_LOUDNESS - integrated) / 20.0);
+    if let Some(peak) = true_peak {
+        volume = volume.min(10f64.powf((PEAK_CEILING - peak) / 20.0));
+    }
+    let volume = volume.clamp(MIN_SUGGESTED_VOLUME, 1.0);
+    ((volume * 100.0).round() / 100.0) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 48000;
+
+    /// Stereo sine with the same signal on both channels
+    fn sine(freq: f64, amplitude: f64, phase: f64, seconds: f64) -> Vec<f32> {
+        (0..(seconds * RATE as f64) as usize)
+            .flat_map(|n| {
+                let x = (amplitude * (2.0 * PI * freq * n as f64 / RATE as f64 + phase).sin()) as f32;
+                [x, x]
+            })
+            .collect()
+    }
+
+    fn measure(parts: &[Vec<f32>]) -> LoudnessAnalysis {
+        let mut meter = LoudnessMeter::new(RATE, 2);
+        parts.iter().for_each(|part| meter.add_samples(part));
+        meter.finish()
+    }
+
+    #[test]
+    fn measures_a_steady_sine() {
+        // A 1 kHz sine at -20 dBFS on both channels reads -20 LUFS
+        let analysis = measure(&[sine(1000.0, 0.1, 0.0, 10.0)]);
+        assert!((analysis.integrated.unwrap() + 20.0).abs() < 0.1, "{:?}", analysis);
+        assert!(analysis.range.unwrap() < 0.1);
+        assert!((analysis.true_peak.unwrap() + 20.0).abs() < 0.1);
+
+        assert_eq!(measure(&[vec![0.0; RATE as usize * 8]]), LoudnessAnalysis::default());
+    }
+
+    #[test]
+    fn gates_silence_and_spreads_range() {
+        let tone = sine(1000.0, 0.1, 0.0, 10.0);
+        let with_silence = measure(&[tone.clone(), vec![0.0; tone.len()]]);
+        assert!((with_silence.integrated.unwrap() + 20.0).abs() < 0.1);
+
+        let two_levels = measure(&[tone, sine(1000.0, 0.1 / 10f64.sqrt(), 0.0, 10.0)]);
+        assert!((two_levels.range.unwrap() - 10.0).abs() < 0.5, "{:?}", two_levels);
+    }
+
+    #[test]
+    fn finds_peaks_between_samples() {
+        // Sampled at 45 degrees the samples only reach 0.707 of the amplitude
+        let analysis = measure(&[sine(RATE as f64 / 4.0, 0.5, PI / 4.0, 1.0)]);
+        let true_peak = analysis.true_peak.unwrap();
+        assert!((true_peak - 20.0 * 0.5f64.log10()).abs() < 0.5, "{}", true_peak);
+    }
+
+    #[test]
+    fn suggests_volumes_towards_the_target() {
+        assert_eq!(suggested_volume(TARGET_LOUDNESS, None), REFERENCE_VOLUME);
+        let loud = suggested_volume(-12.0, Some(-1.0));
+        let quiet = suggested_volume(-30.0, Some(-12.0));
+        assert!(loud < REFERENCE_VOLUME && quiet > REFERENCE_VOLUME);
+        // Raising a quiet sound stops at the peak ceiling and at full volume
+        assert_eq!(suggested_volume(-40.0, Some(0.0)), 0.89);
+        assert_eq!(suggested_volume(-40.0, None), 1.0);
+        assert_eq!(suggested_volume(0.0, None), 0.05);
+    }
+}
@@ -7,14 +7,30 @@ use std::sync::Mutex;
 use tauri::AppHandle;
 
 use virtual_folder_handler::{suggest_folders_for_file, get_auto_organization_suggestions, apply_auto_organization_suggestions, auto_organize_sounds};
-use gemini_handler::{check_gemini_api_key, get_untagged_files, auto_tag_files, get_tagging_history};
+use gemini_handler::{check_gemini_api_key, get_untagged_files, auto_tag_files, auto_tag_files_offline, get_tagging_history};
 
 mod models;
 mod database;
 mod data;
 mod audio_handler;
+mod tag_formats;
+mod ucs;
+mod loudness;
+mod loop_analysis;
+mod waveform;
+mod key_detection;
+mod tempo;
+mod silence;
+mod fingerprint;
+mod sound_classifier;
+mod content_hash;
+mod quality;
+mod cover_art;
+mod tag_backup;
 mod tag_manager;
 mod file_scanner;
+mod library_sync;
+mod library_watcher;
 mod atmosphere_handler;
 mod import_export_handler;
 mod audio_processing_handler;
@@ -31,9 +47,10 @@ use database::{Database, DatabasePool};
 use audio_handler::AudioHandler;
 use tag_manager::TagManager;
 use file_scanner::FileScanner;
+use library_watcher::LibraryWatcher;
 use atmosphere_handler::AtmosphereHandler;
 use import_export_handler::ImportExportHandler;
-use audio_processing_handler::AudioProcessingHandler;
+use audio_processing_handler::{AnalysisJob, AudioProcessingHandler};
 use audio_file_handler::AudioFileHandler;
 use tag_handler::TagHandler;
 
@@ -41,6 +58,10 @@ struct AppState {
     db: Mutex<Database>, // Keep for backward compatibility during transition
     db_pool: DatabasePool, // New connection pool
     tag_manager: TagManager,
+    duration_job: AnalysisJob,
+    /// The batch analysis running, if any; one at a time since each keeps every core busy
+    analysis_job: AnalysisJob,
+    library_watcher: LibraryWatcher,
 }
 
 #[tauri::command]
@@ -69,18 +90,68 @@ async fn get_all_audio_files(app_handle: AppHandle) -> Result<Vec<AudioFile>, St
 }
 
 #[tauri::command]
-async fn update_audio_file_tags(app_handle: AppHandle, file_path: String, updates: AudioFile) -> Result<(), String> {
+async fn update_audio_file_tags(app_handle: AppHandle, file_path: String, updates: AudioFilePatch) -> Result<(), String> {
     AudioFileHandler::update_audio_file_tags(app_handle, file_path, updates)
 }
 
 #[tauri::command]
-async fn write_rpg_tags_to_file(app_handle: AppHandle, file_path: String) -> Result<(), String> {
-    AudioFileHandler::write_rpg_tags_to_file(app_handle, file_path)
+async fn get_audio_files_by_content_hash(app_handle: AppHandle, content_hash: String) -> Result<Vec<AudioFile>, String> {
+    AudioFileHandler::get_audio_files_by_content_hash(app_handle, content_hash)
 }
 
 #[tauri::command]
-async fn scan_directory_recursive(dir_path: String) -> Result<Vec<String>, String> {
-    AudioFileHandler::scan_directory_recursive(dir_path)
+async fn search_audio_files_by_stream(app_handle: AppHandle, request: StreamSearchRequest) -> Result<Vec<AudioFile>, String> {
+    AudioFileHandler::search_audio_files_by_stream(app_handle, request)
+}
+
+#[tauri::command]
+async fn get_cover_art(file_path: String) -> Result<Vec<CoverArt>, String> {
+    AudioFileHandler::get_cover_art(file_path)
+}
+
+#[tauri::command]
+async fn get_waveform_peaks(file_path: String, start: Option<f64>, end: Option<f64>, width: usize) -> Result<WaveformPeaks, String> {
+    AudioFileHandler::get_waveform_peaks(file_path, start, end, width)
+}
+
+#[tauri::command]
+async fn write_rpg_tags_to_file(app_handle: AppHandle, file_path: String, backup: Option<bool>) -> Result<(), String> {
+    AudioFileHandler::write_rpg_tags_to_file(app_handle, file_path, backup.unwrap_or(true))
+}
+
+#[tauri::command]
+async fn scan_directory_recursive(dir_path: String, extensions: Option<Vec<String>>) -> Result<Vec<String>, String> {
+    AudioFileHandler::scan_directory_recursive(dir_path, extensions)
+}
+
+#[tauri::command]
+async fn sync_library(app_handle: AppHandle, dir_path: String, extensions: Option<Vec<String>>) -> Result<LibrarySyncSummary, String> {
+    AudioFileHandler::sync_library(app_handle, dir_path, extensions)
+}
+
+#[tauri::command]
+async fn watch_library(app_handle: AppHandle, dir_paths: Vec<String>, extensions: Option<Vec<String>>) -> Result<(), String> {
+    AudioFileHandler::watch_library(app_handle, dir_paths, extensions)
+}
+
+#[tauri::command]
+async fn stop_watching_library(app_handle: AppHandle) -> Result<bool, String> {
+    Ok(AudioFileHandler::stop_watching_library(app_handle))
+}
+
+#[tauri::command]
+async fn get_watched_library_directories(app_handle: AppHandle) -> Vec<String> {
+    AudioFileHandler::get_watched_library_directories(app_handle)
+}
+
+#[tauri::command]
+async fn get_supported_extensions() -> Vec<String> {
+    FileScanner::get_supported_extensions()
+}
+
+#[tauri::command]
+async fn get_unplayable_files(app_handle: AppHandle) -> Result<Vec<UnplayableFile>, String> {
+    AudioFileHandler::get_unplayable_files(app_handle)
 }
 
 #[tauri::command]
@@ -169,13 +240,43 @@ async fn import_library_data(app_handle: AppHandle, data: String) -> Result<(),
 }
 
 #[tauri::command]
-async fn store_all_tags_in_files(app_handle: AppHandle) -> Result<StoreTagsResult, String> {
-    store_tags_handler::store_all_tags_in_files(app_handle).await
+async fn store_all_tags_in_files(app_handle: AppHandle, backup: Option<bool>) -> Result<StoreTagsResult, String> {
+    store_tags_handler::store_all_tags_in_files(app_handle, backup.unwrap_or(true)).await
+}
+
+#[tauri::command]
+async fn remove_all_tags_from_files(app_handle: AppHandle, backup: Option<bool>) -> Result<StoreTagsResult, String> {
+    remove_tags_handler::remove_all_tags_from_files(app_handle, backup.unwrap_or(true)).await
+}
+
+#[tauri::command]
+async fn preview_store_tags(app_handle: AppHandle, filter: Option<TagPreviewFilter>) -> Result<TagPreviewResult, String> {
+    store_tags_handler::preview_store_tags(app_handle, filter.unwrap_or_default()).await
+}
+
+#[tauri::command]
+async fn store_tags_in_selected_files(app_handle: AppHandle, file_paths: Vec<String>, backup: Option<bool>) -> Result<StoreTagsResult, String> {
+    store_tags_handler::store_tags_in_selected_files(app_handle, file_paths, backup.unwrap_or(true)).await
 }
 
 #[tauri::command]
-async fn remove_all_tags_from_files(app_handle: AppHandle) -> Result<StoreTagsResult, String> {
-    remove_tags_handler::remove_all_tags_from_files(app_handle).await
+async fn preview_remove_tags(app_handle: AppHandle, filter: Option<TagPreviewFilter>) -> Result<TagPreviewResult, String> {
+    remove_tags_handler::preview_remove_tags(app_handle, filter.unwrap_or_default()).await
+}
+
+#[tauri::command]
+async fn remove_tags_from_selected_files(app_handle: AppHandle, file_paths: Vec<String>, backup: Option<bool>) -> Result<StoreTagsResult, String> {
+    remove_tags_handler::remove_tags_from_selected_files(app_handle, file_paths, backup.unwrap_or(true)).await
+}
+
+#[tauri::command]
+async fn remove_tags_scoped(app_handle: AppHandle, request: ScopedTagRemovalRequest, backup: Option<bool>) -> Result<StoreTagsResult, String> {
+    remove_tags_handler::remove_tags_scoped(app_handle, request, backup.unwrap_or(true)).await
+}
+
+#[tauri::command]
+async fn restore_last_tag_write_run(app_handle: AppHandle) -> Result<StoreTagsResult, String> {
+    store_tags_handler::restore_last_tag_write_run(app_handle).await
 }
 
 // Atmosphere Commands
@@ -200,7 +301,12 @@ async fn delete_atmosphere(app_handle: AppHandle, id: i64) -> Result<(), String>
 }
 
 #[tauri::command]
-async fn add_sound_to_atmosphere(app_handle: AppHandle, atmosphere_id: i64, audio_file_id: i64, volume: f32, is_looping: bool) -> Result<i64, String> {
+async fn set_atmosphere_cover_art(app_handle: AppHandle, atmosphere_id: i64, cover_art_hash: String) -> Result<Atmosphere, String> {
+    AtmosphereHandler::set_atmosphere_cover_art(app_handle, atmosphere_id, cover_art_hash)
+}
+
+#[tauri::command]
+async fn add_sound_to_atmosphere(app_handle: AppHandle, atmosphere_id: i64, audio_file_id: i64, volume: Option<f32>, is_looping: bool) -> Result<i64, String> {
     AtmosphereHandler::add_sound_to_atmosphere(app_handle, atmosphere_id, audio_file_id, volume, is_looping)
 }
 
@@ -250,6 +356,91 @@ async fn calculate_missing_durations(app_handle: AppHandle) -> Result<String, St
     AudioProcessingHandler::calculate_missing_durations(app_handle)
 }
 
+#[tauri::command]
+async fn cancel_duration_calculation(app_handle: AppHandle) -> Result<bool, String> {
+    Ok(AudioProcessingHandler::cancel_duration_calculation(app_handle))
+}
+
+#[tauri::command]
+async fn cancel_analysis(app_handle: AppHandle) -> Result<bool, String> {
+    Ok(AudioProcessingHandler::cancel_analysis(app_handle))
+}
+
+#[tauri::command]
+async fn analyze_missing_loudness(app_handle: AppHandle) -> Result<String, String> {
+    AudioProcessingHandler::analyze_missing_loudness(app_handle)
+}
+
+#[tauri::command]
+async fn analyze_missing_loops(app_handle: AppHandle) -> Result<String, String> {
+    AudioProcessingHandler::analyze_missing_loops(app_handle)
+}
+
+#[tauri::command]
+async fn detect_missing_keys(app_handle: AppHandle) -> Result<String, String> {
+    AudioProcessingHandler::detect_missing_keys(app_handle)
+}
+
+#[tauri::command]
+async fn analyze_missing_silence(app_handle: AppHandle) -> Result<String, String> {
+    AudioProcessingHandler::analyze_missing_silence(app_handle)
+}
+
+#[tauri::command]
+async fn probe_missing_decodability(app_handle: AppHandle) -> Result<String, String> {
+    AudioProcessingHandler::probe_missing_decodability(app_handle)
+}
+
+#[tauri::command]
+async fn read_missing_stream_properties(app_handle: AppHandle) -> Result<String, String> {
+    AudioProcessingHandler::read_missing_stream_properties(app_handle)
+}
+
+#[tauri::command]
+async fn assess_missing_quality(app_handle: AppHandle) -> Result<String, String> {
+    AudioProcessingHandler::assess_missing_quality(app_handle)
+}
+
+#[tauri::command]
+async fn hash_missing_files(app_handle: AppHandle) -> Result<String, String> {
+    AudioProcessingHandler::hash_missing_files(app_handle)
+}
+
+#[tauri::command]
+async fn fingerprint_missing_files(app_handle: AppHandle) -> Result<String, String> {
+    AudioProcessingHandler::fingerprint_missing_files(app_handle)
+}
+
+#[tauri::command]
+async fn find_duplicates(app_handle: AppHandle) -> Result<Vec<DuplicateGroup>, String> {
+    AudioProcessingHandler::find_duplicates(app_handle)
+}
+
+#[tauri::command]
+async fn merge_duplicates(app_handle: AppHandle, canonical_id: i64, duplicate_ids: Vec<i64>) -> Result<usize, String> {
+    AudioProcessingHandler::merge_duplicates(app_handle, canonical_id, duplicate_ids)
+}
+
+#[tauri::command]
+async fn get_loopable_proposals(app_handle: AppHandle) -> Result<Vec<LoopableProposal>, String> {
+    AudioProcessingHandler::get_loopable_proposals(app_handle)
+}
+
+#[tauri::command]
+async fn apply_loopable_proposals(app_handle: AppHandle, audio_file_ids: Vec<i64>) -> Result<usize, String> {
+    AudioProcessingHandler::apply_loopable_proposals(app_handle, audio_file_ids)
+}
+
+#[tauri::command]
+async fn get_quality_proposals(app_handle: AppHandle) -> Result<Vec<QualityProposal>, String> {
+    AudioProcessingHandler::get_quality_proposals(app_handle)
+}
+
+#[tauri::command]
+async fn apply_quality_proposals(app_handle: AppHandle, audio_file_ids: Vec<i64>) -> Result<usize, String> {
+    AudioProcessingHandler::apply_quality_proposals(app_handle, audio_file_ids)
+}
+
 // Import virtual folder commands from handler
 use virtual_folder_handler::{
     create_virtual_folder, get_virtual_folder_by_id, update_virtual_folder, delete_virtual_folder,
@@ -279,6 +470,9 @@ pub fn run() {
             db: Mutex::new(db),
             db_pool,
             tag_manager,
+            duration_job: AnalysisJob::default(),
+            analysis_job: AnalysisJob::default(),
+            library_watcher: LibraryWatcher::default(),
         })
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
@@ -303,8 +497,18 @@ pub fn run() {
             get_all_audio_files,
             delete_audio_file,
             update_audio_file_tags,
+            get_audio_files_by_content_hash,
+            search_audio_files_by_stream,
+            get_cover_art,
+            get_waveform_peaks,
             write_rpg_tags_to_file,
             scan_directory_recursive,
+            sync_library,
+            watch_library,
+            stop_watching_library,
+            get_watched_library_directories,
+            get_supported_extensions,
+            get_unplayable_files,
             get_tag_vocabulary,
             add_rpg_tag,
             remove_rpg_tag,
@@ -318,11 +522,35 @@ pub fn run() {
             import_library_data,
             store_all_tags_in_files,
             remove_all_tags_from_files,
+            preview_store_tags,
+            store_tags_in_selected_files,
+            preview_remove_tags,
+            remove_tags_from_selected_files,
+            remove_tags_scoped,
+            restore_last_tag_write_run,
             calculate_missing_durations,
+            cancel_duration_calculation,
+            cancel_analysis,
+            analyze_missing_loudness,
+            analyze_missing_loops,
+            detect_missing_keys,
+            analyze_missing_silence,
+            probe_missing_decodability,
+            read_missing_stream_properties,
+            assess_missing_quality,
+            hash_missing_files,
+            fingerprint_missing_files,
+            find_duplicates,
+            merge_duplicates,
+            get_loopable_proposals,
+            apply_loopable_proposals,
+            get_quality_proposals,
+            apply_quality_proposals,
             save_atmosphere,
             get_all_atmospheres,
             get_atmosphere_by_id,
             delete_atmosphere,
+            set_atmosphere_cover_art,
             add_sound_to_atmosphere,
             remove_sound_from_atmosphere,
             update_atmosphere_sound,
@@ -353,6 +581,7 @@ pub fn run() {
             check_gemini_api_key,
             get_untagged_files,
             auto_tag_files,
+            auto_tag_files_offline,
             get_tagging_history
         ])
         .run(tauri::generate_context!())
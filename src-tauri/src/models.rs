@@ -89,11 +89,75 @@ pub struct AudioFile {
     pub tempo: Option<String>,
     pub content_type: Option<String>,
     pub category: Option<String>,
+    pub subcategory: Option<String>,
     
     // Auto-tagging metadata
     pub auto_tagged: Option<bool>,
     pub auto_tag_date: Option<String>,
     pub auto_tag_version: Option<String>,
+
+    // Loudness analysis (EBU R128)
+    /// Integrated loudness in LUFS
+    pub loudness_integrated: Option<f64>,
+    /// Loudness range in LU
+    pub loudness_range: Option<f64>,
+    /// True peak in dBTP
+    pub true_peak: Option<f64>,
+
+    // Loop analysis
+    /// Suggested loop points in frames, the end exclusive
+    pub loop_start: Option<i64>,
+    pub loop_end: Option<i64>,
+    pub loop_seamless: Option<bool>,
+
+    /// How clearly the audio points to its `initial_key`, 0..1; set once detection ran, also for non-tonal audio
+    pub key_confidence: Option<f64>,
+
+    // Silence detection
+    /// Seconds of dead air before the sound starts and after it ends; playback can skip them
+    pub leading_silence: Option<f64>,
+    pub trailing_silence: Option<f64>,
+    pub mostly_silent: Option<bool>,
+
+    /// SHA-256 of the decoded audio; identifies the sound across moves, renames and re-tagging
+    pub content_hash: Option<String>,
+
+    /// How clearly the audio has a steady beat at `bpm`, 0..1; set once detection ran, also for non-rhythmic audio
+    pub bpm_confidence: Option<f64>,
+
+    // Quality assessment
+    /// Grade proposed for the `quality` tag from the measurements below
+    pub quality_grade: Option<String>,
+    pub clipping_ratio: Option<f64>,
+    /// dBFS
+    pub noise_floor: Option<f64>,
+    /// Highest frequency with content, in Hz
+    pub bandwidth: Option<f64>,
+
+    // Stream properties
+    /// Short codec name, e.g. "mp3", "flac", "vorbis", "pcm_s16le"
+    pub codec: Option<String>,
+    /// Container format, e.g. "wav", "mp4", "ogg"
+    pub container: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    /// `None` for lossy codecs
+    pub bit_depth: Option<u32>,
+    /// Average bitrate in kbps
+    pub bitrate: Option<u32>,
+    /// Size on disk in bytes
+    pub file_size: Option<u64>,
+
+    /// Whether the file opened and its first packets decoded when it was last probed
+    pub decodable: Option<bool>,
+    /// Why the file can't be decoded
+    pub decode_error: Option<String>,
+
+    // Library sync
+    /// Modification time in seconds since the Unix epoch when the file was last read
+    pub file_modified: Option<i64>,
+    /// Set when a library sync no longer finds the file on disk
+    pub missing: Option<bool>,
 }
 
 impl Default for AudioFile {
@@ -150,13 +214,442 @@ impl Default for AudioFile {
             tempo: None,
             content_type: None,
             category: None,
+            subcategory: None,
             auto_tagged: None,
             auto_tag_date: None,
             auto_tag_version: None,
+            loudness_integrated: None,
+            loudness_range: None,
+            true_peak: None,
+            loop_start: None,
+            loop_end: None,
+            loop_seamless: None,
+            key_confidence: None,
+            leading_silence: None,
+            trailing_silence: None,
+            mostly_silent: None,
+            content_hash: None,
+            bpm_confidence: None,
+            quality_grade: None,
+            clipping_ratio: None,
+            noise_floor: None,
+            bandwidth: None,
+            codec: None,
+            container: None,
+            sample_rate: None,
+            channels: None,
+            bit_depth: None,
+            bitrate: None,
+            file_size: None,
+            decodable: None,
+            decode_error: None,
+            file_modified: None,
+            missing: None,
         }
     }
 }
 
+/// Change to a single `AudioFile` field
+///
+/// In JSON a missing key leaves the field alone, `null` clears it and a value sets it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum FieldPatch<T> {
+    #[default]
+    Leave,
+    Clear,
+    Set(T),
+}
+
+impl<T> FieldPatch<T> {
+    /// `Some` sets the field, `None` leaves it untouched
+    pub fn from_option(value: Option<T>) -> Self {
+        match value {
+            Some(value) => FieldPatch::Set(value),
+            None => FieldPatch::Leave,
+        }
+    }
+
+    pub fn is_leave(&self) -> bool {
+        matches!(self, FieldPatch::Leave)
+    }
+
+    pub fn map<U>(&self, f: impl FnOnce(&T) -> U) -> FieldPatch<U> {
+        match self {
+            FieldPatch::Leave => FieldPatch::Leave,
+            FieldPatch::Clear => FieldPatch::Clear,
+            FieldPatch::Set(value) => FieldPatch::Set(f(value)),
+        }
+    }
+
+    /// This patch, or `other` when this one leaves the field alone
+    pub fn or(self, other: FieldPatch<T>) -> FieldPatch<T> {
+        if self.is_leave() { other } else { self }
+    }
+
+    /// Value of the field after applying the patch to `current`
+    pub fn apply(&self, current: Option<T>) -> Option<T>
+    where
+        T: Clone,
+    {
+        match self {
+            FieldPatch::Leave => current,
+            FieldPatch::Clear => None,
+            FieldPatch::Set(value) => Some(value.clone()),
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for FieldPatch<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // Only reached for keys that are present; absent keys use `Default` (Leave)
+        Ok(match Option::<T>::deserialize(deserializer)? {
+            Some(value) => FieldPatch::Set(value),
+            None => FieldPatch::Clear,
+        })
+    }
+}
+
+/// Partial update of an `AudioFile`, applied to both the database row and the file tags
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct AudioFilePatch {
+    // Basic tags
+    pub title: FieldPatch<String>,
+    pub artist: FieldPatch<String>,
+    pub album: FieldPatch<String>,
+    pub album_artist: FieldPatch<String>,
+    pub genre: FieldPatch<String>,
+    pub year: FieldPatch<i32>,
+    pub date: FieldPatch<String>,
+    pub track_number: FieldPatch<u32>,
+    pub total_tracks: FieldPatch<u32>,
+    pub disc_number: FieldPatch<u32>,
+    pub total_discs: FieldPatch<u32>,
+    pub duration: FieldPatch<f64>,
+
+    // Additional ID3v2 tags
+    pub composer: FieldPatch<String>,
+    pub conductor: FieldPatch<String>,
+    pub lyricist: FieldPatch<String>,
+    pub original_artist: FieldPatch<String>,
+    pub remixer: FieldPatch<String>,
+    pub arranger: FieldPatch<String>,
+    pub engineer: FieldPatch<String>,
+    pub producer: FieldPatch<String>,
+    pub dj_mixer: FieldPatch<String>,
+    pub mixer: FieldPatch<String>,
+
+    // Content tags
+    pub content_group: FieldPatch<String>,
+    pub subtitle: FieldPatch<String>,
+    pub initial_key: FieldPatch<String>,
+    pub bpm: FieldPatch<u32>,
+    pub language: FieldPatch<String>,
+    pub media_type: FieldPatch<String>,
+    pub original_filename: FieldPatch<String>,
+    pub original_lyricist: FieldPatch<String>,
+    pub original_release_time: FieldPatch<String>,
+    pub playlist_delay: FieldPatch<u32>,
+
+    // Recording info
+    pub recording_time: FieldPatch<String>,
+    pub release_time: FieldPatch<String>,
+    pub tagging_time: FieldPatch<String>,
+    pub encoding_time: FieldPatch<String>,
+    pub encoding_settings: FieldPatch<String>,
+    pub encoded_by: FieldPatch<String>,
+
+    // Copyright and legal
+    pub copyright: FieldPatch<String>,
+    pub file_owner: FieldPatch<String>,
+    pub internet_radio_station_name: FieldPatch<String>,
+    pub internet_radio_station_owner: FieldPatch<String>,
+    pub isrc: FieldPatch<String>,
+    pub publisher: FieldPatch<String>,
+
+    // Additional metadata
+    pub mood: FieldPatch<String>,
+    pub occasion: FieldPatch<String>,
+    pub tempo: FieldPatch<String>,
+    pub content_type: FieldPatch<String>,
+    pub category: FieldPatch<String>,
+    pub subcategory: FieldPatch<String>,
+
+    // Auto-tagging metadata
+    pub auto_tagged: FieldPatch<bool>,
+    pub auto_tag_date: FieldPatch<String>,
+    pub auto_tag_version: FieldPatch<String>,
+}
+
+impl From<&AudioFile> for AudioFilePatch {
+    /// Set every field that is `Some`, leave the rest untouched
+    fn from(audio_file: &AudioFile) -> Self {
+        let a = audio_file.clone();
+        AudioFilePatch {
+            title: FieldPatch::from_option(a.title),
+            artist: FieldPatch::from_option(a.artist),
+            album: FieldPatch::from_option(a.album),
+            album_artist: FieldPatch::from_option(a.album_artist),
+            genre: FieldPatch::from_option(a.genre),
+            year: FieldPatch::from_option(a.year),
+            date: FieldPatch::from_option(a.date),
+            track_number: FieldPatch::from_option(a.track_number),
+            total_tracks: FieldPatch::from_option(a.total_tracks),
+            disc_number: FieldPatch::from_option(a.disc_number),
+            total_discs: FieldPatch::from_option(a.total_discs),
+            duration: FieldPatch::from_option(a.duration),
+            composer: FieldPatch::from_option(a.composer),
+            conductor: FieldPatch::from_option(a.conductor),
+            lyricist: FieldPatch::from_option(a.lyricist),
+            original_artist: FieldPatch::from_option(a.original_artist),
+            remixer: FieldPatch::from_option(a.remixer),
+            arranger: FieldPatch::from_option(a.arranger),
+            engineer: FieldPatch::from_option(a.engineer),
+            producer: FieldPatch::from_option(a.producer),
+            dj_mixer: FieldPatch::from_option(a.dj_mixer),
+            mixer: FieldPatch::from_option(a.mixer),
+            content_group: FieldPatch::from_option(a.content_group),
+            subtitle: FieldPatch::from_option(a.subtitle),
+            initial_key: FieldPatch::from_option(a.initial_key),
+            bpm: FieldPatch::from_option(a.bpm),
+            language: FieldPatch::from_option(a.language),
+            media_type: FieldPatch::from_option(a.media_type),
+            original_filename: FieldPatch::from_option(a.original_filename),
+            original_lyricist: FieldPatch::from_option(a.original_lyricist),
+            original_release_time: FieldPatch::from_option(a.original_release_time),
+            playlist_delay: FieldPatch::from_option(a.playlist_delay),
+            recording_time: FieldPatch::from_option(a.recording_time),
+            release_time: FieldPatch::from_option(a.release_time),
+            tagging_time: FieldPatch::from_option(a.tagging_time),
+            encoding_time: FieldPatch::from_option(a.encoding_time),
+            encoding_settings: FieldPatch::from_option(a.encoding_settings),
+            encoded_by: FieldPatch::from_option(a.encoded_by),
+            copyright: FieldPatch::from_option(a.copyright),
+            file_owner: FieldPatch::from_option(a.file_owner),
+            internet_radio_station_name: FieldPatch::from_option(a.internet_radio_station_name),
+            internet_radio_station_owner: FieldPatch::from_option(a.internet_radio_station_owner),
+            isrc: FieldPatch::from_option(a.isrc),
+            publisher: FieldPatch::from_option(a.publisher),
+            mood: FieldPatch::from_option(a.mood),
+            occasion: FieldPatch::from_option(a.occasion),
+            tempo: FieldPatch::from_option(a.tempo),
+            content_type: FieldPatch::from_option(a.content_type),
+            category: FieldPatch::from_option(a.category),
+            subcategory: FieldPatch::from_option(a.subcategory),
+            auto_tagged: FieldPatch::from_option(a.auto_tagged),
+            auto_tag_date: FieldPatch::from_option(a.auto_tag_date),
+            auto_tag_version: FieldPatch::from_option(a.auto_tag_version),
+        }
+    }
+}
+
+/// EBU R128 loudness measurement of a file; `None` where the audio is too short or silent to tell
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct LoudnessAnalysis {
+    /// Integrated loudness in LUFS
+    pub integrated: Option<f64>,
+    /// Loudness range in LU
+    pub range: Option<f64>,
+    /// True peak in dBTP
+    pub true_peak: Option<f64>,
+}
+
+/// Seam check of a file played as a loop; loop points are frame offsets, the end exclusive
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct LoopAnalysis {
+    pub loop_start: u64,
+    pub loop_end: u64,
+    /// Largest per-channel mean sample value
+    pub dc_offset: f64,
+    /// Seconds of silence before the sound starts and after it ends
+    pub leading_silence: f64,
+    pub trailing_silence: f64,
+    /// Jump across the seam relative to the typical sample-to-sample step
+    pub seam_jump: Option<f64>,
+    pub level_change_db: Option<f64>,
+    pub spectral_distance_db: Option<f64>,
+    /// Everything that keeps the file from looping cleanly; empty when it does
+    pub issues: Vec<String>,
+}
+
+/// Estimated musical key of a file
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct KeyEstimate {
+    /// Key in standard notation ("C", "F#m", "Bb"); `None` for non-tonal audio
+    pub key: Option<String>,
+    /// Correlation of the pitch class profile with the best matching key, 0..1
+    pub confidence: f64,
+}
+
+/// Detected tempo; `bpm` is `None` for audio without a steady beat, like ambiences, rain or speech
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct TempoEstimate {
+    pub bpm: Option<f64>,
+    /// Periodicity of the onsets at the tempo, 0..1
+    pub confidence: f64,
+}
+
+/// Dead air at either end of a file, in seconds, with a little kept around the sound
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct SilenceAnalysis {
+    pub leading_silence: f64,
+    pub trailing_silence: f64,
+    /// Sound for less than a tenth of the file, or none at all
+    pub mostly_silent: bool,
+}
+
+/// Technical quality measurements of a file and the grade they add up to
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct QualityAnalysis {
+    pub sample_rate: u32,
+    /// Stored bits per sample; `None` for lossy codecs, which have no fixed depth
+    pub bit_depth: Option<u32>,
+    /// Share of samples in flattened runs at full scale
+    pub clipping_ratio: f64,
+    /// Level of the quietest non-silent stretches in dBFS; `None` for audio too short to tell
+    pub noise_floor: Option<f64>,
+    /// Highest frequency with content in Hz; `None` for audio too quiet to tell
+    pub bandwidth: Option<f64>,
+    /// Proposed `quality` tag value: "High", "Medium" or "Low"
+    pub grade: String,
+}
+
+/// Technical properties of a file's audio stream, read from its headers
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct StreamProperties {
+    /// Short codec name, e.g. "mp3", "flac", "vorbis", "pcm_s16le"
+    pub codec: Option<String>,
+    /// Container format, e.g. "wav", "mp4", "ogg"
+    pub container: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    /// Stored bits per sample; `None` for lossy codecs
+    pub bit_depth: Option<u32>,
+    /// Average bitrate in kbps, from the file size and duration
+    pub bitrate: Option<u32>,
+    /// Size on disk in bytes
+    pub file_size: Option<u64>,
+}
+
+/// A library file that can't be decoded, with the atmospheres that would go silent
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct UnplayableFile {
+    pub audio_file_id: i64,
+    pub file_path: String,
+    pub decode_error: Option<String>,
+    /// Names of the atmospheres using the file
+    pub atmospheres: Vec<String>,
+}
+
+/// File counts of a library sync of one directory
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct LibrarySyncSummary {
+    /// New files loaded and saved
+    pub added: usize,
+    /// Changed files read again
+    pub updated: usize,
+    /// Rows whose file is no longer on disk
+    pub missing: usize,
+    /// Rows flagged missing whose file was found again
+    pub restored: usize,
+    pub unchanged: usize,
+    /// Files that couldn't be read or saved
+    pub failed: usize,
+}
+
+/// A batch of changes the library watcher applied, emitted as `library-changed`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct LibraryChangedEvent {
+    pub summary: LibrarySyncSummary,
+    /// Rows that followed their file or directory to a new path
+    pub renamed: usize,
+    /// Files and directories the batch touched
+    pub paths: Vec<String>,
+}
+
+/// A file whose measured quality grade differs from its `quality` tag
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct QualityProposal {
+    pub audio_file_id: i64,
+    pub file_path: String,
+    pub quality_grade: String,
+    /// The file's current `quality` tag, if any
+    pub current_quality: Option<String>,
+    pub clipping_ratio: Option<f64>,
+    pub noise_floor: Option<f64>,
+    pub bandwidth: Option<f64>,
+}
+
+/// A file in a group of duplicates
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DuplicateFile {
+    pub audio_file_id: i64,
+    pub file_path: String,
+    pub duration: f64,
+    /// Size on disk in bytes; `None` when the file can't be read
+    pub file_size: Option<u64>,
+    pub tag_count: i64,
+}
+
+/// Files that sound the same regardless of container and bitrate; `canonical_id` is the suggested row to keep
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DuplicateGroup {
+    pub canonical_id: i64,
+    /// Lowest share of matching fingerprint bits that joined the group
+    pub similarity: f64,
+    pub files: Vec<DuplicateFile>,
+}
+
+/// Progress of the duration/BPM job, emitted as `duration-progress` after every written batch
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct DurationProgress {
+    pub total_files: usize,
+    pub processed_files: usize,
+    pub failed_files: usize,
+    pub duration_updated: usize,
+    pub bpm_updated: usize,
+    /// Files analyzed without finding a steady beat
+    pub non_rhythmic: usize,
+    pub status: String,
+}
+
+/// Progress of a batch analysis (loudness, loops, keys, ...), emitted as `analysis-progress` after
+/// every written batch
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct AnalysisProgress {
+    /// Which analysis is running, e.g. "loudness analysis"
+    pub analysis: String,
+    pub total_files: usize,
+    pub processed_files: usize,
+    pub failed_files: usize,
+    pub status: String,
+}
+
+/// Min/max peaks of a stretch of a file, one pair per pixel (fewer when zoomed in past the cached detail)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WaveformPeaks {
+    pub sample_rate: u32,
+    /// Length of the whole file in seconds
+    pub duration: f64,
+    /// Range the peaks cover, in seconds
+    pub start: f64,
+    pub end: f64,
+    pub min: Vec<f32>,
+    pub max: Vec<f32>,
+}
+
+/// A cleanly looping file that isn't tagged `util:loopable` yet
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LoopableProposal {
+    pub audio_file_id: i64,
+    pub file_path: String,
+    pub loop_start: Option<i64>,
+    pub loop_end: Option<i64>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RpgTag {
     pub id: Option<i64>,
@@ -183,13 +676,30 @@ pub struct BulkTagRequest {
     pub tags_to_remove: Vec<RpgTag>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TagSearchRequest {
     pub tag_types: Option<Vec<String>>,
     pub tag_values: Option<Vec<String>>,
     pub match_all: bool, // true for AND, false for OR
 }
 
+/// Find files by their stream properties; criteria left `None` match every file
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct StreamSearchRequest {
+    pub codecs: Option<Vec<String>>,
+    pub containers: Option<Vec<String>>,
+    pub channels: Option<u32>,
+    pub min_sample_rate: Option<u32>,
+    pub max_sample_rate: Option<u32>,
+    pub min_bit_depth: Option<u32>,
+    pub max_bit_depth: Option<u32>,
+    /// kbps
+    pub min_bitrate: Option<u32>,
+    pub max_bitrate: Option<u32>,
+    /// Bytes
+    pub min_file_size: Option<u64>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AudioFileWithTags {
     pub audio_file: AudioFile,
@@ -370,6 +880,18 @@ impl Default for Atmosphere {
     }
 }
 
+/// Embedded picture extracted into the thumbnail cache
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CoverArt {
+    pub hash: String,       // SHA-256 of the original picture, also the cache key
+    pub path: String,       // Cached JPEG thumbnail
+    pub mime_type: String,  // MIME type of the original picture
+    pub picture_type: u8,   // ID3/FLAC picture type, 3 = front cover
+    pub description: String,
+    pub width: u32,
+    pub height: u32,
+}
+
 // Store Tags functionality models
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct StoreTagsResult {
@@ -381,7 +903,7 @@ pub struct StoreTagsResult {
     pub duration_seconds: f64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FileTagComparison {
     pub file_path: String,
     pub needs_update: bool,
@@ -389,13 +911,86 @@ pub struct FileTagComparison {
     pub different_values: Vec<TagDifference>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TagDifference {
     pub field_name: String,
     pub current_value: String,
     pub new_value: String,
 }
 
+/// Narrows a store/remove tags preview; empty filters match everything
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TagPreviewFilter {
+    pub path_contains: Option<String>, // Case-insensitive substring of the file path
+    pub formats: Vec<String>,          // File extensions, e.g. ["mp3", "flac"]
+    pub fields: Vec<String>,           // Only files where one of these fields is missing or changes
+    pub limit: Option<usize>,          // Maximum number of files returned
+}
+
+impl TagPreviewFilter {
+    /// Path and format checks, done before a file is read
+    pub fn matches_file(&self, file_path: &str) -> bool {
+        if let Some(ref needle) = self.path_contains {
+            if !file_path.to_lowercase().contains(&needle.to_lowercase()) {
+                return false;
+            }
+        }
+        if !self.formats.is_empty() {
+            let extension = std::path::Path::new(file_path)
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("");
+            if !self.formats.iter().any(|f| f.trim_start_matches('.').eq_ignore_ascii_case(extension)) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Field check on the computed changes
+    pub fn matches_comparison(&self, comparison: &FileTagComparison) -> bool {
+        self.fields.is_empty()
+            || self.fields.iter().any(|field| {
+                comparison.missing_tags.iter().any(|name| name.eq_ignore_ascii_case(field))
+                    || comparison.different_values.iter().any(|d| d.field_name.eq_ignore_ascii_case(field))
+            })
+    }
+}
+
+/// Group of frames a scoped tag removal strips
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TagFrameGroup {
+    Rpg,     // RPG genre/mood/occasion/keywords/quality frames
+    Credits, // Involved people lists and the composer/conductor/lyricist/remixer/original artist frames
+    All,     // Everything except a user-set title, like the unscoped removal
+}
+
+/// Files a scoped tag removal touches; all given criteria must match
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TagRemovalScope {
+    pub file_ids: Option<Vec<i64>>,
+    pub virtual_folder_id: Option<i64>, // Files directly in the folder
+    pub search: Option<TagSearchRequest>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScopedTagRemovalRequest {
+    pub scope: TagRemovalScope,
+    pub groups: Vec<TagFrameGroup>,
+}
+
+/// Files a store/remove tags operation would change, without writing anything
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TagPreviewResult {
+    pub total_files: usize,               // Files in the library
+    pub matched_files: usize,             // Files that would change and match the filter
+    pub files: Vec<FileTagComparison>,    // Up to `limit` of the matched files
+    pub errors: Vec<String>,
+}
+
 // Virtual Folders models
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct VirtualFolder {
@@ -489,4 +1084,47 @@ pub struct FolderSuggestion {
     pub folder: VirtualFolder,
     pub confidence_score: f64,
     pub matching_tags: Vec<String>,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preview_filter_matches_path_format_and_fields() {
+        let filter = TagPreviewFilter {
+            path_contains: Some("Forest".to_string()),
+            formats: vec![".FLAC".to_string()],
+            fields: vec!["rpg_mood".to_string()],
+            limit: None,
+        };
+        assert!(filter.matches_file("/music/forest/birds.flac"));
+        assert!(!filter.matches_file("/music/forest/birds.mp3"));
+        assert!(!filter.matches_file("/music/cave/drips.flac"));
+
+        let mut comparison = FileTagComparison {
+            file_path: "/music/forest/birds.flac".to_string(),
+            needs_update: true,
+            missing_tags: vec!["TITLE".to_string()],
+            different_values: Vec::new(),
+        };
+        assert!(!filter.matches_comparison(&comparison));
+        comparison.missing_tags.push("RPG_MOOD".to_string());
+        assert!(filter.matches_comparison(&comparison));
+        assert!(TagPreviewFilter::default().matches_comparison(&comparison));
+    }
+
+    #[test]
+    fn patch_distinguishes_missing_null_and_value() {
+        let patch: AudioFilePatch = serde_json::from_str(
+            r#"{"title": "Night Watch", "mood": null, "bpm": 90, "file_path": "/x.mp3"}"#,
+        ).unwrap();
+        assert_eq!(patch.title, FieldPatch::Set("Night Watch".to_string()));
+        assert_eq!(patch.mood, FieldPatch::Clear);
+        assert_eq!(patch.bpm, FieldPatch::Set(90));
+        assert_eq!(patch.artist, FieldPatch::Leave);
+
+        assert_eq!(patch.mood.apply(Some("tense".to_string())), None);
+        assert_eq!(patch.artist.apply(Some("Kept".to_string())), Some("Kept".to_string()));
+    }
+}
@@ -0,0 +1,301 @@
+use crate::models::QualityAnalysis;
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// Values of the `quality` RPG tag (`TXXX:Quality`), best first
+pub const GRADE_HIGH: &str = "High";
+pub const GRADE_MEDIUM: &str = "Medium";
+pub const GRADE_LOW: &str = "Low";
+
+/// Samples this close to full scale on two consecutive frames are flattened tops
+const CLIP_LEVEL: f32 = 0.999;
+/// Noise floor and signal level are percentiles of the levels of 50 ms blocks
+const BLOCK_SECONDS: f64 = 0.05;
+const NOISE_FLOOR_PERCENTILE: f64 = 0.1;
+const SIGNAL_PERCENTILE: f64 = 0.95;
+/// Less audio holds too few blocks to tell noise from signal
+const MIN_BLOCKS: usize = 20;
+/// A floor closer to the signal is the sound itself (rain, wind, crowds), not hiss under it
+const MIN_SIGNAL_ABOVE_FLOOR_DB: f64 = 20.0;
+
+const FFT_SIZE: usize = 4096;
+/// Spectra of quieter frames would mostly show dither
+const MIN_SPECTRUM_RMS_DB: f64 = -60.0;
+/// The spectrum is summed in bands of this width
+const BAND_HZ: f64 = 500.0;
+/// Bands further below the loudest one hold no content: the cliff a lossy encoder's low-pass leaves,
+/// even after the file was upsampled or re-encoded losslessly
+const BANDWIDTH_RANGE_DB: f64 = 80.0;
+
+/// Below these a file grades Low, below the Medium ones Medium
+const LOW_CLIPPING_RATIO: f64 = 0.001;
+const MEDIUM_CLIPPING_RATIO: f64 = 0.0001;
+const LOW_NOISE_FLOOR_DB: f64 = -40.0;
+const MEDIUM_NOISE_FLOOR_DB: f64 = -60.0;
+/// 64 kbps MP3s cut off around 11-14 kHz, 96-128 kbps ones around 15-16 kHz
+const LOW_BANDWIDTH: f64 = 14000.0;
+const MEDIUM_BANDWIDTH: f64 = 16500.0;
+/// Narrower content is the sound itself (drones, hums, muffled booms), not an encoder's low-pass
+const MIN_JUDGED_BANDWIDTH: f64 = 8000.0;
+const LOW_SAMPLE_RATE: u32 = 32000;
+const MEDIUM_SAMPLE_RATE: u32 = 44100;
+const MEDIUM_BIT_DEPTH: u32 = 16;
+
+/// Streaming technical quality measurement, fed with interleaved samples
+pub struct QualityAnalyzer {
+    sample_rate: u32,
+    channels: usize,
+    bit_depth: Option<u32>,
+    samples: u64,
+    clipped: u64,
+    /// Whether the previous sample of each channel was at full scale
+    at_full_scale: Vec<bool>,
+    block_frames: usize,
+    block_sum: f64,
+    block_len: usize,
+    /// Mean square level of every non-silent block
+    blocks: Vec<f64>,
+    /// Mono samples not yet covered by a spectrum
+    pending: Vec<f64>,
+    window: Vec<f64>,
+    fft: Arc<dyn Fft<f64>>,
+    /// Power spectrum summed over the frames loud enough to show content
+    power: Vec<f64>,
+    spectra: usize,
+}
+
+impl QualityAnalyzer {
+    /// `bit_depth` is the stored sample size of the stream; `None` for lossy codecs
+    pub fn new(sample_rate: u32, channels: usize, bit_depth: Option<u32>) -> Self {
+        let channels = channels.max(1);
+        QualityAnalyzer {
+            sample_rate,
+            channels,
+            bit_depth,
+            samples: 0,
+            clipped: 0,
+            at_full_scale: vec![false; channels],
+            block_frames: ((BLOCK_SECONDS * sample_rate as f64) as usize).max(1),
+            block_sum: 0.0,
+            block_len: 0,
+            blocks: Vec::new(),
+            pending: Vec::with_capacity(FFT_SIZE),
+            window: (0..FFT_SIZE).map(|n| 0.5 - 0.5 * (2.0 * PI * n as f64 / FFT_SIZE as f64).cos()).collect(),
+            fft: FftPlanner::new().plan_fft_forward(FFT_SIZE),
+            power: vec![0.0; FFT_SIZE / 2],
+            spectra: 0,
+        }
+    }
+
+    /// Feed interleaved samples; a trailing partial frame is ignored
+    pub fn add_samples(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(self.channels) {
+            let mut square_sum = 0.0;
+            let mut mono = 0.0;
+            for (sample, previous) in frame.iter().zip(self.at_full_scale.iter_mut()) {
+                let full_scale = sample.abs() >= CLIP_LEVEL;
+                if full_scale && *previous {
+                    self.clipped += 1;
+                }
+                *previous = full_scale;
+                square_sum += (*sample as f64) * (*sample as f64);
+                mono += *sample as f64;
+            }
+            self.samples += frame.len() as u64;
+
+            self.block_sum += square_sum / self.channels as f64;
+            self.block_len += 1;
+            if self.block_len == self.block_frames {
+                // Digital silence has no floor to measure
+                if self.block_sum > 0.0 {
+                    self.blocks.push(self.block_sum / self.block_len as f64);
+                }
+                self.block_sum = 0.0;
+                self.block_len = 0;
+            }
+
+            self.pending.push(mono / self.channels as f64);
+            if self.pending.len() == FFT_SIZE {
+                self.analyze_spectrum();
+                self.pending.clear();
+            }
+        }
+    }
+
+    fn analyze_spectrum(&mut self) {
+        let mean_square = self.pending.iter().map(|s| s * s).sum::<f64>() / FFT_SIZE as f64;
+        if to_db(mean_square) < MIN_SPECTRUM_RMS_DB {
+            return;
+        }
+        let mut spectrum: Vec<Complex<f64>> = self.pending.iter()
+            .zip(&self.window)
+            .map(|(s, w)| Complex::new(s * w, 0.0))
+            .collect();
+        self.fft.process(&mut spectrum);
+        for (power, bin) in self.power.iter_mut().zip(&spectrum) {
+            *power += bin.norm_sqr();
+        }
+        self.spectra += 1;
+    }
+
+    /// Highest frequency with content, the upper edge of the last band within range of the loudest
+    fn bandwidth(&self) -> Option<f64> {
+        if self.spectra == 0 {
+            return None;
+        }
+        let bin_hz = self.sample_rate as f64 / FFT_SIZE as f64;
+        let bins_per_band = ((BAND_HZ / bin_hz) as usize).max(1);
+        // The DC bin says nothing about the bandwidth
+        let bands: Vec<f64> = self.power[1..].chunks(bins_per_band).map(|band| band.iter().sum()).collect();
+        let loudest = bands.iter().copied().fold(0.0, f64::max);
+        if loudest <= 0.0 {
+            return None;
+        }
+        let threshold = loudest * 10f64.powf(-BANDWIDTH_RANGE_DB / 10.0);
+        let last = bands.iter().rposition(|&energy| energy > threshold)?;
+        Some((((last + 1) * bins_per_band + 1) as f64 * bin_hz).min(self.sample_rate as f64 / 2.0))
+    }
+
+    pub fn finish(self) -> QualityAnalysis {
+        let clipping_ratio = if self.samples > 0 { self.clipped as f64 / self.samples as f64 } else { 0.0 };
+        let bandwidth = self.bandwidth();
+
+        let (noise_floor, signal_level) = if self.blocks.len() >= MIN_BLOCKS {
+            let mut levels = self.blocks;
+            levels.sort_by(f64::total_cmp);
+            let percentile = |p: f64| to_db(levels[((levels.len() - 1) as f64 * p).round() as usize]);
+            (Some(percentile(NOISE_FLOOR_PERCENTILE)), Some(percentile(SIGNAL_PERCENTILE)))
+        } else {
+            (None, None)
+        };
+
+        let mut analysis = QualityAnalysis {
+            sample_rate: self.sample_rate,
+            bit_depth: self.bit_depth,
+            clipping_ratio,
+            noise_floor,
+            bandwidth,
+            grade: String::new(),
+        };
+        analysis.grade = grade(&analysis, signal_level).to_string();
+        analysis
+    }
+}
+
+fn to_db(mean_square: f64) -> f64 {
+    10.0 * mean_square.max(1e-20).log10()
+}
+
+/// Grade of the worst measurement; the noise floor only counts when the signal stands well above it,
+/// the bandwidth only when the sound reaches into the treble
+pub fn grade(analysis: &QualityAnalysis, signal_level: Option<f64>) -> &'static str {
+    let audible_floor = analysis.noise_floor
+        .filter(|&floor| signal_level.is_some_and(|signal| signal - floor >= MIN_SIGNAL_ABOVE_FLOOR_DB));
+    let cutoff = analysis.bandwidth.filter(|&bandwidth| bandwidth >= MIN_JUDGED_BANDWIDTH);
+    let below = |value: Option<f64>, limit: f64| value.is_some_and(|value| value < limit);
+    let above = |value: Option<f64>, limit: f64| value.is_some_and(|value| value > limit);
+
+    if analysis.clipping_ratio > LOW_CLIPPING_RATIO
+        || above(audible_floor, LOW_NOISE_FLOOR_DB)
+        || below(cutoff, LOW_BANDWIDTH)
+        || analysis.sample_rate < LOW_SAMPLE_RATE
+    {
+        GRADE_LOW
+    } else if analysis.clipping_ratio > MEDIUM_CLIPPING_RATIO
+        || above(audible_floor, MEDIUM_NOISE_FLOOR_DB)
+        || below(cutoff, MEDIUM_BANDWIDTH)
+        || analysis.sample_rate < MEDIUM_SAMPLE_RATE
+        || analysis.bit_depth.is_some_and(|bits| bits < MEDIUM_BIT_DEPTH)
+    {
+        GRADE_MEDIUM
+    } else {
+        GRADE_HIGH
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 44100;
+
+    fn noise(seed: &mut u32) -> f64 {
+        *seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+        (*seed >> 8) as f64 / (1 << 24) as f64 - 0.5
+    }
+
+    fn analyze(samples: &[f32]) -> QualityAnalysis {
+        let mut analyzer = QualityAnalyzer::new(RATE, 1, Some(16));
+        for chunk in samples.chunks(4096) {
+            analyzer.add_samples(chunk);
+        }
+        analyzer.finish()
+    }
+
+    /// Half-second noise bursts every second over steady hiss, or with `partials` steady sines below 10 kHz
+    fn bursts(gain: f64, hiss: f64, partials: bool) -> Vec<f32> {
+        let mut seed = 3u32;
+        let frequencies: Vec<f64> = (0..150).map(|i| 60.0 + i as f64 * 66.0).collect();
+        (0..RATE as usize * 6)
+            .map(|n| {
+                let t = n as f64 / RATE as f64;
+                let sound = if partials {
+                    // A gate would click across the whole spectrum
+                    frequencies.iter().map(|f| (2.0 * PI * f * t + f).sin()).sum::<f64>() / 150.0
+                } else if t % 1.0 < 0.5 {
+                    noise(&mut seed) * 1.5
+                } else {
+                    0.0
+                };
+                (sound * gain + noise(&mut seed) * hiss).clamp(-1.0, 1.0) as f32
+            })
+            .collect()
+    }
+
+    #[test]
+    fn measures_clipping_noise_floor_and_bandwidth() {
+        let clean = analyze(&bursts(0.5, 0.0005, false));
+        assert_eq!(clean.clipping_ratio, 0.0);
+        assert!(clean.bandwidth.unwrap() > 21000.0, "{:?}", clean);
+        assert!(clean.noise_floor.unwrap() < -70.0, "{:?}", clean);
+        assert_eq!(clean.grade, GRADE_HIGH);
+
+        let clipped = analyze(&bursts(4.0, 0.0005, false));
+        assert!(clipped.clipping_ratio > LOW_CLIPPING_RATIO, "{:?}", clipped);
+        assert_eq!(clipped.grade, GRADE_LOW);
+
+        let hissy = analyze(&bursts(0.5, 0.05, false));
+        assert!((-40.0..-30.0).contains(&hissy.noise_floor.unwrap()), "{:?}", hissy);
+        assert_eq!(hissy.grade, GRADE_LOW);
+
+        // Band-limited content in a full-rate file, as a low-bitrate MP3 converted to WAV
+        let upsampled = analyze(&bursts(0.5, 0.0, true));
+        let bandwidth = upsampled.bandwidth.unwrap();
+        assert!((9500.0..11500.0).contains(&bandwidth), "{:?}", upsampled);
+        assert_eq!(upsampled.grade, GRADE_LOW);
+    }
+
+    #[test]
+    fn grades_by_the_worst_measurement() {
+        let good = QualityAnalysis {
+            sample_rate: 48000,
+            bit_depth: Some(24),
+            clipping_ratio: 0.0,
+            noise_floor: Some(-80.0),
+            bandwidth: Some(22000.0),
+            grade: String::new(),
+        };
+        assert_eq!(grade(&good, Some(-20.0)), GRADE_HIGH);
+        assert_eq!(grade(&QualityAnalysis { bit_depth: Some(8), ..good.clone() }, Some(-20.0)), GRADE_MEDIUM);
+        assert_eq!(grade(&QualityAnalysis { bandwidth: Some(15000.0), ..good.clone() }, Some(-20.0)), GRADE_MEDIUM);
+        assert_eq!(grade(&QualityAnalysis { bandwidth: Some(1000.0), ..good.clone() }, Some(-20.0)), GRADE_HIGH);
+        assert_eq!(grade(&QualityAnalysis { sample_rate: 22050, ..good.clone() }, Some(-20.0)), GRADE_LOW);
+
+        // Steady rain at -30 dBFS has no floor below it, hiss under a sound at -10 dBFS does
+        let loud_floor = QualityAnalysis { noise_floor: Some(-30.0), ..good };
+        assert_eq!(grade(&loud_floor, Some(-25.0)), GRADE_HIGH);
+        assert_eq!(grade(&loud_floor, Some(-10.0)), GRADE_LOW);
+    }
+}
@@ -1,25 +1,85 @@
-use crate::models::{StoreTagsResult};
+use crate::models::{AudioFile, StoreTagsResult, FileTagComparison, TagPreviewFilter, TagPreviewResult, ScopedTagRemovalRequest, TagFrameGroup, TagRemovalScope};
 use crate::database::Database;
+use crate::store_tags_handler::{compare_field_values, id3_field_values, mp4_field_values, write_planned_tags, PlannedTags};
+use crate::tag_backup::{self, TagWriteRun};
+use crate::tag_formats::{self, TagFormat, mp4::{self, Mp4Key, Mp4Tag}, vorbis::VorbisComments};
 use tauri::AppHandle;
-use id3::{Tag, TagLike};
+use id3::{Frame, Tag, TagLike};
 use std::path::Path;
 use std::time::Instant;
 use log::{info, warn, error};
 
-/// Remove all RPG tags and metadata from actual audio files
-pub async fn remove_all_tags_from_files(_app_handle: AppHandle) -> Result<StoreTagsResult, String> {
-    let start_time = Instant::now();
-    info!("Starting remove tags from files operation");
+/// TXXX descriptions, Vorbis comments and MP4 freeform atoms holding RPG tags
+const RPG_FIELDS: [&str; 8] = [
+    "RPG_GENRE", "RPG_MOOD", "RPG_OCCASION", "RPG_KEYWORDS", "RPG_ALL_TAGS",
+    "Occasion", "Keywords", "Quality",
+];
 
-    let mut result = StoreTagsResult {
-        total_files: 0,
-        updated_files: 0,
-        skipped_files: 0,
-        failed_files: 0,
+/// ID3 frames holding extended credits
+const CREDIT_FRAMES: [&str; 9] = ["TIPL", "TMCL", "IPLS", "TCOM", "TPE3", "TEXT", "TPE4", "TOPE", "TOLY"];
+
+/// Vorbis comments and MP4 freeform atoms holding extended credits (the MP4 composer is `©wrt`)
+const CREDIT_FIELDS: [&str; 11] = [
+    "COMPOSER", "CONDUCTOR", "LYRICIST", "REMIXER", "ORIGINALARTIST", "ORIGINALLYRICIST",
+    "ARRANGER", "ENGINEER", "PRODUCER", "DJMIXER", "MIXER",
+];
+
+/// Remove all RPG tags and metadata from actual audio files,
+/// backing up the original tag block of every rewritten file when `backup` is set
+pub async fn remove_all_tags_from_files(_app_handle: AppHandle, backup: bool) -> Result<StoreTagsResult, String> {
+    remove_tags(None, backup)
+}
+
+/// Remove tags from the given files only (e.g. the approved part of a preview)
+pub async fn remove_tags_from_selected_files(_app_handle: AppHandle, file_paths: Vec<String>, backup: bool) -> Result<StoreTagsResult, String> {
+    remove_tags(Some(&file_paths), backup)
+}
+
+/// Remove selected frame groups from the files of a scope (file ids, a virtual folder and/or a tag search)
+pub async fn remove_tags_scoped(_app_handle: AppHandle, request: ScopedTagRemovalRequest, backup: bool) -> Result<StoreTagsResult, String> {
+    if request.groups.is_empty() {
+        return Err("No frame groups selected".to_string());
+    }
+
+    let db = Database::new().map_err(|e| format!("Failed to create database connection: {}", e))?;
+    let audio_files = resolve_scope(&db, &request.scope)?;
+    info!("Scoped tag removal of {:?} from {} files", request.groups, audio_files.len());
+    run_removal(audio_files, Vec::new(), &request.groups, backup)
+}
+
+/// Preview which tags removal would drop, file by file, without writing anything
+pub async fn preview_remove_tags(_app_handle: AppHandle, filter: TagPreviewFilter) -> Result<TagPreviewResult, String> {
+    let db = Database::new().map_err(|e| format!("Failed to create database connection: {}", e))?;
+    let audio_files = db.get_all_audio_files().map_err(|e| format!("Failed to get audio files: {}", e))?;
+
+    let mut preview = TagPreviewResult {
+        total_files: audio_files.len(),
+        matched_files: 0,
+        files: Vec::new(),
         errors: Vec::new(),
-        duration_seconds: 0.0,
     };
 
+    for audio_file in audio_files.iter().filter(|f| filter.matches_file(&f.file_path)) {
+        match plan_single_file_removal(audio_file, &[TagFrameGroup::All]) {
+            Ok(Some((comparison, _))) => {
+                if filter.matches_comparison(&comparison) {
+                    preview.matched_files += 1;
+                    if filter.limit.is_none_or(|limit| preview.files.len() < limit) {
+                        preview.files.push(comparison);
+                    }
+                }
+            }
+            Ok(None) => {}
+            Err(e) => preview.errors.push(format!("{}: {}", audio_file.file_path, e)),
+        }
+    }
+
+    info!("Remove tags preview: {} of {} files would change", preview.matched_files, preview.total_files);
+    Ok(preview)
+}
+
+/// Remove tags from every file of the library, or only from `file_paths`
+fn remove_tags(file_paths: Option<&[String]>, backup: bool) -> Result<StoreTagsResult, String> {
     // Get database instance
     let db = match Database::new() {
         Ok(db) => db,
@@ -30,7 +90,7 @@ pub async fn remove_all_tags_from_files(_app_handle: AppHandle) -> Result<StoreT
     };
 
     // Get all audio files with metadata
-    let audio_files = match db.get_all_audio_files() {
+    let mut audio_files = match db.get_all_audio_files() {
         Ok(files) => files,
         Err(e) => {
             error!("Failed to get audio files from database: {}", e);
@@ -38,11 +98,73 @@ pub async fn remove_all_tags_from_files(_app_handle: AppHandle) -> Result<StoreT
         }
     };
 
-    result.total_files = audio_files.len();
+    let mut unknown_paths = Vec::new();
+    if let Some(file_paths) = file_paths {
+        audio_files.retain(|f| file_paths.contains(&f.file_path));
+        unknown_paths = file_paths.iter()
+            .filter(|p| !audio_files.iter().any(|f| &f.file_path == *p))
+            .cloned()
+            .collect();
+    }
+
+    run_removal(audio_files, unknown_paths, &[TagFrameGroup::All], backup)
+}
+
+/// Library files matching every criterion of a scope
+fn resolve_scope(db: &Database, scope: &TagRemovalScope) -> Result<Vec<AudioFile>, String> {
+    if scope.file_ids.is_none() && scope.virtual_folder_id.is_none() && scope.search.is_none() {
+        return Err("Scope needs file ids, a virtual folder or a search".to_string());
+    }
+
+    let mut audio_files = db.get_all_audio_files().map_err(|e| format!("Failed to get audio files: {}", e))?;
+
+    if let Some(ref file_ids) = scope.file_ids {
+        audio_files.retain(|f| f.id.is_some_and(|id| file_ids.contains(&id)));
+    }
+    if let Some(folder_id) = scope.virtual_folder_id {
+        let contents = db.get_virtual_folder_contents(folder_id)
+            .map_err(|e| format!("Failed to get virtual folder contents: {}", e))?;
+        let folder_ids: Vec<i64> = contents.audio_files.iter().filter_map(|f| f.id).collect();
+        audio_files.retain(|f| f.id.is_some_and(|id| folder_ids.contains(&id)));
+    }
+    if let Some(ref search) = scope.search {
+        let found = db.search_files_by_tags(search.tag_types.as_deref(), search.tag_values.as_deref(), search.match_all)
+            .map_err(|e| format!("Failed to search files: {}", e))?;
+        let found_ids: Vec<i64> = found.iter().filter_map(|f| f.audio_file.id).collect();
+        audio_files.retain(|f| f.id.is_some_and(|id| found_ids.contains(&id)));
+    }
+
+    Ok(audio_files)
+}
+
+/// Remove frame groups from each file, reporting files that aren't in the library as failed
+fn run_removal(
+    audio_files: Vec<AudioFile>,
+    unknown_paths: Vec<String>,
+    groups: &[TagFrameGroup],
+    backup: bool,
+) -> Result<StoreTagsResult, String> {
+    let start_time = Instant::now();
+    info!("Starting remove tags from files operation");
+
+    let mut result = StoreTagsResult {
+        total_files: audio_files.len() + unknown_paths.len(),
+        updated_files: 0,
+        skipped_files: 0,
+        failed_files: unknown_paths.len(),
+        errors: unknown_paths.iter().map(|p| format!("{}: Not in the library", p)).collect(),
+        duration_seconds: 0.0,
+    };
     info!("Processing {} audio files for tag removal", result.total_files);
 
+    let mut backup_run = if backup {
+        Some(TagWriteRun::start(Path::new(tag_backup::TAG_BACKUP_DIR), "remove_tags")?)
+    } else {
+        None
+    };
+
     for audio_file in audio_files {
-        match process_single_file_removal(&audio_file, &mut result) {
+        match process_single_file_removal(&audio_file, groups, &mut result, backup_run.as_mut()) {
             Ok(updated) => {
                 if updated {
                     result.updated_files += 1;
@@ -58,6 +180,10 @@ pub async fn remove_all_tags_from_files(_app_handle: AppHandle) -> Result<StoreT
         }
     }
 
+    if let Some(run) = backup_run {
+        run.finish()?;
+    }
+
     result.duration_seconds = start_time.elapsed().as_secs_f64();
     
     info!(
@@ -72,11 +198,97 @@ pub async fn remove_all_tags_from_files(_app_handle: AppHandle) -> Result<StoreT
     Ok(result)
 }
 
-/// Process a single audio file - remove all tags from the actual file
-fn process_single_file_removal(
-    audio_file: &crate::models::AudioFile,
-    _result: &mut StoreTagsResult,
-) -> Result<bool, String> {
+/// Title worth keeping when everything else is removed: one the user set, not just the filename
+fn user_title(title: Option<&str>, file_path: &str) -> Option<String> {
+    let filename_without_ext = Path::new(file_path)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("");
+    title.filter(|t| *t != filename_without_ext && !t.trim().is_empty()).map(|t| t.to_string())
+}
+
+fn is_rpg_field(name: &str) -> bool {
+    RPG_FIELDS.iter().any(|f| f.eq_ignore_ascii_case(name))
+}
+
+fn is_credit_field(name: &str) -> bool {
+    CREDIT_FIELDS.iter().any(|f| f.eq_ignore_ascii_case(name))
+}
+
+/// Build the cleaned ID3 tag; `None` when there is nothing to remove
+fn strip_id3_tag(current: &Tag, groups: &[TagFrameGroup], file_path: &str) -> Option<Tag> {
+    if groups.contains(&TagFrameGroup::All) {
+        if !has_removable_tags(current) {
+            return None;
+        }
+        // Create a new empty tag, preserving only a user-set title
+        let mut new_tag = Tag::new();
+        if let Some(title) = user_title(current.title(), file_path) {
+            new_tag.set_title(title);
+        }
+        return Some(new_tag);
+    }
+
+    let removed = |frame: &Frame| {
+        groups.iter().any(|group| match group {
+            TagFrameGroup::Rpg => frame.content().extended_text().is_some_and(|t| is_rpg_field(&t.description)),
+            TagFrameGroup::Credits => CREDIT_FRAMES.contains(&frame.id()),
+            TagFrameGroup::All => true,
+        })
+    };
+    if !current.frames().any(removed) {
+        return None;
+    }
+    Some(current.frames().filter(|frame| !removed(frame)).cloned().collect())
+}
+
+/// Remove frame groups from Vorbis comments, keeping the vendor string
+fn strip_vorbis_comments(comments: &mut VorbisComments, groups: &[TagFrameGroup], file_path: &str) {
+    if groups.contains(&TagFrameGroup::All) {
+        let title = user_title(comments.get("TITLE"), file_path);
+        comments.comments.clear();
+        if let Some(title) = title {
+            comments.set("TITLE", &title);
+        }
+        return;
+    }
+
+    comments.comments.retain(|(key, _)| {
+        !groups.iter().any(|group| match group {
+            TagFrameGroup::Rpg => is_rpg_field(key),
+            TagFrameGroup::Credits => is_credit_field(key),
+            TagFrameGroup::All => true,
+        })
+    });
+}
+
+/// Remove frame groups from iTunes atoms
+fn strip_mp4_tag(tag: &mut Mp4Tag, groups: &[TagFrameGroup], file_path: &str) {
+    if groups.contains(&TagFrameGroup::All) {
+        let title = user_title(tag.text(b"\xa9nam").as_deref(), file_path);
+        tag.items.clear();
+        if let Some(title) = title {
+            tag.set_text(b"\xa9nam", &title);
+        }
+        return;
+    }
+
+    tag.items.retain(|item| {
+        !groups.iter().any(|group| match (group, &item.key) {
+            (TagFrameGroup::Rpg, Mp4Key::Freeform { name, .. }) => is_rpg_field(name),
+            (TagFrameGroup::Credits, Mp4Key::Freeform { name, .. }) => is_credit_field(name),
+            (TagFrameGroup::Credits, Mp4Key::Atom(kind)) => kind == b"\xa9wrt",
+            (TagFrameGroup::Rpg, Mp4Key::Atom(_)) => false,
+            (TagFrameGroup::All, _) => true,
+        })
+    });
+}
+
+/// Build the cleaned tags for a file; `None` when there is nothing to remove
+fn plan_single_file_removal(
+    audio_file: &AudioFile,
+    groups: &[TagFrameGroup],
+) -> Result<Option<(FileTagComparison, PlannedTags)>, String> {
     let file_path = &audio_file.file_path;
     
     // Check if file exists and is readable
@@ -92,41 +304,67 @@ fn process_single_file_removal(
         return Err("File is read-only".to_string());
     }
 
-    // Read current tags from file
-    let current_tag = match Tag::read_from_path(file_path) {
-        Ok(tag) => tag,
-        Err(_) => {
-            // File has no tags, nothing to remove
-            return Ok(false);
+    let mut comparison = FileTagComparison {
+        file_path: file_path.clone(),
+        needs_update: false,
+        missing_tags: Vec::new(),
+        different_values: Vec::new(),
+    };
+
+    let planned = match TagFormat::from_path(file_path) {
+        // No tags are read from these, so there is nothing to remove
+        TagFormat::Unsupported => return Ok(None),
+        TagFormat::Id3 | TagFormat::Wav => {
+            // Read current tags from file; a file without tags has nothing to remove
+            let Some(current) = tag_formats::read_id3_tag(file_path) else { return Ok(None) };
+            let Some(new_tag) = strip_id3_tag(&current, groups, file_path) else { return Ok(None) };
+            compare_field_values(&id3_field_values(&current), &id3_field_values(&new_tag), &mut comparison);
+            comparison.needs_update = true;
+            PlannedTags::Id3(new_tag)
+        }
+        TagFormat::Flac | TagFormat::Ogg => {
+            let Some(current) = tag_formats::read_vorbis_comments(file_path)? else { return Ok(None) };
+            let mut comments = current.clone();
+            strip_vorbis_comments(&mut comments, groups, file_path);
+            compare_field_values(&current.comments, &comments.comments, &mut comparison);
+            comparison.needs_update = comments != current;
+            PlannedTags::Vorbis(comments)
+        }
+        TagFormat::Mp4 => {
+            let Some(current) = mp4::read_tag(file_path)? else { return Ok(None) };
+            let mut tag = current.clone();
+            strip_mp4_tag(&mut tag, groups, file_path);
+            compare_field_values(&mp4_field_values(&current), &mp4_field_values(&tag), &mut comparison);
+            comparison.needs_update = tag != current;
+            PlannedTags::Mp4(tag)
         }
     };
 
-    // Check if there are any tags to remove
-    let has_tags = has_removable_tags(&current_tag);
-    
-    if !has_tags {
-        // No tags to remove, skip this file
-        return Ok(false);
+    if !comparison.needs_update {
+        return Ok(None);
     }
+    Ok(Some((comparison, planned)))
+}
 
-    // Create a new empty tag to replace the current one
-    let mut new_tag = Tag::new();
-    
-    // Preserve only basic file identification info (keep title if it's the filename)
-    if let Some(title) = current_tag.title() {
-        let filename_without_ext = Path::new(file_path)
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("");
-            
-        // Only preserve title if it's different from filename (user-set title)
-        if title != filename_without_ext && !title.trim().is_empty() {
-            new_tag.set_title(title);
-        }
+/// Process a single audio file - remove the selected frame groups from the actual file
+fn process_single_file_removal(
+    audio_file: &AudioFile,
+    groups: &[TagFrameGroup],
+    _result: &mut StoreTagsResult,
+    backup: Option<&mut TagWriteRun>,
+) -> Result<bool, String> {
+    let file_path = &audio_file.file_path;
+    let planned = match plan_single_file_removal(audio_file, groups)? {
+        Some((_, planned)) => planned,
+        None => return Ok(false),
+    };
+
+    if let Some(run) = backup {
+        run.backup(file_path)?;
     }
 
-    // Write the cleaned tag back to the file
-    match new_tag.write_to_path(file_path, id3::Version::Id3v24) {
+    // Write the cleaned tags back to the file
+    match write_planned_tags(file_path, &planned) {
         Ok(_) => {
             info!("Removed tags from: {}", file_path);
             Ok(true)
@@ -178,4 +416,69 @@ fn has_removable_tags(tag: &Tag) -> bool {
             "COMM"   // Comments
         )
     })
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use id3::frame::{Content, ExtendedText};
+
+    fn txxx(description: &str, value: &str) -> Frame {
+        Frame::with_content("TXXX", Content::ExtendedText(ExtendedText {
+            description: description.to_string(),
+            value: value.to_string(),
+        }))
+    }
+
+    #[test]
+    fn strips_only_selected_id3_groups() {
+        let mut tag = Tag::new();
+        tag.set_title("Storm");
+        tag.set_artist("Ann Lee");
+        tag.add_frame(txxx("RPG_MOOD", "tense"));
+        tag.add_frame(txxx("Keywords", "weather:storm"));
+        tag.add_frame(txxx("Mood", "dark"));
+        tag.add_frame(Frame::text("TCOM", "Bo Chen"));
+
+        let rpg_only = strip_id3_tag(&tag, &[TagFrameGroup::Rpg], "storm.mp3").unwrap();
+        let descriptions: Vec<String> = rpg_only.extended_texts().map(|t| t.description.clone()).collect();
+        assert_eq!(descriptions, vec!["Mood"]);
+        assert_eq!(rpg_only.get("TCOM").and_then(|f| f.content().text()), Some("Bo Chen"));
+        assert_eq!(rpg_only.artist(), Some("Ann Lee"));
+
+        let credits = strip_id3_tag(&rpg_only, &[TagFrameGroup::Credits], "storm.mp3").unwrap();
+        assert!(credits.get("TCOM").is_none());
+        assert!(strip_id3_tag(&credits, &[TagFrameGroup::Rpg, TagFrameGroup::Credits], "storm.mp3").is_none());
+
+        // A title that is just the filename isn't worth keeping
+        let all = strip_id3_tag(&tag, &[TagFrameGroup::All], "Storm.mp3").unwrap();
+        assert_eq!(all.frames().count(), 0);
+        let all = strip_id3_tag(&tag, &[TagFrameGroup::All], "track01.mp3").unwrap();
+        assert_eq!(all.title(), Some("Storm"));
+        assert_eq!(all.frames().count(), 1);
+    }
+
+    #[test]
+    fn strips_vorbis_and_mp4_groups() {
+        let mut comments = VorbisComments {
+            vendor: "test".to_string(),
+            comments: vec![
+                ("TITLE".to_string(), "Rainfall".to_string()),
+                ("OCCASION".to_string(), "travel".to_string()),
+                ("PRODUCER".to_string(), "Cy Diaz".to_string()),
+            ],
+        };
+        strip_vorbis_comments(&mut comments, &[TagFrameGroup::Credits], "rain.flac");
+        assert_eq!(comments.comments.len(), 2);
+        strip_vorbis_comments(&mut comments, &[TagFrameGroup::All], "rain.flac");
+        assert_eq!(comments.comments, vec![("TITLE".to_string(), "Rainfall".to_string())]);
+
+        let mut tag = Mp4Tag::default();
+        tag.set_text(b"\xa9wrt", "Bo Chen");
+        tag.set_freeform("Quality", "good");
+        tag.set_freeform("LYRICIST", "Kim Lo");
+        strip_mp4_tag(&mut tag, &[TagFrameGroup::Credits], "rain.m4a");
+        assert!(tag.text(b"\xa9wrt").is_none());
+        assert!(tag.freeform("LYRICIST").is_empty());
+        assert_eq!(tag.freeform("Quality"), vec!["good"]);
+    }
 }
\ No newline at end of file
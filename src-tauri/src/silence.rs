@@ -0,0 +1,130 @@
+use crate::models::SilenceAnalysis;
+
+/// Windows quieter than -50 dBFS RMS count as dead air; room tone and dither stay below it
+const SILENCE_THRESHOLD_DB: f64 = -50.0;
+const WINDOW_SECONDS: f64 = 0.01;
+/// Kept before the first sounding window so soft attacks aren't clipped
+const PRE_ROLL_SECONDS: f64 = 0.005;
+/// Kept after the last sounding window for decays just below the threshold
+const POST_ROLL_SECONDS: f64 = 0.05;
+/// Files sounding for less than this share of their length are mostly silent
+const MOSTLY_SILENT_RATIO: f64 = 0.1;
+
+/// Streaming leading/trailing silence detection, fed with interleaved samples
+pub struct SilenceDetector {
+    sample_rate: u32,
+    channels: usize,
+    window_frames: usize,
+    threshold: f64,
+    window_sum: f64,
+    window_len: usize,
+    windows: u64,
+    sounding_windows: u64,
+    first_sound: Option<u64>,
+    last_sound: u64,
+    frames: u64,
+}
+
+impl SilenceDetector {
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        let threshold = 10f64.powf(SILENCE_THRESHOLD_DB / 20.0);
+        SilenceDetector {
+            sample_rate,
+            channels: channels.max(1),
+            window_frames: ((WINDOW_SECONDS * sample_rate as f64) as usize).max(1),
+            threshold: threshold * threshold,
+            window_sum: 0.0,
+            window_len: 0,
+            windows: 0,
+            sounding_windows: 0,
+            first_sound: None,
+            last_sound: 0,
+            frames: 0,
+        }
+    }
+
+    /// Feed interleaved samples; a trailing partial frame is ignored
+    pub fn add_samples(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(self.channels) {
+            self.window_sum += frame.iter().map(|&s| (s as f64) * (s as f64)).sum::<f64>() / self.channels as f64;
+            self.window_len += 1;
+            self.frames += 1;
+            if self.window_len == self.window_frames {
+                self.close_window();
+            }
+        }
+    }
+
+    fn close_window(&mut self) {
+        if self.window_sum / self.window_len as f64 > self.threshold {
+            let start = self.frames - self.window_len as u64;
+            self.first_sound.get_or_insert(start);
+            self.last_sound = self.frames;
+            self.sounding_windows += 1;
+        }
+        self.windows += 1;
+        self.window_sum = 0.0;
+        self.window_len = 0;
+    }
+
+    pub fn finish(mut self) -> SilenceAnalysis {
+        if self.window_len > 0 {
+            self.close_window();
+        }
+        let rate = self.sample_rate as f64;
+        let duration = self.frames as f64 / rate;
+
+        let Some(first_sound) = self.first_sound else {
+            return SilenceAnalysis { leading_silence: duration, trailing_silence: 0.0, mostly_silent: true };
+        };
+        let leading_silence = (first_sound as f64 / rate - PRE_ROLL_SECONDS).max(0.0);
+        let trailing_silence = ((self.frames - self.last_sound) as f64 / rate - POST_ROLL_SECONDS).max(0.0);
+        SilenceAnalysis {
+            leading_silence,
+            trailing_silence,
+            mostly_silent: (self.sounding_windows as f64) < self.windows as f64 * MOSTLY_SILENT_RATIO,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 1000;
+
+    fn analyze(samples: &[f32]) -> SilenceAnalysis {
+        let mut detector = SilenceDetector::new(RATE, 1);
+        for chunk in samples.chunks(333) {
+            detector.add_samples(chunk);
+        }
+        detector.finish()
+    }
+
+    fn tone(frames: usize) -> Vec<f32> {
+        (0..frames).map(|n| if n % 2 == 0 { 0.5 } else { -0.5 }).collect()
+    }
+
+    #[test]
+    fn finds_the_onset_behind_dead_air() {
+        // One second of hiss at -70 dBFS, half a second of sound, a quarter second of silence
+        let mut samples = vec![0.0003; 1000];
+        samples.extend(tone(500));
+        samples.extend(vec![0.0; 250]);
+
+        let analysis = analyze(&samples);
+        assert!((analysis.leading_silence - 0.995).abs() < 1e-9, "{:?}", analysis);
+        assert!((analysis.trailing_silence - 0.2).abs() < 1e-9, "{:?}", analysis);
+        assert!(!analysis.mostly_silent);
+    }
+
+    #[test]
+    fn flags_mostly_silent_files() {
+        let mut samples = tone(50);
+        samples.extend(vec![0.0; 950]);
+        assert!(analyze(&samples).mostly_silent);
+
+        let silent = analyze(&[0.0; 500]);
+        assert_eq!((silent.leading_silence, silent.mostly_silent), (0.5, true));
+    }
+}
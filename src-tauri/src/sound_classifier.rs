@@ -0,0 +1,410 @@
+use crate::gemini_tagger::{AudioFile, TaggedFile};
+use crate::key_detection::{Decimator, KeyDetector};
+use crate::models::TagVocabulary;
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+const FRAME_SIZE: usize = 1024;
+const HOP_SIZE: usize = 512;
+/// Only the start of long files is listened to; beds and music show their character well before this
+const MAX_SECONDS: f64 = 120.0;
+/// Frames quieter than this count as silence, as in the silence detection
+const SILENCE_DB: f64 = -50.0;
+/// Level assumed before the first frame, so a sound starting right away still has an onset
+const FLOOR_DB: f64 = -120.0;
+/// Rise over the two previous frames that counts as a new event
+const ONSET_RISE_DB: f64 = 6.0;
+/// Frames (about 90 ms) after an onset in which no further onset is counted
+const ONSET_REFRACTORY: usize = 2;
+/// Spectrum range for centroid and flatness; the decimator filters out everything above
+const MIN_FREQUENCY: f64 = 60.0;
+const MAX_FREQUENCY: f64 = 3000.0;
+
+/// Sounds shorter than this (counting only sounding time) are one-shots
+const ONE_SHOT_SECONDS: f64 = 6.0;
+/// Attacks reaching their peak faster than this are hits rather than swells
+const SHARP_ATTACK_SECONDS: f64 = 0.1;
+/// Level spread up to which a sound counts as steady
+const STEADY_SPREAD_DB: f64 = 6.0;
+/// Syllable rates of speech, onsets per sounding second
+const SPEECH_ONSET_RATE: (f64, f64) = (1.5, 7.0);
+/// Centroid range of voices; lower is rumble, higher is hiss
+const SPEECH_CENTROID: (f64, f64) = (250.0, 2000.0);
+/// Spectra flatter than this are noise-like: wind, rain, surf, crowds
+const NOISY_FLATNESS: f64 = 0.3;
+/// Centroids below this sound dark: rumbles, drones, booms
+const DARK_CENTROID: f64 = 400.0;
+/// Onset rates below this make music sustained, above `DRIVING_ONSET_RATE` rhythmic
+const SUSTAINED_ONSET_RATE: f64 = 0.5;
+const DRIVING_ONSET_RATE: f64 = 3.0;
+
+/// Audio features the offline classifier decides on
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SoundFeatures {
+    /// Seconds heard, at most `MAX_SECONDS`
+    pub duration: f64,
+    /// Seconds above the silence threshold
+    pub sounding_seconds: f64,
+    /// Standard deviation of the sounding frames' level in dB; steady beds stay low
+    pub level_spread: f64,
+    /// Seconds from the first sound to the loudest point of the second after it
+    pub attack: f64,
+    /// Onsets per sounding second
+    pub onset_rate: f64,
+    /// Mean spectral centroid of the sounding frames in Hz
+    pub centroid: f64,
+    /// Mean spectral flatness of the sounding frames: near 0 for tones, about 0.5 for white noise
+    pub flatness: f64,
+    /// Key of tonal audio, from the key detection
+    pub key: Option<String>,
+}
+
+/// What kind of sound a file is, as far as its features tell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundClass {
+    AmbienceBed,
+    OneShot,
+    Music,
+    Speech,
+}
+
+/// Streaming feature extraction, fed with interleaved samples
+pub struct SoundAnalyzer {
+    decimator: Decimator,
+    key_detector: KeyDetector,
+    hop_seconds: f64,
+    remaining: u64,
+    frames: u64,
+    sample_rate: u32,
+    /// Decimated mono samples not yet covered by a full frame
+    pending: Vec<f64>,
+    window: Vec<f64>,
+    fft: Arc<dyn Fft<f64>>,
+    /// Frequency of every spectrum bin inside the analyzed range
+    bin_frequencies: Vec<(usize, f64)>,
+    /// Level of every frame in dBFS
+    levels: Vec<f64>,
+    centroid_sum: f64,
+    flatness_sum: f64,
+}
+
+impl SoundAnalyzer {
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        let decimator = Decimator::new(sample_rate, channels);
+        let rate = decimator.rate(sample_rate);
+
+        let bin_frequencies = (1..FRAME_SIZE / 2)
+            .map(|bin| (bin, bin as f64 * rate / FRAME_SIZE as f64))
+            .filter(|(_, frequency)| (MIN_FREQUENCY..=MAX_FREQUENCY).contains(frequency))
+            .collect();
+
+        SoundAnalyzer {
+            key_detector: KeyDetector::new(sample_rate, channels),
+            decimator,
+            hop_seconds: HOP_SIZE as f64 / rate,
+            remaining: (MAX_SECONDS * sample_rate as f64) as u64,
+            frames: 0,
+            sample_rate,
+            pending: Vec::with_capacity(FRAME_SIZE),
+            window: (0..FRAME_SIZE).map(|n| 0.5 - 0.5 * (2.0 * PI * n as f64 / FRAME_SIZE as f64).cos()).collect(),
+            fft: FftPlanner::new().plan_fft_forward(FRAME_SIZE),
+            bin_frequencies,
+            levels: Vec::new(),
+            centroid_sum: 0.0,
+            flatness_sum: 0.0,
+        }
+    }
+
+    /// Feed interleaved samples; returns `false` once enough audio has been heard
+    pub fn add_samples(&mut self, samples: &[f32]) -> bool {
+        self.key_detector.add_samples(samples);
+        for frame in samples.chunks_exact(self.decimator.channels()) {
+            if self.remaining == 0 {
+                return false;
+            }
+            self.remaining -= 1;
+            self.frames += 1;
+
+            let Some(mono) = self.decimator.push(frame) else { continue };
+            self.pending.push(mono);
+            if self.pending.len() == FRAME_SIZE {
+                self.analyze_frame();
+                self.pending.drain(..HOP_SIZE);
+            }
+        }
+        self.remaining > 0
+    }
+
+    fn analyze_frame(&mut self) {
+        let rms = (self.pending.iter().map(|s| s * s).sum::<f64>() / FRAME_SIZE as f64).sqrt();
+        let level = 20.0 * rms.max(1e-6).log10();
+        self.levels.push(level);
+        if level < SILENCE_DB {
+            return;
+        }
+
+        let mut spectrum: Vec<Complex<f64>> = self.pending.iter()
+            .zip(&self.window)
+            .map(|(s, w)| Complex::new(s * w, 0.0))
+            .collect();
+        self.fft.process(&mut spectrum);
+
+        let mut weighted = 0.0;
+        let mut magnitude_sum = 0.0;
+        let mut log_sum = 0.0;
+        let mut power_sum = 0.0;
+        for &(bin, frequency) in &self.bin_frequencies {
+            let power = spectrum[bin].norm_sqr() + 1e-12;
+            weighted += frequency * power.sqrt();
+            magnitude_sum += power.sqrt();
+            log_sum += power.ln();
+            power_sum += power;
+        }
+        let bins = self.bin_frequencies.len() as f64;
+        self.centroid_sum += weighted / magnitude_sum;
+        self.flatness_sum += (log_sum / bins).exp() / (power_sum / bins);
+    }
+
+    pub fn finish(self) -> SoundFeatures {
+        let duration = self.frames as f64 / self.sample_rate as f64;
+        let key = self.key_detector.finish().key;
+        let sounding: Vec<f64> = self.levels.iter().copied().filter(|&level| level >= SILENCE_DB).collect();
+        let Some(first_sound) = self.levels.iter().position(|&level| level >= SILENCE_DB) else {
+            return SoundFeatures { duration, key, ..Default::default() };
+        };
+
+        let count = sounding.len() as f64;
+        let mean_level = sounding.iter().sum::<f64>() / count;
+        let level_spread = (sounding.iter().map(|level| (level - mean_level).powi(2)).sum::<f64>() / count).sqrt();
+
+        let frames_per_second = (1.0 / self.hop_seconds).ceil() as usize;
+        let peak = (first_sound..self.levels.len().min(first_sound + frames_per_second))
+            .max_by(|&a, &b| self.levels[a].total_cmp(&self.levels[b]))
+            .unwrap_or(first_sound);
+
+        let level_before = |i: usize, back: usize| i.checked_sub(back).map_or(FLOOR_DB, |j| self.levels[j]);
+        let mut onsets = 0;
+        let mut last_onset: Option<usize> = None;
+        for (i, &level) in self.levels.iter().enumerate() {
+            let rise = level - level_before(i, 1).min(level_before(i, 2));
+            let rested = last_onset.is_none_or(|last| i - last > ONSET_REFRACTORY);
+            if level >= SILENCE_DB && rise >= ONSET_RISE_DB && rested {
+                onsets += 1;
+                last_onset = Some(i);
+            }
+        }
+
+        let sounding_seconds = count * self.hop_seconds;
+        SoundFeatures {
+            duration,
+            sounding_seconds,
+            level_spread,
+            attack: (peak - first_sound) as f64 * self.hop_seconds,
+            onset_rate: onsets as f64 / sounding_seconds,
+            centroid: self.centroid_sum / count,
+            flatness: self.flatness_sum / count,
+            key,
+        }
+    }
+}
+
+/// Sort a sound into one of the classes; `None` when nothing in it sounds
+pub fn classify(features: &SoundFeatures) -> Option<SoundClass> {
+    if features.sounding_seconds == 0.0 {
+        return None;
+    }
+    if features.sounding_seconds <= ONE_SHOT_SECONDS {
+        return Some(SoundClass::OneShot);
+    }
+    if features.key.is_some() {
+        return Some(SoundClass::Music);
+    }
+    let speech_rate = (SPEECH_ONSET_RATE.0..=SPEECH_ONSET_RATE.1).contains(&features.onset_rate);
+    let voice_centroid = (SPEECH_CENTROID.0..=SPEECH_CENTROID.1).contains(&features.centroid);
+    if speech_rate && voice_centroid && features.level_spread > STEADY_SPREAD_DB && features.flatness < NOISY_FLATNESS {
+        return Some(SoundClass::Speech);
+    }
+    Some(SoundClass::AmbienceBed)
+}
+
+/// Genre, mood, occasions and keywords a sound's features suggest, before checking the vocabulary
+fn suggest(class: SoundClass, features: &SoundFeatures) -> (&'static str, &'static str, Vec<&'static str>, Vec<&'static str>) {
+    let dark = features.centroid < DARK_CENTROID;
+    let minor = features.key.as_deref().is_some_and(|key| key.ends_with('m'));
+    match class {
+        SoundClass::AmbienceBed => {
+            let (genre, mut keywords) = if dark && features.flatness < NOISY_FLATNESS {
+                ("ambient:drone", vec!["util:bed", "util:drone"])
+            } else if features.flatness >= NOISY_FLATNESS {
+                ("ambient:nature-ambient", vec!["util:bed"])
+            } else {
+                ("ambient:textural", vec!["util:bed"])
+            };
+            keywords.extend(["util:stem-ambient", "util:diegetic"]);
+            let mood = if dark {
+                "ominous"
+            } else if features.level_spread > STEADY_SPREAD_DB {
+                "tense"
+            } else {
+                "serene"
+            };
+            (genre, mood, vec![], keywords)
+        }
+        SoundClass::OneShot if features.key.is_some() => {
+            ("sound-design:stingers", if minor { "ominous" } else { "hopeful" }, vec![], vec!["util:stinger"])
+        }
+        SoundClass::OneShot => {
+            let (genre, mood) = if features.attack > SHARP_ATTACK_SECONDS {
+                ("sound-design:whooshes", "airy")
+            } else if dark {
+                ("sound-design:booms", "explosive")
+            } else {
+                ("sound-design:impacts", "percussive")
+            };
+            (genre, mood, vec![], vec!["util:diegetic"])
+        }
+        SoundClass::Music => {
+            let sustained = features.onset_rate < SUSTAINED_ONSET_RATE;
+            let genre = if sustained { "ambient:drone" } else { "orchestral:cinematic" };
+            let mood = if features.onset_rate >= DRIVING_ONSET_RATE {
+                "driving"
+            } else if minor {
+                "melancholic"
+            } else {
+                "hopeful"
+            };
+            let mut keywords = vec!["util:non-diegetic"];
+            if sustained {
+                keywords.push("util:drone");
+            }
+            (genre, mood, vec![], keywords)
+        }
+        SoundClass::Speech => ("sound-design:voice", "dry", vec!["conversation"], vec!["util:diegetic"]),
+    }
+}
+
+/// Tags for a file from its features, limited to the active vocabulary; where the suggested genre or
+/// mood isn't in it, the file keeps what it has. `None` for files without any sound
+pub fn propose_tags(file: &AudioFile, features: &SoundFeatures, vocabulary: &[TagVocabulary]) -> Option<TaggedFile> {
+    let class = classify(features)?;
+    let (genre, mood, occasions, keywords) = suggest(class, features);
+    let known = |tag_type: &str, value: &str| {
+        vocabulary.iter().any(|entry| entry.is_active && entry.tag_type == tag_type && entry.tag_value == value)
+    };
+    let known_all = |tag_type: &str, values: Vec<&str>| {
+        values.into_iter().filter(|value| known(tag_type, value)).map(String::from).collect()
+    };
+
+    Some(TaggedFile {
+        id: file.id,
+        file_path: file.file_path.clone(),
+        genre: if known("genre", genre) { genre.to_string() } else { file.genre.clone().unwrap_or_default() },
+        mood: if known("mood", mood) { mood.to_string() } else { file.mood.clone().unwrap_or_default() },
+        rpg_occasion: known_all("occasion", occasions),
+        rpg_keywords: known_all("keywords", keywords),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 44100;
+
+    fn noise(seed: &mut u32) -> f64 {
+        *seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+        (*seed >> 8) as f64 / (1 << 24) as f64 - 0.5
+    }
+
+    fn analyze(samples: &[f32]) -> SoundFeatures {
+        let mut analyzer = SoundAnalyzer::new(RATE, 1);
+        for chunk in samples.chunks(4096) {
+            analyzer.add_samples(chunk);
+        }
+        analyzer.finish()
+    }
+
+    #[test]
+    fn tells_beds_one_shots_and_music_apart() {
+        let mut seed = 99u32;
+        let bed: Vec<f32> = (0..RATE as usize * 20).map(|_| (noise(&mut seed) * 0.4) as f32).collect();
+        let bed = analyze(&bed);
+        assert_eq!(classify(&bed), Some(SoundClass::AmbienceBed), "{:?}", bed);
+        assert!(bed.flatness >= NOISY_FLATNESS, "{:?}", bed);
+
+        // Half a second of silence, then a noise burst decaying within a quarter second
+        let hit: Vec<f32> = (0..RATE as usize * 2)
+            .map(|n| {
+                let t = n as f64 / RATE as f64 - 0.5;
+                if t < 0.0 { 0.0 } else { (noise(&mut seed) * (-t * 20.0).exp()) as f32 }
+            })
+            .collect();
+        let hit = analyze(&hit);
+        assert_eq!(classify(&hit), Some(SoundClass::OneShot), "{:?}", hit);
+        assert!(hit.attack <= SHARP_ATTACK_SECONDS, "{:?}", hit);
+
+        // C major chords struck twice a second for eight seconds
+        let chords: [&[f64]; 4] = [&[48.0, 60.0, 64.0, 67.0], &[53.0, 60.0, 65.0, 69.0], &[55.0, 59.0, 62.0, 67.0], &[48.0, 60.0, 64.0, 67.0]];
+        let music: Vec<f32> = (0..RATE as usize * 8)
+            .map(|n| {
+                let t = n as f64 / RATE as f64;
+                let notes = chords[(t / 2.0) as usize];
+                let envelope = 0.3 + 0.7 * (-(t % 0.5) * 6.0).exp();
+                (notes.iter()
+                    .map(|&note| (2.0 * PI * 440.0 * 2f64.powf((note - 69.0) / 12.0) * t).sin())
+                    .sum::<f64>() * 0.15 * envelope) as f32
+            })
+            .collect();
+        let music = analyze(&music);
+        assert_eq!(classify(&music), Some(SoundClass::Music), "{:?}", music);
+        assert_eq!(music.key.as_deref(), Some("C"));
+
+        assert_eq!(classify(&analyze(&vec![0.0; RATE as usize * 4])), None);
+    }
+
+    #[test]
+    fn proposes_tags_from_the_active_vocabulary() {
+        let entry = |tag_type: &str, tag_value: &str, is_active: bool| TagVocabulary {
+            id: None,
+            tag_type: tag_type.to_string(),
+            tag_value: tag_value.to_string(),
+            description: None,
+            parent_tag: None,
+            is_active,
+        };
+        let vocabulary = [
+            entry("genre", "sound-design:voice", true),
+            entry("mood", "dry", false),
+            entry("occasion", "conversation", true),
+            entry("keywords", "util:diegetic", true),
+        ];
+        let file = AudioFile {
+            id: 7,
+            file_path: "/packs/voices/innkeeper_greeting.wav".to_string(),
+            title: None,
+            artist: None,
+            album: None,
+            genre: None,
+            mood: Some("warm".to_string()),
+        };
+        let speech = SoundFeatures {
+            duration: 12.0,
+            sounding_seconds: 9.0,
+            level_spread: 9.0,
+            attack: 0.05,
+            onset_rate: 3.5,
+            centroid: 900.0,
+            flatness: 0.12,
+            key: None,
+        };
+
+        let tagged = propose_tags(&file, &speech, &vocabulary).unwrap();
+        assert_eq!((tagged.id, tagged.genre.as_str(), tagged.mood.as_str()), (7, "sound-design:voice", "warm"));
+        assert_eq!(tagged.rpg_occasion, vec!["conversation"]);
+        assert_eq!(tagged.rpg_keywords, vec!["util:diegetic"]);
+
+        assert!(propose_tags(&file, &SoundFeatures::default(), &vocabulary).is_none());
+    }
+}
@@ -1,13 +1,56 @@
-use crate::models::{StoreTagsResult, FileTagComparison, TagDifference};
+use crate::models::{AudioFilePatch, StoreTagsResult, FileTagComparison, TagDifference, TagPreviewFilter, TagPreviewResult};
 use crate::database::Database;
+use crate::tag_backup::{self, TagWriteRun};
+use crate::tag_formats::{self, TagFormat, involved_people, mp4::{self, Mp4Tag}, vorbis::{self, VorbisComments}};
 use tauri::AppHandle;
 use id3::{Tag, TagLike, Frame, Content};
 use std::path::Path;
 use std::time::Instant;
 use log::{info, warn, error};
 
-/// Store all database metadata and RPG tags into the actual audio files
-pub async fn store_all_tags_in_files(_app_handle: AppHandle) -> Result<StoreTagsResult, String> {
+/// Store all database metadata and RPG tags into the actual audio files,
+/// backing up the original tag block of every rewritten file when `backup` is set
+pub async fn store_all_tags_in_files(_app_handle: AppHandle, backup: bool) -> Result<StoreTagsResult, String> {
+    store_tags(None, backup)
+}
+
+/// Store database metadata and RPG tags into the given files only (e.g. the approved part of a preview)
+pub async fn store_tags_in_selected_files(_app_handle: AppHandle, file_paths: Vec<String>, backup: bool) -> Result<StoreTagsResult, String> {
+    store_tags(Some(&file_paths), backup)
+}
+
+/// Preview what storing tags would change, file by file, without writing anything
+pub async fn preview_store_tags(_app_handle: AppHandle, filter: TagPreviewFilter) -> Result<TagPreviewResult, String> {
+    let db = Database::new().map_err(|e| format!("Failed to create database connection: {}", e))?;
+    let audio_files = db.get_all_audio_files().map_err(|e| format!("Failed to get audio files: {}", e))?;
+
+    let mut preview = TagPreviewResult {
+        total_files: audio_files.len(),
+        matched_files: 0,
+        files: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    for audio_file in audio_files.iter().filter(|f| filter.matches_file(&f.file_path)) {
+        match plan_single_file(&db, audio_file) {
+            Ok((comparison, _)) => {
+                if comparison.needs_update && filter.matches_comparison(&comparison) {
+                    preview.matched_files += 1;
+                    if filter.limit.is_none_or(|limit| preview.files.len() < limit) {
+                        preview.files.push(comparison);
+                    }
+                }
+            }
+            Err(e) => preview.errors.push(format!("{}: {}", audio_file.file_path, e)),
+        }
+    }
+
+    info!("Store tags preview: {} of {} files would change", preview.matched_files, preview.total_files);
+    Ok(preview)
+}
+
+/// Store tags into every file of the library, or only into `file_paths`
+fn store_tags(file_paths: Option<&[String]>, backup: bool) -> Result<StoreTagsResult, String> {
     let start_time = Instant::now();
     info!("Starting store tags in files operation");
 
@@ -30,7 +73,7 @@ pub async fn store_all_tags_in_files(_app_handle: AppHandle) -> Result<StoreTags
     };
 
     // Get all audio files with metadata
-    let audio_files = match db.get_all_audio_files() {
+    let mut audio_files = match db.get_all_audio_files() {
         Ok(files) => files,
         Err(e) => {
             error!("Failed to get audio files from database: {}", e);
@@ -38,11 +81,25 @@ pub async fn store_all_tags_in_files(_app_handle: AppHandle) -> Result<StoreTags
         }
     };
 
-    result.total_files = audio_files.len();
+    if let Some(file_paths) = file_paths {
+        audio_files.retain(|f| file_paths.contains(&f.file_path));
+        for file_path in file_paths.iter().filter(|p| !audio_files.iter().any(|f| &f.file_path == *p)) {
+            result.failed_files += 1;
+            result.errors.push(format!("{}: Not in the library", file_path));
+        }
+    }
+
+    result.total_files = audio_files.len() + result.failed_files;
     info!("Processing {} audio files", result.total_files);
 
+    let mut backup_run = if backup {
+        Some(TagWriteRun::start(Path::new(tag_backup::TAG_BACKUP_DIR), "store_tags")?)
+    } else {
+        None
+    };
+
     for audio_file in audio_files {
-        match process_single_file(&db, &audio_file, &mut result) {
+        match process_single_file(&db, &audio_file, &mut result, backup_run.as_mut()) {
             Ok(updated) => {
                 if updated {
                     result.updated_files += 1;
@@ -58,6 +115,10 @@ pub async fn store_all_tags_in_files(_app_handle: AppHandle) -> Result<StoreTags
         }
     }
 
+    if let Some(run) = backup_run {
+        run.finish()?;
+    }
+
     result.duration_seconds = start_time.elapsed().as_secs_f64();
     
     info!(
@@ -72,12 +133,23 @@ pub async fn store_all_tags_in_files(_app_handle: AppHandle) -> Result<StoreTags
     Ok(result)
 }
 
-/// Process a single audio file - compare current tags with database and update if needed
-fn process_single_file(
+/// Put back the original tags of every file touched by the last store/remove/write run
+pub async fn restore_last_tag_write_run(_app_handle: AppHandle) -> Result<StoreTagsResult, String> {
+    tag_backup::restore_last_run(Path::new(tag_backup::TAG_BACKUP_DIR))
+}
+
+/// Tags to write to a file, in its native container
+pub enum PlannedTags {
+    Id3(Tag),
+    Vorbis(VorbisComments),
+    Mp4(Mp4Tag),
+}
+
+/// Compare the current tags of a file with the database and build the tags to write
+fn plan_single_file(
     db: &Database,
     audio_file: &crate::models::AudioFile,
-    _result: &mut StoreTagsResult,
-) -> Result<bool, String> {
+) -> Result<(FileTagComparison, PlannedTags), String> {
     let file_path = &audio_file.file_path;
     
     // Check if file exists and is readable
@@ -85,15 +157,6 @@ fn process_single_file(
         return Err("File does not exist".to_string());
     }
 
-    // Read current tags from file
-    let current_tag = match Tag::read_from_path(file_path) {
-        Ok(tag) => Some(tag),
-        Err(_) => {
-            // File might not have tags yet, create new tag
-            None
-        }
-    };
-
     // Get RPG tags for this file from database
     let rpg_tags = if let Some(audio_file_id) = audio_file.id {
         match db.get_rpg_tags_for_file(audio_file_id) {
@@ -107,21 +170,53 @@ fn process_single_file(
         Vec::new()
     };
 
+    // FLAC, OGG and MP4 files use their native tag containers instead of ID3
+    match TagFormat::from_path(file_path) {
+        TagFormat::Flac | TagFormat::Ogg => return plan_vorbis_comments(audio_file, &rpg_tags),
+        TagFormat::Mp4 => return plan_mp4_atoms(audio_file, &rpg_tags),
+        TagFormat::Unsupported => return Err(tag_formats::unsupported_format(file_path)),
+        TagFormat::Id3 | TagFormat::Wav => {}
+    }
+
+    // Read current tags from file (None if the file has no tag yet)
+    let current_tag = tag_formats::read_id3_tag(file_path);
+
     // Compare current file tags with database values
     let comparison = compare_file_tags_with_database(&current_tag, audio_file, &rpg_tags);
-    
-    if !comparison.needs_update {
-        return Ok(false); // No update needed
-    }
 
     // Create new tag with all database metadata
     let mut new_tag = current_tag.unwrap_or_else(Tag::new);
     
     // Write all metadata fields to the tag
     write_metadata_to_tag(&mut new_tag, audio_file, &rpg_tags)?;
+
+    Ok((comparison, PlannedTags::Id3(new_tag)))
+}
+
+/// Process a single audio file - compare current tags with database and update if needed
+fn process_single_file(
+    db: &Database,
+    audio_file: &crate::models::AudioFile,
+    _result: &mut StoreTagsResult,
+    backup: Option<&mut TagWriteRun>,
+) -> Result<bool, String> {
+    let file_path = &audio_file.file_path;
+    // Containers without a tag writer are left as they are
+    if TagFormat::from_path(file_path) == TagFormat::Unsupported {
+        return Ok(false);
+    }
+    let (comparison, planned) = plan_single_file(db, audio_file)?;
     
-    // Write the updated tag back to the file
-    match new_tag.write_to_path(file_path, id3::Version::Id3v24) {
+    if !comparison.needs_update {
+        return Ok(false); // No update needed
+    }
+
+    if let Some(run) = backup {
+        run.backup(file_path)?;
+    }
+    
+    // Write the updated tags back to the file
+    match write_planned_tags(file_path, &planned) {
         Ok(_) => {
             info!("Successfully updated tags for: {}", file_path);
             Ok(true)
@@ -133,6 +228,228 @@ fn process_single_file(
     }
 }
 
+/// Write planned tags with the verified writer of their container
+pub fn write_planned_tags(file_path: &str, planned: &PlannedTags) -> Result<(), String> {
+    match planned {
+        PlannedTags::Id3(tag) => tag_formats::write_id3_tag(file_path, tag),
+        PlannedTags::Vorbis(comments) => tag_formats::write_vorbis_comments(file_path, comments),
+        PlannedTags::Mp4(tag) => tag_formats::write_mp4_tag(file_path, tag),
+    }
+}
+
+/// Record the fields `new` adds, changes or drops compared to `current`.
+/// Fields with several values are compared as one ";" joined value.
+pub fn compare_field_values(
+    current: &[(String, String)],
+    new: &[(String, String)],
+    comparison: &mut FileTagComparison,
+) {
+    fn joined(fields: &[(String, String)]) -> Vec<(String, String)> {
+        let mut grouped: Vec<(String, String)> = Vec::new();
+        for (name, value) in fields {
+            match grouped.iter_mut().find(|(n, _)| n == name) {
+                Some((_, joined)) => {
+                    joined.push(';');
+                    joined.push_str(value);
+                }
+                None => grouped.push((name.clone(), value.clone())),
+            }
+        }
+        grouped
+    }
+    let current = joined(current);
+    let new = joined(new);
+
+    for (name, value) in &new {
+        match current.iter().find(|(n, _)| n == name) {
+            None => comparison.missing_tags.push(name.clone()),
+            Some((_, current_value)) if current_value != value => comparison.different_values.push(TagDifference {
+                field_name: name.clone(),
+                current_value: current_value.clone(),
+                new_value: value.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (name, current_value) in &current {
+        if !new.iter().any(|(n, _)| n == name) {
+            comparison.different_values.push(TagDifference {
+                field_name: name.clone(),
+                current_value: current_value.clone(),
+                new_value: String::new(),
+            });
+        }
+    }
+
+    if !comparison.missing_tags.is_empty() || !comparison.different_values.is_empty() {
+        comparison.needs_update = true;
+    }
+}
+
+/// ID3 frames as (field, value) pairs; TXXX frames are named "TXXX:<description>"
+pub fn id3_field_values(tag: &Tag) -> Vec<(String, String)> {
+    tag.frames()
+        .map(|frame| match frame.content().extended_text() {
+            Some(extended) => (format!("TXXX:{}", extended.description), extended.value.clone()),
+            None => (frame.id().to_string(), frame.content().to_string()),
+        })
+        .collect()
+}
+
+/// iTunes atoms as (field, value) pairs; freeform atoms are named by their `name`
+pub fn mp4_field_values(tag: &Mp4Tag) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+    for item in &tag.items {
+        let name = match &item.key {
+            // Atom names are Latin-1 ("©nam")
+            mp4::Mp4Key::Atom(kind) => kind.iter().map(|&b| b as char).collect(),
+            mp4::Mp4Key::Freeform { name, .. } => name.clone(),
+        };
+        for value in &item.values {
+            let text = match value.type_code {
+                1 => String::from_utf8_lossy(&value.bytes).to_string(),
+                _ => format!("<{} bytes>", value.bytes.len()),
+            };
+            fields.push((name.clone(), text));
+        }
+    }
+    fields
+}
+
+/// Build the native Vorbis comments holding the database metadata and RPG tags
+fn plan_vorbis_comments(
+    audio_file: &crate::models::AudioFile,
+    rpg_tags: &[crate::models::RpgTag],
+) -> Result<(FileTagComparison, PlannedTags), String> {
+    let file_path = &audio_file.file_path;
+    let current = tag_formats::read_or_new_vorbis_comments(file_path)?;
+
+    let mut comments = current.clone();
+    vorbis::apply_patch(&mut comments, &AudioFilePatch::from(audio_file));
+    write_rpg_tags_to_vorbis(&mut comments, rpg_tags);
+
+    let mut comparison = FileTagComparison {
+        file_path: file_path.clone(),
+        needs_update: false,
+        missing_tags: Vec::new(),
+        different_values: Vec::new(),
+    };
+    compare_field_values(&current.comments, &comments.comments, &mut comparison);
+    comparison.needs_update = comments != current;
+
+    // Add Ligeia-specific metadata
+    comments.set("LIGEIA_VERSION", "1.0");
+    comments.set("LIGEIA_TIMESTAMP", &chrono::Utc::now().to_rfc3339());
+    if let Some(id) = audio_file.id {
+        comments.set("LIGEIA_DATABASE_ID", &id.to_string());
+    }
+    comments.set("ORIGINAL_PATH", file_path);
+
+    Ok((comparison, PlannedTags::Vorbis(comments)))
+}
+
+/// Write RPG tags as Vorbis comments (one entry per value)
+fn write_rpg_tags_to_vorbis(comments: &mut VorbisComments, rpg_tags: &[crate::models::RpgTag]) {
+    let mut genre_tags = Vec::new();
+    let mut mood_tags = Vec::new();
+    let mut occasion_tags = Vec::new();
+    let mut keyword_tags = Vec::new();
+    let mut quality = None;
+
+    for rpg_tag in rpg_tags {
+        match rpg_tag.tag_type.as_str() {
+            "genre" => genre_tags.push(rpg_tag.tag_value.clone()),
+            "mood" => mood_tags.push(rpg_tag.tag_value.clone()),
+            "occasion" => occasion_tags.push(rpg_tag.tag_value.clone()),
+            "keyword" | "keywords" => keyword_tags.push(rpg_tag.tag_value.clone()),
+            "quality" => quality = Some(rpg_tag.tag_value.clone()),
+            _ => {}
+        }
+    }
+
+    if !occasion_tags.is_empty() {
+        comments.set_all("OCCASION", &occasion_tags);
+    }
+    if !keyword_tags.is_empty() {
+        comments.set_all("KEYWORDS", &keyword_tags);
+    }
+    if let Some(quality) = quality {
+        comments.set("QUALITY", &quality);
+    }
+    if !genre_tags.is_empty() {
+        comments.set_all("RPG_GENRE", &genre_tags);
+    }
+    if !mood_tags.is_empty() {
+        comments.set_all("RPG_MOOD", &mood_tags);
+    }
+}
+
+/// Build the iTunes atoms holding the database metadata and RPG tags
+fn plan_mp4_atoms(
+    audio_file: &crate::models::AudioFile,
+    rpg_tags: &[crate::models::RpgTag],
+) -> Result<(FileTagComparison, PlannedTags), String> {
+    let file_path = &audio_file.file_path;
+    let current = mp4::read_tag(file_path)?.unwrap_or_default();
+
+    let mut tag = current.clone();
+    mp4::apply_patch(&mut tag, &AudioFilePatch::from(audio_file));
+    write_rpg_tags_to_mp4(&mut tag, rpg_tags);
+
+    let mut comparison = FileTagComparison {
+        file_path: file_path.clone(),
+        needs_update: false,
+        missing_tags: Vec::new(),
+        different_values: Vec::new(),
+    };
+    compare_field_values(&mp4_field_values(&current), &mp4_field_values(&tag), &mut comparison);
+    comparison.needs_update = tag != current;
+
+    // Add Ligeia-specific metadata
+    tag.set_freeform("LIGEIA_VERSION", "1.0");
+    tag.set_freeform("LIGEIA_TIMESTAMP", &chrono::Utc::now().to_rfc3339());
+    if let Some(id) = audio_file.id {
+        tag.set_freeform("LIGEIA_DATABASE_ID", &id.to_string());
+    }
+    tag.set_freeform("ORIGINAL_PATH", file_path);
+
+    Ok((comparison, PlannedTags::Mp4(tag)))
+}
+
+/// Write RPG tags as freeform atoms (semicolon-separated, like the TXXX frames)
+fn write_rpg_tags_to_mp4(tag: &mut Mp4Tag, rpg_tags: &[crate::models::RpgTag]) {
+    let mut genre_tags = Vec::new();
+    let mut mood_tags = Vec::new();
+    let mut occasion_tags = Vec::new();
+    let mut keyword_tags = Vec::new();
+    let mut quality = None;
+
+    for rpg_tag in rpg_tags {
+        match rpg_tag.tag_type.as_str() {
+            "genre" => genre_tags.push(rpg_tag.tag_value.clone()),
+            "mood" => mood_tags.push(rpg_tag.tag_value.clone()),
+            "occasion" => occasion_tags.push(rpg_tag.tag_value.clone()),
+            "keyword" | "keywords" => keyword_tags.push(rpg_tag.tag_value.clone()),
+            "quality" => quality = Some(rpg_tag.tag_value.clone()),
+            _ => {}
+        }
+    }
+
+    for (name, values) in [
+        ("Occasion", occasion_tags),
+        ("Keywords", keyword_tags),
+        ("RPG_GENRE", genre_tags),
+        ("RPG_MOOD", mood_tags),
+    ] {
+        if !values.is_empty() {
+            tag.set_freeform(name, &values.join("; "));
+        }
+    }
+    if let Some(quality) = quality {
+        tag.set_freeform("Quality", &quality);
+    }
+}
+
 /// Compare current file tags with database values to determine what needs updating
 fn compare_file_tags_with_database(
     current_tag: &Option<Tag>,
@@ -341,9 +658,8 @@ fn write_metadata_to_tag(
         set_text_frame(tag, "TPE3", conductor);
     }
 
-    if let Some(ref producer) = audio_file.producer {
-        set_text_frame(tag, "TPRO", producer);
-    }
+    // Arranger, engineer, producer, DJ-mixer and mixer credits go to TIPL
+    involved_people::update_tag(tag, &AudioFilePatch::from(audio_file));
 
     if let Some(ref publisher) = audio_file.publisher {
         set_text_frame(tag, "TPUB", publisher);
@@ -454,4 +770,54 @@ fn set_txxx_frame(tag: &mut Tag, description: &str, value: &str) -> Result<(), S
     let frame = Frame::with_content("TXXX", content);
     tag.add_frame(frame);
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn leaves_containers_without_a_tag_writer_untouched() {
+        let mut tag = Tag::new();
+        tag.set_title("Cave drips");
+        let patch = AudioFilePatch { title: crate::models::FieldPatch::Set("Cave drips".to_string()), ..Default::default() };
+        let rpg_tags = [("occasion".to_string(), vec!["dungeon".to_string()])];
+
+        for name in ["ligeia_unsupported.webm", "ligeia_unsupported.caf"] {
+            let path = std::env::temp_dir().join(name).to_string_lossy().to_string();
+            let bytes = b"\x1aE\xdf\xa3 container bytes".to_vec();
+            std::fs::write(&path, &bytes).unwrap();
+
+            assert!(write_planned_tags(&path, &PlannedTags::Id3(tag.clone())).is_err());
+            assert!(crate::AudioHandler::update_audio_file_tags(&path, &patch).is_err());
+            assert!(crate::AudioHandler::write_rpg_tags_to_file(&path, &rpg_tags).is_err());
+            assert_eq!(std::fs::read(&path).unwrap(), bytes);
+            std::fs::remove_file(&path).ok();
+        }
+    }
+
+    #[test]
+    fn compares_added_changed_and_dropped_fields() {
+        let mut comparison = FileTagComparison {
+            file_path: "a.flac".to_string(),
+            needs_update: false,
+            missing_tags: Vec::new(),
+            different_values: Vec::new(),
+        };
+        compare_field_values(
+            &fields(&[("TITLE", "Rain"), ("RPG_MOOD", "calm"), ("COMMENT", "old")]),
+            &fields(&[("TITLE", "Rain"), ("RPG_MOOD", "calm"), ("RPG_MOOD", "dark"), ("ARTIST", "Ann")]),
+            &mut comparison,
+        );
+
+        assert!(comparison.needs_update);
+        assert_eq!(comparison.missing_tags, vec!["ARTIST"]);
+        let changes: Vec<(&str, &str, &str)> = comparison.different_values.iter()
+            .map(|d| (d.field_name.as_str(), d.current_value.as_str(), d.new_value.as_str()))
+            .collect();
+        assert_eq!(changes, vec![("RPG_MOOD", "calm", "calm;dark"), ("COMMENT", "old", "")]);
+    }
+}
@@ -0,0 +1,247 @@
+use crate::models::StoreTagsResult;
+use crate::tag_formats::safe_write;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Tag block backups of write runs, kept next to the database
+pub const TAG_BACKUP_DIR: &str = "../db/tag_backups";
+
+const RUN_FILE: &str = "run.json";
+const ENTRIES_FILE: &str = "entries.jsonl";
+
+/// A tag write run (one store/remove/write command)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TagRunInfo {
+    pub id: String,
+    pub operation: String,
+    pub started_at: String,
+    pub restored_at: Option<String>,
+}
+
+/// A file touched by a run, with the backup of its original tag block
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TagBackupEntry {
+    pub file_path: String,
+    /// Backup file inside the run directory; `None` when the file had no tags
+    pub backup_file: Option<String>,
+}
+
+/// Backs up the original tag block of every file a run is about to write
+pub struct TagWriteRun {
+    dir: PathBuf,
+    info: TagRunInfo,
+    entries: Vec<TagBackupEntry>,
+}
+
+/// Write a file and flush it to disk
+fn write_synced(path: &Path, data: &[u8]) -> Result<(), String> {
+    let mut file = fs::File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    file.write_all(data)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn write_info(dir: &Path, info: &TagRunInfo) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(info).map_err(|e| e.to_string())?;
+    write_synced(&dir.join(RUN_FILE), &json)
+}
+
+fn read_info(dir: &Path) -> Result<TagRunInfo, String> {
+    let json = fs::read(dir.join(RUN_FILE)).map_err(|e| format!("Failed to read tag run: {}", e))?;
+    serde_json::from_slice(&json).map_err(|e| format!("Invalid tag run: {}", e))
+}
+
+fn read_entries(dir: &Path) -> Result<Vec<TagBackupEntry>, String> {
+    let text = match fs::read_to_string(dir.join(ENTRIES_FILE)) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read tag run entries: {}", e)),
+    };
+    // A crash while appending can leave a partial last line; everything before it is intact
+    Ok(text.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+}
+
+impl TagWriteRun {
+    /// Start a run in `root`; runs are named by start time so they sort chronologically
+    pub fn start(root: &Path, operation: &str) -> Result<Self, String> {
+        let now = chrono::Utc::now();
+        let id = format!("{}_{}", now.format("%Y%m%dT%H%M%S%.6fZ"), operation);
+        let dir = root.join(&id);
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create tag backup directory: {}", e))?;
+
+        let info = TagRunInfo {
+            id,
+            operation: operation.to_string(),
+            started_at: now.to_rfc3339(),
+            restored_at: None,
+        };
+        write_info(&dir, &info)?;
+        Ok(TagWriteRun { dir, info, entries: Vec::new() })
+    }
+
+    /// Save the current tag block of a file before it is rewritten.
+    /// Only the first call per file counts, so the backup always holds the original.
+    pub fn backup(&mut self, file_path: &str) -> Result<(), String> {
+        if self.entries.iter().any(|entry| entry.file_path == file_path) {
+            return Ok(());
+        }
+
+        let backup_file = match safe_write::read_tag_block(file_path)? {
+            Some(block) => {
+                let name = format!("{}.tag", self.entries.len());
+                write_synced(&self.dir.join(&name), &block)?;
+                Some(name)
+            }
+            None => None,
+        };
+        let entry = TagBackupEntry { file_path: file_path.to_string(), backup_file };
+
+        // Appended per file so a run interrupted halfway can still be restored
+        let mut line = serde_json::to_string(&entry).map_err(|e| e.to_string())?;
+        line.push('\n');
+        let mut entries_file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(ENTRIES_FILE))
+            .map_err(|e| format!("Failed to open tag backup entries: {}", e))?;
+        entries_file.write_all(line.as_bytes())
+            .and_then(|_| entries_file.sync_data())
+            .map_err(|e| format!("Failed to record tag backup: {}", e))?;
+
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Finish the run, dropping its directory when no file was touched
+    pub fn finish(self) -> Result<(), String> {
+        if self.entries.is_empty() {
+            fs::remove_dir_all(&self.dir).map_err(|e| format!("Failed to remove empty tag run: {}", e))?;
+        } else {
+            info!("Tag write run {} backed up {} files", self.info.id, self.entries.len());
+        }
+        Ok(())
+    }
+}
+
+/// Most recent run in `root` that touched files and hasn't been restored yet
+fn last_unrestored_run(root: &Path) -> Result<Option<PathBuf>, String> {
+    let mut dirs: Vec<PathBuf> = match fs::read_dir(root) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| p.is_dir()).collect(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read tag backups: {}", e)),
+    };
+    dirs.sort();
+
+    for dir in dirs.into_iter().rev() {
+        match read_info(&dir) {
+            Ok(info) if info.restored_at.is_none() && !read_entries(&dir)?.is_empty() => return Ok(Some(dir)),
+            Ok(_) => {}
+            Err(e) => warn!("Skipping tag backup {}: {}", dir.display(), e),
+        }
+    }
+    Ok(None)
+}
+
+/// Put back the original tag blocks of every file touched by the last unrestored run.
+///
+/// The run is only marked as restored when every file succeeded, so a failed restore can be retried;
+/// restoring again after that steps back to the run before it.
+pub fn restore_last_run(root: &Path) -> Result<StoreTagsResult, String> {
+    let start_time = Instant::now();
+    let dir = last_unrestored_run(root)?.ok_or("No tag write run to restore")?;
+    let mut run_info = read_info(&dir)?;
+    let entries = read_entries(&dir)?;
+    info!("Restoring tag write run {} ({} files)", run_info.id, entries.len());
+
+    let mut result = StoreTagsResult {
+        total_files: entries.len(),
+        updated_files: 0,
+        skipped_files: 0,
+        failed_files: 0,
+        errors: Vec::new(),
+        duration_seconds: 0.0,
+    };
+
+    for entry in &entries {
+        let restored = if !Path::new(&entry.file_path).exists() {
+            Err("File does not exist".to_string())
+        } else {
+            match &entry.backup_file {
+                Some(name) => fs::read(dir.join(name))
+                    .map_err(|e| format!("Failed to read backup: {}", e))
+                    .and_then(|block| safe_write::restore_tag_block(&entry.file_path, Some(&block))),
+                None => safe_write::restore_tag_block(&entry.file_path, None),
+            }
+        };
+
+        match restored {
+            Ok(()) => result.updated_files += 1,
+            Err(e) => {
+                result.failed_files += 1;
+                result.errors.push(format!("{}: {}", entry.file_path, e));
+                warn!("Failed to restore tags of {}: {}", entry.file_path, e);
+            }
+        }
+    }
+
+    if result.failed_files == 0 {
+        run_info.restored_at = Some(chrono::Utc::now().to_rfc3339());
+        write_info(&dir, &run_info)?;
+    }
+
+    result.duration_seconds = start_time.elapsed().as_secs_f64();
+    info!(
+        "Restored tag write run {} in {:.2}s: {} restored, {} failed",
+        run_info.id, result.duration_seconds, result.updated_files, result.failed_files
+    );
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tag_formats;
+    use id3::{Tag, TagLike};
+
+    #[test]
+    fn restores_every_file_of_the_last_run() {
+        let root = std::env::temp_dir().join("ligeia_tag_backup_test");
+        fs::remove_dir_all(&root).ok();
+        fs::create_dir_all(&root).unwrap();
+
+        let tagged = root.join("tagged.mp3").to_string_lossy().to_string();
+        let untagged = root.join("untagged.mp3").to_string_lossy().to_string();
+        fs::write(&tagged, b"\xFF\xFBaudio").unwrap();
+        fs::write(&untagged, b"\xFF\xFBaudio").unwrap();
+        let mut tag = Tag::new();
+        tag.set_title("Before");
+        tag_formats::write_id3_tag(&tagged, &tag).unwrap();
+        let tagged_before = fs::read(&tagged).unwrap();
+
+        let backups = root.join("backups");
+        let mut run = TagWriteRun::start(&backups, "store_tags").unwrap();
+        tag.set_title("After");
+        for path in [&tagged, &untagged] {
+            run.backup(path).unwrap();
+            tag_formats::write_id3_tag(path, &tag).unwrap();
+        }
+        // A second write in the same run keeps the first backup
+        run.backup(&tagged).unwrap();
+        run.finish().unwrap();
+
+        let result = restore_last_run(&backups).unwrap();
+        assert_eq!((result.total_files, result.updated_files, result.failed_files), (2, 2, 0));
+        assert_eq!(fs::read(&tagged).unwrap(), tagged_before);
+        assert_eq!(fs::read(&untagged).unwrap(), b"\xFF\xFBaudio");
+
+        assert!(restore_last_run(&backups).is_err());
+        // Runs that touched nothing leave no trace
+        TagWriteRun::start(&backups, "remove_tags").unwrap().finish().unwrap();
+        assert_eq!(fs::read_dir(&backups).unwrap().count(), 1);
+        fs::remove_dir_all(&root).ok();
+    }
+}
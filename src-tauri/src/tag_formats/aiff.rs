@@ -0,0 +1,140 @@
+use std::fs::{self, File};
+use std::io::Read;
+
+/// Chunk location inside an AIFF/AIFC `FORM` container
+#[derive(Debug, Clone, Copy)]
+struct Chunk {
+    id: [u8; 4],
+    /// Offset of the chunk header
+    start: usize,
+    size: usize,
+}
+
+impl Chunk {
+    fn data_start(&self) -> usize {
+        self.start + 8
+    }
+
+    /// End of the chunk including the pad byte for odd sizes
+    fn end(&self) -> usize {
+        self.data_start() + self.size + (self.size & 1)
+    }
+
+    fn is_id3(&self) -> bool {
+        self.id.eq_ignore_ascii_case(b"ID3 ")
+    }
+}
+
+/// Whether a file is an AIFF or AIFC `FORM` container, where ID3 tags live in an `ID3 ` chunk
+pub fn is_aiff(file_path: &str) -> Result<bool, String> {
+    let file = File::open(file_path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut header = Vec::with_capacity(12);
+    file.take(12).read_to_end(&mut header).map_err(|e| format!("Failed to read file: {}", e))?;
+    Ok(header.len() == 12 && &header[0..4] == b"FORM" && matches!(&header[8..12], b"AIFF" | b"AIFC"))
+}
+
+/// Scan the top-level chunks of an AIFF/AIFC file; returns them with the end of the `FORM`
+fn scan_chunks(data: &[u8]) -> Result<(Vec<Chunk>, usize), String> {
+    if data.len() < 12 || &data[0..4] != b"FORM" {
+        return Err("Not an AIFF file".to_string());
+    }
+    let form_size = u32::from_be_bytes(data[4..8].try_into().unwrap()) as usize;
+    // Never read past the end of the file, whatever the FORM size says
+    let form_end = 8usize.saturating_add(form_size).min(data.len());
+
+    let mut chunks = Vec::new();
+    let mut pos = 12;
+    while pos + 8 <= form_end {
+        let chunk = Chunk {
+            id: data[pos..pos + 4].try_into().unwrap(),
+            start: pos,
+            size: u32::from_be_bytes(data[pos + 4..pos + 8].try_into().unwrap()) as usize,
+        };
+        if chunk.data_start() + chunk.size > form_end {
+            return Err("AIFF chunk extends past the end of the file".to_string());
+        }
+        chunks.push(chunk);
+        pos = chunk.end();
+    }
+    Ok((chunks, form_end))
+}
+
+/// Raw contents of the `ID3 ` chunk
+pub fn read_id3_chunk(file_path: &str) -> Result<Option<Vec<u8>>, String> {
+    let data = fs::read(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let (chunks, _) = scan_chunks(&data)?;
+    Ok(chunks.iter()
+        .find(|chunk| chunk.is_id3())
+        .map(|chunk| data[chunk.data_start()..chunk.data_start() + chunk.size].to_vec()))
+}
+
+/// Replace the `ID3 ` chunk with raw tag bytes, or drop it for `None`. The other chunks keep
+/// their order and the tag goes last, where the id3 crate puts a new one
+pub fn write_id3_chunk(file_path: &str, tag_bytes: Option<&[u8]>) -> Result<(), String> {
+    let data = fs::read(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let (chunks, form_end) = scan_chunks(&data)?;
+
+    let mut output = data[..12].to_vec();
+    for chunk in chunks.iter().filter(|chunk| !chunk.is_id3()) {
+        output.extend_from_slice(&data[chunk.start..chunk.end().min(form_end)]);
+    }
+    if let Some(tag_bytes) = tag_bytes {
+        let size = u32::try_from(tag_bytes.len()).map_err(|_| "ID3 tag too large for an AIFF chunk")?;
+        output.extend_from_slice(b"ID3 ");
+        output.extend_from_slice(&size.to_be_bytes());
+        output.extend_from_slice(tag_bytes);
+        if tag_bytes.len() % 2 == 1 {
+            output.push(0);
+        }
+    }
+
+    let form_size = u32::try_from(output.len() - 8).map_err(|_| "File too large for AIFF")?;
+    output[4..8].copy_from_slice(&form_size.to_be_bytes());
+    // Anything trailing the FORM is kept as it was
+    output.extend_from_slice(&data[form_end..]);
+    fs::write(file_path, output).map_err(|e| format!("Failed to write file: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_vec();
+        bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            bytes.push(0);
+        }
+        bytes
+    }
+
+    fn aiff(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body: Vec<u8> = chunks.concat();
+        let mut bytes = b"FORM".to_vec();
+        bytes.extend_from_slice(&(body.len() as u32 + 4).to_be_bytes());
+        bytes.extend_from_slice(b"AIFF");
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+
+    #[test]
+    fn replaces_and_drops_the_id3_chunk() {
+        let path = std::env::temp_dir().join("ligeia_aiff_id3_chunk_test.aiff");
+        let path_str = path.to_string_lossy().to_string();
+        let comm = chunk(b"COMM", &[0; 18]);
+        let ssnd = chunk(b"SSND", &[1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        fs::write(&path, aiff(&[comm.clone(), chunk(b"ID3 ", b"old"), ssnd.clone()])).unwrap();
+
+        assert!(is_aiff(&path_str).unwrap());
+        assert_eq!(read_id3_chunk(&path_str).unwrap(), Some(b"old".to_vec()));
+
+        write_id3_chunk(&path_str, Some(b"new tag")).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), aiff(&[comm.clone(), ssnd.clone(), chunk(b"ID3 ", b"new tag")]));
+
+        write_id3_chunk(&path_str, None).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), aiff(&[comm, ssnd]));
+        assert_eq!(read_id3_chunk(&path_str).unwrap(), None);
+        fs::remove_file(&path).ok();
+    }
+}
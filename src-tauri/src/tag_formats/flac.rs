@@ -0,0 +1,268 @@
+use super::vorbis::VorbisComments;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+pub const BLOCK_STREAMINFO: u8 = 0;
+pub const BLOCK_PADDING: u8 = 1;
+pub const BLOCK_VORBIS_COMMENT: u8 = 4;
+
+/// Padding reserved when the metadata region has to grow, so later edits can be done in place
+const DEFAULT_PADDING: usize = 4096;
+
+/// A single FLAC metadata block
+#[derive(Debug, Clone)]
+pub struct MetadataBlock {
+    pub block_type: u8,
+    pub data: Vec<u8>,
+}
+
+/// Metadata region of a FLAC file
+#[derive(Debug, Clone)]
+pub struct FlacMetadata {
+    /// Offset of the "fLaC" marker (non-zero when an ID3v2 tag is prepended)
+    pub marker_offset: u64,
+    /// Offset of the first audio frame
+    pub audio_offset: u64,
+    pub blocks: Vec<MetadataBlock>,
+}
+
+impl FlacMetadata {
+    /// Read all metadata blocks from a FLAC file
+    pub fn read_from_path(file_path: &str) -> Result<Self, String> {
+        let mut file = File::open(file_path).map_err(|e| format!("Failed to open file: {}", e))?;
+        Self::read_from(&mut file)
+    }
+
+    pub fn read_from<R: Read + Seek>(reader: &mut R) -> Result<Self, String> {
+        let marker_offset = skip_id3v2(reader)?;
+
+        let mut marker = [0u8; 4];
+        reader.read_exact(&mut marker).map_err(|e| format!("Failed to read FLAC marker: {}", e))?;
+        if &marker != b"fLaC" {
+            return Err("Not a FLAC file".to_string());
+        }
+
+        let mut blocks = Vec::new();
+        let mut offset = marker_offset + 4;
+        loop {
+            let mut header = [0u8; 4];
+            reader.read_exact(&mut header).map_err(|e| format!("Truncated FLAC metadata: {}", e))?;
+            let is_last = header[0] & 0x80 != 0;
+            let block_type = header[0] & 0x7f;
+            let length = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+
+            if block_type == 127 {
+                return Err("Invalid FLAC metadata block type".to_string());
+            }
+
+            let mut data = vec![0u8; length];
+            reader.read_exact(&mut data).map_err(|e| format!("Truncated FLAC metadata block: {}", e))?;
+            blocks.push(MetadataBlock { block_type, data });
+            offset += 4 + length as u64;
+
+            if is_last {
+                break;
+            }
+        }
+
+        Ok(FlacMetadata { marker_offset, audio_offset: offset, blocks })
+    }
+
+    /// Find the first block of the given type
+    pub fn find(&self, block_type: u8) -> Option<&MetadataBlock> {
+        self.blocks.iter().find(|b| b.block_type == block_type)
+    }
+
+    /// Parse the VORBIS_COMMENT block, if present
+    pub fn vorbis_comments(&self) -> Result<Option<VorbisComments>, String> {
+        match self.find(BLOCK_VORBIS_COMMENT) {
+            Some(block) => VorbisComments::parse(&block.data).map(|(c, _)| Some(c)),
+            None => Ok(None),
+        }
+    }
+
+    /// Replace (or insert) the VORBIS_COMMENT block
+    pub fn set_vorbis_comments(&mut self, comments: &VorbisComments) {
+        let data = comments.to_bytes();
+        match self.blocks.iter_mut().find(|b| b.block_type == BLOCK_VORBIS_COMMENT) {
+            Some(block) => block.data = data,
+            None => {
+                // Place comments right after STREAMINFO, which must stay first
+                let insert_at = if self.blocks.is_empty() { 0 } else { 1 };
+                self.blocks.insert(insert_at, MetadataBlock { block_type: BLOCK_VORBIS_COMMENT, data });
+            }
+        }
+    }
+
+    /// Stream duration in seconds from STREAMINFO
+    pub fn duration(&self) -> Option<f64> {
+        let info = self.find(BLOCK_STREAMINFO)?;
+        if info.data.len() < 18 {
+            return None;
+        }
+        // Bytes 10..18: 20-bit sample rate, 3-bit channels, 5-bit bps, 36-bit total samples
+        let d = &info.data;
+        let sample_rate = ((d[10] as u32) << 12) | ((d[11] as u32) << 4) | ((d[12] as u32) >> 4);
+        let total_samples = (((d[13] & 0x0f) as u64) << 32)
+            | ((d[14] as u64) << 24)
+            | ((d[15] as u64) << 16)
+            | ((d[16] as u64) << 8)
+            | (d[17] as u64);
+
+        if sample_rate == 0 || total_samples == 0 {
+            None
+        } else {
+            Some(total_samples as f64 / sample_rate as f64)
+        }
+    }
+
+    /// Write the metadata back, in place when it fits in the existing region (using padding),
+    /// otherwise by rewriting the whole file
+    pub fn write_to_path(&self, file_path: &str) -> Result<(), String> {
+        let old_region = (self.audio_offset - self.marker_offset - 4) as usize;
+        let content_blocks: Vec<&MetadataBlock> = self
+            .blocks
+            .iter()
+            .filter(|b| b.block_type != BLOCK_PADDING)
+            .collect();
+        let content_size: usize = content_blocks.iter().map(|b| 4 + b.data.len()).sum();
+
+        for block in &content_blocks {
+            if block.data.len() >= 1 << 24 {
+                return Err("FLAC metadata block too large".to_string());
+            }
+        }
+
+        // Fits exactly, or leaves room for a padding block (4 byte header)
+        let in_place_padding = if content_size == old_region {
+            Some(None)
+        } else if content_size + 4 <= old_region {
+            Some(Some(old_region - content_size - 4))
+        } else {
+            None
+        };
+
+        match in_place_padding {
+            Some(padding) => {
+                let region = encode_blocks(&content_blocks, padding);
+                let mut file = std::fs::OpenOptions::new()
+                    .write(true)
+                    .open(file_path)
+                    .map_err(|e| format!("Failed to open file for writing: {}", e))?;
+                file.seek(SeekFrom::Start(self.marker_offset + 4))
+                    .and_then(|_| file.write_all(&region))
+                    .map_err(|e| format!("Failed to write FLAC metadata: {}", e))?;
+            }
+            None => {
+                let original = std::fs::read(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+                let mut output = Vec::with_capacity(original.len() + DEFAULT_PADDING);
+                output.extend_from_slice(&original[..(self.marker_offset + 4) as usize]);
+                output.extend_from_slice(&encode_blocks(&content_blocks, Some(DEFAULT_PADDING)));
+                output.extend_from_slice(&original[self.audio_offset as usize..]);
+                std::fs::write(file_path, output).map_err(|e| format!("Failed to write file: {}", e))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn encode_blocks(blocks: &[&MetadataBlock], padding: Option<usize>) -> Vec<u8> {
+    let mut out = Vec::new();
+    let total = blocks.len() + padding.map_or(0, |_| 1);
+
+    for (index, block) in blocks.iter().enumerate() {
+        encode_block_header(&mut out, block.block_type, block.data.len(), index + 1 == total);
+        out.extend_from_slice(&block.data);
+    }
+
+    if let Some(size) = padding {
+        encode_block_header(&mut out, BLOCK_PADDING, size, true);
+        out.resize(out.len() + size, 0);
+    }
+
+    out
+}
+
+fn encode_block_header(out: &mut Vec<u8>, block_type: u8, length: usize, is_last: bool) {
+    let flag = if is_last { 0x80 } else { 0 };
+    let len = (length as u32).to_be_bytes();
+    out.extend_from_slice(&[flag | block_type, len[1], len[2], len[3]]);
+}
+
+/// Skip a leading ID3v2 tag (some taggers prepend one to FLAC files), returning the new offset
+pub(super) fn skip_id3v2<R: Read + Seek>(reader: &mut R) -> Result<u64, String> {
+    let mut header = [0u8; 10];
+    reader.seek(SeekFrom::Start(0)).map_err(|e| e.to_string())?;
+    if reader.read_exact(&mut header).is_err() || &header[0..3] != b"ID3" {
+        reader.seek(SeekFrom::Start(0)).map_err(|e| e.to_string())?;
+        return Ok(0);
+    }
+
+    // Syncsafe size, plus 10 byte footer when flagged
+    let size = ((header[6] as u64 & 0x7f) << 21)
+        | ((header[7] as u64 & 0x7f) << 14)
+        | ((header[8] as u64 & 0x7f) << 7)
+        | (header[9] as u64 & 0x7f);
+    let footer = if header[5] & 0x10 != 0 { 10 } else { 0 };
+    let offset = 10 + size + footer;
+
+    reader.seek(SeekFrom::Start(offset)).map_err(|e| e.to_string())?;
+    Ok(offset)
+}
+
+/// Read Vorbis comments from a FLAC file
+pub fn read_comments(file_path: &str) -> Result<Option<VorbisComments>, String> {
+    FlacMetadata::read_from_path(file_path)?.vorbis_comments()
+}
+
+/// Write Vorbis comments to a FLAC file, keeping every other metadata block
+pub fn write_comments(file_path: &str, comments: &VorbisComments) -> Result<(), String> {
+    let mut metadata = FlacMetadata::read_from_path(file_path)?;
+    metadata.set_vorbis_comments(comments);
+    metadata.write_to_path(file_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal FLAC file: marker, STREAMINFO, padding and a few fake frame bytes
+    fn write_test_flac(name: &str, padding: usize) -> String {
+        let path = std::env::temp_dir().join(name).to_string_lossy().to_string();
+        let mut streaminfo = vec![0u8; 34];
+        // 44100 Hz, 2 channels, 16 bit, 441000 samples (10 seconds)
+        streaminfo[10] = 0x0a;
+        streaminfo[11] = 0xc4;
+        streaminfo[12] = 0x42;
+        streaminfo[13] = 0xf0;
+        streaminfo[14..18].copy_from_slice(&441000u32.to_be_bytes());
+
+        let info = MetadataBlock { block_type: BLOCK_STREAMINFO, data: streaminfo };
+        let mut bytes = b"fLaC".to_vec();
+        bytes.extend_from_slice(&encode_blocks(&[&info], Some(padding)));
+        bytes.extend_from_slice(&[0xff, 0xf8, 0x01, 0x02, 0x03]);
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn writes_comments_in_place_and_by_rewrite() {
+        for (name, padding) in [("ligeia_flac_inplace.flac", 1024), ("ligeia_flac_rewrite.flac", 8)] {
+            let path = write_test_flac(name, padding);
+            let comments = VorbisComments {
+                vendor: "ligeia".to_string(),
+                comments: vec![("TITLE".to_string(), "Thunder".to_string())],
+            };
+            write_comments(&path, &comments).unwrap();
+
+            let metadata = FlacMetadata::read_from_path(&path).unwrap();
+            assert_eq!(metadata.vorbis_comments().unwrap(), Some(comments));
+            assert_eq!(metadata.duration(), Some(10.0));
+
+            let bytes = std::fs::read(&path).unwrap();
+            assert_eq!(&bytes[bytes.len() - 5..], &[0xff, 0xf8, 0x01, 0x02, 0x03]);
+            std::fs::remove_file(&path).ok();
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use crate::models::{AudioFile, AudioFilePatch, FieldPatch};
+use id3::frame::{Content, InvolvedPeopleList, InvolvedPeopleListItem};
+use id3::{Frame, Tag, TagLike};
+
+/// Credit fields of `AudioFile` that live in involved-people lists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CreditRole {
+    Arranger,
+    Engineer,
+    Producer,
+    DjMixer,
+    Mixer,
+}
+
+impl CreditRole {
+    pub const ALL: [CreditRole; 5] = [
+        CreditRole::Arranger,
+        CreditRole::Engineer,
+        CreditRole::Producer,
+        CreditRole::DjMixer,
+        CreditRole::Mixer,
+    ];
+
+    /// Map a TIPL/TMCL/IPLS involvement to a credit field
+    pub fn from_involvement(involvement: &str) -> Option<Self> {
+        let role = involvement.trim().to_lowercase();
+        match role.as_str() {
+            "arranger" | "arrangement" | "arranged by" => Some(CreditRole::Arranger),
+            "dj-mix" | "dj mix" | "djmix" | "dj-mixer" | "dj mixer" | "djmixer" => Some(CreditRole::DjMixer),
+            "mix" | "mixer" | "mixing" | "mixed by" | "mix engineer" => Some(CreditRole::Mixer),
+            _ if role.ends_with("engineer") => Some(CreditRole::Engineer),
+            _ if role.ends_with("producer") || role == "produced by" => Some(CreditRole::Producer),
+            _ => None,
+        }
+    }
+
+    /// Involvement written for new entries (the names Picard and foobar2000 use)
+    pub fn involvement(self) -> &'static str {
+        match self {
+            CreditRole::Arranger => "arranger",
+            CreditRole::Engineer => "engineer",
+            CreditRole::Producer => "producer",
+            CreditRole::DjMixer => "DJ-mix",
+            CreditRole::Mixer => "mix",
+        }
+    }
+
+    pub fn patch(self, patch: &AudioFilePatch) -> &FieldPatch<String> {
+        match self {
+            CreditRole::Arranger => &patch.arranger,
+            CreditRole::Engineer => &patch.engineer,
+            CreditRole::Producer => &patch.producer,
+            CreditRole::DjMixer => &patch.dj_mixer,
+            CreditRole::Mixer => &patch.mixer,
+        }
+    }
+
+    pub fn field_mut(self, audio_file: &mut AudioFile) -> &mut Option<String> {
+        match self {
+            CreditRole::Arranger => &mut audio_file.arranger,
+            CreditRole::Engineer => &mut audio_file.engineer,
+            CreditRole::Producer => &mut audio_file.producer,
+            CreditRole::DjMixer => &mut audio_file.dj_mixer,
+            CreditRole::Mixer => &mut audio_file.mixer,
+        }
+    }
+}
+
+/// Split a "; " joined credit field into individual names
+pub fn split_names(value: &str) -> Vec<String> {
+    value
+        .split(';')
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Role/name pairs from every TIPL, TMCL and IPLS frame of a tag
+fn involved_people(tag: &Tag) -> Vec<InvolvedPeopleListItem> {
+    tag.frames()
+        .filter(|frame| matches!(frame.id(), "TIPL" | "TMCL" | "IPLS"))
+        .filter_map(|frame| frame.content().involved_people_list())
+        .flat_map(|list| list.items.iter().cloned())
+        .collect()
+}
+
+/// Fill arranger/engineer/producer/dj_mixer/mixer from the involved-people lists,
+/// joining multiple people with "; "
+pub fn apply_to_audio_file(tag: &Tag, audio_file: &mut AudioFile) {
+    let items = involved_people(tag);
+    for role in CreditRole::ALL {
+        let mut names: Vec<&str> = Vec::new();
+        for item in &items {
+            let name = item.involvee.trim();
+            if CreditRole::from_involvement(&item.involvement) == Some(role) && !name.is_empty() && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        if !names.is_empty() {
+            *role.field_mut(audio_file) = Some(names.join("; "));
+        }
+    }
+}
+
+/// Rewrite the TIPL frame for the credit fields that `updates` sets or clears
+///
+/// Entries of roles that are left alone, and roles we don't map, are kept.
+/// A person who keeps a role also keeps its original involvement text
+/// (e.g. "mastering engineer"). ID3v2.3 IPLS entries are merged into TIPL.
+pub fn update_tag(tag: &mut Tag, updates: &AudioFilePatch) {
+    if CreditRole::ALL.iter().all(|role| role.patch(updates).is_leave()) {
+        return;
+    }
+
+    let existing: Vec<InvolvedPeopleListItem> = tag.frames()
+        .filter(|frame| matches!(frame.id(), "TIPL" | "IPLS"))
+        .filter_map(|frame| frame.content().involved_people_list())
+        .flat_map(|list| list.items.iter().cloned())
+        .collect();
+
+    let mut items: Vec<InvolvedPeopleListItem> = existing.iter()
+        .filter(|item| match CreditRole::from_involvement(&item.involvement) {
+            Some(role) => role.patch(updates).is_leave(),
+            None => true,
+        })
+        .cloned()
+        .collect();
+
+    for role in CreditRole::ALL {
+        let FieldPatch::Set(value) = role.patch(updates) else { continue };
+        for name in split_names(value) {
+            let involvement = existing.iter()
+                .find(|item| item.involvee.trim() == name && CreditRole::from_involvement(&item.involvement) == Some(role))
+                .map(|item| item.involvement.clone())
+                .unwrap_or_else(|| role.involvement().to_string());
+            items.push(InvolvedPeopleListItem { involvement, involvee: name });
+        }
+    }
+
+    tag.remove("IPLS");
+    tag.remove("TIPL");
+    if !items.is_empty() {
+        tag.add_frame(Frame::with_content("TIPL", Content::InvolvedPeopleList(InvolvedPeopleList { items })));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn people_frame(id: &str, pairs: &[(&str, &str)]) -> Frame {
+        let items = pairs.iter()
+            .map(|(involvement, involvee)| InvolvedPeopleListItem {
+                involvement: involvement.to_string(),
+                involvee: involvee.to_string(),
+            })
+            .collect();
+        Frame::with_content(id, Content::InvolvedPeopleList(InvolvedPeopleList { items }))
+    }
+
+    #[test]
+    fn parses_roles_with_multiple_people() {
+        let mut tag = Tag::new();
+        tag.add_frame(people_frame("TIPL", &[
+            ("producer", "Ann Lee"),
+            ("mastering engineer", "Bo Chen"),
+            ("executive producer", "Cy Diaz"),
+            ("DJ-mix", "DJ Eve"),
+            ("composer's assistant", "Fay Gold"),
+        ]));
+        tag.add_frame(people_frame("TMCL", &[("arranger", "Hal Ito"), ("violin", "Ivy Jones")]));
+
+        let mut audio_file = AudioFile::default();
+        apply_to_audio_file(&tag, &mut audio_file);
+        assert_eq!(audio_file.producer.as_deref(), Some("Ann Lee; Cy Diaz"));
+        assert_eq!(audio_file.engineer.as_deref(), Some("Bo Chen"));
+        assert_eq!(audio_file.dj_mixer.as_deref(), Some("DJ Eve"));
+        assert_eq!(audio_file.arranger.as_deref(), Some("Hal Ito"));
+        assert_eq!(audio_file.mixer, None);
+    }
+
+    #[test]
+    fn round_trips_through_tipl() {
+        let mut tag = Tag::new();
+        tag.add_frame(people_frame("IPLS", &[("mastering engineer", "Bo Chen"), ("lyrics translation", "Kim Lo")]));
+        tag.add_frame(people_frame("TIPL", &[("producer", "Ann Lee")]));
+
+        let updates = AudioFilePatch {
+            engineer: FieldPatch::Set("Bo Chen; Max Ng".to_string()),
+            mixer: FieldPatch::Set("Ola Park".to_string()),
+            ..Default::default()
+        };
+        update_tag(&mut tag, &updates);
+
+        let mut bytes = Vec::new();
+        tag.write_to(&mut bytes, id3::Version::Id3v24).unwrap();
+        let reread = Tag::read_from2(std::io::Cursor::new(bytes)).unwrap();
+        assert!(reread.get("IPLS").is_none());
+
+        let mut audio_file = AudioFile::default();
+        apply_to_audio_file(&reread, &mut audio_file);
+        assert_eq!(audio_file.engineer.as_deref(), Some("Bo Chen; Max Ng"));
+        assert_eq!(audio_file.producer.as_deref(), Some("Ann Lee"));
+        assert_eq!(audio_file.mixer.as_deref(), Some("Ola Park"));
+
+        let items = involved_people(&reread);
+        assert!(items.iter().any(|i| i.involvement == "mastering engineer" && i.involvee == "Bo Chen"));
+        assert!(items.iter().any(|i| i.involvement == "lyrics translation" && i.involvee == "Kim Lo"));
+
+        let mut tag = reread;
+        update_tag(&mut tag, &AudioFilePatch { producer: FieldPatch::Clear, ..Default::default() });
+        let mut audio_file = AudioFile::default();
+        apply_to_audio_file(&tag, &mut audio_file);
+        assert_eq!(audio_file.producer, None);
+        assert_eq!(audio_file.engineer.as_deref(), Some("Bo Chen; Max Ng"));
+    }
+}
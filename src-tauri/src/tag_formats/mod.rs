@@ -0,0 +1,133 @@
+pub mod vorbis;
+pub mod flac;
+pub mod ogg;
+pub mod mp4;
+pub mod wav;
+pub mod aiff;
+pub mod involved_people;
+pub mod picture;
+pub mod safe_write;
+
+use id3::Tag;
+use mp4::Mp4Tag;
+use std::io::Cursor;
+use std::path::Path;
+use vorbis::VorbisComments;
+
+/// Tag container used by an audio file, chosen by extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagFormat {
+    /// ID3v2 (MP3 and anything we don't have a native writer for)
+    Id3,
+    /// FLAC VORBIS_COMMENT metadata block
+    Flac,
+    /// Ogg Vorbis / Ogg Opus comment header
+    Ogg,
+    /// iTunes-style `ilst` atoms in MP4/M4A containers
+    Mp4,
+    /// RIFF INFO / BWF bext for reading, `id3 ` chunk for reading and writing
+    Wav,
+    /// Containers we can decode but neither read nor write tags in (CAF, Matroska/WebM); an ID3
+    /// header in front of them would break the file
+    Unsupported,
+}
+
+impl TagFormat {
+    pub fn from_path(file_path: &str) -> Self {
+        let extension = Path::new(file_path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            "flac" => TagFormat::Flac,
+            "ogg" | "oga" | "opus" => TagFormat::Ogg,
+            "m4a" | "m4b" | "m4p" | "mp4" => TagFormat::Mp4,
+            "wav" | "wave" | "bwf" => TagFormat::Wav,
+            "caf" | "webm" | "mka" | "mkv" => TagFormat::Unsupported,
+            _ => TagFormat::Id3,
+        }
+    }
+}
+
+/// Error for a file whose container has no tag writer
+pub fn unsupported_format(file_path: &str) -> String {
+    format!("Writing tags to this format is not supported: {}", file_path)
+}
+
+/// Read the ID3 tag of a file (the `id3 ` chunk for WAV files)
+pub fn read_id3_tag(file_path: &str) -> Option<Tag> {
+    match TagFormat::from_path(file_path) {
+        TagFormat::Wav => wav::read_id3_tag(file_path).ok().flatten(),
+        TagFormat::Unsupported => None,
+        _ => Tag::read_from_path(file_path).ok(),
+    }
+}
+
+/// Write an ID3v2.4 tag to a file (the `id3 ` chunk for WAV files).
+/// The file is replaced atomically once the tag reads back as written.
+pub fn write_id3_tag(file_path: &str, tag: &Tag) -> Result<(), String> {
+    if TagFormat::from_path(file_path) == TagFormat::Unsupported {
+        return Err(unsupported_format(file_path));
+    }
+    let mut tag_bytes = Vec::new();
+    tag.write_to(&mut tag_bytes, id3::Version::Id3v24)
+        .map_err(|e| format!("Failed to encode ID3 tag: {}", e))?;
+    let expected = Tag::read_from2(Cursor::new(tag_bytes)).unwrap_or_default();
+
+    safe_write::write_verified(
+        file_path,
+        |temp| match TagFormat::from_path(temp) {
+            TagFormat::Wav => wav::write_id3_tag(temp, tag),
+            _ => tag.write_to_path(temp, id3::Version::Id3v24).map_err(|e| e.to_string()),
+        },
+        |temp| safe_write::check_reread(&expected, &read_id3_tag(temp).unwrap_or_default()),
+    )
+}
+
+/// Read Vorbis comments from a FLAC or OGG file
+pub fn read_vorbis_comments(file_path: &str) -> Result<Option<VorbisComments>, String> {
+    match TagFormat::from_path(file_path) {
+        TagFormat::Flac => flac::read_comments(file_path),
+        TagFormat::Ogg => ogg::read_comments(file_path),
+        TagFormat::Id3 | TagFormat::Mp4 | TagFormat::Wav | TagFormat::Unsupported => Err(format!("File does not use Vorbis comments: {}", file_path)),
+    }
+}
+
+/// Write Vorbis comments to a FLAC or OGG file.
+/// The file is replaced atomically once the comments read back as written.
+pub fn write_vorbis_comments(file_path: &str, comments: &VorbisComments) -> Result<(), String> {
+    let write: fn(&str, &VorbisComments) -> Result<(), String> = match TagFormat::from_path(file_path) {
+        TagFormat::Flac => flac::write_comments,
+        TagFormat::Ogg => ogg::write_comments,
+        TagFormat::Id3 | TagFormat::Mp4 | TagFormat::Wav | TagFormat::Unsupported => return Err(format!("File does not use Vorbis comments: {}", file_path)),
+    };
+    let (expected, _) = VorbisComments::parse(&comments.to_bytes())?;
+
+    safe_write::write_verified(
+        file_path,
+        |temp| write(temp, comments),
+        |temp| safe_write::check_reread(&Some(expected), &read_vorbis_comments(temp)?),
+    )
+}
+
+/// Write the iTunes metadata of an MP4/M4A file.
+/// The file is replaced atomically once the tag reads back as written.
+pub fn write_mp4_tag(file_path: &str, tag: &Mp4Tag) -> Result<(), String> {
+    let expected = mp4::decode_tag(&mp4::encode_tag(tag))?;
+
+    safe_write::write_verified(
+        file_path,
+        |temp| mp4::write_tag(temp, tag),
+        |temp| safe_write::check_reread(&Some(expected), &mp4::read_tag(temp)?),
+    )
+}
+
+/// Existing Vorbis comments of a file, or an empty block when it has none yet
+pub fn read_or_new_vorbis_comments(file_path: &str) -> Result<VorbisComments, String> {
+    Ok(read_vorbis_comments(file_path)?.unwrap_or_else(|| VorbisComments {
+        vendor: "Ligeia".to_string(),
+        comments: Vec::new(),
+    }))
+}
@@ -0,0 +1,884 @@
+use crate::models::{AudioFile, AudioFilePatch, FieldPatch};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+
+/// `data` atom type codes (well-known types from the QuickTime spec)
+pub const TYPE_IMPLICIT: u32 = 0;
+pub const TYPE_UTF8: u32 = 1;
+pub const TYPE_BE_SIGNED: u32 = 21;
+
+/// Mean used by iTunes (and most taggers) for freeform `----` atoms
+pub const ITUNES_MEAN: &str = "com.apple.iTunes";
+
+/// Identifier of an `ilst` item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mp4Key {
+    /// Standard four character atom such as `©nam` or `tmpo`
+    Atom([u8; 4]),
+    /// Freeform `----` atom identified by mean and name
+    Freeform { mean: String, name: String },
+}
+
+/// Value held in a `data` atom
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mp4Data {
+    pub type_code: u32,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mp4Item {
+    pub key: Mp4Key,
+    pub values: Vec<Mp4Data>,
+}
+
+/// iTunes-style metadata list (`moov/udta/meta/ilst`)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mp4Tag {
+    pub items: Vec<Mp4Item>,
+}
+
+impl Mp4Tag {
+    fn item(&self, key: &Mp4Key) -> Option<&Mp4Item> {
+        self.items.iter().find(|item| key_matches(&item.key, key))
+    }
+
+    /// Replace the values of an item, keeping its position (or appending it)
+    pub fn set(&mut self, key: Mp4Key, values: Vec<Mp4Data>) {
+        match self.items.iter_mut().find(|item| key_matches(&item.key, &key)) {
+            Some(item) => item.values = values,
+            None => self.items.push(Mp4Item { key, values }),
+        }
+    }
+
+    pub fn remove(&mut self, key: &Mp4Key) {
+        self.items.retain(|item| !key_matches(&item.key, key));
+    }
+
+    /// First UTF-8 value of a standard atom
+    pub fn text(&self, ident: &[u8; 4]) -> Option<String> {
+        self.item(&Mp4Key::Atom(*ident))?
+            .values
+            .iter()
+            .find(|v| v.type_code == TYPE_UTF8)
+            .map(|v| String::from_utf8_lossy(&v.bytes).to_string())
+    }
+
+    pub fn set_text(&mut self, ident: &[u8; 4], value: &str) {
+        self.set(Mp4Key::Atom(*ident), vec![utf8_data(value)]);
+    }
+
+    /// Big-endian integer value of a standard atom (e.g. `tmpo`)
+    pub fn integer(&self, ident: &[u8; 4]) -> Option<u32> {
+        let data = self.item(&Mp4Key::Atom(*ident))?.values.first()?;
+        if data.bytes.is_empty() || data.bytes.len() > 4 {
+            return None;
+        }
+        Some(data.bytes.iter().fold(0u32, |acc, &b| (acc << 8) | b as u32))
+    }
+
+    pub fn set_integer(&mut self, ident: &[u8; 4], value: u16) {
+        self.set(
+            Mp4Key::Atom(*ident),
+            vec![Mp4Data { type_code: TYPE_BE_SIGNED, bytes: value.to_be_bytes().to_vec() }],
+        );
+    }
+
+    /// Number and total from `trkn`/`disk` atoms
+    pub fn number_pair(&self, ident: &[u8; 4]) -> (Option<u32>, Option<u32>) {
+        let data = match self.item(&Mp4Key::Atom(*ident)).and_then(|i| i.values.first()) {
+            Some(data) if data.bytes.len() >= 6 => data,
+            _ => return (None, None),
+        };
+        let number = u16::from_be_bytes([data.bytes[2], data.bytes[3]]) as u32;
+        let total = u16::from_be_bytes([data.bytes[4], data.bytes[5]]) as u32;
+        (Some(number).filter(|n| *n > 0), Some(total).filter(|t| *t > 0))
+    }
+
+    /// Set `trkn` (8 bytes) or `disk` (6 bytes); a missing part is stored as 0
+    pub fn set_number_pair(&mut self, ident: &[u8; 4], number: Option<u32>, total: Option<u32>) {
+        let number = number.unwrap_or(0) as u16;
+        let total = total.unwrap_or(0) as u16;
+
+        let mut bytes = vec![0, 0];
+        bytes.extend_from_slice(&number.to_be_bytes());
+        bytes.extend_from_slice(&total.to_be_bytes());
+        if ident == b"trkn" {
+            bytes.extend_from_slice(&[0, 0]);
+        }
+        self.set(Mp4Key::Atom(*ident), vec![Mp4Data { type_code: TYPE_IMPLICIT, bytes }]);
+    }
+
+    /// All UTF-8 values of an iTunes freeform atom (name is case-insensitive)
+    pub fn freeform(&self, name: &str) -> Vec<String> {
+        self.item(&freeform_key(name))
+            .map(|item| {
+                item.values
+                    .iter()
+                    .filter(|v| v.type_code == TYPE_UTF8)
+                    .map(|v| String::from_utf8_lossy(&v.bytes).to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Freeform values split on ';' (RPG tags are stored "a; b" like TXXX frames)
+    pub fn freeform_list(&self, name: &str) -> Vec<String> {
+        self.freeform(name)
+            .iter()
+            .flat_map(|v| v.split(';'))
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .map(|v| v.to_string())
+            .collect()
+    }
+
+    pub fn set_freeform(&mut self, name: &str, value: &str) {
+        self.set(freeform_key(name), vec![utf8_data(value)]);
+    }
+
+    pub fn remove_freeform(&mut self, name: &str) {
+        self.remove(&freeform_key(name));
+    }
+}
+
+fn utf8_data(value: &str) -> Mp4Data {
+    Mp4Data { type_code: TYPE_UTF8, bytes: value.as_bytes().to_vec() }
+}
+
+fn freeform_key(name: &str) -> Mp4Key {
+    Mp4Key::Freeform { mean: ITUNES_MEAN.to_string(), name: name.to_string() }
+}
+
+/// Freeform names are matched case-insensitively, since taggers disagree on casing
+fn key_matches(a: &Mp4Key, b: &Mp4Key) -> bool {
+    match (a, b) {
+        (Mp4Key::Atom(x), Mp4Key::Atom(y)) => x == y,
+        (Mp4Key::Freeform { mean: m1, name: n1 }, Mp4Key::Freeform { mean: m2, name: n2 }) => {
+            m1 == m2 && n1.eq_ignore_ascii_case(n2)
+        }
+        _ => false,
+    }
+}
+
+/// Location of an atom inside a byte buffer
+#[derive(Debug, Clone, Copy)]
+struct Atom {
+    kind: [u8; 4],
+    start: usize,
+    header_len: usize,
+    end: usize,
+}
+
+impl Atom {
+    fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    fn content_start(&self) -> usize {
+        self.start + self.header_len
+    }
+}
+
+/// Parse the sibling atoms in `data[start..end]`
+fn parse_atoms(data: &[u8], start: usize, end: usize) -> Result<Vec<Atom>, String> {
+    let mut atoms = Vec::new();
+    let mut pos = start;
+
+    while pos + 8 <= end {
+        let size = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as u64;
+        let kind: [u8; 4] = data[pos + 4..pos + 8].try_into().unwrap();
+
+        let (header_len, atom_len) = match size {
+            0 => (8, (end - pos) as u64),
+            1 => {
+                let large = data.get(pos + 8..pos + 16).ok_or("Truncated MP4 atom header")?;
+                (16, u64::from_be_bytes(large.try_into().unwrap()))
+            }
+            _ => (8, size),
+        };
+
+        let atom_end = (pos as u64).checked_add(atom_len).filter(|&atom_end| atom_end <= end as u64);
+        if atom_len < header_len as u64 || atom_end.is_none() {
+            return Err(format!("Invalid MP4 atom size for '{}'", String::from_utf8_lossy(&kind)));
+        }
+
+        atoms.push(Atom { kind, start: pos, header_len, end: pos + atom_len as usize });
+        pos += atom_len as usize;
+    }
+
+    Ok(atoms)
+}
+
+fn find_child(data: &[u8], parent: &Atom, offset: usize, kind: &[u8; 4]) -> Result<Option<Atom>, String> {
+    let children = parse_atoms(data, parent.content_start() + offset, parent.end)?;
+    Ok(children.into_iter().find(|a| &a.kind == kind))
+}
+
+/// `meta` is a full atom in MP4 files, but a plain container in some QuickTime files
+fn meta_offset(data: &[u8], meta: &Atom) -> usize {
+    let start = meta.content_start();
+    match data.get(start + 4..start + 8) {
+        Some(kind) if kind == b"hdlr" => 0,
+        _ => 4,
+    }
+}
+
+fn encode_atom(kind: &[u8], content: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(8 + content.len());
+    out.extend_from_slice(&((8 + content.len()) as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(content);
+    out
+}
+
+fn parse_ilst(data: &[u8], ilst: &Atom) -> Result<Mp4Tag, String> {
+    let mut tag = Mp4Tag::default();
+
+    for item_atom in parse_atoms(data, ilst.content_start(), ilst.end)? {
+        let mut mean = None;
+        let mut name = None;
+        let mut values = Vec::new();
+
+        for child in parse_atoms(data, item_atom.content_start(), item_atom.end)? {
+            let content = &data[child.content_start()..child.end];
+            match &child.kind {
+                b"data" if content.len() >= 8 => values.push(Mp4Data {
+                    type_code: u32::from_be_bytes(content[0..4].try_into().unwrap()) & 0x00ff_ffff,
+                    bytes: content[8..].to_vec(),
+                }),
+                b"mean" if content.len() >= 4 => mean = Some(String::from_utf8_lossy(&content[4..]).to_string()),
+                b"name" if content.len() >= 4 => name = Some(String::from_utf8_lossy(&content[4..]).to_string()),
+                _ => {}
+            }
+        }
+
+        let key = if &item_atom.kind == b"----" {
+            match (mean, name) {
+                (Some(mean), Some(name)) => Mp4Key::Freeform { mean, name },
+                _ => continue, // Malformed freeform atom
+            }
+        } else {
+            Mp4Key::Atom(item_atom.kind)
+        };
+
+        tag.items.push(Mp4Item { key, values });
+    }
+
+    Ok(tag)
+}
+
+fn encode_ilst(tag: &Mp4Tag) -> Vec<u8> {
+    let mut content = Vec::new();
+
+    for item in &tag.items {
+        if item.values.is_empty() {
+            continue;
+        }
+
+        let mut item_content = Vec::new();
+        if let Mp4Key::Freeform { mean, name } = &item.key {
+            item_content.extend(encode_atom(b"mean", &[&[0u8; 4][..], mean.as_bytes()].concat()));
+            item_content.extend(encode_atom(b"name", &[&[0u8; 4][..], name.as_bytes()].concat()));
+        }
+        for value in &item.values {
+            let mut data = value.type_code.to_be_bytes().to_vec();
+            data.extend_from_slice(&[0, 0, 0, 0]); // locale
+            data.extend_from_slice(&value.bytes);
+            item_content.extend(encode_atom(b"data", &data));
+        }
+
+        let kind = match &item.key {
+            Mp4Key::Atom(kind) => kind,
+            Mp4Key::Freeform { .. } => b"----",
+        };
+        content.extend(encode_atom(kind, &item_content));
+    }
+
+    encode_atom(b"ilst", &content)
+}
+
+/// Handler atom required inside `meta` for iTunes metadata
+fn metadata_handler() -> Vec<u8> {
+    let mut content = vec![0u8; 8]; // version/flags, pre-defined
+    content.extend_from_slice(b"mdir");
+    content.extend_from_slice(b"appl");
+    content.extend_from_slice(&[0u8; 9]); // reserved and empty name
+    encode_atom(b"hdlr", &content)
+}
+
+/// Re-encode `parent` with its first `kind` child replaced by `new_child` (or appended)
+fn replace_child(data: &[u8], parent: &Atom, offset: usize, kind: &[u8; 4], new_child: &[u8]) -> Result<Vec<u8>, String> {
+    let content_start = parent.content_start();
+    let mut content = data[content_start..content_start + offset].to_vec();
+    let mut replaced = false;
+
+    for child in parse_atoms(data, content_start + offset, parent.end)? {
+        if !replaced && &child.kind == kind {
+            content.extend_from_slice(new_child);
+            replaced = true;
+        } else {
+            content.extend_from_slice(&data[child.start..child.end]);
+        }
+    }
+    if !replaced {
+        content.extend_from_slice(new_child);
+    }
+
+    Ok(encode_atom(&parent.kind, &content))
+}
+
+/// Read the `moov` atom of a file without loading the media data
+fn read_moov(file_path: &str) -> Result<Option<Vec<u8>>, String> {
+    let mut file = File::open(file_path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let file_len = file.metadata().map_err(|e| e.to_string())?.len();
+    let mut pos = 0u64;
+
+    while pos + 8 <= file_len {
+        let mut header = [0u8; 16];
+        file.seek(SeekFrom::Start(pos)).map_err(|e| e.to_string())?;
+        file.read_exact(&mut header[..8]).map_err(|e| format!("Failed to read MP4 atom: {}", e))?;
+
+        let size = u32::from_be_bytes(header[0..4].try_into().unwrap()) as u64;
+        let atom_len = match size {
+            0 => file_len - pos,
+            1 => {
+                file.read_exact(&mut header[8..16]).map_err(|e| format!("Failed to read MP4 atom: {}", e))?;
+                u64::from_be_bytes(header[8..16].try_into().unwrap())
+            }
+            _ => size,
+        };
+        if atom_len < 8 || pos.checked_add(atom_len).is_none_or(|atom_end| atom_end > file_len) {
+            return Err("Invalid MP4 atom size".to_string());
+        }
+
+        if &header[4..8] == b"moov" {
+            let mut moov = vec![0u8; atom_len as usize];
+            file.seek(SeekFrom::Start(pos)).map_err(|e| e.to_string())?;
+            file.read_exact(&mut moov).map_err(|e| format!("Failed to read moov atom: {}", e))?;
+            return Ok(Some(moov));
+        }
+        pos += atom_len;
+    }
+
+    Ok(None)
+}
+
+fn find_ilst(moov: &[u8]) -> Result<Option<Atom>, String> {
+    let moov_atom = match parse_atoms(moov, 0, moov.len())?.into_iter().next() {
+        Some(atom) => atom,
+        None => return Ok(None),
+    };
+    let udta = match find_child(moov, &moov_atom, 0, b"udta")? {
+        Some(atom) => atom,
+        None => return Ok(None),
+    };
+    let meta = match find_child(moov, &udta, 0, b"meta")? {
+        Some(atom) => atom,
+        None => return Ok(None),
+    };
+    find_child(moov, &meta, meta_offset(moov, &meta), b"ilst")
+}
+
+/// Read the iTunes metadata list of an MP4/M4A file
+pub fn read_tag(file_path: &str) -> Result<Option<Mp4Tag>, String> {
+    let moov = read_moov(file_path)?.ok_or("Not an MP4 file (no moov atom)")?;
+    match find_ilst(&moov)? {
+        Some(ilst) => parse_ilst(&moov, &ilst).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Serialize a tag to a standalone `ilst` atom, as it is written into files
+pub fn encode_tag(tag: &Mp4Tag) -> Vec<u8> {
+    encode_ilst(tag)
+}
+
+/// Parse a standalone `ilst` atom produced by `encode_tag`
+pub fn decode_tag(data: &[u8]) -> Result<Mp4Tag, String> {
+    let atoms = parse_atoms(data, 0, data.len())?;
+    let ilst = atoms.iter().find(|a| &a.kind == b"ilst").ok_or("No ilst atom")?;
+    parse_ilst(data, ilst)
+}
+
+/// Movie duration in seconds from `mvhd`
+pub fn read_duration(file_path: &str) -> Option<f64> {
+    let moov = read_moov(file_path).ok()??;
+    let moov_atom = *parse_atoms(&moov, 0, moov.len()).ok()?.first()?;
+    let mvhd = find_child(&moov, &moov_atom, 0, b"mvhd").ok()??;
+    let content = &moov[mvhd.content_start()..mvhd.end];
+
+    let (timescale, duration) = match content.first()? {
+        1 => (
+            u32::from_be_bytes(content.get(20..24)?.try_into().ok()?) as u64,
+            u64::from_be_bytes(content.get(24..32)?.try_into().ok()?),
+        ),
+        _ => (
+            u32::from_be_bytes(content.get(12..16)?.try_into().ok()?) as u64,
+            u32::from_be_bytes(content.get(16..20)?.try_into().ok()?) as u64,
+        ),
+    };
+
+    if timescale == 0 || duration == 0 {
+        None
+    } else {
+        Some(duration as f64 / timescale as f64)
+    }
+}
+
+/// Write the iTunes metadata list, shifting chunk offsets when `moov` changes size
+pub fn write_tag(file_path: &str, tag: &Mp4Tag) -> Result<(), String> {
+    let data = std::fs::read(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let top = parse_atoms(&data, 0, data.len())?;
+    let moov_index = top.iter().position(|a| &a.kind == b"moov").ok_or("Not an MP4 file (no moov atom)")?;
+    let moov = top[moov_index];
+    // Fragments point into the file by absolute offset too, and aren't rewritten here
+    if top.iter().any(|a| &a.kind == b"moof" || &a.kind == b"mfra") {
+        return Err("Fragmented MP4 files are not supported for writing".to_string());
+    }
+
+    // Rebuild moov/udta/meta/ilst bottom-up, creating missing containers
+    let ilst = encode_ilst(tag);
+    let udta = find_child(&data, &moov, 0, b"udta")?;
+    let meta = match &udta {
+        Some(udta) => find_child(&data, udta, 0, b"meta")?,
+        None => None,
+    };
+    let new_meta = match &meta {
+        Some(meta) => replace_child(&data, meta, meta_offset(&data, meta), b"ilst", &ilst)?,
+        None => encode_atom(b"meta", &[vec![0u8; 4], metadata_handler(), ilst].concat()),
+    };
+    let new_udta = match &udta {
+        Some(udta) => replace_child(&data, udta, 0, b"meta", &new_meta)?,
+        None => encode_atom(b"udta", &new_meta),
+    };
+    let mut new_moov = replace_child(&data, &moov, 0, b"udta", &new_udta)?;
+
+    let delta = new_moov.len() as i64 - moov.len() as i64;
+    let media_follows = top[moov_index + 1..].iter().any(|a| &a.kind == b"mdat");
+    let mut rest_start = moov.end;
+    let mut filler = Vec::new();
+
+    if delta != 0 && media_follows {
+        // Prefer absorbing the size change into a following free atom so media offsets stay valid
+        let free = top.get(moov_index + 1).filter(|a| &a.kind == b"free" || &a.kind == b"skip");
+        let absorbed = free.and_then(|free| {
+            let remaining = free.len() as i64 - delta;
+            if remaining == 0 || remaining >= 8 {
+                Some((free, remaining as usize))
+            } else {
+                None
+            }
+        });
+
+        match absorbed {
+            Some((free, remaining)) => {
+                if remaining > 0 {
+                    filler = encode_atom(b"free", &vec![0u8; remaining - 8]);
+                }
+                rest_start = free.end;
+            }
+            None => shift_chunk_offsets(&mut new_moov, moov.start as u64, delta)?,
+        }
+    }
+
+    let mut output = Vec::with_capacity(data.len() + new_moov.len());
+    output.extend_from_slice(&data[..moov.start]);
+    output.extend_from_slice(&new_moov);
+    output.extend_from_slice(&filler);
+    output.extend_from_slice(&data[rest_start..]);
+
+    std::fs::write(file_path, output).map_err(|e| format!("Failed to write file: {}", e))
+}
+
+/// Adjust every `stco`/`co64` entry pointing past `after` by `delta`
+fn shift_chunk_offsets(moov: &mut [u8], after: u64, delta: i64) -> Result<(), String> {
+    let mut tables = Vec::new();
+    collect_offset_tables(moov, 0, moov.len(), &mut tables)?;
+
+    for table in tables {
+        let content = table.content_start();
+        let count = u32::from_be_bytes(moov[content + 4..content + 8].try_into().unwrap()) as usize;
+        let entry_size = if &table.kind == b"co64" { 8 } else { 4 };
+
+        for i in 0..count {
+            let pos = content + 8 + i * entry_size;
+            if pos + entry_size > table.end {
+                return Err("Truncated MP4 chunk offset table".to_string());
+            }
+            let offset = if entry_size == 8 {
+                u64::from_be_bytes(moov[pos..pos + 8].try_into().unwrap())
+            } else {
+                u32::from_be_bytes(moov[pos..pos + 4].try_into().unwrap()) as u64
+            };
+            if offset < after {
+                continue;
+            }
+
+            let shifted = offset as i64 + delta;
+            if entry_size == 8 {
+                moov[pos..pos + 8].copy_from_slice(&(shifted as u64).to_be_bytes());
+            } else {
+                let shifted = u32::try_from(shifted).map_err(|_| "MP4 chunk offset overflow")?;
+                moov[pos..pos + 4].copy_from_slice(&shifted.to_be_bytes());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn collect_offset_tables(data: &[u8], start: usize, end: usize, tables: &mut Vec<Atom>) -> Result<(), String> {
+    for atom in parse_atoms(data, start, end)? {
+        match &atom.kind {
+            b"moov" | b"trak" | b"mdia" | b"minf" | b"stbl" => {
+                collect_offset_tables(data, atom.content_start(), atom.end, tables)?
+            }
+            b"stco" | b"co64" if atom.len() >= atom.header_len + 8 => tables.push(atom),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Fill an AudioFile from iTunes metadata
+pub fn apply_to_audio_file(tag: &Mp4Tag, audio_file: &mut AudioFile) {
+    let freeform = |name: &str| {
+        let values = tag.freeform(name);
+        if values.is_empty() { None } else { Some(values.join("; ")) }
+    };
+
+    // Basic tags
+    audio_file.title = tag.text(b"\xa9nam");
+    audio_file.artist = tag.text(b"\xa9ART");
+    audio_file.album = tag.text(b"\xa9alb");
+    audio_file.album_artist = tag.text(b"aART");
+    audio_file.genre = tag.text(b"\xa9gen");
+    audio_file.date = tag.text(b"\xa9day");
+    audio_file.year = audio_file.date.as_ref().and_then(|d| d.get(0..4).and_then(|y| y.parse().ok()));
+    (audio_file.track_number, audio_file.total_tracks) = tag.number_pair(b"trkn");
+    (audio_file.disc_number, audio_file.total_discs) = tag.number_pair(b"disk");
+
+    // Extended tags
+    audio_file.composer = tag.text(b"\xa9wrt");
+    audio_file.conductor = freeform("CONDUCTOR");
+    audio_file.lyricist = freeform("LYRICIST");
+    audio_file.original_artist = freeform("ORIGINALARTIST");
+    audio_file.remixer = freeform("REMIXER");
+    audio_file.arranger = freeform("ARRANGER");
+    audio_file.engineer = freeform("ENGINEER");
+    audio_file.producer = freeform("PRODUCER");
+    audio_file.dj_mixer = freeform("DJMIXER");
+    audio_file.mixer = freeform("MIXER");
+
+    // Content tags
+    audio_file.content_group = tag.text(b"\xa9grp");
+    audio_file.subtitle = freeform("SUBTITLE");
+    audio_file.initial_key = freeform("initialkey");
+    audio_file.bpm = tag.integer(b"tmpo").filter(|b| *b > 0);
+    audio_file.language = freeform("LANGUAGE");
+    audio_file.media_type = freeform("MEDIA");
+    audio_file.original_filename = freeform("ORIGINALFILENAME");
+    audio_file.original_lyricist = freeform("ORIGINALLYRICIST");
+    audio_file.original_release_time = freeform("ORIGINALDATE");
+    audio_file.playlist_delay = freeform("PLAYLISTDELAY").and_then(|d| d.trim().parse().ok());
+
+    // Recording info
+    audio_file.recording_time = freeform("RECORDINGDATE");
+    audio_file.release_time = freeform("RELEASEDATE");
+    audio_file.tagging_time = freeform("TAGGINGDATE");
+    audio_file.encoding_time = freeform("ENCODINGTIME");
+    audio_file.encoding_settings = tag.text(b"\xa9too");
+    audio_file.encoded_by = freeform("ENCODEDBY");
+
+    // Copyright and legal
+    audio_file.copyright = tag.text(b"cprt");
+    audio_file.file_owner = freeform("OWNER");
+    audio_file.internet_radio_station_name = freeform("RADIOSTATION");
+    audio_file.internet_radio_station_owner = freeform("RADIOSTATIONOWNER");
+    audio_file.isrc = freeform("ISRC");
+    audio_file.publisher = freeform("LABEL");
+
+    // Additional metadata
+    audio_file.mood = freeform("MOOD");
+    audio_file.occasion = freeform("Occasion");
+    audio_file.tempo = freeform("TEMPO");
+    audio_file.content_type = freeform("CONTENTTYPE");
+    audio_file.category = freeform("CATEGORY");
+    audio_file.subcategory = freeform("SUBCATEGORY");
+}
+
+/// Apply a patch to iTunes metadata: set fields are replaced, cleared fields removed
+pub fn apply_patch(tag: &mut Mp4Tag, updates: &AudioFilePatch) {
+    let text_atoms: [(&[u8; 4], &FieldPatch<String>); 9] = [
+        (b"\xa9nam", &updates.title),
+        (b"\xa9ART", &updates.artist),
+        (b"\xa9alb", &updates.album),
+        (b"aART", &updates.album_artist),
+        (b"\xa9wrt", &updates.composer),
+        (b"\xa9grp", &updates.content_group),
+        (b"\xa9too", &updates.encoding_settings),
+        (b"cprt", &updates.copyright),
+        (b"\xa9gen", &updates.genre),
+    ];
+    for (ident, value) in text_atoms {
+        match value {
+            FieldPatch::Set(value) => tag.set_text(ident, value),
+            FieldPatch::Clear => tag.remove(&Mp4Key::Atom(*ident)),
+            FieldPatch::Leave => {}
+        }
+    }
+    if !updates.genre.is_leave() {
+        // Numeric ID3v1 genre would shadow the text genre in some players
+        tag.remove(&Mp4Key::Atom(*b"gnre"));
+    }
+
+    match updates.date.clone().or(updates.year.map(|y| y.to_string())) {
+        FieldPatch::Set(date) => tag.set_text(b"\xa9day", &date),
+        FieldPatch::Clear => tag.remove(&Mp4Key::Atom(*b"\xa9day")),
+        FieldPatch::Leave => {}
+    }
+    for (ident, number, total) in [
+        (b"trkn", &updates.track_number, &updates.total_tracks),
+        (b"disk", &updates.disc_number, &updates.total_discs),
+    ] {
+        if number.is_leave() && total.is_leave() {
+            continue;
+        }
+        let (old_number, old_total) = tag.number_pair(ident);
+        match (number.apply(old_number), total.apply(old_total)) {
+            (None, None) => tag.remove(&Mp4Key::Atom(*ident)),
+            (number, total) => tag.set_number_pair(ident, number, total),
+        }
+    }
+    match updates.bpm {
+        FieldPatch::Set(bpm) => tag.set_integer(b"tmpo", bpm.min(u16::MAX as u32) as u16),
+        FieldPatch::Clear => tag.remove(&Mp4Key::Atom(*b"tmpo")),
+        FieldPatch::Leave => {}
+    }
+
+    let playlist_delay = updates.playlist_delay.map(|d| d.to_string());
+    let freeform_fields: [(&str, &FieldPatch<String>); 33] = [
+        ("CONDUCTOR", &updates.conductor),
+        ("LYRICIST", &updates.lyricist),
+        ("ORIGINALARTIST", &updates.original_artist),
+        ("REMIXER", &updates.remixer),
+        ("ARRANGER", &updates.arranger),
+        ("ENGINEER", &updates.engineer),
+        ("PRODUCER", &updates.producer),
+        ("DJMIXER", &updates.dj_mixer),
+        ("MIXER", &updates.mixer),
+        ("SUBTITLE", &updates.subtitle),
+        ("initialkey", &updates.initial_key),
+        ("LANGUAGE", &updates.language),
+        ("MEDIA", &updates.media_type),
+        ("ORIGINALFILENAME", &updates.original_filename),
+        ("ORIGINALLYRICIST", &updates.original_lyricist),
+        ("ORIGINALDATE", &updates.original_release_time),
+        ("PLAYLISTDELAY", &playlist_delay),
+        ("RECORDINGDATE", &updates.recording_time),
+        ("RELEASEDATE", &updates.release_time),
+        ("TAGGINGDATE", &updates.tagging_time),
+        ("ENCODINGTIME", &updates.encoding_time),
+        ("ENCODEDBY", &updates.encoded_by),
+        ("OWNER", &updates.file_owner),
+        ("RADIOSTATION", &updates.internet_radio_station_name),
+        ("RADIOSTATIONOWNER", &updates.internet_radio_station_owner),
+        ("ISRC", &updates.isrc),
+        ("LABEL", &updates.publisher),
+        ("MOOD", &updates.mood),
+        ("Occasion", &updates.occasion),
+        ("TEMPO", &updates.tempo),
+        ("CONTENTTYPE", &updates.content_type),
+        ("CATEGORY", &updates.category),
+        ("SUBCATEGORY", &updates.subcategory),
+    ];
+    for (name, value) in freeform_fields {
+        match value {
+            FieldPatch::Set(value) => tag.set_freeform(name, value),
+            FieldPatch::Clear => tag.remove_freeform(name),
+            FieldPatch::Leave => {}
+        }
+    }
+}
+
+/// Read RPG tags from the Occasion/Keywords/Quality freeform atoms (and legacy RPG_* atoms)
+pub fn read_rpg_tags(tag: &Mp4Tag) -> Vec<(String, String)> {
+    let mut rpg_tags = Vec::new();
+
+    for occasion in tag.freeform_list("Occasion") {
+        rpg_tags.push(("occasion".to_string(), occasion));
+    }
+    for keyword in tag.freeform_list("Keywords") {
+        rpg_tags.push(("keyword".to_string(), keyword));
+    }
+    if let Some(quality) = tag.freeform("Quality").first().map(|q| q.trim()).filter(|q| !q.is_empty()) {
+        rpg_tags.push(("quality".to_string(), quality.to_string()));
+    }
+
+    for (name, tag_type) in [
+        ("RPG_GENRE", "genre"),
+        ("RPG_MOOD", "mood"),
+        ("RPG_OCCASION", "occasion"),
+        ("RPG_KEYWORDS", "keyword"),
+    ] {
+        for value in tag.freeform_list(name) {
+            let entry = (tag_type.to_string(), value);
+            if !rpg_tags.contains(&entry) {
+                rpg_tags.push(entry);
+            }
+        }
+    }
+
+    rpg_tags
+}
+
+/// Replace the Occasion/Keywords/Quality freeform atoms with the given grouped RPG tags
+pub fn write_rpg_tags(tag: &mut Mp4Tag, rpg_tags: &[(String, Vec<String>)]) {
+    let mut occasions = Vec::new();
+    let mut keywords = Vec::new();
+    let mut quality = None;
+
+    for (tag_type, tag_values) in rpg_tags {
+        match tag_type.as_str() {
+            "occasion" => occasions.extend(tag_values.clone()),
+            "keyword" | "keywords" => keywords.extend(tag_values.clone()),
+            "quality" => quality = tag_values.first().cloned(),
+            _ => {} // Skip other tag types
+        }
+    }
+
+    // Semicolon-separated, matching the TXXX frames written for MP3
+    for (name, values) in [("Occasion", occasions), ("Keywords", keywords)] {
+        if values.is_empty() {
+            tag.remove_freeform(name);
+        } else {
+            tag.set_freeform(name, &values.join("; "));
+        }
+    }
+    match quality {
+        Some(q) => tag.set_freeform("Quality", &q),
+        None => tag.remove_freeform("Quality"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ftyp + moov (mvhd, one track with an stco entry) + mdat, optionally with free space after moov
+    fn build_test_file(free: Option<usize>) -> (Vec<u8>, usize) {
+        let ftyp = encode_atom(b"ftyp", b"M4A \0\0\0\0M4A mp42isom");
+
+        let mut mvhd = vec![0u8; 100];
+        mvhd[12..16].copy_from_slice(&1000u32.to_be_bytes()); // timescale
+        mvhd[16..20].copy_from_slice(&2500u32.to_be_bytes()); // duration
+        let mvhd = encode_atom(b"mvhd", &mvhd);
+
+        let build_moov = |chunk_offset: u32| {
+            let mut stco = vec![0u8; 4];
+            stco.extend_from_slice(&1u32.to_be_bytes());
+            stco.extend_from_slice(&chunk_offset.to_be_bytes());
+            let stbl = encode_atom(b"stbl", &encode_atom(b"stco", &stco));
+            let trak = encode_atom(b"trak", &encode_atom(b"mdia", &encode_atom(b"minf", &stbl)));
+            encode_atom(b"moov", &[mvhd.clone(), trak].concat())
+        };
+
+        let free_atom = free.map(|size| encode_atom(b"free", &vec![0u8; size])).unwrap_or_default();
+        let moov_len = build_moov(0).len();
+        let media_offset = ftyp.len() + moov_len + free_atom.len() + 8;
+
+        let mut file = ftyp.clone();
+        file.extend(build_moov(media_offset as u32));
+        file.extend(free_atom);
+        file.extend(encode_atom(b"mdat", b"AUDIO-FRAMES"));
+        (file, media_offset)
+    }
+
+    fn chunk_offset(data: &[u8]) -> usize {
+        let mut tables = Vec::new();
+        let moov = parse_atoms(data, 0, data.len()).unwrap().into_iter().find(|a| &a.kind == b"moov").unwrap();
+        collect_offset_tables(data, moov.start, moov.end, &mut tables).unwrap();
+        let content = tables[0].content_start();
+        u32::from_be_bytes(data[content + 8..content + 12].try_into().unwrap()) as usize
+    }
+
+    #[test]
+    fn writes_tags_and_keeps_media_offsets_valid() {
+        for (name, free) in [("ligeia_mp4_shift.m4a", None), ("ligeia_mp4_free.m4a", Some(2048))] {
+            let path = std::env::temp_dir().join(name).to_string_lossy().to_string();
+            let (bytes, _) = build_test_file(free);
+            std::fs::write(&path, bytes).unwrap();
+
+            let mut tag = Mp4Tag::default();
+            tag.set_text(b"\xa9nam", "Tavern Night");
+            tag.set_integer(b"tmpo", 96);
+            write_rpg_tags(&mut tag, &[
+                ("occasion".to_string(), vec!["tavern".to_string(), "rest".to_string()]),
+                ("keyword".to_string(), vec!["loc:inn".to_string()]),
+            ]);
+            write_tag(&path, &tag).unwrap();
+
+            let read = read_tag(&path).unwrap().unwrap();
+            assert_eq!(read, tag);
+            assert_eq!(read.freeform("Occasion"), vec!["tavern; rest"]);
+            assert_eq!(read_duration(&path), Some(2.5));
+
+            let data = std::fs::read(&path).unwrap();
+            let offset = chunk_offset(&data);
+            assert_eq!(&data[offset..offset + 12], b"AUDIO-FRAMES");
+            std::fs::remove_file(&path).ok();
+        }
+    }
+
+    #[test]
+    fn refuses_fragmented_files_and_oversized_atoms() {
+        let path = std::env::temp_dir().join("ligeia_mp4_fragmented.m4a").to_string_lossy().to_string();
+        let (mut bytes, _) = build_test_file(None);
+        bytes.extend(encode_atom(b"moof", &encode_atom(b"mfhd", &[0u8; 8])));
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(write_tag(&path, &Mp4Tag::default()).unwrap_err().contains("Fragmented"));
+        std::fs::remove_file(&path).ok();
+
+        // A 64-bit size that would wrap the end offset around
+        let mut atom = 1u32.to_be_bytes().to_vec();
+        atom.extend_from_slice(b"free");
+        atom.extend_from_slice(&u64::MAX.to_be_bytes());
+        assert!(parse_atoms(&atom, 0, atom.len()).is_err());
+    }
+
+    #[test]
+    fn maps_atoms_to_audio_file() {
+        let mut tag = Mp4Tag::default();
+        let updates = AudioFile {
+            title: Some("Dragon's Lair".to_string()),
+            track_number: Some(4),
+            total_tracks: Some(10),
+            bpm: Some(120),
+            producer: Some("A. Smith".to_string()),
+            ..Default::default()
+        };
+        apply_patch(&mut tag, &AudioFilePatch::from(&updates));
+
+        let mut audio_file = AudioFile::default();
+        apply_to_audio_file(&tag, &mut audio_file);
+        assert_eq!(audio_file.title.as_deref(), Some("Dragon's Lair"));
+        assert_eq!((audio_file.track_number, audio_file.total_tracks), (Some(4), Some(10)));
+        assert_eq!(audio_file.bpm, Some(120));
+        assert_eq!(audio_file.producer.as_deref(), Some("A. Smith"));
+
+        let clear = AudioFilePatch {
+            title: FieldPatch::Clear,
+            track_number: FieldPatch::Clear,
+            bpm: FieldPatch::Clear,
+            ..Default::default()
+        };
+        apply_patch(&mut tag, &clear);
+        let mut audio_file = AudioFile::default();
+        apply_to_audio_file(&tag, &mut audio_file);
+        assert_eq!(audio_file.title, None);
+        assert_eq!((audio_file.track_number, audio_file.total_tracks), (None, Some(10)));
+        assert_eq!(audio_file.bpm, None);
+        assert_eq!(audio_file.producer.as_deref(), Some("A. Smith"));
+    }
+}
@@ -0,0 +1,377 @@
+use super::vorbis::VorbisComments;
+use std::fs::File;
+use std::io::{BufReader, Read};
+
+const VORBIS_COMMENT_PREFIX: &[u8] = b"\x03vorbis";
+const OPUS_COMMENT_PREFIX: &[u8] = b"OpusTags";
+
+/// Codec carried by the first logical stream of an Ogg file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OggCodec {
+    Vorbis,
+    Opus,
+}
+
+impl OggCodec {
+    fn from_id_header(packet: &[u8]) -> Result<Self, String> {
+        if packet.starts_with(b"\x01vorbis") {
+            Ok(OggCodec::Vorbis)
+        } else if packet.starts_with(b"OpusHead") {
+            Ok(OggCodec::Opus)
+        } else {
+            Err("Unsupported Ogg codec (only Vorbis and Opus are supported)".to_string())
+        }
+    }
+
+    /// Number of header packets before audio data starts
+    fn header_packet_count(self) -> usize {
+        match self {
+            OggCodec::Vorbis => 3,
+            OggCodec::Opus => 2,
+        }
+    }
+
+    fn comment_prefix(self) -> &'static [u8] {
+        match self {
+            OggCodec::Vorbis => VORBIS_COMMENT_PREFIX,
+            OggCodec::Opus => OPUS_COMMENT_PREFIX,
+        }
+    }
+}
+
+/// A single Ogg page
+#[derive(Debug, Clone)]
+struct Page {
+    header_type: u8,
+    granule_position: u64,
+    serial: u32,
+    sequence: u32,
+    segments: Vec<u8>,
+    data: Vec<u8>,
+}
+
+impl Page {
+    fn read<R: Read>(reader: &mut R) -> Result<Option<Page>, String> {
+        let mut header = [0u8; 27];
+        match reader.read_exact(&mut header) {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(format!("Failed to read Ogg page: {}", e)),
+        }
+        if &header[0..4] != b"OggS" {
+            return Err("Invalid Ogg page capture pattern".to_string());
+        }
+
+        let segment_count = header[26] as usize;
+        let mut segments = vec![0u8; segment_count];
+        reader.read_exact(&mut segments).map_err(|e| format!("Truncated Ogg page: {}", e))?;
+
+        let data_len: usize = segments.iter().map(|&s| s as usize).sum();
+        let mut data = vec![0u8; data_len];
+        reader.read_exact(&mut data).map_err(|e| format!("Truncated Ogg page: {}", e))?;
+
+        Ok(Some(Page {
+            header_type: header[5],
+            granule_position: u64::from_le_bytes(header[6..14].try_into().unwrap()),
+            serial: u32::from_le_bytes(header[14..18].try_into().unwrap()),
+            sequence: u32::from_le_bytes(header[18..22].try_into().unwrap()),
+            segments,
+            data,
+        }))
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(27 + self.segments.len() + self.data.len());
+        out.extend_from_slice(b"OggS");
+        out.push(0); // stream structure version
+        out.push(self.header_type);
+        out.extend_from_slice(&self.granule_position.to_le_bytes());
+        out.extend_from_slice(&self.serial.to_le_bytes());
+        out.extend_from_slice(&self.sequence.to_le_bytes());
+        out.extend_from_slice(&[0, 0, 0, 0]); // CRC placeholder
+        out.push(self.segments.len() as u8);
+        out.extend_from_slice(&self.segments);
+        out.extend_from_slice(&self.data);
+
+        let crc = crc32(&out);
+        out[22..26].copy_from_slice(&crc.to_le_bytes());
+        out
+    }
+}
+
+/// Ogg CRC-32 (polynomial 0x04c11db7, no reflection, zero init)
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0u32;
+    for &byte in data {
+        crc ^= (byte as u32) << 24;
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 { (crc << 1) ^ 0x04c1_1db7 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// Header packets of the first logical stream and the pages they span
+struct HeaderPackets {
+    codec: OggCodec,
+    serial: u32,
+    packets: Vec<Vec<u8>>,
+    /// Index (into the page list) of the page holding the end of the last header packet
+    last_header_page: usize,
+}
+
+/// Collect the header packets of the first logical stream from a page sequence
+fn collect_headers<I: Iterator<Item = Result<Page, String>>>(pages: I) -> Result<(HeaderPackets, Vec<Page>), String> {
+    let mut seen = Vec::new();
+    let mut packets: Vec<Vec<u8>> = Vec::new();
+    let mut current = Vec::new();
+    let mut codec = None;
+    let mut serial = None;
+
+    for page in pages {
+        let page = page?;
+        let index = seen.len();
+        let stream = *serial.get_or_insert(page.serial);
+
+        if page.serial == stream {
+            let mut offset = 0usize;
+            for &lacing in &page.segments {
+                current.extend_from_slice(&page.data[offset..offset + lacing as usize]);
+                offset += lacing as usize;
+
+                if lacing < 255 {
+                    let packet = std::mem::take(&mut current);
+                    if codec.is_none() {
+                        codec = Some(OggCodec::from_id_header(&packet)?);
+                    }
+                    packets.push(packet);
+
+                    let expected = codec.map_or(usize::MAX, |c| c.header_packet_count());
+                    if packets.len() == expected {
+                        if offset != page.data.len() {
+                            return Err("Ogg header packets do not end on a page boundary".to_string());
+                        }
+                        seen.push(page);
+                        return Ok((
+                            HeaderPackets {
+                                codec: codec.unwrap(),
+                                serial: stream,
+                                packets,
+                                last_header_page: index,
+                            },
+                            seen,
+                        ));
+                    }
+                }
+            }
+        }
+
+        seen.push(page);
+    }
+
+    Err("Ogg stream ended before all header packets were read".to_string())
+}
+
+/// Read Vorbis comments from an Ogg Vorbis or Ogg Opus file
+pub fn read_comments(file_path: &str) -> Result<Option<VorbisComments>, String> {
+    let file = File::open(file_path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut reader = BufReader::new(file);
+
+    let pages = std::iter::from_fn(|| Page::read(&mut reader).transpose());
+    let (headers, _) = collect_headers(pages)?;
+
+    let packet = &headers.packets[1];
+    let prefix = headers.codec.comment_prefix();
+    if !packet.starts_with(prefix) {
+        return Ok(None);
+    }
+
+    VorbisComments::parse(&packet[prefix.len()..]).map(|(c, _)| Some(c))
+}
+
+/// Write Vorbis comments to an Ogg Vorbis or Ogg Opus file.
+/// The comment header pages are rebuilt and following pages of the stream are renumbered.
+pub fn write_comments(file_path: &str, comments: &VorbisComments) -> Result<(), String> {
+    let original = std::fs::read(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let mut cursor = std::io::Cursor::new(&original);
+    let mut all_pages = Vec::new();
+    while let Some(page) = Page::read(&mut cursor)? {
+        all_pages.push(page);
+    }
+
+    let (headers, _) = collect_headers(all_pages.iter().cloned().map(Ok))?;
+
+    // Build the new comment packet, keeping any data after the comment block (Opus allows it)
+    let old_packet = &headers.packets[1];
+    let prefix = headers.codec.comment_prefix();
+    let mut comment_packet = prefix.to_vec();
+    comment_packet.extend_from_slice(&comments.to_bytes());
+    match headers.codec {
+        OggCodec::Vorbis => comment_packet.push(1), // framing bit
+        OggCodec::Opus => {
+            if old_packet.starts_with(prefix) {
+                if let Ok((_, consumed)) = VorbisComments::parse(&old_packet[prefix.len()..]) {
+                    comment_packet.extend_from_slice(&old_packet[prefix.len() + consumed..]);
+                }
+            }
+        }
+    }
+
+    let mut new_packets = vec![comment_packet];
+    new_packets.extend(headers.packets[2..].iter().cloned());
+    let new_header_pages = paginate(&new_packets, headers.serial, 1);
+
+    // The identification header always sits alone on the first page of the stream
+    let first_page = all_pages
+        .iter()
+        .position(|p| p.serial == headers.serial)
+        .ok_or("Ogg stream not found")?;
+    let old_header_pages = all_pages[first_page + 1..=headers.last_header_page]
+        .iter()
+        .filter(|p| p.serial == headers.serial)
+        .count();
+    let sequence_shift = new_header_pages.len() as i64 - old_header_pages as i64;
+
+    let mut output = Vec::with_capacity(original.len() + 4096);
+    let mut header_written = false;
+    for (index, page) in all_pages.iter().enumerate() {
+        let is_old_header = index > first_page
+            && index <= headers.last_header_page
+            && page.serial == headers.serial;
+
+        if is_old_header {
+            if !header_written {
+                for new_page in &new_header_pages {
+                    output.extend_from_slice(&new_page.to_bytes());
+                }
+                header_written = true;
+            }
+            continue;
+        }
+
+        if page.serial == headers.serial && index > headers.last_header_page && sequence_shift != 0 {
+            let mut renumbered = page.clone();
+            renumbered.sequence = (page.sequence as i64 + sequence_shift) as u32;
+            output.extend_from_slice(&renumbered.to_bytes());
+        } else {
+            output.extend_from_slice(&page.to_bytes());
+        }
+    }
+
+    std::fs::write(file_path, output).map_err(|e| format!("Failed to write file: {}", e))
+}
+
+/// Split packets into pages (max 255 segments each) starting at the given sequence number
+fn paginate(packets: &[Vec<u8>], serial: u32, first_sequence: u32) -> Vec<Page> {
+    let mut pages: Vec<Page> = Vec::new();
+    let mut segments = Vec::new();
+    let mut data = Vec::new();
+    // Whether the current page starts inside a packet, and whether any packet ends on it
+    let mut continued = false;
+    let mut any_packet_ended = false;
+
+    for packet in packets {
+        let mut remaining = packet.as_slice();
+        loop {
+            if segments.len() == 255 {
+                pages.push(Page {
+                    header_type: if continued { 0x01 } else { 0x00 },
+                    // Header pages carry granule 0; pages where no packet ends carry -1
+                    granule_position: if any_packet_ended { 0 } else { u64::MAX },
+                    serial,
+                    sequence: first_sequence + pages.len() as u32,
+                    segments: std::mem::take(&mut segments),
+                    data: std::mem::take(&mut data),
+                });
+                // Part of this packet already went onto the previous page
+                continued = remaining.len() < packet.len();
+                any_packet_ended = false;
+            }
+
+            let take = remaining.len().min(255);
+            segments.push(take as u8);
+            data.extend_from_slice(&remaining[..take]);
+            remaining = &remaining[take..];
+
+            if take < 255 {
+                any_packet_ended = true;
+                break;
+            }
+        }
+    }
+
+    if !segments.is_empty() {
+        pages.push(Page {
+            header_type: if continued { 0x01 } else { 0x00 },
+            granule_position: if any_packet_ended { 0 } else { u64::MAX },
+            serial,
+            sequence: first_sequence + pages.len() as u32,
+            segments,
+            data,
+        });
+    }
+
+    pages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(serial: u32, sequence: u32, granule: u64, packets: &[&[u8]]) -> Page {
+        let mut segments = Vec::new();
+        let mut data = Vec::new();
+        for packet in packets {
+            let mut remaining = *packet;
+            loop {
+                let take = remaining.len().min(255);
+                segments.push(take as u8);
+                data.extend_from_slice(&remaining[..take]);
+                remaining = &remaining[take..];
+                if take < 255 {
+                    break;
+                }
+            }
+        }
+        Page { header_type: 0, granule_position: granule, serial, sequence, segments, data }
+    }
+
+    #[test]
+    fn rewrites_vorbis_comment_header() {
+        let path = std::env::temp_dir().join("ligeia_ogg_rewrite.ogg").to_string_lossy().to_string();
+
+        let mut comment = VORBIS_COMMENT_PREFIX.to_vec();
+        comment.extend_from_slice(&VorbisComments { vendor: "test".into(), comments: vec![] }.to_bytes());
+        comment.push(1);
+        let setup = b"\x05vorbis-setup".to_vec();
+
+        let mut bytes = Vec::new();
+        bytes.extend(page(7, 0, 0, &[b"\x01vorbis-id"]).to_bytes());
+        bytes.extend(page(7, 1, 0, &[&comment, &setup]).to_bytes());
+        bytes.extend(page(7, 2, 4096, &[b"audio-1"]).to_bytes());
+        bytes.extend(page(7, 3, 8192, &[b"audio-2"]).to_bytes());
+        std::fs::write(&path, bytes).unwrap();
+
+        // Large enough to spill over several pages
+        let comments = VorbisComments {
+            vendor: "ligeia".into(),
+            comments: vec![("KEYWORDS".into(), "x".repeat(70_000))],
+        };
+        write_comments(&path, &comments).unwrap();
+        assert_eq!(read_comments(&path).unwrap(), Some(comments));
+
+        let data = std::fs::read(&path).unwrap();
+        let mut cursor = std::io::Cursor::new(&data);
+        let mut pages = Vec::new();
+        while let Some(p) = Page::read(&mut cursor).unwrap() {
+            pages.push(p);
+        }
+        // Sequence numbers stay contiguous and audio pages are preserved
+        for (i, p) in pages.iter().enumerate() {
+            assert_eq!(p.sequence, i as u32);
+        }
+        assert_eq!(pages.last().unwrap().data, b"audio-2");
+        assert_eq!(pages.last().unwrap().granule_position, 8192);
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,174 @@
+use super::{flac::FlacMetadata, mp4, TagFormat};
+
+/// FLAC metadata block type holding a picture
+pub const BLOCK_PICTURE: u8 = 6;
+
+/// Picture type of the front cover (ID3 APIC / FLAC PICTURE numbering)
+pub const PICTURE_TYPE_FRONT_COVER: u8 = 3;
+
+/// `covr` data atom type codes
+const MP4_TYPE_JPEG: u32 = 13;
+const MP4_TYPE_PNG: u32 = 14;
+const MP4_TYPE_BMP: u32 = 27;
+
+/// A picture embedded in the tags of an audio file
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddedPicture {
+    pub mime_type: String,
+    pub picture_type: u8,
+    pub description: String,
+    pub data: Vec<u8>,
+}
+
+impl EmbeddedPicture {
+    /// Parse a FLAC PICTURE block (also the payload of Ogg METADATA_BLOCK_PICTURE)
+    pub fn parse_flac_block(data: &[u8]) -> Option<Self> {
+        let mut pos = 0;
+        let read_u32 = |pos: &mut usize| -> Option<u32> {
+            let bytes = data.get(*pos..*pos + 4)?;
+            *pos += 4;
+            Some(u32::from_be_bytes(bytes.try_into().ok()?))
+        };
+
+        let picture_type = read_u32(&mut pos)?;
+        let mime_len = read_u32(&mut pos)? as usize;
+        let mime_type = String::from_utf8_lossy(data.get(pos..pos + mime_len)?).to_string();
+        pos += mime_len;
+        let description_len = read_u32(&mut pos)? as usize;
+        let description = String::from_utf8_lossy(data.get(pos..pos + description_len)?).to_string();
+        pos += description_len;
+
+        // Width, height, colour depth and palette size; the image itself carries them too
+        pos += 16;
+        let data_len = read_u32(&mut pos)? as usize;
+        let picture = data.get(pos..pos + data_len)?;
+
+        Some(EmbeddedPicture {
+            mime_type,
+            picture_type: picture_type.min(u8::MAX as u32) as u8,
+            description,
+            data: picture.to_vec(),
+        })
+    }
+}
+
+/// Decode standard base64, ignoring whitespace
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in text.bytes().filter(|c| !c.is_ascii_whitespace() && *c != b'=') {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+/// All pictures embedded in a file, front covers first
+///
+/// Reads ID3 APIC frames (MP3 and the WAV `id3 ` chunk), FLAC PICTURE blocks,
+/// Ogg METADATA_BLOCK_PICTURE comments and MP4 `covr` atoms.
+pub fn read_pictures(file_path: &str) -> Result<Vec<EmbeddedPicture>, String> {
+    let mut pictures: Vec<EmbeddedPicture> = match TagFormat::from_path(file_path) {
+        TagFormat::Id3 | TagFormat::Wav => super::read_id3_tag(file_path)
+            .map(|tag| {
+                tag.pictures()
+                    .map(|picture| EmbeddedPicture {
+                        mime_type: picture.mime_type.clone(),
+                        picture_type: picture.picture_type.into(),
+                        description: picture.description.clone(),
+                        data: picture.data.clone(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        TagFormat::Unsupported => Vec::new(),
+        TagFormat::Flac => FlacMetadata::read_from_path(file_path)?
+            .blocks
+            .iter()
+            .filter(|block| block.block_type == BLOCK_PICTURE)
+            .filter_map(|block| EmbeddedPicture::parse_flac_block(&block.data))
+            .collect(),
+        TagFormat::Ogg => super::read_vorbis_comments(file_path)?
+            .map(|comments| {
+                comments.get_all("METADATA_BLOCK_PICTURE")
+                    .into_iter()
+                    .filter_map(decode_base64)
+                    .filter_map(|data| EmbeddedPicture::parse_flac_block(&data))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        TagFormat::Mp4 => mp4::read_tag(file_path)?
+            .and_then(|tag| tag.items.into_iter().find(|item| item.key == mp4::Mp4Key::Atom(*b"covr")))
+            .map(|item| {
+                item.values
+                    .into_iter()
+                    .map(|value| EmbeddedPicture {
+                        mime_type: match value.type_code {
+                            MP4_TYPE_PNG => "image/png",
+                            MP4_TYPE_BMP => "image/bmp",
+                            MP4_TYPE_JPEG => "image/jpeg",
+                            _ => "",
+                        }.to_string(),
+                        picture_type: PICTURE_TYPE_FRONT_COVER,
+                        description: String::new(),
+                        data: value.bytes,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+    };
+
+    pictures.retain(|picture| !picture.data.is_empty());
+    pictures.sort_by_key(|picture| picture.picture_type != PICTURE_TYPE_FRONT_COVER);
+    Ok(pictures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flac_picture_block(picture_type: u32, mime: &str, data: &[u8]) -> Vec<u8> {
+        let mut block = Vec::new();
+        block.extend_from_slice(&picture_type.to_be_bytes());
+        block.extend_from_slice(&(mime.len() as u32).to_be_bytes());
+        block.extend_from_slice(mime.as_bytes());
+        block.extend_from_slice(&5u32.to_be_bytes());
+        block.extend_from_slice(b"Cover");
+        block.extend_from_slice(&[0u8; 16]);
+        block.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        block.extend_from_slice(data);
+        block
+    }
+
+    #[test]
+    fn parses_flac_picture_blocks() {
+        let block = flac_picture_block(3, "image/png", b"\x89PNG-DATA");
+        let picture = EmbeddedPicture::parse_flac_block(&block).unwrap();
+        assert_eq!(picture.mime_type, "image/png");
+        assert_eq!(picture.picture_type, PICTURE_TYPE_FRONT_COVER);
+        assert_eq!(picture.description, "Cover");
+        assert_eq!(picture.data, b"\x89PNG-DATA");
+
+        assert!(EmbeddedPicture::parse_flac_block(&block[..block.len() - 1]).is_none());
+    }
+
+    #[test]
+    fn decodes_base64() {
+        assert_eq!(decode_base64("TGlnZWlh").unwrap(), b"Ligeia");
+        assert_eq!(decode_base64("TGln\nZWk=").unwrap(), b"Ligei");
+        assert!(decode_base64("not*base64").is_none());
+    }
+}
@@ -0,0 +1,292 @@
+use super::flac::{self, FlacMetadata};
+use super::mp4::{self, Mp4Tag};
+use super::vorbis::VorbisComments;
+use super::{aiff, wav, TagFormat};
+use std::fs::{self, File, OpenOptions};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Marks the name of a temporary copy, between its stem and its extension
+const TEMP_MARKER: &str = ".ligeia-tmp";
+
+/// Files this process rewrote, with their size and modification time right after
+static OWN_WRITES: Mutex<Vec<(PathBuf, u64, SystemTime)>> = Mutex::new(Vec::new());
+
+/// Hidden sibling of a file used while rewriting it; keeps the extension so the format is still detected
+fn temp_path(file_path: &str) -> PathBuf {
+    let path = Path::new(file_path);
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let name = match path.extension() {
+        Some(extension) => format!(".{}{}.{}", stem, TEMP_MARKER, extension.to_string_lossy()),
+        None => format!(".{}{}", stem, TEMP_MARKER),
+    };
+    path.with_file_name(name)
+}
+
+/// Whether a path is a temporary copy made while rewriting a file, possibly left behind by a crash
+pub fn is_temp_file(file_path: &str) -> bool {
+    let Some(name) = Path::new(file_path).file_name().map(|name| name.to_string_lossy()) else {
+        return false;
+    };
+    name.ends_with(TEMP_MARKER) || name.contains(&format!("{}.", TEMP_MARKER))
+}
+
+fn disk_state(path: &Path) -> Option<(u64, SystemTime)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.len(), metadata.modified().ok()?))
+}
+
+fn remember_own_write(file_path: &str) {
+    let path = PathBuf::from(file_path);
+    let mut own_writes = OWN_WRITES.lock().unwrap();
+    own_writes.retain(|(written, _, _)| written != &path);
+    if let Some((size, modified)) = disk_state(&path) {
+        own_writes.push((path, size, modified));
+    }
+}
+
+/// Whether a file is as this process last rewrote it, so a change seen on it is the app's own
+pub fn is_own_write(file_path: &str) -> bool {
+    let path = Path::new(file_path);
+    let mut own_writes = OWN_WRITES.lock().unwrap();
+    let Some(index) = own_writes.iter().position(|(written, _, _)| written == path) else {
+        return false;
+    };
+    let (_, size, modified) = own_writes[index];
+    if disk_state(path) == Some((size, modified)) {
+        true
+    } else {
+        // Something else wrote it since
+        own_writes.swap_remove(index);
+        false
+    }
+}
+
+/// Flush a file's contents to disk
+fn sync_file(path: &Path) -> Result<(), String> {
+    OpenOptions::new()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.sync_all())
+        .map_err(|e| format!("Failed to flush file: {}", e))
+}
+
+/// Flush the directory entry after a rename; not supported (nor needed) on every platform
+fn sync_parent_dir(file_path: &str) {
+    #[cfg(unix)]
+    if let Some(parent) = Path::new(file_path).parent().filter(|p| !p.as_os_str().is_empty()) {
+        if let Ok(dir) = File::open(parent) {
+            dir.sync_all().ok();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = file_path;
+}
+
+/// Rewrite a file through a temporary copy.
+///
+/// `write` edits the copy, which is flushed to disk and checked by `verify` before it is
+/// renamed over the original. On any error the copy is removed and the original is untouched.
+pub fn write_verified<W, V>(file_path: &str, write: W, verify: V) -> Result<(), String>
+where
+    W: FnOnce(&str) -> Result<(), String>,
+    V: FnOnce(&str) -> Result<(), String>,
+{
+    let temp = temp_path(file_path);
+    let temp_str = temp.to_string_lossy().to_string();
+    fs::copy(file_path, &temp).map_err(|e| format!("Failed to copy file: {}", e))?;
+
+    let result = write(&temp_str)
+        .and_then(|_| sync_file(&temp))
+        .and_then(|_| verify(&temp_str))
+        .and_then(|_| fs::rename(&temp, file_path).map_err(|e| format!("Failed to replace file: {}", e)));
+
+    match result {
+        Ok(()) => {
+            sync_parent_dir(file_path);
+            remember_own_write(file_path);
+            Ok(())
+        }
+        Err(e) => {
+            fs::remove_file(&temp).ok();
+            Err(e)
+        }
+    }
+}
+
+/// Verification step: the tag read back must equal the tag that was meant to be written
+pub fn check_reread<T: PartialEq>(written: &T, reread: &T) -> Result<(), String> {
+    if written == reread {
+        Ok(())
+    } else {
+        Err("Verification failed: tags read back differ from the tags written".to_string())
+    }
+}
+
+/// First `len` bytes of a file
+fn read_prefix(file_path: &str, len: u64) -> Result<Vec<u8>, String> {
+    let file = File::open(file_path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut prefix = Vec::with_capacity(len as usize);
+    file.take(len).read_to_end(&mut prefix).map_err(|e| format!("Failed to read file: {}", e))?;
+    if prefix.len() as u64 != len {
+        return Err("Tag block extends past the end of the file".to_string());
+    }
+    Ok(prefix)
+}
+
+fn id3v2_len(file: &mut File) -> Result<u64, String> {
+    flac::skip_id3v2(file)
+}
+
+fn flac_metadata_len(file: &mut File) -> Result<u64, String> {
+    FlacMetadata::read_from(file).map(|metadata| metadata.audio_offset)
+}
+
+/// Raw tag block of a file, as needed by `restore_tag_block`; `None` when the file has no tags.
+///
+/// That is the leading ID3v2 tag for MP3s, the `ID3 ` chunk for AIFF, everything before the
+/// first audio frame for FLAC, the Vorbis comment block for Ogg, the `ilst` atom for MP4 and
+/// the `id3 ` chunk for WAV. An Ogg stream without comments fails, as its tags couldn't be restored.
+pub fn read_tag_block(file_path: &str) -> Result<Option<Vec<u8>>, String> {
+    let open = || File::open(file_path).map_err(|e| format!("Failed to open file: {}", e));
+    match TagFormat::from_path(file_path) {
+        TagFormat::Id3 if aiff::is_aiff(file_path)? => aiff::read_id3_chunk(file_path),
+        TagFormat::Id3 => match id3v2_len(&mut open()?)? {
+            0 => Ok(None),
+            len => read_prefix(file_path, len).map(Some),
+        },
+        TagFormat::Flac => read_prefix(file_path, flac_metadata_len(&mut open()?)?).map(Some),
+        TagFormat::Ogg => super::read_vorbis_comments(file_path)?
+            .map(|comments| Some(comments.to_bytes()))
+            .ok_or_else(|| "No Vorbis comments to back up".to_string()),
+        TagFormat::Mp4 => Ok(mp4::read_tag(file_path)?.map(|tag| mp4::encode_tag(&tag))),
+        TagFormat::Wav => wav::read_id3_chunk(file_path),
+        TagFormat::Unsupported => Err(super::unsupported_format(file_path)),
+    }
+}
+
+/// Replace the leading `prefix_len` bytes of a file with `block`
+fn splice_prefix(file_path: &str, block: &[u8], prefix_len: fn(&mut File) -> Result<u64, String>) -> Result<(), String> {
+    write_verified(
+        file_path,
+        |temp| {
+            let data = fs::read(temp).map_err(|e| format!("Failed to read file: {}", e))?;
+            let len = prefix_len(&mut File::open(temp).map_err(|e| format!("Failed to open file: {}", e))?)?;
+            let mut output = block.to_vec();
+            output.extend_from_slice(&data[len as usize..]);
+            fs::write(temp, output).map_err(|e| format!("Failed to write file: {}", e))
+        },
+        |temp| {
+            let len = prefix_len(&mut File::open(temp).map_err(|e| format!("Failed to open file: {}", e))?)?;
+            check_reread(&block.to_vec(), &read_prefix(temp, len)?)
+        },
+    )
+}
+
+/// Put back a block saved by `read_tag_block`, replacing the current tags of the file.
+/// `None` removes the tags again (for MP4 an empty `ilst` is left behind).
+pub fn restore_tag_block(file_path: &str, block: Option<&[u8]>) -> Result<(), String> {
+    match TagFormat::from_path(file_path) {
+        TagFormat::Id3 if aiff::is_aiff(file_path)? => write_verified(
+            file_path,
+            |temp| aiff::write_id3_chunk(temp, block),
+            |temp| check_reread(&block.map(|b| b.to_vec()), &aiff::read_id3_chunk(temp)?),
+        ),
+        TagFormat::Id3 => splice_prefix(file_path, block.unwrap_or_default(), id3v2_len),
+        TagFormat::Flac => splice_prefix(file_path, block.ok_or("No FLAC metadata to restore")?, flac_metadata_len),
+        TagFormat::Ogg => super::write_vorbis_comments(file_path, &VorbisComments::parse(block.ok_or("No Vorbis comments to restore")?)?.0),
+        TagFormat::Mp4 => {
+            let tag = match block {
+                Some(block) => mp4::decode_tag(block)?,
+                None => Mp4Tag::default(),
+            };
+            super::write_mp4_tag(file_path, &tag)
+        }
+        TagFormat::Wav => write_verified(
+            file_path,
+            |temp| wav::write_id3_chunk(temp, block),
+            |temp| check_reread(&block.map(|b| b.to_vec()), &wav::read_id3_chunk(temp)?),
+        ),
+        TagFormat::Unsupported => Err(super::unsupported_format(file_path)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use id3::{Tag, TagLike};
+
+    fn temp_file(name: &str, contents: &[u8]) -> String {
+        let path = std::env::temp_dir().join(name);
+        fs::write(&path, contents).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn failed_verification_keeps_the_original() {
+        let path = temp_file("ligeia_safe_write_test.mp3", b"original audio");
+
+        let result = write_verified(
+            &path,
+            |temp| fs::write(temp, b"broken").map_err(|e| e.to_string()),
+            |_| check_reread(&1, &2),
+        );
+        assert!(result.is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"original audio");
+        assert!(!temp_path(&path).exists());
+
+        assert!(is_temp_file(&temp_path(&path).to_string_lossy()) && !is_temp_file(&path));
+
+        assert!(!is_own_write(&path));
+        write_verified(&path, |temp| fs::write(temp, b"new audio").map_err(|e| e.to_string()), |_| Ok(())).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new audio");
+        assert!(is_own_write(&path));
+        fs::write(&path, b"edited elsewhere").unwrap();
+        assert!(!is_own_write(&path));
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn restores_backed_up_id3_block() {
+        let audio = vec![0xFFu8, 0xFB, 0x90, 0x00, 1, 2, 3, 4];
+        let path = temp_file("ligeia_tag_block_test.mp3", &audio);
+        assert_eq!(read_tag_block(&path).unwrap(), None);
+
+        let mut tag = Tag::new();
+        tag.set_title("Original");
+        super::super::write_id3_tag(&path, &tag).unwrap();
+        let original = fs::read(&path).unwrap();
+        let block = read_tag_block(&path).unwrap().unwrap();
+
+        tag.set_title("Changed with a much longer title than before");
+        super::super::write_id3_tag(&path, &tag).unwrap();
+        assert_eq!(Tag::read_from_path(&path).unwrap().title(), Some("Changed with a much longer title than before"));
+
+        restore_tag_block(&path, Some(&block)).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), original);
+
+        restore_tag_block(&path, None).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), audio);
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn restores_backed_up_aiff_id3_chunk() {
+        let mut aiff = b"FORM\0\0\0\x16AIFFSSND\0\0\0\x0a".to_vec();
+        aiff.extend_from_slice(&[0; 10]);
+        let path = temp_file("ligeia_tag_block_test.aiff", &aiff);
+
+        let mut tag = Tag::new();
+        tag.set_title("Original");
+        super::super::write_id3_tag(&path, &tag).unwrap();
+        let block = read_tag_block(&path).unwrap().expect("the ID3 chunk is backed up");
+
+        tag.set_title("Changed");
+        super::super::write_id3_tag(&path, &tag).unwrap();
+        restore_tag_block(&path, Some(&block)).unwrap();
+        assert_eq!(Tag::read_from_path(&path).unwrap().title(), Some("Original"));
+        fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,423 @@
+use crate::models::{AudioFile, AudioFilePatch, FieldPatch};
+use super::involved_people;
+
+/// Vorbis comment block as stored in FLAC and OGG files
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VorbisComments {
+    pub vendor: String,
+    pub comments: Vec<(String, String)>,
+}
+
+impl VorbisComments {
+    /// Parse a raw Vorbis comment block (without any codec-specific prefix or framing bit).
+    /// Returns the comments and the number of bytes consumed.
+    pub fn parse(data: &[u8]) -> Result<(Self, usize), String> {
+        let mut pos = 0usize;
+
+        let vendor_len = read_u32_le(data, &mut pos)? as usize;
+        let vendor = read_string(data, &mut pos, vendor_len)?;
+
+        let count = read_u32_le(data, &mut pos)? as usize;
+        let mut comments = Vec::with_capacity(count.min(1024));
+
+        for _ in 0..count {
+            let len = read_u32_le(data, &mut pos)? as usize;
+            let entry = read_string(data, &mut pos, len)?;
+
+            // Entries without '=' are invalid per spec, skip them instead of failing the whole block
+            if let Some((key, value)) = entry.split_once('=') {
+                comments.push((key.to_uppercase(), value.to_string()));
+            }
+        }
+
+        Ok((VorbisComments { vendor, comments }, pos))
+    }
+
+    /// Serialize to a raw Vorbis comment block (without framing bit)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&(self.vendor.len() as u32).to_le_bytes());
+        out.extend_from_slice(self.vendor.as_bytes());
+        out.extend_from_slice(&(self.comments.len() as u32).to_le_bytes());
+
+        for (key, value) in &self.comments {
+            let entry = format!("{}={}", key, value);
+            out.extend_from_slice(&(entry.len() as u32).to_le_bytes());
+            out.extend_from_slice(entry.as_bytes());
+        }
+
+        out
+    }
+
+    /// Get the first value for a field (field names are case-insensitive)
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.comments
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Get all values for a field
+    pub fn get_all(&self, key: &str) -> Vec<&str> {
+        self.comments
+            .iter()
+            .filter(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+
+    /// Replace all values of a field with a single value
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.set_all(key, &[value.to_string()]);
+    }
+
+    /// Replace all values of a field, keeping the position of the first existing entry
+    pub fn set_all(&mut self, key: &str, values: &[String]) {
+        let key = key.to_uppercase();
+        let position = self.comments.iter().position(|(k, _)| *k == key);
+        self.remove(&key);
+
+        let insert_at = position.unwrap_or(self.comments.len());
+        for (offset, value) in values.iter().enumerate() {
+            self.comments.insert(insert_at + offset, (key.clone(), value.clone()));
+        }
+    }
+
+    /// Remove every value of a field
+    pub fn remove(&mut self, key: &str) {
+        self.comments.retain(|(k, _)| !k.eq_ignore_ascii_case(key));
+    }
+
+    /// Split multi-valued fields written either as repeated comments or as "a; b" lists
+    pub fn get_list(&self, key: &str) -> Vec<String> {
+        self.get_all(key)
+            .iter()
+            .flat_map(|v| v.split(';'))
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .map(|v| v.to_string())
+            .collect()
+    }
+}
+
+fn read_u32_le(data: &[u8], pos: &mut usize) -> Result<u32, String> {
+    let bytes = data
+        .get(*pos..*pos + 4)
+        .ok_or("Truncated Vorbis comment block")?;
+    *pos += 4;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_string(data: &[u8], pos: &mut usize, len: usize) -> Result<String, String> {
+    let bytes = data
+        .get(*pos..pos.saturating_add(len))
+        .ok_or("Truncated Vorbis comment block")?;
+    *pos += len;
+    Ok(String::from_utf8_lossy(bytes).to_string())
+}
+
+/// Parse "N" or "N/M" style numbers (TRACKNUMBER=3/12)
+fn parse_number_pair(value: &str) -> (Option<u32>, Option<u32>) {
+    let mut parts = value.splitn(2, '/');
+    let first = parts.next().and_then(|s| s.trim().parse().ok());
+    let second = parts.next().and_then(|s| s.trim().parse().ok());
+    (first, second)
+}
+
+/// Fill an AudioFile from Vorbis comments
+pub fn apply_to_audio_file(comments: &VorbisComments, audio_file: &mut AudioFile) {
+    let text = |key: &str| comments.get(key).map(|s| s.to_string());
+    let joined = |key: &str| {
+        let values = comments.get_all(key);
+        if values.is_empty() { None } else { Some(values.join("; ")) }
+    };
+
+    // Basic tags
+    audio_file.title = text("TITLE");
+    audio_file.artist = joined("ARTIST");
+    audio_file.album = text("ALBUM");
+    audio_file.album_artist = text("ALBUMARTIST").or_else(|| text("ALBUM ARTIST"));
+    audio_file.genre = joined("GENRE");
+    audio_file.date = text("DATE");
+    audio_file.year = text("YEAR")
+        .or_else(|| text("DATE"))
+        .and_then(|d| d.get(0..4).and_then(|y| y.parse().ok()));
+
+    if let Some(track) = comments.get("TRACKNUMBER") {
+        let (number, total) = parse_number_pair(track);
+        audio_file.track_number = number;
+        audio_file.total_tracks = total;
+    }
+    if let Some(total) = comments.get("TRACKTOTAL").or_else(|| comments.get("TOTALTRACKS")) {
+        audio_file.total_tracks = total.trim().parse().ok();
+    }
+    if let Some(disc) = comments.get("DISCNUMBER") {
+        let (number, total) = parse_number_pair(disc);
+        audio_file.disc_number = number;
+        audio_file.total_discs = total;
+    }
+    if let Some(total) = comments.get("DISCTOTAL").or_else(|| comments.get("TOTALDISCS")) {
+        audio_file.total_discs = total.trim().parse().ok();
+    }
+
+    // Extended tags
+    audio_file.composer = joined("COMPOSER");
+    audio_file.conductor = text("CONDUCTOR");
+    audio_file.lyricist = joined("LYRICIST");
+    audio_file.original_artist = text("ORIGINALARTIST");
+    audio_file.remixer = text("REMIXER");
+    audio_file.arranger = joined("ARRANGER");
+    audio_file.engineer = joined("ENGINEER");
+    audio_file.producer = joined("PRODUCER");
+    audio_file.dj_mixer = joined("DJMIXER");
+    audio_file.mixer = joined("MIXER");
+
+    // Content tags
+    audio_file.content_group = text("GROUPING").or_else(|| text("CONTENTGROUP"));
+    audio_file.subtitle = text("SUBTITLE");
+    audio_file.initial_key = text("INITIALKEY").or_else(|| text("KEY"));
+    audio_file.bpm = text("BPM").and_then(|b| b.trim().parse::<f64>().ok()).map(|b| b.round() as u32);
+    audio_file.language = text("LANGUAGE");
+    audio_file.media_type = text("MEDIA");
+    audio_file.original_filename = text("ORIGINALFILENAME");
+    audio_file.original_lyricist = text("ORIGINALLYRICIST");
+    audio_file.original_release_time = text("ORIGINALDATE");
+    audio_file.playlist_delay = text("PLAYLISTDELAY").and_then(|d| d.trim().parse().ok());
+
+    // Recording info
+    audio_file.recording_time = text("RECORDINGDATE");
+    audio_file.release_time = text("RELEASEDATE");
+    audio_file.tagging_time = text("TAGGINGDATE");
+    audio_file.encoding_time = text("ENCODINGTIME");
+    audio_file.encoding_settings = text("ENCODERSETTINGS").or_else(|| text("ENCODER"));
+    audio_file.encoded_by = text("ENCODEDBY");
+
+    // Copyright and legal
+    audio_file.copyright = text("COPYRIGHT");
+    audio_file.file_owner = text("OWNER");
+    audio_file.internet_radio_station_name = text("RADIOSTATION");
+    audio_file.internet_radio_station_owner = text("RADIOSTATIONOWNER");
+    audio_file.isrc = text("ISRC");
+    audio_file.publisher = text("PUBLISHER").or_else(|| text("LABEL"));
+
+    // Additional metadata
+    audio_file.mood = joined("MOOD");
+    audio_file.occasion = joined("OCCASION");
+    audio_file.tempo = text("TEMPO");
+    audio_file.content_type = text("CONTENTTYPE");
+    audio_file.category = text("CATEGORY");
+    audio_file.subcategory = text("SUBCATEGORY");
+}
+
+/// Apply a patch to Vorbis comments: set fields are replaced, cleared fields removed
+pub fn apply_patch(comments: &mut VorbisComments, updates: &AudioFilePatch) {
+    let mut set = |key: &str, value: FieldPatch<String>| match value {
+        FieldPatch::Set(value) => comments.set(key, &value),
+        FieldPatch::Clear => comments.remove(key),
+        FieldPatch::Leave => {}
+    };
+
+    // Basic tags
+    set("TITLE", updates.title.clone());
+    set("ARTIST", updates.artist.clone());
+    set("ALBUM", updates.album.clone());
+    set("ALBUMARTIST", updates.album_artist.clone());
+    set("GENRE", updates.genre.clone());
+    set("DATE", updates.date.clone().or(updates.year.map(|y| y.to_string())));
+    set("TRACKNUMBER", updates.track_number.map(|n| n.to_string()));
+    set("TRACKTOTAL", updates.total_tracks.map(|n| n.to_string()));
+    set("DISCNUMBER", updates.disc_number.map(|n| n.to_string()));
+    set("DISCTOTAL", updates.total_discs.map(|n| n.to_string()));
+
+    // Extended tags
+    set("COMPOSER", updates.composer.clone());
+    set("CONDUCTOR", updates.conductor.clone());
+    set("LYRICIST", updates.lyricist.clone());
+    set("ORIGINALARTIST", updates.original_artist.clone());
+    set("REMIXER", updates.remixer.clone());
+
+    // Content tags
+    set("GROUPING", updates.content_group.clone());
+    set("SUBTITLE", updates.subtitle.clone());
+    set("INITIALKEY", updates.initial_key.clone());
+    set("BPM", updates.bpm.map(|b| b.to_string()));
+    set("LANGUAGE", updates.language.clone());
+    set("MEDIA", updates.media_type.clone());
+    set("ORIGINALFILENAME", updates.original_filename.clone());
+    set("ORIGINALLYRICIST", updates.original_lyricist.clone());
+    set("ORIGINALDATE", updates.original_release_time.clone());
+    set("PLAYLISTDELAY", updates.playlist_delay.map(|d| d.to_string()));
+
+    // Recording info
+    set("RECORDINGDATE", updates.recording_time.clone());
+    set("RELEASEDATE", updates.release_time.clone());
+    set("TAGGINGDATE", updates.tagging_time.clone());
+    set("ENCODINGTIME", updates.encoding_time.clone());
+    set("ENCODERSETTINGS", updates.encoding_settings.clone());
+    set("ENCODEDBY", updates.encoded_by.clone());
+
+    // Copyright and legal
+    set("COPYRIGHT", updates.copyright.clone());
+    set("OWNER", updates.file_owner.clone());
+    set("RADIOSTATION", updates.internet_radio_station_name.clone());
+    set("RADIOSTATIONOWNER", updates.internet_radio_station_owner.clone());
+    set("ISRC", updates.isrc.clone());
+    set("PUBLISHER", updates.publisher.clone());
+
+    // Additional metadata
+    set("MOOD", updates.mood.clone());
+    set("TEMPO", updates.tempo.clone());
+    set("CONTENTTYPE", updates.content_type.clone());
+    set("CATEGORY", updates.category.clone());
+    set("SUBCATEGORY", updates.subcategory.clone());
+
+    // OCCASION (shared with the RPG occasion tags) and credits are multi-valued:
+    // they are read back joined with "; ", so write one comment per value
+    for (key, value) in [
+        ("OCCASION", &updates.occasion),
+        ("ARRANGER", &updates.arranger),
+        ("ENGINEER", &updates.engineer),
+        ("PRODUCER", &updates.producer),
+        ("DJMIXER", &updates.dj_mixer),
+        ("MIXER", &updates.mixer),
+    ] {
+        match value {
+            FieldPatch::Set(value) => comments.set_all(key, &involved_people::split_names(value)),
+            FieldPatch::Clear => comments.remove(key),
+            FieldPatch::Leave => {}
+        }
+    }
+}
+
+/// Read RPG tags (occasion, keyword, quality and legacy RPG_* fields) from Vorbis comments
+pub fn read_rpg_tags(comments: &VorbisComments) -> Vec<(String, String)> {
+    let mut rpg_tags = Vec::new();
+
+    for occasion in comments.get_list("OCCASION") {
+        rpg_tags.push(("occasion".to_string(), occasion));
+    }
+    for keyword in comments.get_list("KEYWORDS") {
+        rpg_tags.push(("keyword".to_string(), keyword));
+    }
+    if let Some(quality) = comments.get("QUALITY").map(|q| q.trim()).filter(|q| !q.is_empty()) {
+        rpg_tags.push(("quality".to_string(), quality.to_string()));
+    }
+
+    // Legacy RPG_ prefixed fields written by older store operations
+    for (key, tag_type) in [
+        ("RPG_GENRE", "genre"),
+        ("RPG_MOOD", "mood"),
+        ("RPG_OCCASION", "occasion"),
+        ("RPG_KEYWORDS", "keyword"),
+    ] {
+        for value in comments.get_list(key) {
+            let tag = (tag_type.to_string(), value);
+            if !rpg_tags.contains(&tag) {
+                rpg_tags.push(tag);
+            }
+        }
+    }
+
+    rpg_tags
+}
+
+/// Replace the OCCASION/KEYWORDS/QUALITY fields with the given grouped RPG tags
+pub fn write_rpg_tags(comments: &mut VorbisComments, rpg_tags: &[(String, Vec<String>)]) {
+    let mut occasions = Vec::new();
+    let mut keywords = Vec::new();
+    let mut quality = None;
+
+    for (tag_type, tag_values) in rpg_tags {
+        match tag_type.as_str() {
+            "occasion" => occasions.extend(tag_values.clone()),
+            "keyword" | "keywords" => keywords.extend(tag_values.clone()),
+            "quality" => quality = tag_values.first().cloned(),
+            _ => {} // Skip other tag types
+        }
+    }
+
+    // Vorbis comments are natively multi-valued: one entry per tag
+    comments.set_all("OCCASION", &occasions);
+    comments.set_all("KEYWORDS", &keywords);
+    match quality {
+        Some(q) => comments.set("QUALITY", &q),
+        None => comments.remove("QUALITY"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_comments() -> VorbisComments {
+        VorbisComments {
+            vendor: "reference libFLAC 1.4.3".to_string(),
+            comments: vec![
+                ("TITLE".to_string(), "Rain on Stone".to_string()),
+                ("TRACKNUMBER".to_string(), "3/12".to_string()),
+                ("DATE".to_string(), "2021-05-04".to_string()),
+                ("BPM".to_string(), "92.6".to_string()),
+                ("MOOD".to_string(), "calm".to_string()),
+                ("MOOD".to_string(), "melancholic".to_string()),
+                ("OCCASION".to_string(), "travel; rest".to_string()),
+                ("KEYWORDS".to_string(), "weather:rain".to_string()),
+                ("KEYWORDS".to_string(), "biome:forest".to_string()),
+                ("QUALITY".to_string(), "high".to_string()),
+            ],
+        }
+    }
+
+    #[test]
+    fn round_trips_raw_block() {
+        let comments = sample_comments();
+        let bytes = comments.to_bytes();
+        let (parsed, consumed) = VorbisComments::parse(&bytes).unwrap();
+        assert_eq!(consumed, bytes.len());
+        assert_eq!(parsed, comments);
+    }
+
+    #[test]
+    fn maps_comments_to_audio_file() {
+        let mut audio_file = AudioFile::default();
+        apply_to_audio_file(&sample_comments(), &mut audio_file);
+
+        assert_eq!(audio_file.title.as_deref(), Some("Rain on Stone"));
+        assert_eq!(audio_file.track_number, Some(3));
+        assert_eq!(audio_file.total_tracks, Some(12));
+        assert_eq!(audio_file.year, Some(2021));
+        assert_eq!(audio_file.bpm, Some(93));
+        assert_eq!(audio_file.mood.as_deref(), Some("calm; melancholic"));
+    }
+
+    #[test]
+    fn patch_sets_and_clears_fields() {
+        let mut comments = sample_comments();
+        let patch = AudioFilePatch {
+            title: FieldPatch::Clear,
+            mood: FieldPatch::Set("tense".to_string()),
+            producer: FieldPatch::Set("Ann Lee; Cy Diaz".to_string()),
+            ..Default::default()
+        };
+        apply_patch(&mut comments, &patch);
+
+        assert!(comments.get("TITLE").is_none());
+        assert_eq!(comments.get_all("MOOD"), vec!["tense"]);
+        assert_eq!(comments.get_all("PRODUCER"), vec!["Ann Lee", "Cy Diaz"]);
+        assert_eq!(comments.get("TRACKNUMBER"), Some("3/12"));
+    }
+
+    #[test]
+    fn reads_and_rewrites_rpg_tags() {
+        let mut comments = sample_comments();
+        let tags = read_rpg_tags(&comments);
+        assert!(tags.contains(&("occasion".to_string(), "rest".to_string())));
+        assert!(tags.contains(&("keyword".to_string(), "biome:forest".to_string())));
+        assert!(tags.contains(&("quality".to_string(), "high".to_string())));
+
+        write_rpg_tags(&mut comments, &[("keyword".to_string(), vec!["weather:storm".to_string()])]);
+        assert_eq!(comments.get_all("KEYWORDS"), vec!["weather:storm"]);
+        assert!(comments.get("OCCASION").is_none());
+        assert!(comments.get("QUALITY").is_none());
+        assert_eq!(comments.get("TITLE"), Some("Rain on Stone"));
+    }
+}
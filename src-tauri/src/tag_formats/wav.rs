@@ -0,0 +1,504 @@
+use crate::models::AudioFile;
+use id3::Tag;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Cursor, Read, Seek, SeekFrom, Write};
+
+/// Fixed part of a BWF `bext` chunk, before the free-form coding history
+const BEXT_FIXED_LEN: usize = 602;
+
+/// Chunk location inside a RIFF/WAVE file
+#[derive(Debug, Clone, Copy)]
+struct Chunk {
+    id: [u8; 4],
+    /// Offset of the chunk header
+    start: u64,
+    size: u32,
+}
+
+impl Chunk {
+    fn data_start(&self) -> u64 {
+        self.start + 8
+    }
+
+    /// End of the chunk including the pad byte for odd sizes
+    fn end(&self) -> u64 {
+        self.data_start() + self.size as u64 + (self.size & 1) as u64
+    }
+
+    fn is_id3(&self) -> bool {
+        self.id.eq_ignore_ascii_case(b"id3 ")
+    }
+}
+
+/// Broadcast Wave `bext` chunk fields
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BextChunk {
+    pub description: String,
+    pub originator: String,
+    pub originator_reference: String,
+    pub origination_date: String,
+    pub origination_time: String,
+    pub coding_history: String,
+}
+
+/// Metadata found in a WAV file
+#[derive(Debug, Clone, Default)]
+pub struct WavMetadata {
+    /// `LIST/INFO` entries as (id, text), e.g. ("INAM", "Door Creak")
+    pub info: Vec<(String, String)>,
+    pub bext: Option<BextChunk>,
+    /// Raw XML document of an `iXML` chunk
+    pub ixml: Option<String>,
+    /// Tag from an embedded `id3 ` chunk
+    pub id3: Option<Tag>,
+    /// Duration computed from the `fmt ` byte rate and `data` size
+    pub duration: Option<f64>,
+}
+
+impl WavMetadata {
+    /// Text of a `LIST/INFO` entry
+    pub fn info(&self, id: &str) -> Option<&str> {
+        self.info.iter().find(|(k, _)| k == id).map(|(_, v)| v.as_str())
+    }
+}
+
+/// Scan the top-level chunks of a RIFF/WAVE stream
+fn scan_chunks<R: Read + Seek>(reader: &mut R) -> Result<(Vec<Chunk>, u64), String> {
+    let mut header = [0u8; 12];
+    reader.seek(SeekFrom::Start(0)).map_err(|e| e.to_string())?;
+    reader.read_exact(&mut header).map_err(|_| "Not a WAV file".to_string())?;
+    if &header[0..4] == b"RF64" {
+        return Err("RF64 WAV files are not supported".to_string());
+    }
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        return Err("Not a RIFF/WAVE file".to_string());
+    }
+
+    let file_len = reader.seek(SeekFrom::End(0)).map_err(|e| e.to_string())?;
+    // Some writers leave a bogus RIFF size; never read past the end of the file
+    let riff_end = (8 + u32::from_le_bytes(header[4..8].try_into().unwrap()) as u64).min(file_len);
+
+    let mut chunks = Vec::new();
+    let mut pos = 12u64;
+    while pos + 8 <= riff_end {
+        let mut chunk_header = [0u8; 8];
+        reader.seek(SeekFrom::Start(pos)).map_err(|e| e.to_string())?;
+        reader.read_exact(&mut chunk_header).map_err(|e| format!("Failed to read WAV chunk: {}", e))?;
+
+        let chunk = Chunk {
+            id: chunk_header[0..4].try_into().unwrap(),
+            start: pos,
+            size: u32::from_le_bytes(chunk_header[4..8].try_into().unwrap()),
+        };
+        if chunk.data_start() + chunk.size as u64 > riff_end {
+            // Truncated final chunk (common for interrupted recordings): stop scanning
+            break;
+        }
+        chunks.push(chunk);
+        pos = chunk.end();
+    }
+
+    Ok((chunks, riff_end))
+}
+
+fn read_chunk_data<R: Read + Seek>(reader: &mut R, chunk: &Chunk) -> Result<Vec<u8>, String> {
+    let mut data = vec![0u8; chunk.size as usize];
+    reader.seek(SeekFrom::Start(chunk.data_start())).map_err(|e| e.to_string())?;
+    reader.read_exact(&mut data).map_err(|e| format!("Failed to read WAV chunk: {}", e))?;
+    Ok(data)
+}
+
+/// Fixed-width, NUL padded ASCII field
+fn fixed_text(data: &[u8]) -> String {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..end]).trim().to_string()
+}
+
+fn parse_info_list(data: &[u8]) -> Vec<(String, String)> {
+    let mut entries = Vec::new();
+    let mut pos = 4; // skip "INFO"
+
+    while pos + 8 <= data.len() {
+        let id = String::from_utf8_lossy(&data[pos..pos + 4]).to_string();
+        let size = u32::from_le_bytes(data[pos + 4..pos + 8].try_into().unwrap()) as usize;
+        let start = pos + 8;
+        let end = (start + size).min(data.len());
+
+        let text = fixed_text(&data[start..end]);
+        if !text.is_empty() {
+            entries.push((id, text));
+        }
+        pos = start + size + (size & 1);
+    }
+
+    entries
+}
+
+fn parse_bext(data: &[u8]) -> Option<BextChunk> {
+    if data.len() < 356 {
+        return None;
+    }
+    Some(BextChunk {
+        description: fixed_text(&data[0..256]),
+        originator: fixed_text(&data[256..288]),
+        originator_reference: fixed_text(&data[288..320]),
+        origination_date: fixed_text(&data[320..330]),
+        origination_time: fixed_text(&data[330..338]),
+        coding_history: data.get(BEXT_FIXED_LEN..).map(fixed_text).unwrap_or_default(),
+    })
+}
+
+fn duration_from_chunks<R: Read + Seek>(reader: &mut R, chunks: &[Chunk]) -> Option<f64> {
+    let fmt = chunks.iter().find(|c| &c.id == b"fmt ")?;
+    let data = chunks.iter().find(|c| &c.id == b"data")?;
+    let fmt_data = read_chunk_data(reader, fmt).ok()?;
+    let byte_rate = u32::from_le_bytes(fmt_data.get(8..12)?.try_into().ok()?);
+
+    if byte_rate == 0 {
+        None
+    } else {
+        Some(data.size as f64 / byte_rate as f64)
+    }
+}
+
+/// Read INFO, bext, iXML and id3 metadata from a WAV file without loading the audio data
+pub fn read_metadata(file_path: &str) -> Result<WavMetadata, String> {
+    let mut file = File::open(file_path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let (chunks, _) = scan_chunks(&mut file)?;
+    let mut metadata = WavMetadata::default();
+
+    for chunk in &chunks {
+        match &chunk.id {
+            b"LIST" => {
+                let data = read_chunk_data(&mut file, chunk)?;
+                if data.starts_with(b"INFO") {
+                    metadata.info.extend(parse_info_list(&data));
+                }
+            }
+            b"bext" => metadata.bext = parse_bext(&read_chunk_data(&mut file, chunk)?),
+            b"iXML" => {
+                let data = read_chunk_data(&mut file, chunk)?;
+                let text = String::from_utf8_lossy(&data);
+                metadata.ixml = Some(text.trim_end_matches('\0').to_string());
+            }
+            _ if chunk.is_id3() => {
+                let data = read_chunk_data(&mut file, chunk)?;
+                metadata.id3 = Tag::read_from2(Cursor::new(data)).ok();
+            }
+            _ => {}
+        }
+    }
+
+    metadata.duration = duration_from_chunks(&mut file, &chunks);
+    Ok(metadata)
+}
+
+/// Read only the tag from the `id3 ` chunk
+pub fn read_id3_tag(file_path: &str) -> Result<Option<Tag>, String> {
+    Ok(read_metadata(file_path)?.id3)
+}
+
+/// Raw contents of the `id3 ` chunk
+pub fn read_id3_chunk(file_path: &str) -> Result<Option<Vec<u8>>, String> {
+    let mut file = File::open(file_path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let (chunks, _) = scan_chunks(&mut file)?;
+    match chunks.iter().find(|c| c.is_id3()) {
+        Some(chunk) => read_chunk_data(&mut file, chunk).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Write an ID3v2.4 tag into the `id3 ` chunk, replacing any existing one
+pub fn write_id3_tag(file_path: &str, tag: &Tag) -> Result<(), String> {
+    let mut tag_bytes = Vec::new();
+    tag.write_to(&mut tag_bytes, id3::Version::Id3v24)
+        .map_err(|e| format!("Failed to encode ID3 tag: {}", e))?;
+    write_id3_chunk(file_path, Some(&tag_bytes))
+}
+
+/// Replace the `id3 ` chunk with raw tag bytes, or drop it for `None`.
+/// When the tag chunk is (or can become) the last chunk the file is only truncated/appended,
+/// otherwise the chunks are rewritten in order.
+pub fn write_id3_chunk(file_path: &str, tag_bytes: Option<&[u8]>) -> Result<(), String> {
+    let mut new_chunk = Vec::new();
+    if let Some(tag_bytes) = tag_bytes {
+        new_chunk.extend_from_slice(b"id3 ");
+        new_chunk.extend_from_slice(&(tag_bytes.len() as u32).to_le_bytes());
+        new_chunk.extend_from_slice(tag_bytes);
+        if tag_bytes.len() % 2 == 1 {
+            new_chunk.push(0);
+        }
+    }
+
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(file_path)
+        .map_err(|e| format!("Failed to open file for writing: {}", e))?;
+    let (chunks, riff_end) = scan_chunks(&mut file)?;
+    let file_len = file.seek(SeekFrom::End(0)).map_err(|e| e.to_string())?;
+
+    let id3_chunks: Vec<&Chunk> = chunks.iter().filter(|c| c.is_id3()).collect();
+    let last_end = chunks.last().map(|c| c.end()).unwrap_or(12);
+    let can_append = riff_end == file_len
+        && last_end == file_len
+        && match id3_chunks.as_slice() {
+            [] => true,
+            [only] => only.end() == last_end,
+            _ => false,
+        };
+
+    if can_append {
+        let write_at = id3_chunks.first().map(|c| c.start).unwrap_or(last_end);
+        let riff_size = write_at - 8 + new_chunk.len() as u64;
+        let riff_size = u32::try_from(riff_size).map_err(|_| "WAV file too large for RIFF")?;
+
+        file.set_len(write_at).map_err(|e| format!("Failed to truncate file: {}", e))?;
+        file.seek(SeekFrom::Start(write_at))
+            .and_then(|_| file.write_all(&new_chunk))
+            .and_then(|_| file.seek(SeekFrom::Start(4)))
+            .and_then(|_| file.write_all(&riff_size.to_le_bytes()))
+            .map_err(|e| format!("Failed to write id3 chunk: {}", e))?;
+        return file.flush().map_err(|e| e.to_string());
+    }
+
+    // Rewrite the RIFF body into a sibling file, dropping old id3 chunks. Whatever follows the last
+    // complete chunk (the cut-off data chunk of an interrupted recording, a data chunk past a wrong
+    // RIFF size) is copied unchanged after the tag, so no audio is lost
+    let kept: Vec<&Chunk> = chunks.iter().filter(|c| !c.is_id3()).collect();
+    let tail_start = last_end.min(file_len);
+    let body_len = 4 + kept.iter().map(|c| c.end().min(file_len) - c.start).sum::<u64>()
+        + new_chunk.len() as u64 + (file_len - tail_start);
+    let riff_size = u32::try_from(body_len).map_err(|_| "WAV file too large for RIFF")?;
+
+    let rewrite_path = format!("{}.rewrite", file_path);
+    let rewritten = File::create(&rewrite_path)
+        .map_err(|e| format!("Failed to create file: {}", e))
+        .and_then(|output| {
+            let mut output = BufWriter::new(output);
+            output.write_all(b"RIFF")
+                .and_then(|_| output.write_all(&riff_size.to_le_bytes()))
+                .and_then(|_| output.write_all(b"WAVE"))
+                .map_err(|e| format!("Failed to write file: {}", e))?;
+            for chunk in &kept {
+                copy_range(&mut file, chunk.start, chunk.end().min(file_len) - chunk.start, &mut output)?;
+            }
+            output.write_all(&new_chunk).map_err(|e| format!("Failed to write id3 chunk: {}", e))?;
+            copy_range(&mut file, tail_start, file_len - tail_start, &mut output)?;
+            output.flush().map_err(|e| format!("Failed to write file: {}", e))
+        })
+        .and_then(|_| std::fs::rename(&rewrite_path, file_path).map_err(|e| format!("Failed to replace file: {}", e)));
+
+    if rewritten.is_err() {
+        std::fs::remove_file(&rewrite_path).ok();
+    }
+    rewritten
+}
+
+/// Copy `len` bytes of `from` starting at `start`
+fn copy_range<W: Write>(from: &mut File, start: u64, len: u64, to: &mut W) -> Result<(), String> {
+    from.seek(SeekFrom::Start(start)).map_err(|e| e.to_string())?;
+    let copied = std::io::copy(&mut Read::take(&mut *from, len), to)
+        .map_err(|e| format!("Failed to copy WAV data: {}", e))?;
+    if copied != len {
+        return Err("WAV file ended while it was being copied".to_string());
+    }
+    Ok(())
+}
+
+/// Fill fields not already set (e.g. from the id3 chunk) from INFO and bext metadata
+pub fn apply_to_audio_file(metadata: &WavMetadata, audio_file: &mut AudioFile) {
+    fn fill(field: &mut Option<String>, value: Option<&str>) {
+        if field.is_none() {
+            *field = value.filter(|v| !v.is_empty()).map(|v| v.to_string());
+        }
+    }
+
+    // RIFF LIST/INFO
+    fill(&mut audio_file.title, metadata.info("INAM"));
+    fill(&mut audio_file.artist, metadata.info("IART"));
+    fill(&mut audio_file.album, metadata.info("IPRD"));
+    fill(&mut audio_file.genre, metadata.info("IGNR"));
+    fill(&mut audio_file.date, metadata.info("ICRD"));
+    fill(&mut audio_file.subtitle, metadata.info("ICMT"));
+    fill(&mut audio_file.copyright, metadata.info("ICOP"));
+    fill(&mut audio_file.engineer, metadata.info("IENG"));
+    fill(&mut audio_file.encoding_settings, metadata.info("ISFT"));
+    fill(&mut audio_file.language, metadata.info("ILNG"));
+    fill(&mut audio_file.media_type, metadata.info("IMED"));
+    if audio_file.track_number.is_none() {
+        audio_file.track_number = metadata.info("ITRK").or(metadata.info("IPRT")).and_then(|t| t.trim().parse().ok());
+    }
+
+    // Broadcast Wave: description is the library's per-file description, originator the
+    // library/organisation that produced the recording
+    if let Some(bext) = &metadata.bext {
+        fill(&mut audio_file.subtitle, Some(&bext.description));
+        fill(&mut audio_file.publisher, Some(&bext.originator));
+        fill(&mut audio_file.encoding_settings, Some(bext.coding_history.trim()));
+
+        if !bext.origination_date.is_empty() {
+            let recording_time = if bext.origination_time.is_empty() {
+                bext.origination_date.clone()
+            } else {
+                format!("{}T{}", bext.origination_date, bext.origination_time.replace(['-', '.'], ":"))
+            };
+            fill(&mut audio_file.recording_time, Some(&recording_time));
+            fill(&mut audio_file.date, Some(&bext.origination_date));
+        }
+    }
+
+    if audio_file.year.is_none() {
+        audio_file.year = audio_file.date.as_ref().and_then(|d| d.get(0..4)).and_then(|y| y.parse().ok());
+    }
+    if audio_file.duration.is_none() {
+        audio_file.duration = metadata.duration;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use id3::TagLike;
+
+    fn chunk(id: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut out = id.to_vec();
+        out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        out.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            out.push(0);
+        }
+        out
+    }
+
+    fn write_test_wav(path: &str, trailing: Option<&[u8]>) {
+        let mut fmt = vec![1, 0, 1, 0];
+        fmt.extend_from_slice(&8000u32.to_le_bytes()); // sample rate
+        fmt.extend_from_slice(&16000u32.to_le_bytes()); // byte rate
+        fmt.extend_from_slice(&[2, 0, 16, 0]);
+
+        let mut bext = vec![0u8; BEXT_FIXED_LEN];
+        bext[..11].copy_from_slice(b"Door creaks");
+        bext[256..266].copy_from_slice(b"SFX Studio");
+        bext[320..330].copy_from_slice(b"2019-08-14");
+        bext[330..338].copy_from_slice(b"10:22:05");
+
+        let mut info = b"INFO".to_vec();
+        info.extend(chunk(b"INAM", b"Creaky Door\0"));
+        info.extend(chunk(b"IGNR", b"Foley\0"));
+
+        let mut body = b"WAVE".to_vec();
+        body.extend(chunk(b"fmt ", &fmt));
+        body.extend(chunk(b"bext", &bext));
+        body.extend(chunk(b"data", &[7u8; 32000]));
+        body.extend(chunk(b"LIST", &info));
+        if let Some(data) = trailing {
+            body.extend(chunk(b"junk", data));
+        }
+
+        let mut file = b"RIFF".to_vec();
+        file.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        file.extend(body);
+        std::fs::write(path, file).unwrap();
+    }
+
+    #[test]
+    fn reads_info_and_bext() {
+        let path = std::env::temp_dir().join("ligeia_wav_read.wav").to_string_lossy().to_string();
+        write_test_wav(&path, None);
+
+        let metadata = read_metadata(&path).unwrap();
+        let mut audio_file = AudioFile::default();
+        apply_to_audio_file(&metadata, &mut audio_file);
+
+        assert_eq!(audio_file.title.as_deref(), Some("Creaky Door"));
+        assert_eq!(audio_file.genre.as_deref(), Some("Foley"));
+        assert_eq!(audio_file.subtitle.as_deref(), Some("Door creaks"));
+        assert_eq!(audio_file.publisher.as_deref(), Some("SFX Studio"));
+        assert_eq!(audio_file.recording_time.as_deref(), Some("2019-08-14T10:22:05"));
+        assert_eq!(audio_file.year, Some(2019));
+        assert_eq!(audio_file.duration, Some(2.0));
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn writes_id3_chunk_without_breaking_riff() {
+        for (name, trailing) in [("ligeia_wav_append.wav", None), ("ligeia_wav_rewrite.wav", Some(&b"xyz"[..]))] {
+            let path = std::env::temp_dir().join(name).to_string_lossy().to_string();
+            write_test_wav(&path, trailing);
+
+            for title in ["First", "A much longer second title"] {
+                let mut tag = Tag::new();
+                tag.set_title(title);
+                write_id3_tag(&path, &tag).unwrap();
+
+                let bytes = std::fs::read(&path).unwrap();
+                let riff_size = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+                assert_eq!(riff_size + 8, bytes.len());
+
+                let metadata = read_metadata(&path).unwrap();
+                assert_eq!(metadata.id3.as_ref().and_then(|t| t.title()), Some(title));
+                assert_eq!(metadata.duration, Some(2.0));
+                assert_eq!(metadata.info("INAM"), Some("Creaky Door"));
+            }
+
+            let (chunks, _) = scan_chunks(&mut File::open(&path).unwrap()).unwrap();
+            assert_eq!(chunks.iter().filter(|c| c.is_id3()).count(), 1);
+            std::fs::remove_file(&path).ok();
+        }
+    }
+
+    /// Position of the `data` chunk header
+    fn data_chunk_at(bytes: &[u8]) -> usize {
+        bytes.windows(4).position(|w| w == b"data").unwrap()
+    }
+
+    #[test]
+    fn keeps_a_truncated_data_chunk_when_writing_the_tag() {
+        let path = std::env::temp_dir().join("ligeia_wav_truncated.wav").to_string_lossy().to_string();
+        let audio: Vec<u8> = (0..20000u32).map(|i| (i % 251) as u8).collect();
+        let mut file = b"RIFF".to_vec();
+        file.extend_from_slice(&(4 + 24 + 8 + 32000u32).to_le_bytes());
+        file.extend_from_slice(b"WAVE");
+        file.extend(chunk(b"fmt ", &[1, 0, 1, 0, 64, 31, 0, 0, 128, 62, 0, 0, 2, 0, 16, 0]));
+        // Declares 32000 bytes, but the recording stopped after 20000
+        file.extend_from_slice(b"data");
+        file.extend_from_slice(&32000u32.to_le_bytes());
+        file.extend_from_slice(&audio);
+        std::fs::write(&path, file).unwrap();
+
+        let mut tag = Tag::new();
+        tag.set_title("Interrupted");
+        write_id3_tag(&path, &tag).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        let data = data_chunk_at(&bytes);
+        assert_eq!(&bytes[data + 8..], &audio[..]);
+        assert_eq!(read_metadata(&path).unwrap().id3.as_ref().and_then(|t| t.title()), Some("Interrupted"));
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn keeps_data_past_a_wrong_riff_size_when_writing_the_tag() {
+        let path = std::env::temp_dir().join("ligeia_wav_bad_riff.wav").to_string_lossy().to_string();
+        let audio: Vec<u8> = (0..20000u32).map(|i| (i % 251) as u8).collect();
+        let mut file = b"RIFF".to_vec();
+        // Only covers the fmt chunk
+        file.extend_from_slice(&(4 + 24u32).to_le_bytes());
+        file.extend_from_slice(b"WAVE");
+        file.extend(chunk(b"fmt ", &[1, 0, 1, 0, 64, 31, 0, 0, 128, 62, 0, 0, 2, 0, 16, 0]));
+        file.extend(chunk(b"data", &audio));
+        std::fs::write(&path, file).unwrap();
+
+        let mut tag = Tag::new();
+        tag.set_title("Mislabelled");
+        write_id3_tag(&path, &tag).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        let data = data_chunk_at(&bytes);
+        assert_eq!(&bytes[data + 8..data + 8 + audio.len()], &audio[..]);
+        let riff_size = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+        assert_eq!(riff_size + 8, bytes.len());
+        std::fs::remove_file(&path).ok();
+    }
+}
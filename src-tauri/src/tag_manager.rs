@@ -42,6 +42,24 @@ impl TagManager {
         db.remove_rpg_tag(audio_file_id, tag_type, tag_value).map_err(|e| e.to_string())
     }
 
+    /// Replace the tags read from an audio file before with the ones read from it now
+    pub fn replace_file_rpg_tags(&self, audio_file_id: i64, rpg_tags: &[(String, String)]) -> Result<(), String> {
+        let db = self.db.lock().unwrap();
+
+        for (tag_type, tag_value) in rpg_tags {
+            if !self.is_valid_tag(&db, tag_type, tag_value)? {
+                self.auto_add_tag_to_vocabulary(&db, tag_type, tag_value)?;
+            }
+        }
+
+        db.replace_file_rpg_tags(audio_file_id, rpg_tags).map_err(|e| e.to_string())
+    }
+
+    pub fn mark_rpg_tags_in_file(&self, audio_file_id: i64) -> Result<(), String> {
+        let db = self.db.lock().unwrap();
+        db.mark_rpg_tags_in_file(audio_file_id).map_err(|e| e.to_string())
+    }
+
     pub fn get_rpg_tags_for_file(&self, audio_file_id: i64) -> Result<Vec<RpgTag>, String> {
         let db = self.db.lock().unwrap();
         db.get_rpg_tags_for_file(audio_file_id).map_err(|e| e.to_string())
@@ -228,7 +246,16 @@ mod tests {
             original_release_time: None, playlist_delay: None, recording_time: None, release_time: None,
             tagging_time: None, encoding_time: None, encoding_settings: None, encoded_by: None, copyright: None,
             file_owner: None, internet_radio_station_name: None, internet_radio_station_owner: None, isrc: None,
-            publisher: None, mood: Some("calm".into()), occasion: None, tempo: None, content_type: None, category: None };
+            publisher: None, mood: Some("calm".into()), occasion: None, tempo: None, content_type: None, category: None,
+            subcategory: None, auto_tagged: None, auto_tag_date: None, auto_tag_version: None,
+            loudness_integrated: None, loudness_range: None, true_peak: None,
+            loop_start: None, loop_end: None, loop_seamless: None,
+            key_confidence: None, leading_silence: None, trailing_silence: None, mostly_silent: None,
+            content_hash: None, bpm_confidence: None,
+            quality_grade: None, clipping_ratio: None, noise_floor: None, bandwidth: None, sample_rate: None, bit_depth: None,
+            codec: None, container: None, channels: None, bitrate: None, file_size: None,
+            decodable: None, decode_error: None,
+            file_modified: None, missing: None };
         let db = mgr.db.lock().unwrap();
         let id = db.save_audio_file(&file).unwrap();
         drop(db);
@@ -0,0 +1,209 @@
+use crate::key_detection::Decimator;
+use crate::models::TempoEstimate;
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+const FRAME_SIZE: usize = 1024;
+/// About 86 onset envelope values per second at the analysis rate
+const HOP_SIZE: usize = 128;
+/// The first minute is enough to find a steady beat
+const MAX_SECONDS: f64 = 60.0;
+/// Less audio holds too few beats to call a tempo steady
+const MIN_SECONDS: f64 = 4.0;
+const MIN_BPM: f64 = 50.0;
+const MAX_BPM: f64 = 200.0;
+/// Octave ambiguities (half or double tempo) are settled towards moderate tempos
+const PREFERRED_BPM: f64 = 120.0;
+const PRIOR_OCTAVES: f64 = 1.0;
+/// Window of the running mean taken out of the onset envelope, so only its pulses correlate
+const DETREND_SECONDS: f64 = 1.0;
+/// Weaker periodicity of the onsets counts as non-rhythmic: rain, crowds, drones, speech
+const MIN_CONFIDENCE: f64 = 0.2;
+
+/// Streaming tempo estimation, fed with interleaved samples
+pub struct TempoDetector {
+    decimator: Decimator,
+    frame_rate: f64,
+    remaining: u64,
+    /// Decimated mono samples not yet covered by a full frame
+    pending: Vec<f64>,
+    window: Vec<f64>,
+    fft: Arc<dyn Fft<f64>>,
+    previous: Vec<f64>,
+    /// Spectral flux of every hop: how much louder the spectrum got
+    onsets: Vec<f64>,
+}
+
+impl TempoDetector {
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        let decimator = Decimator::new(sample_rate, channels);
+        let frame_rate = decimator.rate(sample_rate) / HOP_SIZE as f64;
+        TempoDetector {
+            decimator,
+            frame_rate,
+            remaining: (MAX_SECONDS * sample_rate as f64) as u64,
+            pending: Vec::with_capacity(FRAME_SIZE),
+            window: (0..FRAME_SIZE).map(|n| 0.5 - 0.5 * (2.0 * PI * n as f64 / FRAME_SIZE as f64).cos()).collect(),
+            fft: FftPlanner::new().plan_fft_forward(FRAME_SIZE),
+            previous: vec![0.0; FRAME_SIZE / 2],
+            onsets: Vec::new(),
+        }
+    }
+
+    /// Feed interleaved samples; returns `false` once enough audio has been heard
+    pub fn add_samples(&mut self, samples: &[f32]) -> bool {
+        for frame in samples.chunks_exact(self.decimator.channels()) {
+            if self.remaining == 0 {
+                return false;
+            }
+            self.remaining -= 1;
+
+            let Some(mono) = self.decimator.push(frame) else { continue };
+            self.pending.push(mono);
+            if self.pending.len() == FRAME_SIZE {
+                self.analyze_frame();
+                self.pending.drain(..HOP_SIZE);
+            }
+        }
+        self.remaining > 0
+    }
+
+    fn analyze_frame(&mut self) {
+        let mut spectrum: Vec<Complex<f64>> = self.pending.iter()
+            .zip(&self.window)
+            .map(|(s, w)| Complex::new(s * w, 0.0))
+            .collect();
+        self.fft.process(&mut spectrum);
+
+        let mut flux = 0.0;
+        for (previous, bin) in self.previous.iter_mut().zip(&spectrum) {
+            // Log compression keeps loud partials from drowning out soft hits
+            let magnitude = (1.0 + 100.0 * bin.norm()).ln();
+            flux += (magnitude - *previous).max(0.0);
+            *previous = magnitude;
+        }
+        self.onsets.push(flux);
+    }
+
+    pub fn finish(self) -> TempoEstimate {
+        let count = self.onsets.len();
+        if (count as f64) < MIN_SECONDS * self.frame_rate {
+            return TempoEstimate::default();
+        }
+
+        // Subtract the running mean, centered on each value
+        let half = (DETREND_SECONDS * self.frame_rate / 2.0) as usize;
+        let mut prefix = vec![0.0; count + 1];
+        for (i, value) in self.onsets.iter().enumerate() {
+            prefix[i + 1] = prefix[i] + value;
+        }
+        let pulses: Vec<f64> = (0..count)
+            .map(|i| {
+                let (start, end) = (i.saturating_sub(half), (i + half + 1).min(count));
+                self.onsets[i] - (prefix[end] - prefix[start]) / (end - start) as f64
+            })
+            .collect();
+
+        // Autocorrelation, normalized so the zero lag is 1
+        let correlation = |lag: usize| {
+            pulses.iter().zip(&pulses[lag..]).map(|(a, b)| a * b).sum::<f64>() / (count - lag) as f64
+        };
+        let energy = correlation(0);
+        if energy <= 0.0 {
+            return TempoEstimate::default();
+        }
+        let min_lag = (60.0 * self.frame_rate / MAX_BPM).floor() as usize;
+        let max_lag = ((60.0 * self.frame_rate / MIN_BPM).ceil() as usize).min(count / 2);
+        let correlations: Vec<f64> = (min_lag - 1..=max_lag + 1).map(|lag| correlation(lag) / energy).collect();
+
+        let prior = |lag: f64| {
+            let octaves = (60.0 * self.frame_rate / lag / PREFERRED_BPM).log2() / PRIOR_OCTAVES;
+            (-0.5 * octaves * octaves).exp()
+        };
+        let Some(best) = (1..correlations.len() - 1)
+            .max_by(|&a, &b| {
+                let score = |i: usize| correlations[i] * prior((min_lag - 1 + i) as f64);
+                score(a).total_cmp(&score(b))
+            })
+        else {
+            return TempoEstimate::default();
+        };
+
+        // Parabolic interpolation between the neighbouring lags
+        let (left, peak, right) = (correlations[best - 1], correlations[best], correlations[best + 1]);
+        let curvature = left - 2.0 * peak + right;
+        let offset = if curvature < 0.0 { 0.5 * (left - right) / curvature } else { 0.0 };
+        let lag = (min_lag - 1 + best) as f64 + offset;
+
+        let confidence = peak.clamp(0.0, 1.0);
+        let bpm = 60.0 * self.frame_rate / lag;
+        TempoEstimate { bpm: (confidence >= MIN_CONFIDENCE).then_some(bpm), confidence }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 44100;
+
+    fn noise(seed: &mut u32) -> f32 {
+        *seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+        (*seed >> 8) as f32 / (1 << 24) as f32 - 0.5
+    }
+
+    fn detect(samples: &[f32]) -> TempoEstimate {
+        let mut detector = TempoDetector::new(RATE, 2);
+        for chunk in samples.chunks(4096) {
+            detector.add_samples(chunk);
+        }
+        detector.finish()
+    }
+
+    /// Stereo noise hits decaying within 50 ms at the given onset times, over soft hiss
+    fn hits(seconds: f64, onsets: &[f64], hiss: f32) -> Vec<f32> {
+        let mut seed = 5u32;
+        let mut samples = Vec::new();
+        let mut next = 0;
+        let mut last_hit = f64::MIN;
+        for n in 0..(seconds * RATE as f64) as usize {
+            let t = n as f64 / RATE as f64;
+            if next < onsets.len() && t >= onsets[next] {
+                last_hit = onsets[next];
+                next += 1;
+            }
+            let hit = (-(t - last_hit) * 60.0).exp() as f32;
+            let left = noise(&mut seed) * (hit + hiss);
+            let right = noise(&mut seed) * (hit + hiss);
+            samples.extend([left, right]);
+        }
+        samples
+    }
+
+    #[test]
+    fn finds_the_tempo_of_a_steady_beat() {
+        let beat: Vec<f64> = (0..40).map(|i| i as f64 * 0.6).collect();
+        let estimate = detect(&hits(20.0, &beat, 0.02));
+        let bpm = estimate.bpm.expect("a steady beat");
+        assert!((bpm - 100.0).abs() < 1.5, "{:?}", estimate);
+        assert!(estimate.confidence >= MIN_CONFIDENCE);
+    }
+
+    #[test]
+    fn leaves_rain_and_short_files_without_a_tempo() {
+        // Drops at random times over steady noise
+        let mut seed = 77u32;
+        let mut t = 0.0;
+        let drops: Vec<f64> = std::iter::from_fn(|| {
+            t += 0.05 + (noise(&mut seed) as f64 + 0.5) * 0.4;
+            (t < 20.0).then_some(t)
+        }).collect();
+        let rain = detect(&hits(20.0, &drops, 0.2));
+        assert_eq!(rain.bpm, None, "{:?}", rain);
+
+        assert_eq!(detect(&hits(2.0, &[0.0, 0.6, 1.2], 0.0)), TempoEstimate::default());
+        assert_eq!(detect(&vec![0.0; RATE as usize * 20]), TempoEstimate::default());
+    }
+}
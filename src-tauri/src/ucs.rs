@@ -0,0 +1,185 @@
+use crate::data::tag_mappings::ucs_mappings;
+use crate::models::AudioFile;
+use regex::Regex;
+use std::path::Path;
+
+/// Official UCS CatIDs as (cat_id, category, subcategory)
+const UCS_CATEGORIES: &[(&str, &str, &str)] = &include!("data/ucs_categories.rs");
+
+/// Universal Category System information of a sound effect
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UcsInfo {
+    pub cat_id: Option<String>,
+    pub category: String,
+    pub subcategory: Option<String>,
+    pub fx_name: Option<String>,
+    pub creator_id: Option<String>,
+    pub source_id: Option<String>,
+}
+
+impl UcsInfo {
+    /// Set category/subcategory on the audio file unless its tags already provide them
+    pub fn apply_to_audio_file(&self, audio_file: &mut AudioFile) {
+        if audio_file.category.is_none() {
+            audio_file.category = Some(self.category.clone());
+            audio_file.subcategory = self.subcategory.clone();
+        }
+    }
+}
+
+/// Look up an official CatID, ignoring case
+pub fn lookup_cat_id(cat_id: &str) -> Option<(&'static str, &'static str, &'static str)> {
+    UCS_CATEGORIES.iter()
+        .find(|(id, _, _)| id.eq_ignore_ascii_case(cat_id))
+        .copied()
+}
+
+/// Upper-case category prefix of a CatID, e.g. "AMB" for "AMBForst"
+fn category_prefix(cat_id: &str) -> Option<&str> {
+    let upper = cat_id.chars().take_while(|c| c.is_ascii_uppercase()).count();
+    let next_is_lower = cat_id[upper..].starts_with(|c: char| c.is_ascii_lowercase());
+    if upper < 3 || !next_is_lower {
+        return None;
+    }
+    Some(&cat_id[..upper - 1])
+}
+
+/// Resolve a CatID to (cat_id, category, subcategory)
+///
+/// CatIDs missing from the list still resolve to their category when the
+/// prefix belongs to a known category (e.g. "AMBCastle" -> AMBIENCE).
+fn resolve_cat_id(cat_id: &str) -> Option<(String, String, Option<String>)> {
+    if let Some((id, category, subcategory)) = lookup_cat_id(cat_id) {
+        return Some((id.to_string(), category.to_string(), Some(subcategory.to_string())));
+    }
+
+    let prefix = category_prefix(cat_id)?;
+    UCS_CATEGORIES.iter()
+        .find(|(id, _, _)| category_prefix(id) == Some(prefix))
+        .map(|(_, category, _)| (cat_id.to_string(), category.to_string(), None))
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() { None } else { Some(value.to_string()) }
+}
+
+/// Parse a `CatID(-UserCategory)_FXName_CreatorID_SourceID` filename
+pub fn parse_filename(file_path: &str) -> Option<UcsInfo> {
+    let stem = Path::new(file_path).file_stem()?.to_str()?;
+    let mut parts = stem.split('_');
+    let cat_field = parts.next()?;
+    let fx_name = parts.next()?;
+
+    let cat_id = cat_field.split('-').next()?;
+    let (cat_id, category, subcategory) = resolve_cat_id(cat_id)?;
+
+    Some(UcsInfo {
+        cat_id: Some(cat_id),
+        category,
+        subcategory,
+        fx_name: non_empty(fx_name),
+        creator_id: parts.next().and_then(non_empty),
+        source_id: parts.next().and_then(non_empty),
+    })
+}
+
+/// Text of the first `<name>` element in an XML document, ignoring case
+fn xml_text(xml: &str, name: &str) -> Option<String> {
+    let pattern = format!(r"(?is)<{0}>\s*(.*?)\s*</{0}>", regex::escape(name));
+    let re = Regex::new(&pattern).ok()?;
+    let value = re.captures(xml)?.get(1)?.as_str()
+        .replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'");
+    non_empty(&value)
+}
+
+/// Parse UCS fields from an iXML document (`USER` and `ASWG` sections)
+pub fn parse_ixml(xml: &str) -> Option<UcsInfo> {
+    let fx_name = xml_text(xml, "FXNAME");
+    let creator_id = xml_text(xml, "CREATORID");
+    let source_id = xml_text(xml, "SOURCEID");
+
+    let resolved = xml_text(xml, "CATID").and_then(|cat_id| resolve_cat_id(&cat_id));
+    let (cat_id, category, subcategory) = match resolved {
+        Some((cat_id, category, subcategory)) => (Some(cat_id), category, subcategory),
+        None => {
+            let category = xml_text(xml, "CATEGORY")?.to_uppercase();
+            let subcategory = xml_text(xml, "SUBCATEGORY").map(|s| s.to_uppercase());
+            let cat_id = UCS_CATEGORIES.iter()
+                .find(|(_, cat, sub)| *cat == category && Some(*sub) == subcategory.as_deref())
+                .map(|(id, _, _)| id.to_string());
+            (cat_id, category, subcategory)
+        }
+    };
+
+    Some(UcsInfo { cat_id, category, subcategory, fx_name, creator_id, source_id })
+}
+
+/// UCS information of a file; embedded iXML takes precedence over the filename
+pub fn detect(file_path: &str, ixml: Option<&str>) -> Option<UcsInfo> {
+    ixml.and_then(parse_ixml).or_else(|| parse_filename(file_path))
+}
+
+/// Keyword vocabulary tags mapped from a UCS category as ("keyword", tag) pairs
+pub fn keyword_tags(category: &str, subcategory: Option<&str>) -> Vec<(String, String)> {
+    ucs_mappings::lookup_ucs_keywords(category, subcategory)
+        .iter()
+        .map(|tag| ("keyword".to_string(), tag.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ucs_filenames() {
+        let info = parse_filename("/sfx/AMBForst_Morning Birds Distant_JD_Woodland Pack.wav").unwrap();
+        assert_eq!(info.cat_id.as_deref(), Some("AMBForst"));
+        assert_eq!(info.category, "AMBIENCE");
+        assert_eq!(info.subcategory.as_deref(), Some("FOREST"));
+        assert_eq!(info.fx_name.as_deref(), Some("Morning Birds Distant"));
+        assert_eq!(info.creator_id.as_deref(), Some("JD"));
+        assert_eq!(info.source_id.as_deref(), Some("Woodland Pack"));
+
+        let info = parse_filename("weathrain-Heavy_Downpour On Roof.flac").unwrap();
+        assert_eq!((info.category.as_str(), info.subcategory.as_deref()), ("WEATHER", Some("RAIN")));
+
+        let info = parse_filename("AMBCastle_Great Hall.wav").unwrap();
+        assert_eq!((info.category.as_str(), info.subcategory), ("AMBIENCE", None));
+
+        assert!(parse_filename("Tavern_Theme_01.mp3").is_none());
+        assert!(parse_filename("DOORWood.wav").is_none());
+    }
+
+    #[test]
+    fn parses_ixml_user_fields() {
+        let xml = "<BWFXML><USER><CATID>DOORCreak</CATID><FXNAME>Old Gate</FXNAME></USER></BWFXML>";
+        let info = parse_ixml(xml).unwrap();
+        assert_eq!((info.category.as_str(), info.subcategory.as_deref()), ("DOORS", Some("CREAK")));
+        assert_eq!(info.fx_name.as_deref(), Some("Old Gate"));
+
+        let xml = "<BWFXML><ASWG><category>Wind</category><subCategory>Gust</subCategory></ASWG></BWFXML>";
+        let info = parse_ixml(xml).unwrap();
+        assert_eq!(info.cat_id.as_deref(), Some("WINDGust"));
+        assert!(parse_ixml("<BWFXML><PROJECT>Film</PROJECT></BWFXML>").is_none());
+    }
+
+    #[test]
+    fn maps_categories_to_keywords() {
+        let info = parse_filename("RAINGen_Steady Rain_XX_YY.wav").unwrap();
+        let keywords: Vec<String> = keyword_tags(&info.category, info.subcategory.as_deref())
+            .into_iter()
+            .map(|(_, tag)| tag)
+            .collect();
+        assert_eq!(keywords, vec!["weather:rain", "sfx:rain"]);
+
+        let tags = keyword_tags("DOORS", Some("CREAK"));
+        assert_eq!(tags, vec![("keyword".to_string(), "sfx:door-creak".to_string())]);
+        assert_eq!(keyword_tags("DOORS", Some("WOOD"))[0].1, "sound-design:objects");
+    }
+}
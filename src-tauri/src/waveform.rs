@@ -0,0 +1,322 @@
+use crate::audio_handler::AudioHandler;
+use crate::models::WaveformPeaks;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Waveform peak cache, kept next to the database
+pub const WAVEFORM_CACHE_DIR: &str = "../db/waveforms";
+
+const MAGIC: &[u8; 4] = b"LGWF";
+const FORMAT_VERSION: u32 = 1;
+
+/// Frames per peak at the finest level; every coarser level merges `LEVEL_FACTOR` peaks
+const BASE_FRAMES_PER_PEAK: u64 = 256;
+const LEVEL_FACTOR: u64 = 4;
+const LEVELS: usize = 5;
+
+/// Most peaks returned for one request
+const MAX_WIDTH: usize = 16384;
+
+/// Bytes per stored peak: min and max as 16-bit samples
+const PEAK_BYTES: u64 = 4;
+
+/// Peaks of the whole file at one zoom level
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct Level {
+    frames_per_peak: u64,
+    count: u64,
+    /// Byte offset of the level's peaks after the header
+    offset: u64,
+}
+
+/// Identifies the decoded source so a changed file is decoded again
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct Source {
+    path: String,
+    modified_ns: u64,
+    size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct Header {
+    version: u32,
+    source: Source,
+    sample_rate: u32,
+    frames: u64,
+    levels: Vec<Level>,
+}
+
+/// Min/max of all channels over every `BASE_FRAMES_PER_PEAK` frames, fed with interleaved samples
+struct PeakBuilder {
+    channels: usize,
+    frames: u64,
+    frames_in_peak: u64,
+    current: (f32, f32),
+    peaks: Vec<(f32, f32)>,
+}
+
+impl PeakBuilder {
+    fn new(channels: usize) -> Self {
+        PeakBuilder { channels: channels.max(1), frames: 0, frames_in_peak: 0, current: (0.0, 0.0), peaks: Vec::new() }
+    }
+
+    fn add_samples(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(self.channels) {
+            for &sample in frame {
+                self.current.0 = self.current.0.min(sample);
+                self.current.1 = self.current.1.max(sample);
+            }
+            self.frames += 1;
+            self.frames_in_peak += 1;
+            if self.frames_in_peak == BASE_FRAMES_PER_PEAK {
+                self.peaks.push(self.current);
+                self.current = (0.0, 0.0);
+                self.frames_in_peak = 0;
+            }
+        }
+    }
+
+    /// Total frames and the finest peaks, including a final partial one
+    fn finish(mut self) -> (u64, Vec<(f32, f32)>) {
+        if self.frames_in_peak > 0 {
+            self.peaks.push(self.current);
+        }
+        (self.frames, self.peaks)
+    }
+}
+
+/// Merge every `factor` consecutive peaks into one
+fn merge(peaks: &[(f32, f32)], factor: usize) -> Vec<(f32, f32)> {
+    peaks.chunks(factor)
+        .map(|chunk| chunk.iter().fold((0.0f32, 0.0f32), |(min, max), &(lo, hi)| (min.min(lo), max.max(hi))))
+        .collect()
+}
+
+fn quantize(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16
+}
+
+fn dequantize(sample: i16) -> f32 {
+    sample as f32 / i16::MAX as f32
+}
+
+fn source_of(file_path: &str) -> Result<Source, String> {
+    let metadata = fs::metadata(file_path).map_err(|e| format!("Failed to read file metadata: {}", e))?;
+    let modified_ns = metadata.modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or(0);
+    let path = fs::canonicalize(file_path).map(|p| p.to_string_lossy().to_string()).unwrap_or_else(|_| file_path.to_string());
+    Ok(Source { path, modified_ns, size: metadata.len() })
+}
+
+/// Cache file of a source, keyed by its path and modification time
+fn cache_path(cache_dir: &Path, source: &Source) -> PathBuf {
+    let key: String = Sha256::digest(format!("{}\0{}", source.path, source.modified_ns).as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    cache_dir.join(format!("{}.peaks", key))
+}
+
+/// Write all zoom levels under a temporary name, then move the finished file into place
+fn write_cache(path: &Path, source: Source, sample_rate: u32, frames: u64, finest: Vec<(f32, f32)>) -> Result<(), String> {
+    let mut levels_peaks = vec![finest];
+    while levels_peaks.len() < LEVELS {
+        let coarser = merge(levels_peaks.last().unwrap(), LEVEL_FACTOR as usize);
+        levels_peaks.push(coarser);
+    }
+
+    let mut offset = 0;
+    let levels = levels_peaks.iter().enumerate().map(|(index, peaks)| {
+        let level = Level {
+            frames_per_peak: BASE_FRAMES_PER_PEAK * LEVEL_FACTOR.pow(index as u32),
+            count: peaks.len() as u64,
+            offset,
+        };
+        offset += level.count * PEAK_BYTES;
+        level
+    }).collect();
+    let header = Header { version: FORMAT_VERSION, source, sample_rate, frames, levels };
+    let header_json = serde_json::to_vec(&header).map_err(|e| e.to_string())?;
+
+    let mut data = Vec::with_capacity(8 + header_json.len() + offset as usize);
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&(header_json.len() as u32).to_le_bytes());
+    data.extend_from_slice(&header_json);
+    for &(min, max) in levels_peaks.iter().flatten() {
+        data.extend_from_slice(&quantize(min).to_le_bytes());
+        data.extend_from_slice(&quantize(max).to_le_bytes());
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create waveform cache: {}", e))?;
+    }
+    // Write under a temporary name so an interrupted write never leaves a truncated cache file
+    let temp_path = path.with_extension("peaks.tmp");
+    File::create(&temp_path)
+        .and_then(|mut file| file.write_all(&data))
+        .map_err(|e| format!("Failed to write waveform cache: {}", e))?;
+    fs::rename(&temp_path, path).map_err(|e| format!("Failed to store waveform cache: {}", e))
+}
+
+/// Header of a cache file and where its peak data starts
+fn read_header(file: &mut File) -> Result<(Header, u64), String> {
+    let mut prefix = [0u8; 8];
+    file.read_exact(&mut prefix).map_err(|e| format!("Failed to read waveform cache: {}", e))?;
+    if &prefix[..4] != MAGIC {
+        return Err("Not a waveform cache file".to_string());
+    }
+    let header_len = u32::from_le_bytes(prefix[4..].try_into().unwrap()) as usize;
+    let mut header_json = vec![0u8; header_len];
+    file.read_exact(&mut header_json).map_err(|e| format!("Failed to read waveform cache: {}", e))?;
+    let header = serde_json::from_slice(&header_json).map_err(|e| format!("Invalid waveform cache: {}", e))?;
+    Ok((header, 8 + header_len as u64))
+}
+
+/// Open the cache of a file, decoding it first when there is none or the file changed since
+fn open_cache(file_path: &str, cache_dir: &Path) -> Result<(File, Header, u64), String> {
+    let source = source_of(file_path)?;
+    let path = cache_path(cache_dir, &source);
+
+    if let Ok(mut file) = File::open(&path) {
+        match read_header(&mut file) {
+            Ok((header, data_start)) if header.version == FORMAT_VERSION && header.source == source => {
+                return Ok((file, header, data_start));
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Rebuilding waveform cache of {}: {}", file_path, e),
+        }
+    }
+
+    let mut sample_rate = 0;
+    let mut builder: Option<PeakBuilder> = None;
+    AudioHandler::decode_samples(file_path, |rate, channels, samples| {
+        sample_rate = rate;
+        builder.get_or_insert_with(|| PeakBuilder::new(channels)).add_samples(samples);
+        true
+    })?;
+    let (frames, peaks) = builder.ok_or("No audio could be decoded")?.finish();
+    log::info!("Cached waveform of {} ({} frames)", file_path, frames);
+
+    write_cache(&path, source, sample_rate, frames, peaks)?;
+    let mut file = File::open(&path).map_err(|e| format!("Failed to open waveform cache: {}", e))?;
+    let (header, data_start) = read_header(&mut file)?;
+    Ok((file, header, data_start))
+}
+
+/// Peaks of a file between `start` and `end` seconds (the whole file by default), at most `width` of them.
+///
+/// The first call decodes the file into the cache; later calls only read the zoom level the range needs.
+pub fn get_peaks(file_path: &str, cache_dir: &Path, start: Option<f64>, end: Option<f64>, width: usize) -> Result<WaveformPeaks, String> {
+    let (mut file, header, data_start) = open_cache(file_path, cache_dir)?;
+    let rate = header.sample_rate.max(1) as f64;
+    let width = width.clamp(1, MAX_WIDTH) as u64;
+
+    let start_frame = ((start.unwrap_or(0.0).max(0.0) * rate) as u64).min(header.frames);
+    let end_frame = end.map(|end| (end.max(0.0) * rate) as u64).unwrap_or(header.frames).clamp(start_frame, header.frames);
+    let span = end_frame - start_frame;
+
+    // Coarsest level that still has a peak for every pixel, or the finest one when zoomed in further
+    let level = header.levels.iter().rev()
+        .find(|level| span / level.frames_per_peak >= width)
+        .or(header.levels.first())
+        .ok_or("Waveform cache has no levels")?;
+    let first = (start_frame / level.frames_per_peak).min(level.count);
+    let last = end_frame.div_ceil(level.frames_per_peak).min(level.count);
+
+    let mut bytes = vec![0u8; ((last - first) * PEAK_BYTES) as usize];
+    file.seek(SeekFrom::Start(data_start + level.offset + first * PEAK_BYTES))
+        .and_then(|_| file.read_exact(&mut bytes))
+        .map_err(|e| format!("Failed to read waveform cache: {}", e))?;
+    let peaks: Vec<(f32, f32)> = bytes.chunks_exact(PEAK_BYTES as usize)
+        .map(|peak| (
+            dequantize(i16::from_le_bytes([peak[0], peak[1]])),
+            dequantize(i16::from_le_bytes([peak[2], peak[3]])),
+        ))
+        .collect();
+
+    // Fold the level's peaks into `width` buckets
+    let count = peaks.len() as u64;
+    let buckets = width.min(count);
+    let (min, max) = (0..buckets)
+        .map(|bucket| {
+            let from = (bucket * count / buckets) as usize;
+            let to = ((bucket + 1) * count / buckets) as usize;
+            merge(&peaks[from..to], to - from)[0]
+        })
+        .unzip();
+
+    Ok(WaveformPeaks {
+        sample_rate: header.sample_rate,
+        duration: header.frames as f64 / rate,
+        start: start_frame as f64 / rate,
+        end: end_frame as f64 / rate,
+        min,
+        max,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mono 16-bit WAV
+    fn write_wav(path: &Path, samples: &[i16]) {
+        let data_len = samples.len() as u32 * 2;
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&8000u32.to_le_bytes());
+        wav.extend_from_slice(&16000u32.to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        samples.iter().for_each(|s| wav.extend_from_slice(&s.to_le_bytes()));
+        fs::write(path, wav).unwrap();
+    }
+
+    #[test]
+    fn serves_ranges_from_the_cache() {
+        let dir = std::env::temp_dir().join("ligeia_waveform_test");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let wav = dir.join("steps.wav");
+        let cache_dir = dir.join("cache");
+
+        // Ten segments of 8192 frames at 8 kHz, each louder than the last
+        let samples: Vec<i16> = (0..81920).map(|n| {
+            let level = (n / 8192 + 1) as i16 * 3000;
+            if n % 2 == 0 { level } else { -level }
+        }).collect();
+        write_wav(&wav, &samples);
+        let path = wav.to_string_lossy().to_string();
+
+        let whole = get_peaks(&path, &cache_dir, None, None, 10).unwrap();
+        assert_eq!((whole.sample_rate, whole.duration, whole.min.len()), (8000, 10.24, 10));
+        let expected: Vec<f32> = (1..=10).map(|s| dequantize(quantize(s as f32 * 3000.0 / 32768.0))).collect();
+        assert_eq!(whole.max, expected);
+        assert_eq!(fs::read_dir(&cache_dir).unwrap().count(), 1);
+
+        // Zoomed in past the finest level there are fewer peaks than pixels
+        let zoomed = get_peaks(&path, &cache_dir, Some(2.1), Some(2.2), 1000).unwrap();
+        assert_eq!(zoomed.min.len(), 4);
+        assert!(zoomed.max.iter().all(|&max| max == expected[2]));
+
+        // A rewritten file is decoded again instead of served stale
+        write_wav(&wav, &samples[..8192]);
+        let short = get_peaks(&path, &cache_dir, None, None, 10).unwrap();
+        assert_eq!(short.duration, 1.024);
+        fs::remove_dir_all(&dir).ok();
+    }
+}